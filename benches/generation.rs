@@ -0,0 +1,117 @@
+//! Times generating the documentation of a generated crate of medium size, saving it on one
+//! thread and on one thread per CPU, and compares the size of the store with and without
+//! compression. Run with `cargo bench`.
+//!
+//! Only saving is spread over threads. Parsing and converting stay on one thread, since the
+//! identifiers of the AST are interned per thread by `syntex_syntax`.
+
+extern crate num_cpus;
+extern crate oxidoc;
+extern crate syntex_syntax as syntax;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant};
+
+use oxidoc::document::CrateInfo;
+use oxidoc::generation::{self, GenerateOptions, Verbosity};
+use oxidoc::store;
+
+use syntax::codemap::FilePathMapping;
+use syntax::parse::{self, ParseSess};
+
+const MODULES: usize = 40;
+const STRUCTS_PER_MODULE: usize = 25;
+
+/// The source of a crate with `MODULES` modules of `STRUCTS_PER_MODULE` documented structs, each
+/// with a field and two methods.
+fn crate_source() -> String {
+    let mut source = String::new();
+    for m in 0..MODULES {
+        source.push_str(&format!("/// Module number {}.\npub mod module{} {{\n", m, m));
+        for s in 0..STRUCTS_PER_MODULE {
+            source.push_str(&format!(r#"
+    /// A struct that holds a number, for measuring how long generation takes.
+    pub struct Struct{s} {{
+        /// The number.
+        pub value: u32,
+    }}
+
+    impl Struct{s} {{
+        /// Makes a new struct holding the number.
+        pub fn new(value: u32) -> Self {{ Struct{s} {{ value: value }} }}
+
+        /// Adds to the number.
+        pub fn add(&mut self, other: u32) {{ self.value += other; }}
+    }}
+"#, s = s));
+        }
+        source.push_str("}\n");
+    }
+    source
+}
+
+fn generate(source: &str, jobs: usize) -> Duration {
+    let parse_session = ParseSess::new(FilePathMapping::empty());
+    let krate = parse::parse_crate_from_source_str("bench.rs".to_string(), source.to_string(),
+                                                   &parse_session)
+        .unwrap_or_else(|_| panic!("The benchmark's crate doesn't parse"));
+    let crate_info = CrateInfo {
+        name: "oxidoc_bench".to_string(),
+        version: "0.1.0".to_string(),
+        lib_path: None,
+    };
+    let mut options = GenerateOptions::new();
+    options.jobs = jobs;
+    options.verbosity = Verbosity::Quiet;
+
+    let start = Instant::now();
+    generation::generate_doc_cache(krate, crate_info, parse_session.codemap(), &options)
+        .expect("Generation failed");
+    start.elapsed()
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + duration.subsec_nanos() as u64 / 1_000_000
+}
+
+/// The total size of the files under a directory.
+fn dir_size(dir: &Path) -> u64 {
+    let mut size = 0;
+    for entry in fs::read_dir(dir).expect("Couldn't read the store") {
+        let path = entry.expect("Couldn't read the store").path();
+        if path.is_dir() {
+            size += dir_size(&path);
+        } else {
+            size += fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+        }
+    }
+    size
+}
+
+fn main() {
+    let source = crate_source();
+    let mut dir = env::temp_dir();
+    dir.push(format!("oxidoc_bench_{}", process::id()));
+
+    for &jobs in &[1, num_cpus::get()] {
+        let _ = fs::remove_dir_all(&dir);
+        store::set_store_root(Some(PathBuf::from(&dir)));
+        let elapsed = generate(&source, jobs);
+        println!("Generated with {} saving threads in {} ms", jobs, millis(elapsed));
+    }
+
+    for &(level, name) in &[("0", "uncompressed"), ("3", "compressed at level 3")] {
+        env::set_var("OXIDOC_COMPRESSION_LEVEL", level);
+        let _ = fs::remove_dir_all(&dir);
+        store::set_store_root(Some(PathBuf::from(&dir)));
+        generate(&source, num_cpus::get());
+        println!("Store size {}: {} KiB", name, dir_size(&dir) / 1024);
+    }
+    env::remove_var("OXIDOC_COMPRESSION_LEVEL");
+
+    store::set_store_root(None);
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,222 @@
+//! Coloring of item signatures for terminal output. Lighter than full syntax highlighting, this
+//! only tells keywords, type names and lifetimes apart.
+
+use std::env;
+
+use ansi_term::Colour;
+
+/// The environment variable used to override the signature colors, like
+/// `keyword=purple,type=yellow,lifetime=cyan`. Colors are given by name or as a number from the
+/// 256 color palette.
+const COLORS_VAR: &str = "OXIDOC_SIGNATURE_COLORS";
+
+const KEYWORDS: &[&str] = &[
+    "as", "const", "crate", "dyn", "enum", "extern", "fn", "for", "impl", "in", "macro_rules",
+    "mod", "mut", "pub", "ref", "self", "static", "struct", "super", "trait", "type", "union",
+    "unsafe", "use", "where",
+];
+
+const PRIMITIVE_TYPES: &[&str] = &[
+    "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "str", "u8", "u16",
+    "u32", "u64", "u128", "usize",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TokenKind {
+    Keyword,
+    Type,
+    Lifetime,
+    Plain,
+}
+
+/// The colors used for each kind of token.
+#[derive(Clone, Debug, PartialEq)]
+struct ColorScheme {
+    keyword: Colour,
+    type_: Colour,
+    lifetime: Colour,
+}
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        ColorScheme {
+            keyword: Colour::Purple,
+            type_: Colour::Yellow,
+            lifetime: Colour::Cyan,
+        }
+    }
+}
+
+impl ColorScheme {
+    /// Reads the scheme from a list like `keyword=purple,type=11`. Unknown entries are ignored,
+    /// keeping the default color.
+    fn parse(spec: &str) -> Self {
+        let mut scheme = ColorScheme::default();
+
+        for entry in spec.split(',') {
+            let mut parts = entry.splitn(2, '=');
+            let (kind, color) = match (parts.next(), parts.next().and_then(parse_color)) {
+                (Some(kind), Some(color)) => (kind.trim(), color),
+                _ => {
+                    warn!("Invalid signature color {}", entry);
+                    continue;
+                }
+            };
+
+            match kind {
+                "keyword"  => scheme.keyword = color,
+                "type"     => scheme.type_ = color,
+                "lifetime" => scheme.lifetime = color,
+                _          => warn!("Unknown signature token kind {}", kind),
+            }
+        }
+
+        scheme
+    }
+
+    fn from_env() -> Self {
+        match env::var(COLORS_VAR) {
+            Ok(spec) => ColorScheme::parse(&spec),
+            Err(_)   => ColorScheme::default(),
+        }
+    }
+
+    fn paint(&self, kind: TokenKind, text: &str) -> String {
+        match kind {
+            TokenKind::Keyword  => self.keyword.paint(text).to_string(),
+            TokenKind::Type     => self.type_.paint(text).to_string(),
+            TokenKind::Lifetime => self.lifetime.paint(text).to_string(),
+            TokenKind::Plain    => text.to_string(),
+        }
+    }
+}
+
+fn parse_color(name: &str) -> Option<Colour> {
+    let color = match &*name.trim().to_lowercase() {
+        "black"  => Colour::Black,
+        "red"    => Colour::Red,
+        "green"  => Colour::Green,
+        "yellow" => Colour::Yellow,
+        "blue"   => Colour::Blue,
+        "purple" => Colour::Purple,
+        "cyan"   => Colour::Cyan,
+        "white"  => Colour::White,
+        other    => return other.parse::<u8>().ok().map(Colour::Fixed),
+    };
+    Some(color)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn classify_ident(ident: &str) -> TokenKind {
+    if KEYWORDS.contains(&ident) {
+        TokenKind::Keyword
+    } else if PRIMITIVE_TYPES.contains(&ident) ||
+        ident.chars().next().map_or(false, |c| c.is_uppercase()) {
+        TokenKind::Type
+    } else {
+        TokenKind::Plain
+    }
+}
+
+/// Splits a signature into tokens. Everything that isn't an identifier or a lifetime, including
+/// whitespace, is kept as plain text, so joining the tokens gives back the signature.
+fn tokenize(signature: &str) -> Vec<(TokenKind, &str)> {
+    let mut tokens = Vec::new();
+    let mut rest = signature;
+
+    while let Some(c) = rest.chars().next() {
+        let (kind, len) = if is_ident_char(c) {
+            let len = rest.find(|c: char| !is_ident_char(c)).unwrap_or(rest.len());
+            (classify_ident(&rest[..len]), len)
+        } else if c == '\'' {
+            let after = &rest[1..];
+            let ident_len = after.find(|c: char| !is_ident_char(c)).unwrap_or(after.len());
+            if ident_len > 0 && !after[ident_len..].starts_with('\'') {
+                (TokenKind::Lifetime, ident_len + 1)
+            } else {
+                // A character literal, like the default value of a const.
+                let len = after.find('\'').map_or(rest.len(), |end| end + 2);
+                (TokenKind::Plain, len)
+            }
+        } else if c == '\x1b' {
+            // Escape sequences, like the OSC 8 hyperlinks of returned types, are kept as they
+            // are.
+            let len = rest[1..].find("\x1b\\").map_or(rest.len(), |end| end + 3);
+            (TokenKind::Plain, len)
+        } else if c == '"' {
+            // String literals, like ABI names, aren't colored.
+            let len = rest[1..].find('"').map_or(rest.len(), |end| end + 2);
+            (TokenKind::Plain, len)
+        } else {
+            (TokenKind::Plain, c.len_utf8())
+        };
+
+        tokens.push((kind, &rest[..len]));
+        rest = &rest[len..];
+    }
+
+    tokens
+}
+
+/// Colors the keywords, type names and lifetimes of a signature with ANSI escapes. The colors can
+/// be changed with the `OXIDOC_SIGNATURE_COLORS` environment variable.
+pub fn colorize_signature(signature: &str) -> String {
+    let scheme = ColorScheme::from_env();
+    tokenize(signature).into_iter()
+        .map(|(kind, text)| scheme.paint(kind, text))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("pub fn get<'a>(&'a self, c: char) -> Option<&'a str>");
+        let colored: Vec<(TokenKind, &str)> = tokens.iter()
+            .cloned()
+            .filter(|&(kind, _)| kind != TokenKind::Plain)
+            .collect();
+
+        assert_eq!(colored, vec![(TokenKind::Keyword, "pub"),
+                                 (TokenKind::Keyword, "fn"),
+                                 (TokenKind::Lifetime, "'a"),
+                                 (TokenKind::Lifetime, "'a"),
+                                 (TokenKind::Keyword, "self"),
+                                 (TokenKind::Type, "char"),
+                                 (TokenKind::Type, "Option"),
+                                 (TokenKind::Lifetime, "'a"),
+                                 (TokenKind::Type, "str")]);
+
+        let joined: String = tokens.iter().map(|&(_, text)| text).collect();
+        assert_eq!(joined, "pub fn get<'a>(&'a self, c: char) -> Option<&'a str>");
+    }
+
+    #[test]
+    fn test_tokenize_literals() {
+        let tokens = tokenize("const C: char = 'x'; extern \"C\" fn f()");
+        assert!(tokens.contains(&(TokenKind::Plain, "'x'")));
+        assert!(tokens.contains(&(TokenKind::Plain, "\"C\"")));
+        assert!(!tokens.iter().any(|&(kind, _)| kind == TokenKind::Lifetime));
+    }
+
+    #[test]
+    fn test_tokenize_hyperlinks() {
+        let tokens = tokenize("fn new() -> \x1b]8;;file:///crate/fn\x1b\\Self\x1b]8;;\x1b\\");
+        assert!(tokens.contains(&(TokenKind::Plain, "\x1b]8;;file:///crate/fn\x1b\\")));
+        assert!(tokens.contains(&(TokenKind::Type, "Self")));
+        assert!(tokens.contains(&(TokenKind::Plain, "\x1b]8;;\x1b\\")));
+    }
+
+    #[test]
+    fn test_parse_color_scheme() {
+        let scheme = ColorScheme::parse("keyword=blue, type=208,lifetime=nope,bogus=red");
+        assert_eq!(scheme.keyword, Colour::Blue);
+        assert_eq!(scheme.type_, Colour::Fixed(208));
+        assert_eq!(scheme.lifetime, ColorScheme::default().lifetime);
+    }
+}
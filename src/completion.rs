@@ -0,0 +1,136 @@
+//! A flat list of every stored item with its full path, kind and signature, for editors to offer
+//! completions from without reading the documentation of each item.
+
+use std::path::{Path, PathBuf};
+
+use conversion::{DocType, Documentation};
+use document::{CrateInfo, ModPath};
+use paths;
+use store::{self, CrateIndex};
+
+use ::errors::*;
+
+/// An item as offered for completion.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CompletionItem {
+    /// The fully-qualified module path of the item, like `serde::de::Deserialize`.
+    pub path: String,
+
+    /// The short name of the item's type, like `fn` or `trait`.
+    pub kind: String,
+
+    /// The declaration of the item on a single line.
+    pub signature: Option<String>,
+}
+
+/// The format completions are exported in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// A JSON array of objects with `path`, `kind` and `signature` keys.
+    Json,
+
+    /// The same list in the format of the store's own files.
+    Binary,
+}
+
+/// The completion items of every stored crate. It is saved to its own file next to the name search
+/// index when documentation is generated, so exporting it doesn't have to read every document.
+#[derive(Serialize, Deserialize)]
+pub struct CompletionIndex {
+    crates: Vec<CrateCompletions>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CrateCompletions {
+    crate_info: CrateInfo,
+    items: Vec<CompletionItem>,
+}
+
+impl CrateIndex for CompletionIndex {
+    fn new() -> Self {
+        CompletionIndex {
+            crates: Vec::new(),
+        }
+    }
+
+    fn index_path() -> Result<PathBuf> {
+        paths::completion_index_path()
+    }
+
+    fn insert_docs(&mut self, crate_info: &CrateInfo, documents: &[Documentation]) {
+        let mut items: Vec<CompletionItem> = documents.iter()
+            .filter(|doc| is_completable(doc.get_type()))
+            .map(|doc| CompletionItem {
+                path: doc.mod_path.to_string(),
+                kind: doc.get_type().short_name().to_string(),
+                signature: doc.signature()
+                    .map(|signature| signature.split_whitespace().collect::<Vec<&str>>().join(" ")),
+            })
+            .collect();
+        items.sort_by(|a, b| a.path.cmp(&b.path).then_with(|| a.kind.cmp(&b.kind)));
+
+        self.crates.push(CrateCompletions {
+            crate_info: crate_info.clone(),
+            items: items,
+        });
+        self.sort_crates();
+    }
+
+    fn append(&mut self, other: CompletionIndex) {
+        self.crates.extend(other.crates);
+        self.sort_crates();
+    }
+
+    fn remove_crates(&mut self, crates: &[CrateInfo]) {
+        self.crates.retain(|krate| {
+            !crates.iter().any(|crate_info| {
+                krate.crate_info.name == crate_info.name &&
+                    krate.crate_info.version == crate_info.version
+            })
+        });
+    }
+
+    fn remove_docs<F>(&mut self, crate_info: &CrateInfo, is_removed: F)
+        where F: Fn(&ModPath) -> bool
+    {
+        for krate in self.crates.iter_mut() {
+            if krate.crate_info.name == crate_info.name &&
+                krate.crate_info.version == crate_info.version {
+                krate.items.retain(|item| !is_removed(&ModPath::from(item.path.clone())));
+            }
+        }
+    }
+}
+
+impl CompletionIndex {
+    fn sort_crates(&mut self) {
+        self.crates.sort_by(|a, b| a.crate_info.to_string().cmp(&b.crate_info.to_string()));
+    }
+
+    /// Every item in the index, ordered by crate and then by path.
+    pub fn items(&self) -> Vec<&CompletionItem> {
+        self.crates.iter()
+            .flat_map(|krate| krate.items.iter())
+            .collect()
+    }
+
+    /// Writes every item in the index to a file in the given format.
+    pub fn export<P: AsRef<Path>>(&self, path: P, format: ExportFormat) -> Result<()> {
+        let items = self.items();
+        match format {
+            ExportFormat::Json   => store::serialize_object_json(&items, path),
+            ExportFormat::Binary => store::serialize_object(&items, path),
+        }
+    }
+}
+
+/// Whether items of the type can be named in code. Impl blocks and the links to them can't.
+fn is_completable(doc_type: DocType) -> bool {
+    match doc_type {
+        DocType::Impl |
+        DocType::Implementor |
+        DocType::TraitImpl |
+        DocType::Supertrait => false,
+        _ => true,
+    }
+}
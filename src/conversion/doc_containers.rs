@@ -1,10 +1,11 @@
 use store::StoreLocation;
-use std::collections::HashMap;
-use std::fs;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{self, Display};
+use std::mem;
+use std::str::FromStr;
 
-use document::{CrateInfo, ModPath};
-use generation::ast_ty_wrappers::Attributes;
+use document::{CrateInfo, ModPath, SourceSpan};
+use generation::ast_ty_wrappers::{Attributes, Reexport};
 use store;
 
 use conversion::wrappers::*;
@@ -13,6 +14,26 @@ use ::errors::*;
 
 pub use self::DocInnerData::*;
 
+/// The types that documentation is saved as, which are those returned by
+/// `Documentation::get_type`.
+pub const SAVED_DOC_TYPES: &[DocType] = &[
+    DocType::Module,
+    DocType::Struct,
+    DocType::Enum,
+    DocType::Union,
+    DocType::Trait,
+    DocType::Typedef,
+    DocType::Function,
+    DocType::Const,
+    DocType::Static,
+    DocType::Macro,
+    DocType::TraitItemMethod,
+    DocType::TraitItemProvidedMethod,
+    DocType::TraitItemConst,
+    DocType::TraitItemType,
+    DocType::TraitItemMacro,
+];
+
 /// A piece of displayable documentation, containing relevant information based on the thing being
 /// documented.
 #[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
@@ -23,21 +44,53 @@ pub struct Documentation {
     pub mod_path: ModPath,
     pub inner_data: DocInnerData,
     pub visibility: Option<Visibility>,
-    // TODO: source code reference
+    pub source: Option<SourceSpan>,
     pub links: DocRelatedItems,
+
+    /// Distinguishes items of the same type that share a module path, like two `new` methods in
+    /// separate impls of a type. Zero for the first such item.
+    pub disambiguator: usize,
+
+    /// The declaration of the item, like `pub fn new() -> Self`, built once conversion is done
+    /// so rendering and diffing all use the same string. `None` for crates.
+    pub signature: Option<String>,
 }
 
 impl Display for Visibility {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let vis = match *self {
-            Visibility::Public => "pub",
-            _                  => "",
-        };
-        write!(f, "{}", vis)
+        match *self {
+            Visibility::Public           => write!(f, "pub"),
+            Visibility::Crate            => write!(f, "pub(crate)"),
+            Visibility::Super            => write!(f, "pub(super)"),
+            Visibility::InPath(ref path) => write!(f, "pub(in {})", path),
+            _                            => write!(f, ""),
+        }
     }
 }
 
 impl Documentation {
+    /// Whether the item can't be used from other crates, because it isn't `pub`, its visibility
+    /// is restricted or it's in a private module without being re-exported. Crates are public, and
+    /// trait items and the methods of trait impls are as public as their trait.
+    pub fn is_private(&self) -> bool {
+        if self.attrs.unreachable {
+            return true;
+        }
+
+        match self.visibility {
+            Some(Visibility::Public) | None => false,
+            Some(Visibility::Inherited) => match self.inner_data {
+                DocInnerData::ModuleDoc(ref module) => !module.is_crate,
+                DocInnerData::TraitItemDoc(..) => false,
+                DocInnerData::FnDoc(ref func) => {
+                    func.impl_header.as_ref().map_or(true, |header| header.trait_.is_none())
+                }
+                _ => true,
+            },
+            Some(_) => true,
+        }
+    }
+
     pub fn get_type(&self) -> DocType {
         match self.inner_data {
             DocInnerData::FnDoc(..) => {
@@ -52,16 +105,29 @@ impl Documentation {
             DocInnerData::StructDoc(..) => {
                 DocType::Struct
             },
+            DocInnerData::UnionDoc(..) => {
+                DocType::Union
+            },
             DocInnerData::ConstDoc(..) => {
                 DocType::Const
             },
+            DocInnerData::StaticDoc(..) => {
+                DocType::Static
+            },
+            DocInnerData::TypedefDoc(..) => {
+                DocType::Typedef
+            },
             DocInnerData::TraitDoc(..) => {
                 DocType::Trait
             },
+            DocInnerData::MacroDoc(..) => {
+                DocType::Macro
+            },
             DocInnerData::TraitItemDoc(ref item) => {
                     match item.node {
-                        TraitItemKind::Const(..)  => DocType::TraitItemConst,
-                        TraitItemKind::Method(..) => DocType::TraitItemMethod,
+                        TraitItemKind::Const {..} => DocType::TraitItemConst,
+                        TraitItemKind::Method(_, None) => DocType::TraitItemMethod,
+                        TraitItemKind::Method(_, Some(_)) => DocType::TraitItemProvidedMethod,
                         TraitItemKind::Type(..)   => DocType::TraitItemType,
                         TraitItemKind::Macro(..)  => DocType::TraitItemMacro,
                     }
@@ -70,56 +136,114 @@ impl Documentation {
         }
     }
 
-    // TODO: Better way for formatting the wrapped types, as pprust does.
-    fn subitems(&self) -> String {
-        let categories = match self.inner_data {
+    /// The categories of the items declared inside this item, like the consts, types and methods
+    /// of a trait, which can be listed together in the order they were declared.
+    pub fn member_categories(&self) -> Vec<DocType> {
+        match self.inner_data {
+            DocInnerData::TraitDoc(..) => {
+                vec![DocType::TraitItemConst,
+                     DocType::TraitItemType,
+                     DocType::TraitItemMethod,
+                     DocType::TraitItemProvidedMethod,
+                     DocType::TraitItemMacro]
+            },
+            DocInnerData::StructDoc(..) |
+            DocInnerData::UnionDoc(..) |
+            DocInnerData::EnumDoc(..) => {
+                vec![DocType::AssocConst,
+                     DocType::AssocType,
+                     DocType::Function,
+                     DocType::Macro]
+            },
+            _ => vec![],
+        }
+    }
+
+    /// The related items in the given categories, in the order they were declared. Items without
+    /// a known order come last.
+    pub fn subitems_in_source_order(&self, categories: &[DocType]) -> Vec<(DocType, &DocLink)> {
+        let mut items: Vec<(DocType, &DocLink)> = categories.iter()
+            .filter_map(|category| self.links.get(category).map(|links| (category, links)))
+            .flat_map(|(category, links)| links.iter().map(move |link| (category.clone(), link)))
+            .collect();
+
+        items.sort_by_key(|&(_, link)| (link.order.is_none(), link.order));
+        items
+    }
+
+    /// The categories of related items that are listed for this kind of documentation, in
+    /// display order.
+    pub fn subitem_categories(&self) -> Vec<DocType> {
+        match self.inner_data {
             // NOTE: Any better way to just enumerate all DocType values? This
             // violates OCP.
             DocInnerData::ModuleDoc(..) => {
-                vec![DocType::Function,
+                vec![DocType::Reexport,
+                     DocType::Function,
                      DocType::Module,
                      DocType::Enum,
                      DocType::Struct,
+                     DocType::Union,
                      DocType::Trait,
-                     DocType::Const]
+                     DocType::Const,
+                     DocType::Static,
+                     DocType::Typedef,
+                     DocType::Macro]
             },
             DocInnerData::TraitDoc(..) => {
-                vec![DocType::AssocConst,
+                vec![DocType::Supertrait,
+                     DocType::TraitItemConst,
+                     DocType::TraitItemType,
                      DocType::TraitItemMethod,
-                     DocType::AssocType,
-                     DocType::Macro]
+                     DocType::TraitItemProvidedMethod,
+                     DocType::TraitItemMacro,
+                     DocType::Implementor]
             },
-            DocInnerData::StructDoc(..) => {
+            DocInnerData::StructDoc(..) |
+            DocInnerData::UnionDoc(..) => {
                 vec![DocType::StructField,
                      DocType::Function,
                      DocType::AssocConst,
                      DocType::AssocType,
-                     DocType::Macro]
+                     DocType::Macro,
+                     DocType::TraitImpl,
+                     DocType::Impl]
             },
             DocInnerData::EnumDoc(..) => {
                 vec![DocType::Function,
-                     DocType::Variant]
+                     DocType::Variant,
+                     DocType::TraitImpl,
+                     DocType::Impl]
             },
             _  => vec![]
+        }
+    }
+
+    /// The categories of related items shown when the documentation is rendered. The items of a
+    /// type's impl blocks are shown with their block, so the categories they're also linked under
+    /// aren't shown on their own.
+    pub fn listed_categories(&self) -> Vec<DocType> {
+        let has_impls = match self.inner_data {
+            DocInnerData::StructDoc(ref struct_) => !struct_.impls.is_empty(),
+            DocInnerData::UnionDoc(ref union_)   => !union_.impls.is_empty(),
+            DocInnerData::EnumDoc(ref enum_)     => !enum_.impls.is_empty(),
+            _                                    => false,
         };
+        if !has_impls {
+            return self.subitem_categories();
+        }
 
-        categories.iter().map(|c| self.subitems_in_category(c))
-            .filter(|c| c.is_some())
-            .map(|c| c.unwrap())
-            .collect::<Vec<String>>().join("\n\n")
+        let members = self.member_categories();
+        self.subitem_categories().into_iter()
+            .filter(|category| !members.contains(category))
+            .collect()
     }
 
-    fn subitems_in_category(&self, type_: &DocType) -> Option<String> {
-        if let Some(items) = self.links.get(type_) {
-            if items.len() > 0 {
-                let category_str = type_.to_string();
-                let items_str = items.iter().cloned().map(|i| i.name ).collect::<Vec<String>>().join("\n");
-                Some(format!("==== {}\n{}", category_str, items_str))
-            } else {
-                None
-            }
-        } else {
-            None
+    /// The related items in the given category, or `None` if there are none.
+    pub fn subitems_in_category(&self, type_: &DocType) -> Option<&Vec<DocLink>> {
+        match self.links.get(type_) {
+            Some(items) if !items.is_empty() => Some(items),
+            _                                => None,
         }
     }
 
@@ -129,36 +253,608 @@ impl Documentation {
             crate_info: self.crate_info.clone(),
             mod_path: self.mod_path.clone(),
             doc_type: self.get_type(),
+            disambiguator: self.disambiguator,
+            aliases: self.attrs.aliases.clone(),
+            unstable: self.attrs.stability.as_ref().map_or(false, |s| s.is_unstable()),
+            features: self.attrs.features(),
+            deprecated: self.attrs.deprecation.is_some(),
+        }
+    }
+
+    /// A link pointing to this documentation.
+    pub fn to_doc_link(&self) -> DocLink {
+        DocLink {
+            name: self.name.clone(),
+            path: self.mod_path.clone(),
+            disambiguator: self.disambiguator,
+            doc_type: Some(self.get_type()),
+            order: None,
+            krate: None,
+            alias: None,
+            glob: false,
         }
     }
 
     pub fn save(&self) -> Result<()> {
         let location = self.to_store_location();
-        let path = location.to_filepath();
+        let path = location.to_filepath()?;
+
+        store::create_dir_all(path.parent().unwrap())?;
+
+        store::serialize_object(self, path)
+    }
+
+    /// Saves the documentation as JSON, for consumption by tools that can't read bincode and for
+    /// migrating it to later versions of the format. The file is written next to the `.odoc`
+    /// file, with a `.odoc.json` extension.
+    pub fn save_json(&self) -> Result<()> {
+        let location = self.to_store_location();
+        let path = location.to_json_filepath()?;
 
-        {
-            let parent_path = path.parent().unwrap();
+        store::create_dir_all(path.parent().unwrap())?;
 
-            fs::create_dir_all(parent_path)
-                .chain_err(|| format!("Failed to create directory {}", parent_path.display()))?;
+        store::serialize_object_json(self, path)
+    }
+
+    /// Loads the saved documentation of the item at `mod_path`, which has to be of the given
+    /// type. Items of the same type that share a path, like methods of the same name in different
+    /// impls, are told apart by their disambiguator, which is 0 for the first one. The file is
+    /// named the way `save` names it, so no index is needed.
+    pub fn load(crate_info: &CrateInfo,
+                mod_path: &ModPath,
+                doc_type: DocType,
+                disambiguator: usize) -> Result<Documentation> {
+        let location = Documentation::location(crate_info, mod_path, doc_type, disambiguator)?;
+
+        store::deserialize_object(location.to_filepath()?)
+            .chain_err(|| ErrorKind::DocLoadFailed(mod_path.to_string()))
+    }
+
+    /// Like `load`, for when the type of the item isn't known. Every type is tried in turn, so if
+    /// several items share the path, like a struct and a function with the same name, the first
+    /// one found in `SAVED_DOC_TYPES` with the disambiguator is loaded.
+    pub fn load_any(crate_info: &CrateInfo,
+                    mod_path: &ModPath,
+                    disambiguator: usize) -> Result<Documentation> {
+        for doc_type in SAVED_DOC_TYPES.iter() {
+            let location = Documentation::location(crate_info, mod_path, doc_type.clone(),
+                                                   disambiguator)?;
+            if location.to_filepath()?.is_file() {
+                return Documentation::load(crate_info, mod_path, doc_type.clone(), disambiguator);
+            }
         }
 
-        store::serialize_object(self, path)
+        bail!(ErrorKind::DocLoadFailed(mod_path.to_string()))
+    }
+
+    fn location(crate_info: &CrateInfo,
+                mod_path: &ModPath,
+                doc_type: DocType,
+                disambiguator: usize) -> Result<StoreLocation> {
+        let name = match mod_path.name() {
+            Some(segment) => segment.identifier,
+            None => bail!(ErrorKind::DocLoadFailed(mod_path.to_string())),
+        };
+        let mut location = StoreLocation::new(name, crate_info.clone(), mod_path.clone(), doc_type);
+        location.disambiguator = disambiguator;
+        Ok(location)
+    }
+
+    /// Sorts the related items in each category by name, ignoring case. Fields, variants and
+    /// supertraits keep their declaration order, since it is meaningful.
+    pub fn sort_links(&mut self) {
+        for (category, links) in self.links.iter_mut() {
+            match *category {
+                DocType::StructField | DocType::Variant | DocType::Supertrait => continue,
+                _ => (),
+            }
+
+            links.sort_by(|a, b| {
+                a.shown_name().to_lowercase().cmp(&b.shown_name().to_lowercase())
+                    .then_with(|| a.shown_name().cmp(&b.shown_name()))
+                    .then_with(|| a.disambiguator.cmp(&b.disambiguator))
+            });
+        }
+    }
+
+    /// Finds the related-item links whose target documentation doesn't exist on disk.
+    pub fn dangling_links(&self) -> Vec<(DocType, &DocLink)> {
+        let mut dangling = Vec::new();
+        for (category, links) in self.links.iter() {
+            // Other crates may not have been generated yet, so links to them aren't checked.
+            for link in links.iter().filter(|l| l.has_target() && !l.is_cross_crate()) {
+                let location = link.to_store_location(&self.crate_info, category);
+                if !location.to_filepath().map_or(false, |path| path.exists()) {
+                    dangling.push((category.clone(), link));
+                }
+            }
+        }
+        dangling
+    }
+
+    /// Loads the documentation of the items directly inside a module, in display order. Links to
+    /// anything that isn't a child of the module, like the module itself or items mentioned in
+    /// its doc comments, are skipped, so walking the module tree can't loop. Other kinds of
+    /// documentation have no children.
+    pub fn children(&self) -> Result<Vec<Documentation>> {
+        if self.get_type() != DocType::Module {
+            return Ok(Vec::new());
+        }
+
+        let mut seen = HashSet::new();
+        let mut children = Vec::new();
+        for category in self.subitem_categories() {
+            let links = match self.subitems_in_category(&category) {
+                Some(links) => links,
+                None        => continue,
+            };
+
+            for link in links.iter().filter(|l| l.path.parent().as_ref() == Some(&self.mod_path)) {
+                let path = link.to_store_location(&self.crate_info, &category).to_filepath()?;
+                if seen.insert(path.clone()) {
+                    let child: Documentation = store::deserialize_object(&path)
+                        .chain_err(|| ErrorKind::DocLoadFailed(link.path.to_string()))?;
+                    children.push(child);
+                }
+            }
+        }
+        Ok(children)
+    }
+}
+
+/// Numbers the items of the same type that share a module path, so that each is saved to its own
+/// file, and points the related-item links to them at the matching numbers. Items are numbered in
+/// the order they appear, which keeps regeneration deterministic. The items of a type's impl
+/// blocks are numbered across the blocks, in the order the blocks appear.
+pub fn disambiguate_duplicates(documents: &mut Vec<Documentation>) {
+    let mut seen: HashMap<(DocType, ModPath), usize> = HashMap::new();
+    for doc in documents.iter_mut() {
+        let count = seen.entry((doc.get_type(), doc.mod_path.clone())).or_insert(0);
+        doc.disambiguator = *count;
+        *count += 1;
+    }
+
+    for doc in documents.iter_mut() {
+        for (category, links) in doc.links.iter_mut() {
+            let mut seen_links: HashMap<ModPath, usize> = HashMap::new();
+            disambiguate_links(category, links, &seen, &mut seen_links);
+        }
+
+        let blocks = match doc.inner_data {
+            DocInnerData::StructDoc(ref mut struct_) => &mut struct_.impls,
+            DocInnerData::UnionDoc(ref mut union_)   => &mut union_.impls,
+            DocInnerData::EnumDoc(ref mut enum_)     => &mut enum_.impls,
+            _                                        => continue,
+        };
+        let mut seen_links: HashMap<DocType, HashMap<ModPath, usize>> = HashMap::new();
+        for block in blocks.iter_mut() {
+            for (category, links) in block.items.iter_mut() {
+                let counts = seen_links.entry(category.clone()).or_insert(HashMap::new());
+                disambiguate_links(category, links, &seen, counts);
+            }
+        }
+    }
+}
+
+/// Points the links of a category at the numbers of their items, counting the links to each path
+/// in `seen_links`.
+fn disambiguate_links(category: &DocType,
+                      links: &mut [DocLink],
+                      seen: &HashMap<(DocType, ModPath), usize>,
+                      seen_links: &mut HashMap<ModPath, usize>) {
+    for link in links.iter_mut().filter(|l| l.has_target()) {
+        let count = seen_links.entry(link.path.clone()).or_insert(0);
+        let key = (link.target_type(category), link.path.clone());
+        if seen.get(&key).map_or(false, |n| *count < *n) {
+            link.disambiguator = *count;
+        }
+        *count += 1;
+    }
+}
+
+/// Adds each type that implements a trait of the crate to the trait's implementors, using the
+/// trait implementations found on the types.
+pub fn link_implementors(documents: &mut Vec<Documentation>) {
+    let mut implementors: HashMap<ModPath, Vec<DocLink>> = HashMap::new();
+    for doc in documents.iter() {
+        if let Some(traits) = doc.links.get(&DocType::TraitImpl) {
+            for trait_link in traits.iter().filter(|l| l.has_target()) {
+                implementors.entry(trait_link.path.clone())
+                    .or_insert(Vec::new())
+                    .push(doc.to_doc_link());
+            }
+        }
+    }
+
+    for doc in documents.iter_mut().filter(|d| d.get_type() == DocType::Trait) {
+        if let Some(links) = implementors.remove(&doc.mod_path) {
+            doc.links.entry(DocType::Implementor).or_insert(Vec::new()).extend(links);
+        }
+    }
+}
+
+/// Records for each trait impl block which of the trait's provided methods it overrides, and which
+/// it uses the defaults of. Only the provided methods of traits in the same crate are known.
+pub fn link_provided_methods(documents: &mut Vec<Documentation>) {
+    let mut provided: HashMap<ModPath, Vec<DocLink>> = HashMap::new();
+    for doc in documents.iter().filter(|d| d.get_type() == DocType::Trait) {
+        if let Some(links) = doc.links.get(&DocType::TraitItemProvidedMethod) {
+            provided.insert(doc.mod_path.clone(), links.clone());
+        }
+    }
+
+    for doc in documents.iter_mut() {
+        let blocks = match doc.inner_data {
+            DocInnerData::StructDoc(ref mut struct_) => &mut struct_.impls,
+            DocInnerData::UnionDoc(ref mut union_)   => &mut union_.impls,
+            DocInnerData::EnumDoc(ref mut enum_)     => &mut enum_.impls,
+            _                                        => continue,
+        };
+
+        for block in blocks.iter_mut() {
+            let methods = match block.trait_link.as_ref().and_then(|t| provided.get(&t.path)) {
+                Some(methods) => methods,
+                None          => continue,
+            };
+
+            let provided_methods = methods.iter()
+                .map(|link| {
+                    let defined = block.items.get(&DocType::Function)
+                        .map_or(false, |fns| fns.iter().any(|f| f.name == link.name));
+                    ProvidedMethod {
+                        link: link.clone(),
+                        overridden: defined,
+                    }
+                })
+                .collect();
+            block.provided_methods = provided_methods;
+        }
+    }
+}
+
+/// Builds the signature of every document from its converted data. Runs after the related items
+/// are linked, which some signatures list, like the supertraits of a trait.
+pub fn fill_signatures(documents: &mut Vec<Documentation>) {
+    for doc in documents.iter_mut() {
+        doc.signature = doc.build_signature();
+    }
+}
+
+/// Records which functions of each impl block take `self`, so methods can be listed apart from
+/// associated functions. Functions are found by path and number, so this runs after
+/// `disambiguate_duplicates`, which tells apart functions with the same path in different impls.
+pub fn link_methods(documents: &mut Vec<Documentation>) {
+    let methods: HashSet<(ModPath, usize)> = documents.iter()
+        .filter(|doc| match doc.inner_data {
+            DocInnerData::FnDoc(ref func) => func.has_receiver,
+            _                             => false,
+        })
+        .map(|doc| (doc.mod_path.clone(), doc.disambiguator))
+        .collect();
+
+    for doc in documents.iter_mut() {
+        let blocks = match doc.inner_data {
+            DocInnerData::StructDoc(ref mut struct_) => &mut struct_.impls,
+            DocInnerData::UnionDoc(ref mut union_)   => &mut union_.impls,
+            DocInnerData::EnumDoc(ref mut enum_)     => &mut enum_.impls,
+            _                                        => continue,
+        };
+
+        for block in blocks.iter_mut() {
+            block.methods = block.items.get(&DocType::Function).into_iter()
+                .flat_map(|links| links.iter())
+                .filter(|link| methods.contains(&(link.path.clone(), link.disambiguator)))
+                .map(|link| link.name.clone())
+                .collect();
+        }
+    }
+}
+
+/// Lists the items re-exported by each module's `pub use`s among its related items, pointing to
+/// the original items. Glob re-exports list every item directly inside the module they name.
+/// Re-exports of items outside the crate are skipped.
+pub fn link_reexports(documents: &mut Vec<Documentation>, reexports: &[Reexport]) {
+    let mut links: HashMap<ModPath, Vec<DocLink>> = HashMap::new();
+    for reexport in reexports.iter() {
+        if let Some(ref krate) = reexport.krate {
+            // The items of other crates aren't known until their documentation is loaded, so
+            // glob re-exports of them link to the module, and its items are listed by the store.
+            if let Some(name) = reexport.target.0.last() {
+                let mut link = DocLink::new(name.identifier.clone(), reexport.target.clone());
+                link.krate = Some(krate.clone());
+                if reexport.glob {
+                    link.doc_type = Some(DocType::Module);
+                    link.glob = true;
+                } else {
+                    link.alias = reexport.alias.clone();
+                }
+                let entry = links.entry(reexport.module.clone()).or_insert(Vec::new());
+                if !entry.contains(&link) {
+                    entry.push(link);
+                }
+            }
+            continue;
+        }
+
+        let targets = documents.iter().filter(|doc| {
+            if reexport.glob {
+                doc.mod_path.parent().as_ref() == Some(&reexport.target)
+            } else {
+                doc.mod_path == reexport.target
+            }
+        });
+
+        let entry = links.entry(reexport.module.clone()).or_insert(Vec::new());
+        for target in targets {
+            // Items already in the module don't need listing again, unless they're re-exported
+            // under another name.
+            let in_module = target.mod_path.parent().as_ref() == Some(&reexport.module);
+            if in_module && reexport.alias.is_none() {
+                continue;
+            }
+            let mut link = target.to_doc_link();
+            link.alias = reexport.alias.clone();
+            if !entry.contains(&link) {
+                entry.push(link);
+            }
+        }
+    }
+
+    for doc in documents.iter_mut().filter(|d| d.get_type() == DocType::Module) {
+        if let Some(reexported) = links.remove(&doc.mod_path) {
+            if !reexported.is_empty() {
+                doc.links.insert(DocType::Reexport, reexported);
+            }
+        }
+    }
+}
+
+/// Moves the macros exported with `#[macro_export]` to the crate root, where other crates use
+/// them from, whichever module they're defined in. The links to them move from their modules to
+/// the crate's.
+pub fn move_exported_macros(documents: &mut Vec<Documentation>) {
+    let mut moved: HashMap<ModPath, ModPath> = HashMap::new();
+    for doc in documents.iter_mut() {
+        if doc.get_type() != DocType::Macro || doc.is_private() || doc.mod_path.0.len() <= 2 {
+            continue;
+        }
+        let mut path = ModPath(doc.mod_path.0[..1].to_vec());
+        path.push_string(doc.name.clone());
+        moved.insert(mem::replace(&mut doc.mod_path, path.clone()), path);
+    }
+    if moved.is_empty() {
+        return;
+    }
+
+    let mut root_links = Vec::new();
+    for doc in documents.iter_mut().filter(|d| d.get_type() == DocType::Module) {
+        let is_empty = match doc.links.get_mut(&DocType::Macro) {
+            Some(links) => {
+                let (exported, kept): (Vec<DocLink>, Vec<DocLink>) = links.drain(..)
+                    .partition(|link| moved.contains_key(&link.path));
+                *links = kept;
+                root_links.extend(exported);
+                links.is_empty()
+            }
+            None => false,
+        };
+        if is_empty {
+            doc.links.remove(&DocType::Macro);
+        }
+    }
+
+    for link in root_links.iter_mut() {
+        link.path = moved[&link.path].clone();
+    }
+    let root = documents.iter_mut().find(|doc| match doc.inner_data {
+        DocInnerData::ModuleDoc(ref module) => module.is_crate,
+        _                                   => false,
+    });
+    if let Some(root) = root {
+        root.links.entry(DocType::Macro).or_insert(Vec::new()).extend(root_links);
     }
 }
 
+/// Whether `path` is `ancestor` or inside it.
+fn is_within(path: &ModPath, ancestor: &ModPath) -> bool {
+    path.0.len() >= ancestor.0.len() && path.0[..ancestor.0.len()] == ancestor.0[..]
+}
+
+/// Marks the items inside private modules that aren't re-exported with `pub use` as unreachable.
+/// Re-exports are only followed from modules other crates can reach.
+pub fn mark_unreachable(documents: &mut Vec<Documentation>, reexports: &[Reexport]) {
+    let private_modules: Vec<ModPath> = documents.iter()
+        .filter(|doc| doc.get_type() == DocType::Module && doc.is_private())
+        .map(|doc| doc.mod_path.clone())
+        .collect();
+    let in_private_module = |path: &ModPath| {
+        private_modules.iter().any(|module| is_within(path, module))
+    };
+
+    let mut reachable: Vec<ModPath> = Vec::new();
+    let mut followed = vec![false; reexports.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (i, reexport) in reexports.iter().enumerate() {
+            let hidden = in_private_module(&reexport.module) &&
+                !reachable.iter().any(|root| is_within(&reexport.module, root));
+            if followed[i] || reexport.krate.is_some() || hidden {
+                continue;
+            }
+            followed[i] = true;
+            changed = true;
+
+            if reexport.glob {
+                reachable.extend(documents.iter()
+                    .filter(|doc| doc.mod_path.parent().as_ref() == Some(&reexport.target))
+                    .map(|doc| doc.mod_path.clone()));
+            } else {
+                reachable.push(reexport.target.clone());
+            }
+        }
+    }
+
+    for doc in documents.iter_mut() {
+        if in_private_module(&doc.mod_path) &&
+            !reachable.iter().any(|root| is_within(&doc.mod_path, root)) {
+            doc.attrs.unreachable = true;
+        }
+    }
+}
+
+/// Removes the documentation of private items, along with the links to it. Used when only the
+/// public API is documented, once the items re-exported from private modules were found.
+pub fn remove_private(documents: Vec<Documentation>) -> Vec<Documentation> {
+    let (mut kept, removed): (Vec<Documentation>, Vec<Documentation>) = documents.into_iter()
+        .partition(|doc| !doc.is_private());
+    if removed.is_empty() {
+        return kept;
+    }
+
+    let removed: HashSet<ModPath> = removed.into_iter().map(|doc| doc.mod_path).collect();
+    for doc in kept.iter_mut() {
+        let links = mem::replace(&mut doc.links, DocRelatedItems::new());
+        doc.links = links.into_iter()
+            .map(|(category, mut links)| {
+                links.retain(|link| link.krate.is_some() || !removed.contains(&link.path));
+                (category, links)
+            })
+            .filter(|&(_, ref links)| !links.is_empty())
+            .collect();
+    }
+    kept
+}
+
 /// A list of documentation locations related to the current item, like methods implemented on
-/// structs.
-pub type DocRelatedItems = HashMap<DocType, Vec<DocLink>>;
+/// structs. Ordered by category so serialized documentation is reproducible.
+pub type DocRelatedItems = BTreeMap<DocType, Vec<DocLink>>;
 
 #[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct DocLink
 {
     pub name: String,
     pub path: ModPath,
+    pub disambiguator: usize,
+
+    /// The type of the linked documentation, for categories like implementors where it can't be
+    /// told from the category.
+    pub doc_type: Option<DocType>,
+
+    /// The position of the item among the items declared with it, like the items of a trait or
+    /// the impls of a type, if that order is known.
+    pub order: Option<usize>,
+
+    /// The name of the crate the linked item is in, if it's another crate than the one linking to
+    /// it, like for re-exports of a dependency's types. Such links point to the latest version of
+    /// that crate in the store.
+    pub krate: Option<String>,
+
+    /// The name the item is known by where it's linked from, if it's re-exported under another
+    /// name with `pub use ... as`.
+    pub alias: Option<String>,
+
+    /// Whether the link stands for every item of the linked module, for glob re-exports of the
+    /// modules of other crates, whose items aren't known until that crate is generated.
+    pub glob: bool,
 }
 
-#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
+impl DocLink {
+    pub fn new(name: String, path: ModPath) -> Self {
+        DocLink {
+            name: name,
+            path: path,
+            disambiguator: 0,
+            doc_type: None,
+            order: None,
+            krate: None,
+            alias: None,
+            glob: false,
+        }
+    }
+
+    pub fn with_order(mut self, order: usize) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// The name the item is shown with where it's linked from, like `io::*` for a glob re-export
+    /// of a module.
+    pub fn shown_name(&self) -> String {
+        if self.glob {
+            format!("{}::*", self.name)
+        } else {
+            self.alias.as_ref().unwrap_or(&self.name).clone()
+        }
+    }
+
+    /// The type of the linked documentation, given the category it is listed under.
+    pub fn target_type(&self, category: &DocType) -> DocType {
+        match self.doc_type {
+            Some(ref doc_type) => doc_type.clone(),
+            None               => category.link_target_type(),
+        }
+    }
+
+    /// Whether this link points to a separate piece of documentation. Some related items, like
+    /// struct fields, are only listed by name.
+    pub fn has_target(&self) -> bool {
+        !self.path.0.is_empty()
+    }
+
+    pub fn is_cross_crate(&self) -> bool {
+        self.krate.is_some()
+    }
+
+    /// The location of the linked documentation, given the crate of the documentation linking to
+    /// it and the category it is listed under. Links within the crate always resolve. Links to
+    /// other crates only resolve if the linked item has been generated, since its type may not be
+    /// known until then; they should be shown as plain text otherwise.
+    pub fn resolve(&self, crate_info: &CrateInfo, category: &DocType) -> Option<StoreLocation> {
+        let krate = match self.krate {
+            Some(ref krate) => krate,
+            None            => return Some(self.to_store_location(crate_info, category)),
+        };
+        let target_crate = match store::latest_crate_version(krate) {
+            Some(target_crate) => target_crate,
+            None               => return None,
+        };
+
+        let exists = |location: &StoreLocation| {
+            location.to_filepath().map_or(false, |path| path.is_file())
+        };
+        match self.doc_type {
+            Some(_) => {
+                let location = self.to_store_location(&target_crate, category);
+                if exists(&location) { Some(location) } else { None }
+            }
+            None => SAVED_DOC_TYPES.iter()
+                .map(|doc_type| {
+                    let mut link = self.clone();
+                    link.doc_type = Some(doc_type.clone());
+                    link.to_store_location(&target_crate, category)
+                })
+                .find(|location| exists(location)),
+        }
+    }
+
+    /// The location of the linked documentation, given the category it is listed under.
+    pub fn to_store_location(&self, crate_info: &CrateInfo, doc_type: &DocType) -> StoreLocation {
+        StoreLocation {
+            name: self.name.clone(),
+            crate_info: crate_info.clone(),
+            mod_path: self.path.clone(),
+            doc_type: self.target_type(doc_type),
+            disambiguator: self.disambiguator,
+            aliases: Vec::new(),
+            unstable: false,
+            features: Vec::new(),
+            deprecated: false,
+        }
+    }
+}
+
+#[derive(Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug, Serialize, Deserialize)]
 pub enum DocType {
     Function,
     // Method,
@@ -167,10 +863,26 @@ pub enum DocType {
     Variant,
     Struct,
     StructField,
+    Union,
     Const,
+    Static,
+    Typedef,
     Trait,
+    /// A trait that another trait requires. Links in this category point to traits.
+    Supertrait,
+    /// A trait implemented by a type. Links in this category point to traits.
+    TraitImpl,
+    /// A type implementing a trait. Links in this category carry the type they point to.
+    Implementor,
+    /// The impl blocks of a type. These are rendered from the type's documentation and have no
+    /// links of their own.
+    Impl,
+    /// An item made public in a module with `pub use`. Links in this category carry the type of
+    /// the original item.
+    Reexport,
     AssocConst,
     TraitItemMethod,
+    TraitItemProvidedMethod,
     TraitItemConst,
     TraitItemType,
     TraitItemMacro,
@@ -179,6 +891,17 @@ pub enum DocType {
 }
 
 impl DocType {
+    /// The type of the documentation that links listed in this category point to.
+    pub fn link_target_type(&self) -> DocType {
+        match *self {
+            DocType::Supertrait |
+            DocType::TraitImpl  => DocType::Trait,
+            // Associated constants are documented like any other constant.
+            DocType::AssocConst => DocType::Const,
+            ref other           => other.clone(),
+        }
+    }
+
     pub fn get_file_prefix(&self) -> &str {
         match *self {
             DocType::Function => "",
@@ -187,15 +910,113 @@ impl DocType {
             DocType::Variant => "vdesc-",
             DocType::Struct => "sdesc-",
             DocType::StructField => "sfdesc-",
+            DocType::Union => "udesc-",
             DocType::Const => "cdesc-",
+            DocType::Static => "static-",
+            DocType::Typedef => "tydesc-",
             DocType::Trait => "tdesc-",
+            DocType::Supertrait => "tdesc-",
+            DocType::TraitImpl => "tdesc-",
+            DocType::Implementor => "sdesc-",
+            DocType::Reexport => "",
+            DocType::Impl => "idesc-",
             DocType::AssocConst  => &"acdesc-",
             DocType::TraitItemConst => &"tcdesc-",
             DocType::TraitItemMethod => &"tmcdesc-",
+            DocType::TraitItemProvidedMethod => &"tpmdesc-",
             DocType::TraitItemType => &"ttcdesc-",
             DocType::TraitItemMacro => &"tmdesc-",
             DocType::AssocType   => &"atdesc-",
-            DocType::Macro  => &"macdesc-",
+            DocType::Macro  => &"macro-",
+        }
+    }
+
+    /// Like `get_file_prefix`, but spelled out, for the readable store layout. Types sharing a
+    /// prefix there share one here too, so both layouts tell the same files apart.
+    pub fn get_readable_file_prefix(&self) -> &str {
+        match *self {
+            DocType::Function => "fn-",
+            DocType::Module => "module-",
+            DocType::Enum => "enum-",
+            DocType::Variant => "variant-",
+            DocType::Struct => "struct-",
+            DocType::StructField => "field-",
+            DocType::Union => "union-",
+            DocType::Const => "const-",
+            DocType::Static => "static-",
+            DocType::Typedef => "type-",
+            DocType::Trait => "trait-",
+            DocType::Supertrait => "trait-",
+            DocType::TraitImpl => "trait-",
+            DocType::Implementor => "struct-",
+            DocType::Reexport => "fn-",
+            DocType::Impl => "impl-",
+            DocType::AssocConst  => "assoc-const-",
+            DocType::TraitItemConst => "trait-const-",
+            DocType::TraitItemMethod => "trait-method-",
+            DocType::TraitItemProvidedMethod => "provided-method-",
+            DocType::TraitItemType => "trait-type-",
+            DocType::TraitItemMacro => "trait-macro-",
+            DocType::AssocType   => "assoc-type-",
+            DocType::Macro  => "macro-",
+        }
+    }
+}
+
+impl FromStr for DocType {
+    type Err = Error;
+
+    /// Parses the short names used on the command line, like `fn` or `trait`.
+    fn from_str(s: &str) -> Result<DocType> {
+        let doc_type = match &*s.to_lowercase() {
+            "fn" | "function"     => DocType::Function,
+            "mod" | "module"      => DocType::Module,
+            "enum"                => DocType::Enum,
+            "variant"             => DocType::Variant,
+            "struct"              => DocType::Struct,
+            "field"               => DocType::StructField,
+            "union"               => DocType::Union,
+            "const"               => DocType::Const,
+            "static"              => DocType::Static,
+            "type" | "typedef"    => DocType::Typedef,
+            "trait"               => DocType::Trait,
+            "method"              => DocType::TraitItemMethod,
+            "macro"               => DocType::Macro,
+            _                     => bail!(ErrorKind::UnknownDocType(s.to_string())),
+        };
+        Ok(doc_type)
+    }
+}
+
+impl DocType {
+    /// The short name of the type, like `fn` or `trait`. These are the names accepted by
+    /// `from_str`, with the types of members named after the items they are.
+    pub fn short_name(&self) -> &'static str {
+        match *self {
+            DocType::Function                => "fn",
+            DocType::Module                  => "mod",
+            DocType::Enum                    => "enum",
+            DocType::Variant                 => "variant",
+            DocType::Struct                  => "struct",
+            DocType::StructField             => "field",
+            DocType::Union                   => "union",
+            DocType::Const |
+            DocType::AssocConst |
+            DocType::TraitItemConst          => "const",
+            DocType::Static                  => "static",
+            DocType::Typedef |
+            DocType::AssocType |
+            DocType::TraitItemType           => "type",
+            DocType::Trait |
+            DocType::Supertrait |
+            DocType::TraitImpl               => "trait",
+            DocType::Implementor |
+            DocType::Impl                    => "impl",
+            DocType::Reexport                => "use",
+            DocType::TraitItemMethod |
+            DocType::TraitItemProvidedMethod => "method",
+            DocType::Macro |
+            DocType::TraitItemMacro          => "macro",
         }
     }
 }
@@ -209,11 +1030,20 @@ impl Display for DocType {
             DocType::Variant => "Variants",
             DocType::Struct => "Structs",
             DocType::StructField => "Struct Fields",
+            DocType::Union => "Unions",
             DocType::Const => "Constants",
+            DocType::Static => "Statics",
+            DocType::Typedef => "Type Definitions",
             DocType::Trait => "Traits",
+            DocType::Supertrait => "Supertraits",
+            DocType::TraitImpl => "Trait Implementations",
+            DocType::Implementor => "Implementors",
+            DocType::Reexport => "Re-exports",
+            DocType::Impl => "Implementations",
             DocType::AssocConst  => &"Associated Constants",
             DocType::TraitItemConst => &"Trait Constants",
-            DocType::TraitItemMethod => &"Trait Methods",
+            DocType::TraitItemMethod => &"Required Methods",
+            DocType::TraitItemProvidedMethod => &"Provided Methods",
             DocType::TraitItemType => &"Trait Types",
             DocType::TraitItemMacro => &"Trait Macros",
             DocType::AssocType   => &"Associated Types",
@@ -231,9 +1061,10 @@ pub enum DocInnerData {
     EnumDoc(Enum),
     StructDoc(Struct),
     ConstDoc(Constant),
-    //StaticDoc,
-    //Union,
-    //TypedefDoc,
+    StaticDoc(Static),
+    UnionDoc(Union),
+    TypedefDoc(Typedef),
     TraitDoc(Trait),
     TraitItemDoc(TraitItem),
+    MacroDoc(MacroRules),
 }
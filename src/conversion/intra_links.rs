@@ -0,0 +1,309 @@
+//! Resolution of intra-doc links, like [`HashMap`] or [foo](crate::foo), and of the types named by
+//! return types against the other items documented in the same crate.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use conversion::{DocInnerData, DocLink, DocType, Documentation};
+use document::{ModPath, unraw, unraw_path};
+
+lazy_static! {
+    /// Matches `[text]` and `[text](target)`.
+    static ref LINK_REGEX: Regex = Regex::new(r"\[([^\[\]]+)\](\(([^()\s]*)\))?").unwrap();
+
+    /// Matches the names and paths in a type, like `Builder` and `io::Result` in
+    /// `io::Result<Builder>`.
+    static ref TYPE_NAME_REGEX: Regex =
+        Regex::new(r"[A-Za-z_][A-Za-z0-9_]*(::[A-Za-z_][A-Za-z0-9_]*)*").unwrap();
+}
+
+/// The documented items of a crate, used for looking up link targets.
+struct ItemIndex {
+    /// Full module path string, without the `r#` of raw identifiers => link target
+    by_path: HashMap<String, (DocType, DocLink)>,
+
+    /// Item name => link targets with that name
+    by_name: HashMap<String, Vec<(DocType, DocLink)>>,
+}
+
+impl ItemIndex {
+    fn new(documents: &[Documentation]) -> Self {
+        let mut by_path = HashMap::new();
+        let mut by_name = HashMap::new();
+
+        for doc in documents.iter() {
+            let target = (doc.get_type(), doc.to_doc_link());
+            by_path.insert(unraw_path(&doc.mod_path.to_string()), target.clone());
+            by_name.entry(unraw(&doc.name).to_string()).or_insert(Vec::new()).push(target);
+        }
+
+        ItemIndex {
+            by_path: by_path,
+            by_name: by_name,
+        }
+    }
+
+    /// Resolves a link target found in the documentation of `doc`. Paths are tried relative to
+    /// the containing module, then as absolute paths. Bare names are resolved to items in the
+    /// containing module first, then to an item with that name anywhere in the crate, if it is
+    /// the only one. Raw identifiers match with or without their `r#`.
+    fn resolve(&self, doc: &Documentation, target: &str) -> Option<&(DocType, DocLink)> {
+        let module = containing_module(doc);
+        let target = unraw_path(target);
+
+        if target.contains("::") {
+            let path = expand_path(&module, &target);
+            return self.by_path.get(&unraw_path(&path.to_string()))
+                .or_else(|| self.by_path.get(&target));
+        }
+
+        let local = child_path(&module, &target);
+        if let Some(found) = self.by_path.get(&unraw_path(&local.to_string())) {
+            return Some(found);
+        }
+
+        match self.by_name.get(&target) {
+            Some(targets) if targets.len() == 1 => targets.first(),
+            _                                   => None,
+        }
+    }
+}
+
+fn child_path(module: &ModPath, name: &str) -> ModPath {
+    let mut path = module.clone();
+    path.push_string(name.to_string());
+    path
+}
+
+/// The module an item's documentation is written in. For modules this is the module itself.
+fn containing_module(doc: &Documentation) -> ModPath {
+    match doc.get_type() {
+        DocType::Module => doc.mod_path.clone(),
+        _ => doc.mod_path.parent().unwrap_or(doc.mod_path.clone()),
+    }
+}
+
+/// Expands `crate::`, `self::` and `super::` prefixes, and otherwise treats the path as relative
+/// to the given module.
+fn expand_path(module: &ModPath, target: &str) -> ModPath {
+    let mut segments = target.split("::");
+    let mut path = match segments.next() {
+        Some("crate") => ModPath(module.0.iter().take(1).cloned().collect()),
+        Some("self") => module.clone(),
+        Some("super") => module.parent().unwrap_or(module.clone()),
+        Some(first) => child_path(module, first),
+        None => module.clone(),
+    };
+
+    for segment in segments {
+        if segment == "super" {
+            path.pop();
+        } else {
+            path.push_string(segment.to_string());
+        }
+    }
+    path
+}
+
+/// Strips the parts of a link that aren't part of the item's path, like backticks, rustdoc's
+/// disambiguators (`struct@Foo`, `Foo!`) and the parentheses after function names.
+fn clean_link_target(target: &str) -> Option<String> {
+    let mut target = target.trim().trim_matches('`');
+
+    if let Some(at) = target.find('@') {
+        target = &target[at + 1..];
+    }
+
+    let target = target.trim_right_matches("()").trim_right_matches('!');
+
+    let is_path = !target.is_empty() &&
+        target.split("::").map(unraw).all(|seg| !seg.is_empty() &&
+                                          seg.chars().all(|c| c.is_alphanumeric() || c == '_'));
+
+    if is_path {
+        Some(target.to_string())
+    } else {
+        None
+    }
+}
+
+/// An intra-doc link found in a piece of Markdown.
+struct LinkMatch {
+    /// The byte range of the whole link, like `[text](target)`.
+    start: usize,
+    end: usize,
+
+    /// The text shown for the link.
+    text: String,
+
+    /// The target of the link, cleaned by `clean_link_target`.
+    target: String,
+}
+
+/// Finds all intra-doc links in a piece of Markdown.
+fn find_links(markdown: &str) -> Vec<LinkMatch> {
+    let mut links = Vec::new();
+
+    for cap in LINK_REGEX.captures_iter(markdown) {
+        let whole = cap.get(0).unwrap();
+        let text = cap.get(1).unwrap().as_str();
+
+        let target = match cap.get(3) {
+            Some(dest) => {
+                // External links are left for the Markdown renderer. The `#` of raw identifiers
+                // doesn't start a fragment.
+                if dest.as_str().contains('/') || unraw_path(dest.as_str()).contains('#') {
+                    continue;
+                }
+                dest.as_str()
+            }
+            None => {
+                // Skip reference-style links and their definitions.
+                match markdown[whole.end()..].chars().next() {
+                    Some('[') | Some(':') => continue,
+                    _ => text,
+                }
+            }
+        };
+
+        if let Some(cleaned) = clean_link_target(target) {
+            links.push(LinkMatch {
+                start: whole.start(),
+                end: whole.end(),
+                text: text.to_string(),
+                target: cleaned,
+            });
+        }
+    }
+
+    links
+}
+
+/// Finds the targets of all intra-doc links in a piece of Markdown.
+fn find_link_targets(markdown: &str) -> Vec<String> {
+    find_links(markdown).into_iter().map(|link| link.target).collect()
+}
+
+/// Rewrites the intra-doc links in a piece of Markdown into ordinary links to the URLs given for
+/// their targets, as cleaned of disambiguators and backticks. Links without a URL are left as
+/// they are.
+pub fn rewrite_intra_doc_links(markdown: &str, urls: &HashMap<String, String>) -> String {
+    let mut rewritten = String::with_capacity(markdown.len());
+    let mut last = 0;
+
+    for link in find_links(markdown) {
+        if let Some(url) = urls.get(&link.target) {
+            rewritten.push_str(&markdown[last..link.start]);
+            rewritten.push_str(&format!("[{}]({})", link.text, url));
+            last = link.end;
+        }
+    }
+    rewritten.push_str(&markdown[last..]);
+    rewritten
+}
+
+/// The intra-doc links of each document that resolve to other documents of the crate, as the
+/// target written in the doc comment, cleaned like for `rewrite_intra_doc_links`, with the type
+/// and link of the item it resolves to. The links are in the order of `documents`.
+pub fn intra_doc_link_targets(documents: &[Documentation])
+                              -> Vec<Vec<(String, DocType, DocLink)>> {
+    let index = ItemIndex::new(documents);
+
+    documents.iter()
+        .map(|doc| {
+            let markdown = doc.attrs.doc_body();
+            find_link_targets(&markdown).into_iter()
+                .filter_map(|target| {
+                    let found = index.resolve(doc, &target).cloned();
+                    if found.is_none() {
+                        debug!("Unresolved intra-doc link in {}: {}", doc.mod_path, target);
+                    }
+                    found.map(|(doc_type, link)| (target, doc_type, link))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Resolves the intra-doc links in every piece of documentation against the other documents of
+/// the crate, and adds the resolved items to each document's related items under the type of the
+/// linked item. The doc comments themselves aren't modified, so unresolved links are kept as
+/// plain text.
+pub fn resolve_intra_doc_links(documents: &mut Vec<Documentation>) {
+    let resolved = intra_doc_link_targets(documents);
+
+    for (doc, links) in documents.iter_mut().zip(resolved.into_iter()) {
+        for (_, doc_type, link) in links {
+            if link.path == doc.mod_path {
+                continue;
+            }
+
+            let entry = doc.links.entry(doc_type).or_insert(Vec::new());
+            if !entry.contains(&link) {
+                entry.push(link);
+            }
+        }
+    }
+}
+
+/// Whether items of this type can be named in a return type.
+fn is_type(doc_type: &DocType) -> bool {
+    match *doc_type {
+        DocType::Struct |
+        DocType::Enum |
+        DocType::Union |
+        DocType::Trait |
+        DocType::Typedef => true,
+        _                => false,
+    }
+}
+
+/// The documented types named by a function's return type. `Self` is the type a method is
+/// defined on.
+fn return_type_links(index: &ItemIndex, doc: &Documentation) -> Vec<DocLink> {
+    let output = match doc.inner_data {
+        DocInnerData::FnDoc(ref func) => match func.decl.output {
+            Some(ref output) => output,
+            None             => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+
+    let mut links: Vec<DocLink> = Vec::new();
+    for name in TYPE_NAME_REGEX.find_iter(output) {
+        // Lifetimes, like the `a` of `&'a Self`.
+        if output[..name.start()].ends_with('\'') {
+            continue;
+        }
+
+        let found = if name.as_str() == "Self" {
+            doc.mod_path.parent()
+                .and_then(|parent| index.by_path.get(&unraw_path(&parent.to_string())))
+        } else {
+            index.resolve(doc, name.as_str())
+        };
+
+        if let Some(&(ref doc_type, ref link)) = found {
+            if is_type(doc_type) && !links.contains(link) {
+                links.push(link.clone());
+            }
+        }
+    }
+    links
+}
+
+/// Links the return type of every function and method to the documented types it names, so
+/// builder methods returning `Self` link back to their type.
+pub fn link_return_types(documents: &mut Vec<Documentation>) {
+    let resolved: Vec<Vec<DocLink>> = {
+        let index = ItemIndex::new(documents);
+        documents.iter().map(|doc| return_type_links(&index, doc)).collect()
+    };
+
+    for (doc, links) in documents.iter_mut().zip(resolved.into_iter()) {
+        if let DocInnerData::FnDoc(ref mut func) = doc.inner_data {
+            func.return_links = links;
+        }
+    }
+}
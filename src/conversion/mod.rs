@@ -3,19 +3,26 @@
 
 mod wrappers;
 mod doc_containers;
+mod intra_links;
+mod rustdoc_json;
 
 pub use conversion::doc_containers::*;
+pub use conversion::intra_links::{intra_doc_link_targets, link_return_types,
+                                  resolve_intra_doc_links, rewrite_intra_doc_links};
+pub use conversion::rustdoc_json::{from_rustdoc_json, from_rustdoc_json_with_skipped};
 
 use std::collections::HashMap;
 use std::path::PathBuf;
 
 use syntax::abi;
 use syntax::ast;
+use syntax::attr;
 use syntax::print::pprust;
 use syntax::ptr::P;
 
 use generation::ast_ty_wrappers::{self, Impl, Ty, Attributes};
 use generation::visitor::OxidocVisitor;
+use generation::ty_printer;
 use document::{CrateInfo, ModPath};
 
 pub use conversion::wrappers::*;
@@ -80,12 +87,31 @@ impl Convert<Constness> for ast::Constness {
     }
 }
 
+impl Convert<Mutability> for ast::Mutability {
+    fn convert(&self, _context: &Context) -> Mutability {
+        match *self {
+            ast::Mutability::Mutable   => Mutability::Mutable,
+            ast::Mutability::Immutable => Mutability::Immutable,
+        }
+    }
+}
+
 impl Convert<Visibility> for ast::Visibility {
     fn convert(&self, _context: &Context) -> Visibility {
         match *self {
             ast::Visibility::Public    => Visibility::Public,
+            ast::Visibility::Crate(..) => Visibility::Crate,
+            ast::Visibility::Restricted { ref path, .. } => {
+                let path = ModPath::from((**path).clone());
+                match path.to_string().as_str() {
+                    "crate" => Visibility::Crate,
+                    "super" => Visibility::Super,
+                    // pub(self) is the same as no visibility at all.
+                    "self"  => Visibility::Private,
+                    _       => Visibility::InPath(path),
+                }
+            },
             ast::Visibility::Inherited => Visibility::Inherited,
-            _                          => Visibility::Private,
         }
     }
 }
@@ -115,7 +141,7 @@ impl Convert<Abi> for abi::Abi {
     }
 }
 
-impl Convert<Vec<Documentation>> for OxidocVisitor {
+impl<'a> Convert<Vec<Documentation>> for OxidocVisitor<'a> {
     fn convert(&self, context: &Context) -> Vec<Documentation> {
         debug!("Converting store");
         let documents = self.crate_module.convert(context);
@@ -137,16 +163,16 @@ impl Convert<Vec<Documentation>> for ast_ty_wrappers::Module {
         let mut docs: Vec<Documentation> = vec![];
 
         docs.extend(self.consts.iter().map(|x| x.convert(context)));
-        docs.extend(self.traits.iter().map(|x| x.convert(context)));
+        docs.extend(self.traits.iter().flat_map(|x| x.convert(context)));
         docs.extend(self.fns.iter().map(|x| x.convert(context)));
         docs.extend(self.mods.iter().flat_map(|x| x.convert(context)));
         docs.extend(self.structs.iter().map(|x| x.convert(context)));
-        // unions
+        docs.extend(self.unions.iter().map(|x| x.convert(context)));
         docs.extend(self.enums.iter().map(|x| x.convert(context)));
         // foreigns
-        // typedefs
-        // statics
-        // macros
+        docs.extend(self.typedefs.iter().map(|x| x.convert(context)));
+        docs.extend(self.statics.iter().map(|x| x.convert(context)));
+        docs.extend(self.macros.iter().map(|x| x.convert(context)));
         // def_traits
 
         let name = match self.ident {
@@ -154,16 +180,29 @@ impl Convert<Vec<Documentation>> for ast_ty_wrappers::Module {
             None     => context.crate_info.name.clone(),
         };
 
+        // Only the items directly inside this module are listed, not the ones
+        // found in nested modules or impls.
+        let mut links = DocRelatedItems::new();
+        for doc in &docs {
+            if doc.mod_path.parent().as_ref() == Some(&self.path) {
+                links.entry(doc.get_type()).or_insert(Vec::new())
+                    .push(DocLink::new(doc.name.clone(), doc.mod_path.clone()));
+            }
+        }
+
         let mod_doc = Documentation {
             name: name.clone(),
             attrs: self.attrs.convert(context),
             mod_path: self.path.clone(),
             crate_info: context.crate_info.clone(),
+            source: self.source.clone(),
+            disambiguator: 0,
+            signature: None,
             visibility: Some(self.vis.convert(context)),
             inner_data: ModuleDoc(Module {
                 is_crate: self.is_crate,
             }),
-            links: HashMap::new(),
+            links: links,
         };
 
         docs.push(mod_doc);
@@ -179,33 +218,110 @@ impl Convert<Documentation> for ast_ty_wrappers::Constant {
             attrs: self.attrs.convert(context),
             mod_path: self.path.clone(),
             crate_info: context.crate_info.clone(),
+            source: self.source.clone(),
+            disambiguator: 0,
+            signature: None,
             visibility: Some(self.vis.convert(context)),
             inner_data: ConstDoc(Constant {
                 ty: self.type_.clone(),
                 expr: self.expr.convert(context),
             }),
-            links: HashMap::new(),
+            links: DocRelatedItems::new(),
+        }
+    }
+}
+
+impl Convert<Documentation> for ast_ty_wrappers::Static {
+    fn convert(&self, context: &Context) -> Documentation {
+        Documentation {
+            name: self.ident.convert(context),
+            attrs: self.attrs.convert(context),
+            mod_path: self.path.clone(),
+            crate_info: context.crate_info.clone(),
+            source: self.source.clone(),
+            disambiguator: 0,
+            signature: None,
+            visibility: Some(self.vis.convert(context)),
+            inner_data: StaticDoc(Static {
+                ty: self.type_.clone(),
+                mutability: self.mutability.convert(context),
+                expr: self.expr.as_ref().map(|expr| expr.convert(context)),
+                abi: self.abi.map(|abi| abi.convert(context)),
+            }),
+            links: DocRelatedItems::new(),
+        }
+    }
+}
+
+impl Convert<Documentation> for ast_ty_wrappers::Typedef {
+    fn convert(&self, context: &Context) -> Documentation {
+        Documentation {
+            name: self.ident.convert(context),
+            attrs: self.attrs.convert(context),
+            mod_path: self.path.clone(),
+            crate_info: context.crate_info.clone(),
+            source: self.source.clone(),
+            disambiguator: 0,
+            signature: None,
+            visibility: Some(self.vis.convert(context)),
+            inner_data: TypedefDoc(Typedef {
+                generics: self.generics.convert(context),
+                ty: self.type_.clone(),
+            }),
+            links: DocRelatedItems::new(),
+        }
+    }
+}
+
+impl Convert<Documentation> for ast_ty_wrappers::MacroRules {
+    fn convert(&self, context: &Context) -> Documentation {
+        let visibility = if self.exported {
+            Visibility::Public
+        } else {
+            Visibility::Inherited
+        };
+
+        Documentation {
+            name: self.ident.convert(context),
+            attrs: self.attrs.convert(context),
+            mod_path: self.path.clone(),
+            crate_info: context.crate_info.clone(),
+            source: self.source.clone(),
+            disambiguator: 0,
+            signature: None,
+            visibility: Some(visibility),
+            inner_data: MacroDoc(MacroRules {
+                matchers: self.matchers.clone(),
+            }),
+            links: DocRelatedItems::new(),
         }
     }
 }
 
 impl Convert<Documentation> for ast_ty_wrappers::Function {
     fn convert(&self, context: &Context) -> Documentation {
+        let decl: FnDecl = self.decl.convert(context);
         Documentation {
             name: self.ident.convert(context),
             attrs: self.attrs.convert(context),
             mod_path: self.path.clone(),
             crate_info: context.crate_info.clone(),
+            source: self.source.clone(),
+            disambiguator: 0,
+            signature: None,
             visibility: Some(self.vis.convert(context)),
             inner_data: FnDoc(Function {
-                header: self.decl.convert(context),
-                generics: Generics { } ,
+                has_receiver: decl.has_receiver(),
+                decl: decl,
+                generics: self.generics.convert(context),
                 unsafety: self.unsafety.convert(context),
                 constness: self.constness.convert(context),
                 abi: self.abi.convert(context),
                 kind: self.kind.clone(),
+                impl_header: self.impl_header.as_ref().map(|h| h.convert(context)),
+                return_links: Vec::new(),
             }),
-            links: HashMap::new(),
+            links: DocRelatedItems::new(),
         }
     }
 }
@@ -213,27 +329,46 @@ impl Convert<Documentation> for ast_ty_wrappers::Function {
 impl Convert<MethodSig> for ast::MethodSig {
     fn convert(&self, context: &Context) -> MethodSig {
         MethodSig {
+            generics: self.generics.convert(context),
             unsafety: self.unsafety.convert(context),
             constness: self.constness.node.convert(context),
             abi: self.abi.convert(context),
-            header: self.decl.convert(context),
+            decl: self.decl.convert(context),
         }
     }
 }
 
-impl Convert<Documentation> for ast_ty_wrappers::Trait {
-    fn convert(&self, context: &Context) -> Documentation {
-        Documentation {
+impl Convert<Vec<Documentation>> for ast_ty_wrappers::Trait {
+    fn convert(&self, context: &Context) -> Vec<Documentation> {
+        let mut docs: Vec<Documentation> = self.items.iter().map(|x| x.convert(context)).collect();
+
+        let mut links: DocRelatedItems = self.items.convert(context);
+        if !self.supertraits.is_empty() {
+            let supertraits = self.supertraits.iter()
+                .map(|&(ref name, ref path)| DocLink::new(name.clone(), path.clone()))
+                .collect();
+            links.insert(DocType::Supertrait, supertraits);
+        }
+
+        let trait_doc = Documentation {
             name: self.ident.convert(context),
             attrs: self.attrs.convert(context),
             mod_path: self.path.clone(),
             crate_info: context.crate_info.clone(),
+            source: self.source.clone(),
+            disambiguator: 0,
+            signature: None,
             visibility: Some(self.vis.convert(context)),
             inner_data: TraitDoc(Trait {
                 unsafety: self.unsafety.convert(context),
+                generics: self.generics.convert(context),
+                bounds: self.bounds.convert(context),
             }),
-            links: self.items.convert(context),
-        }
+            links: links,
+        };
+
+        docs.push(trait_doc);
+        docs
     }
 }
 
@@ -244,45 +379,36 @@ impl Convert<Documentation> for ast_ty_wrappers::TraitItem {
             attrs: self.attrs.convert(context),
             mod_path: self.path.clone(),
             crate_info: context.crate_info.clone(),
+            source: self.source.clone(),
+            disambiguator: 0,
+            signature: None,
             visibility: Some(Visibility::Inherited),
             inner_data: TraitItemDoc(TraitItem {
                 node: self.node.convert(context),
             }),
-            links: HashMap::new(),
+            links: DocRelatedItems::new(),
         }
     }
 }
 
 impl Convert<DocRelatedItems> for [ast_ty_wrappers::TraitItem] {
     fn convert(&self, context: &Context) -> DocRelatedItems {
-        let mut consts = Vec::new();
-        let mut methods = Vec::new();
-        let mut types = Vec::new();
-        let mut macros = Vec::new();
+        let mut links = DocRelatedItems::new();
+
+        for (order, item) in self.iter().enumerate() {
+            let category = match item.node {
+                ast::TraitItemKind::Const(..) => DocType::TraitItemConst,
+                ast::TraitItemKind::Method(_, Some(_)) => DocType::TraitItemProvidedMethod,
+                ast::TraitItemKind::Method(_, None) => DocType::TraitItemMethod,
+                ast::TraitItemKind::Type(..) => DocType::TraitItemType,
+                ast::TraitItemKind::Macro(..) => DocType::TraitItemMacro,
+            };
 
-        for item in self {
-            match item.node {
-                ast::TraitItemKind::Const(..) => consts.push(item.clone()),
-                ast::TraitItemKind::Method(..) => methods.push(item.clone()),
-                ast::TraitItemKind::Type(..) => types.push(item.clone()),
-                ast::TraitItemKind::Macro(..) => macros.push(item.clone()),
-            }
+            links.entry(category).or_insert(Vec::new())
+                .push(DocLink::new(item.ident.convert(context), item.path.clone())
+                      .with_order(order));
         }
 
-        let conv = |items: Vec<ast_ty_wrappers::TraitItem>| {
-            items.iter().cloned().map(|item|
-                                      DocLink {
-                                          name: item.ident.convert(context),
-                                          path: item.path.clone(),
-                                      }
-            ).collect()
-        };
-
-        let mut links = HashMap::new();
-        links.insert(DocType::AssocConst, conv(consts));
-        links.insert(DocType::TraitItemMethod, conv(methods));
-        links.insert(DocType::AssocType, conv(types));
-        links.insert(DocType::Macro, conv(macros));
         links
     }
 }
@@ -291,13 +417,18 @@ impl Convert<TraitItemKind> for ast::TraitItemKind {
     fn convert(&self, context: &Context) -> TraitItemKind {
         match *self {
             ast::TraitItemKind::Const(ref ty, ref expr) => {
-                TraitItemKind::Const(ty.convert(context), expr.convert(context))
+                TraitItemKind::Const {
+                    ty: ty.convert(context),
+                    default: expr.convert(context),
+                }
             },
-            ast::TraitItemKind::Method(ref sig, ref _block) => {
-                TraitItemKind::Method(sig.convert(context))
+            ast::TraitItemKind::Method(ref sig, ref block) => {
+                let body = block.as_ref().map(|b| pprust::block_to_string(b));
+                TraitItemKind::Method(sig.convert(context), body)
             },
-            ast::TraitItemKind::Type(ref _bounds, ref ty) => {
-                TraitItemKind::Type(ty.convert(context))
+            ast::TraitItemKind::Type(ref bounds, ref ty) => {
+                let bounds = bounds.iter().map(|b| b.convert(context)).collect();
+                TraitItemKind::Type(bounds, ty.convert(context))
             },
             ast::TraitItemKind::Macro(ref mac) => {
                 TraitItemKind::Macro(mac.convert(context))
@@ -309,60 +440,159 @@ impl Convert<TraitItemKind> for ast::TraitItemKind {
 impl Convert<Documentation> for ast_ty_wrappers::Struct {
     fn convert(&self, context: &Context) -> Documentation {
         let mut links: DocRelatedItems = self.fields.convert(context);
-        if let Some(impls) = context.impls_for_ty.get(&self.path) {
-            for impl_ in impls {
-                let impl_links = impl_.convert(context);
-                debug!("Impl found for {}!", self.path);
-                links.extend(impl_links);
-            }
-        }
+        links.extend(impl_links(&self.path, context));
 
         Documentation {
             name: self.ident.convert(context),
             attrs: self.attrs.convert(context),
             mod_path: self.path.clone(),
             crate_info: context.crate_info.clone(),
+            source: self.source.clone(),
+            disambiguator: 0,
+            signature: None,
             visibility: Some(self.vis.convert(context)),
             inner_data: StructDoc(Struct {
+                kind: self.kind,
+                generics: self.generics.convert(context),
                 fields: self.fields.convert(context),
+                impls: impl_blocks(&self.path, context),
+                non_exhaustive: attr::contains_name(&self.attrs, "non_exhaustive"),
             }),
             links: links,
         }
     }
 }
 
+impl Convert<Documentation> for ast_ty_wrappers::Union {
+    fn convert(&self, context: &Context) -> Documentation {
+        let mut links: DocRelatedItems = self.fields.convert(context);
+        links.extend(impl_links(&self.path, context));
+
+        Documentation {
+            name: self.ident.convert(context),
+            attrs: self.attrs.convert(context),
+            mod_path: self.path.clone(),
+            crate_info: context.crate_info.clone(),
+            source: self.source.clone(),
+            disambiguator: 0,
+            signature: None,
+            visibility: Some(self.vis.convert(context)),
+            inner_data: UnionDoc(Union {
+                generics: self.generics.convert(context),
+                fields: self.fields.convert(context),
+                impls: impl_blocks(&self.path, context),
+            }),
+            links: links,
+        }
+    }
+}
+
+/// The impl blocks of the type at the given path, in the order they were found.
+fn impl_blocks(path: &ModPath, context: &Context) -> Vec<ImplBlock> {
+    match context.impls_for_ty.get(path) {
+        Some(impls) => {
+            impls.iter().map(|impl_| {
+                let block: ImplBlock = impl_.convert(context);
+                block
+            }).collect()
+        },
+        None        => Vec::new(),
+    }
+}
+
+impl Convert<ImplHeader> for ast_ty_wrappers::ImplHeader {
+    fn convert(&self, context: &Context) -> ImplHeader {
+        ImplHeader {
+            unsafety: self.unsafety.convert(context),
+            generics: self.generics.convert(context),
+            trait_: self.trait_.as_ref().map(|t| pprust::path_to_string(&t.path)),
+            for_: ast_ty_wrappers::Ty::from(self.for_.clone()),
+        }
+    }
+}
+
+impl Convert<ImplBlock> for ast_ty_wrappers::Impl {
+    fn convert(&self, context: &Context) -> ImplBlock {
+        let mut items: DocRelatedItems = self.convert(context);
+        items.remove(&DocType::TraitImpl);
+
+        ImplBlock {
+            header: self.header().convert(context),
+            items: items,
+            trait_link: self.trait_link.as_ref()
+                .map(|&(ref name, ref path)| DocLink::new(name.clone(), path.clone())),
+            provided_methods: Vec::new(),
+            methods: Vec::new(),
+        }
+    }
+}
+
+/// The related items from all the impls of the type at the given path, merged by category.
+/// Items are ordered as if the impls were one after another.
+fn impl_links(path: &ModPath, context: &Context) -> DocRelatedItems {
+    let mut links = DocRelatedItems::new();
+    if let Some(impls) = context.impls_for_ty.get(path) {
+        let mut offset = 0;
+        for impl_ in impls {
+            debug!("Impl found for {}!", path);
+            let impl_links: DocRelatedItems = impl_.convert(context);
+            for (category, items) in impl_links {
+                let items = items.into_iter().map(|mut link| {
+                    link.order = link.order.map(|order| order + offset);
+                    link
+                });
+                links.entry(category).or_insert(Vec::new()).extend(items);
+            }
+            offset += impl_.items.len();
+        }
+    }
+    links
+}
+
 impl Convert<DocRelatedItems> for ast_ty_wrappers::Impl {
     fn convert(&self, context: &Context) -> DocRelatedItems {
         let mut consts = Vec::new();
         let mut methods = Vec::new();
         let mut types = Vec::new();
         let mut macros = Vec::new();
-        for item in &self.items {
+        for (order, item) in self.items.iter().enumerate() {
             match item.node {
-                ast::ImplItemKind::Const(..)  => consts.push(item.clone()),
-                ast::ImplItemKind::Method(..) => methods.push(item.clone()),
-                ast::ImplItemKind::Type(..)   => types.push(item.clone()),
-                ast::ImplItemKind::Macro(..)  => macros.push(item.clone()),
+                ast::ImplItemKind::Const(..)  => consts.push((order, item)),
+                ast::ImplItemKind::Method(..) => methods.push((order, item)),
+                ast::ImplItemKind::Type(..)   => types.push((order, item)),
+                ast::ImplItemKind::Macro(..)  => macros.push((order, item)),
             }
         }
 
-        let conv = |items: Vec<ast::ImplItem>| {
-            items.iter().cloned().map(|item| {
+        let conv = |items: Vec<(usize, &ast::ImplItem)>| {
+            items.into_iter().map(|(order, item)| {
                 let name = item.ident.convert(context);
-                DocLink {
-                    name: name.clone(),
-                    path: ModPath::join(&self.path.clone(),
-                                        &ModPath::from(name))
-                }
+                DocLink::new(name.clone(),
+                             ModPath::join(&self.path.clone(),
+                                           &ModPath::from(name)))
+                    .with_order(order)
             }
             ).collect()
         };
 
-        let mut links = HashMap::new();
+        // Associated types and macros don't get documentation of their own yet, so they are
+        // only listed by name.
+        let names = |items: Vec<(usize, &ast::ImplItem)>| {
+            items.into_iter()
+                .map(|(order, item)| {
+                    DocLink::new(item.ident.convert(context), ModPath::new()).with_order(order)
+                })
+                .collect()
+        };
+
+        let mut links = DocRelatedItems::new();
         links.insert(DocType::AssocConst, conv(consts));
         links.insert(DocType::Function, conv(methods));
-        links.insert(DocType::AssocType, conv(types));
-        links.insert(DocType::Macro, conv(macros));
+        links.insert(DocType::AssocType, names(types));
+        links.insert(DocType::Macro, names(macros));
+        if let Some((ref name, ref path)) = self.trait_link {
+            links.insert(DocType::TraitImpl, vec![DocLink::new(name.clone(), path.clone())]);
+        }
         links
     }
 }
@@ -371,19 +601,12 @@ impl Convert<DocRelatedItems> for [ast::StructField] {
     fn convert(&self, context: &Context) -> DocRelatedItems {
         let mut fields = Vec::new();
 
-        for item in self {
-            if item.ident.is_none() {
-                continue;
-            }
-            let field = item.convert(context);
-            let field_link = DocLink {
-                // TODO: Display nicely, with signature
-                name: field.ident.unwrap(),
-                path: field.path.clone(),
-            };
+        for (index, item) in self.iter().enumerate() {
+            let field: StructField = item.convert(context);
+            let field_link = DocLink::new(field.name(index), field.path.clone());
             fields.push(field_link);
         }
-        let mut links = HashMap::new();
+        let mut links = DocRelatedItems::new();
         links.insert(DocType::StructField, fields);
         links
     }
@@ -403,16 +626,25 @@ impl Convert<StructField> for ast::StructField {
 
 impl Convert<Documentation> for ast_ty_wrappers::Enum {
     fn convert(&self, context: &Context) -> Documentation {
+        let mut links: DocRelatedItems = self.variants.convert(context);
+        links.extend(impl_links(&self.path, context));
+
         Documentation {
             name: self.ident.convert(context),
             attrs: self.attrs.convert(context),
             mod_path: self.path.clone(),
             crate_info: context.crate_info.clone(),
-            visibility: Some(Visibility::Inherited),
+            source: self.source.clone(),
+            disambiguator: 0,
+            signature: None,
+            visibility: Some(self.vis.convert(context)),
             inner_data: EnumDoc(Enum {
+                generics: self.generics.convert(context),
                 variants: self.variants.convert(context),
+                impls: impl_blocks(&self.path, context),
+                non_exhaustive: attr::contains_name(&self.attrs, "non_exhaustive"),
             }),
-            links: self.variants.convert(context),
+            links: links,
         }
     }
 }
@@ -424,26 +656,125 @@ impl Convert<Ty> for ast::Ty {
 }
 
 impl Convert<DocRelatedItems> for [ast::Variant] {
-    fn convert(&self, _context: &Context) -> DocRelatedItems {
+    fn convert(&self, context: &Context) -> DocRelatedItems {
         let mut variants = Vec::new();
 
         for item in self {
             // TODO: These are just strings for now, instead of separate docs.
-            let variant_link = DocLink {
-                name: pprust::to_string(|s| s.print_variant(item)),
-                path: ModPath::new(),
-            };
+            let variant: Variant = item.convert(context);
+            let variant_link = DocLink::new(variant.to_string(), ModPath::new());
             variants.push(variant_link);
         }
-        let mut links = HashMap::new();
+        let mut links = DocRelatedItems::new();
         links.insert(DocType::Variant, variants);
         links
     }
 }
 
-impl Convert<String> for ast::FnDecl {
+impl Convert<Variant> for ast::Variant {
+    fn convert(&self, context: &Context) -> Variant {
+        let kind = match self.node.data {
+            ast::VariantData::Unit(..)              => VariantKind::Unit,
+            ast::VariantData::Tuple(ref fields, _)  => VariantKind::Tuple(fields.convert(context)),
+            ast::VariantData::Struct(ref fields, _) => VariantKind::Struct(fields.convert(context)),
+        };
+
+        Variant {
+            name: self.node.name.convert(context),
+            kind: kind,
+            discriminant: self.node.disr_expr.as_ref().map(|expr| expr.convert(context)),
+            attrs: self.node.attrs.convert(context),
+        }
+    }
+}
+
+impl Convert<FnDecl> for ast::FnDecl {
+    fn convert(&self, context: &Context) -> FnDecl {
+        let output = match self.output {
+            ast::FunctionRetTy::Default(..) => None,
+            ast::FunctionRetTy::Ty(ref ty)  => Some(ast_ty_wrappers::ty_to_string(ty)),
+        };
+
+        FnDecl {
+            inputs: self.inputs.convert(context),
+            output: output,
+            variadic: self.variadic,
+        }
+    }
+}
+
+impl Convert<FnArg> for ast::Arg {
+    fn convert(&self, context: &Context) -> FnArg {
+        match self.to_self() {
+            Some(explicit_self) => FnArg::SelfArg(explicit_self.node.convert(context)),
+            None => FnArg::Arg {
+                pattern: pprust::pat_to_string(&self.pat),
+                ty: ast_ty_wrappers::ty_to_string(&self.ty),
+            },
+        }
+    }
+}
+
+impl Convert<SelfKind> for ast::SelfKind {
+    fn convert(&self, context: &Context) -> SelfKind {
+        match *self {
+            ast::SelfKind::Value(mutability) => SelfKind::Value(mutability.convert(context)),
+            ast::SelfKind::Region(ref lifetime, mutability) => {
+                let lifetime = lifetime.as_ref()
+                    .map(|l| pprust::to_string(|s| s.print_lifetime(l)));
+                SelfKind::Region(lifetime, mutability.convert(context))
+            },
+            ast::SelfKind::Explicit(ref ty, mutability) => {
+                SelfKind::Explicit(ast_ty_wrappers::ty_to_string(ty), mutability.convert(context))
+            },
+        }
+    }
+}
+
+impl Convert<Generics> for ast::Generics {
+    fn convert(&self, context: &Context) -> Generics {
+        let mut params: Vec<GenericParam> = self.lifetimes.iter()
+            .map(|l| l.convert(context))
+            .collect();
+        params.extend(self.ty_params.iter().map(|t| t.convert(context)));
+
+        let where_predicates = self.where_clause.predicates.iter()
+            .map(|predicate| {
+                ty_printer::where_predicate_to_string(predicate, ast_ty_wrappers::max_type_depth())
+            })
+            .collect();
+
+        Generics {
+            params: params,
+            where_predicates: where_predicates,
+        }
+    }
+}
+
+impl Convert<GenericParam> for ast::LifetimeDef {
+    fn convert(&self, _context: &Context) -> GenericParam {
+        GenericParam::Lifetime {
+            name: pprust::to_string(|s| s.print_lifetime(&self.lifetime)),
+            bounds: self.bounds.iter()
+                .map(|b| pprust::to_string(|s| s.print_lifetime(b)))
+                .collect(),
+        }
+    }
+}
+
+impl Convert<GenericParam> for ast::TyParam {
+    fn convert(&self, context: &Context) -> GenericParam {
+        GenericParam::Type {
+            name: self.ident.convert(context),
+            bounds: self.bounds.iter().map(|b| b.convert(context)).collect(),
+            default: self.default.as_ref().map(|ty| ast_ty_wrappers::ty_to_string(ty)),
+        }
+    }
+}
+
+impl Convert<String> for ast::TyParamBound {
     fn convert(&self, _context: &Context) -> String {
-        pprust::to_string(|s| s.print_fn_args_and_ret(self))
+        ty_printer::bound_to_string(self, ast_ty_wrappers::max_type_depth())
     }
 }
 
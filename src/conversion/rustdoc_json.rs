@@ -0,0 +1,1297 @@
+//! Import of the JSON written by `rustdoc --output-format=json`, as an alternative to parsing a
+//! crate's source. rustdoc has already expanded macros and resolved re-exports and trait impls, so
+//! the imported documentation can be more complete than oxidoc's own.
+//!
+//! Both the older layout of the format, with separate `kind` and `inner` fields, and the newer
+//! one, where `inner` is an object keyed by the kind, are read. Kinds of items that have no
+//! equivalent here, like primitives and proc macros, are skipped with a warning.
+
+use std::cell::Cell;
+use std::cmp;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde_json::{self, Map, Value};
+
+use conversion::*;
+use document::{CrateInfo, ModPath, SourceSpan};
+use generation::ast_ty_wrappers::{Attributes, CODEGEN_ATTRIBUTES, Deprecation, FnKind, Reexport,
+                                  StructKind, Ty, max_type_depth};
+use generation::SkippedItem;
+use ::errors::*;
+
+lazy_static! {
+    /// Matches `#[must_use]` and `#[must_use = "message"]`.
+    static ref MUST_USE_REGEX: Regex =
+        Regex::new(r#"^#\[must_use(\s*=\s*"((?:[^"\\]|\\.)*)")?\]$"#).unwrap();
+
+    /// Matches the aliases in `#[doc(alias = "name")]`.
+    static ref ALIAS_REGEX: Regex = Regex::new(r#"alias\s*=\s*"([^"]*)""#).unwrap();
+}
+
+/// The kind of an item and the data for it, from either layout of the format.
+fn item_kind(item: &Value) -> Option<(&str, Option<&Value>)> {
+    if let Some(kind) = item.get("kind").and_then(|k| k.as_str()) {
+        return Some((kind, item.get("inner")));
+    }
+    item.get("inner").and_then(tagged)
+}
+
+/// Splits an externally tagged value, like `{"borrowed_ref": {...}}` or `"infer"`, into its tag
+/// and contents. Values in the older `{"kind": ..., "inner": ...}` layout are also accepted.
+fn tagged(value: &Value) -> Option<(&str, Option<&Value>)> {
+    match *value {
+        Value::String(ref tag) => Some((tag, None)),
+        Value::Object(ref map) => {
+            if let (Some(kind), Some(inner)) = (map.get("kind").and_then(|k| k.as_str()),
+                                                map.get("inner")) {
+                return Some((kind, Some(inner)));
+            }
+            if map.len() == 1 {
+                map.iter().next().map(|(tag, inner)| (tag.as_str(), Some(inner)))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn field<'v>(value: Option<&'v Value>, key: &str) -> Option<&'v Value> {
+    value.and_then(|v| v.get(key)).and_then(|v| if v.is_null() { None } else { Some(v) })
+}
+
+fn str_field(value: Option<&Value>, key: &str) -> Option<String> {
+    field(value, key).and_then(|v| v.as_str()).map(|s| s.to_string())
+}
+
+/// Reads a flag that was renamed between versions of the format, like `is_unsafe` and `unsafe`.
+fn bool_field(value: Option<&Value>, keys: &[&str]) -> bool {
+    keys.iter().any(|key| field(value, key).and_then(|v| v.as_bool()).unwrap_or(false))
+}
+
+fn array_field<'v>(value: Option<&'v Value>, key: &str) -> Vec<&'v Value> {
+    match field(value, key).and_then(|v| v.as_array()) {
+        Some(values) => values.iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+fn join_strings<I: Iterator<Item=String>>(strings: I, separator: &str) -> String {
+    strings.collect::<Vec<String>>().join(separator)
+}
+
+thread_local! {
+    // How deeply nested the type being printed is.
+    static TYPE_DEPTH: Cell<usize> = Cell::new(0);
+}
+
+/// Prints a type, with the types nested more than `max_type_depth()` deep inside it shown as
+/// `...`. Every type inside it is printed through here, so the depth is counted as it's walked.
+fn type_to_string(ty: &Value) -> String {
+    TYPE_DEPTH.with(|depth| {
+        let current = depth.get();
+        if current >= cmp::max(max_type_depth(), 1) {
+            return "...".to_string();
+        }
+
+        depth.set(current + 1);
+        let printed = print_type(ty);
+        depth.set(current);
+        printed
+    })
+}
+
+fn print_type(ty: &Value) -> String {
+    let (kind, inner) = match tagged(ty) {
+        Some(tagged) => tagged,
+        None => return "_".to_string(),
+    };
+
+    match kind {
+        "resolved_path" => path_to_string(inner),
+        "generic" | "primitive" => inner.and_then(|v| v.as_str()).unwrap_or("_").to_string(),
+        "tuple" => {
+            let tys: Vec<String> = inner.and_then(|v| v.as_array())
+                .map_or(Vec::new(), |tys| tys.iter().map(type_to_string).collect());
+            match tys.len() {
+                1 => format!("({},)", tys[0]),
+                _ => format!("({})", tys.join(", ")),
+            }
+        }
+        "slice" => format!("[{}]", inner.map_or("_".to_string(), type_to_string)),
+        "array" => format!("[{}; {}]",
+                           field(inner, "type").map_or("_".to_string(), type_to_string),
+                           str_field(inner, "len").unwrap_or("_".to_string())),
+        "raw_pointer" => {
+            let mutability = if bool_field(inner, &["is_mutable", "mutable"]) { "mut" } else {
+                "const"
+            };
+            format!("*{} {}", mutability, pointee_to_string(field(inner, "type")))
+        }
+        "borrowed_ref" => {
+            let mut result = "&".to_string();
+            if let Some(lifetime) = str_field(inner, "lifetime") {
+                result.push_str(&lifetime);
+                result.push(' ');
+            }
+            if bool_field(inner, &["is_mutable", "mutable"]) {
+                result.push_str("mut ");
+            }
+            result.push_str(&pointee_to_string(field(inner, "type")));
+            result
+        }
+        "impl_trait" => {
+            let bounds = inner.and_then(|v| v.as_array())
+                .map_or(Vec::new(), |bounds| bounds.iter().filter_map(bound_to_string).collect());
+            format!("impl {}", bounds.join(" + "))
+        }
+        "dyn_trait" => {
+            let mut bounds: Vec<String> = array_field(inner, "traits").into_iter()
+                .map(|poly| {
+                    let path = path_to_string(field(Some(poly), "trait"));
+                    let params = generic_param_names(array_field(Some(poly), "generic_params"));
+                    if params.is_empty() {
+                        path
+                    } else {
+                        format!("for<{}> {}", params.join(", "), path)
+                    }
+                })
+                .collect();
+            if let Some(lifetime) = str_field(inner, "lifetime") {
+                bounds.push(lifetime);
+            }
+            format!("dyn {}", bounds.join(" + "))
+        }
+        "function_pointer" => {
+            let decl = fn_decl(field(inner, "sig").or(field(inner, "decl")));
+            format!("fn{}", decl)
+        }
+        "qualified_path" => {
+            let self_type = field(inner, "self_type").map_or("_".to_string(), type_to_string);
+            let name = str_field(inner, "name").unwrap_or("_".to_string());
+            match field(inner, "trait") {
+                Some(trait_) => format!("<{} as {}>::{}", self_type, path_to_string(Some(trait_)),
+                                        name),
+                None => format!("{}::{}", self_type, name),
+            }
+        }
+        "pat" => field(inner, "type").map_or("_".to_string(), type_to_string),
+        "infer" => "_".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Prints the type behind a reference or pointer. Trait objects and `impl Trait` types with several
+/// bounds are parenthesized, like `&(dyn Error + Send)`, since `&dyn Error + Send` doesn't parse.
+fn pointee_to_string(ty: Option<&Value>) -> String {
+    let printed = ty.map_or("_".to_string(), type_to_string);
+    let is_trait_type = match ty.and_then(tagged) {
+        Some(("dyn_trait", _)) | Some(("impl_trait", _)) => true,
+        _ => false,
+    };
+
+    if is_trait_type && printed.contains(" + ") {
+        format!("({})", printed)
+    } else {
+        printed
+    }
+}
+
+/// Prints a path to a type or trait with its generic arguments. Older versions of the format
+/// store trait paths as types.
+fn path_to_string(path: Option<&Value>) -> String {
+    match str_field(path, "path").or(str_field(path, "name")) {
+        Some(name) => format!("{}{}", name, generic_args_to_string(field(path, "args"))),
+        None => path.map_or("_".to_string(), type_to_string),
+    }
+}
+
+fn generic_args_to_string(args: Option<&Value>) -> String {
+    let (kind, inner) = match args.and_then(tagged) {
+        Some(tagged) => tagged,
+        None => return "".to_string(),
+    };
+
+    match kind {
+        "angle_bracketed" => {
+            let mut parts: Vec<String> = array_field(inner, "args").into_iter()
+                .filter_map(|arg| tagged(arg))
+                .map(|(kind, value)| match kind {
+                    "lifetime" => value.and_then(|v| v.as_str()).unwrap_or("'_").to_string(),
+                    "type" => value.map_or("_".to_string(), type_to_string),
+                    "const" => str_field(value, "expr").unwrap_or("_".to_string()),
+                    _ => "_".to_string(),
+                })
+                .collect();
+
+            let constraints = array_field(inner, "constraints").into_iter()
+                .chain(array_field(inner, "bindings").into_iter());
+            for constraint in constraints {
+                let name = str_field(Some(constraint), "name").unwrap_or("_".to_string());
+                let binding = field(Some(constraint), "binding").and_then(tagged);
+                parts.push(match binding {
+                    Some(("equality", term)) => {
+                        let term = match term.and_then(tagged) {
+                            Some(("type", ty)) => ty.map_or("_".to_string(), type_to_string),
+                            Some(("constant", constant)) => {
+                                str_field(constant, "expr").unwrap_or("_".to_string())
+                            }
+                            _ => term.map_or("_".to_string(), type_to_string),
+                        };
+                        format!("{} = {}", name, term)
+                    }
+                    Some(("constraint", bounds)) => {
+                        let bounds = bounds.and_then(|v| v.as_array())
+                            .map_or(Vec::new(),
+                                    |bounds| bounds.iter().filter_map(bound_to_string).collect());
+                        format!("{}: {}", name, bounds.join(" + "))
+                    }
+                    _ => name,
+                });
+            }
+
+            if parts.is_empty() {
+                "".to_string()
+            } else {
+                format!("<{}>", parts.join(", "))
+            }
+        }
+        "parenthesized" => {
+            let inputs = join_strings(array_field(inner, "inputs").into_iter().map(type_to_string),
+                                      ", ");
+            match field(inner, "output") {
+                Some(output) => format!("({}) -> {}", inputs, type_to_string(output)),
+                None => format!("({})", inputs),
+            }
+        }
+        _ => "".to_string(),
+    }
+}
+
+fn bound_to_string(bound: &Value) -> Option<String> {
+    match tagged(bound) {
+        Some(("trait_bound", inner)) => {
+            let mut result = String::new();
+            let params = generic_param_names(array_field(inner, "generic_params"));
+            if !params.is_empty() {
+                result.push_str(&format!("for<{}> ", params.join(", ")));
+            }
+            if str_field(inner, "modifier").map_or(false, |m| m == "maybe") {
+                result.push('?');
+            }
+            result.push_str(&path_to_string(field(inner, "trait")));
+            Some(result)
+        }
+        Some(("outlives", lifetime)) => lifetime.and_then(|v| v.as_str()).map(|s| s.to_string()),
+        _ => None,
+    }
+}
+
+fn bounds_to_strings(bounds: Vec<&Value>) -> Vec<String> {
+    bounds.into_iter().filter_map(bound_to_string).collect()
+}
+
+fn generic_param_names(params: Vec<&Value>) -> Vec<String> {
+    params.into_iter().filter_map(|param| str_field(Some(param), "name")).collect()
+}
+
+fn generics(value: Option<&Value>) -> Generics {
+    let mut generics = Generics::new();
+
+    for param in array_field(value, "params") {
+        let name = str_field(Some(param), "name").unwrap_or("_".to_string());
+        let param = match field(Some(param), "kind").and_then(tagged) {
+            Some(("lifetime", kind)) => GenericParam::Lifetime {
+                name: name,
+                bounds: array_field(kind, "outlives").into_iter()
+                    .filter_map(|l| l.as_str().map(|s| s.to_string()))
+                    .collect(),
+            },
+            Some(("type", kind)) => {
+                // Parameters rustdoc made up for `impl Trait` arguments.
+                if bool_field(kind, &["is_synthetic", "synthetic"]) {
+                    continue;
+                }
+                GenericParam::Type {
+                    name: name,
+                    bounds: bounds_to_strings(array_field(kind, "bounds")),
+                    default: field(kind, "default").map(type_to_string),
+                }
+            }
+            Some(("const", kind)) => GenericParam::Const {
+                name: name,
+                ty: field(kind, "type").map_or("_".to_string(), type_to_string),
+                default: str_field(kind, "default"),
+            },
+            _ => continue,
+        };
+        generics.params.push(param);
+    }
+
+    for predicate in array_field(value, "where_predicates") {
+        let printed = match tagged(predicate) {
+            Some(("bound_predicate", inner)) => {
+                let ty = field(inner, "type").or(field(inner, "ty"));
+                // Higher-ranked lifetimes of the whole predicate, like `for<'a> F: Fn(&'a T)`.
+                let params = generic_param_names(array_field(inner, "generic_params"));
+                let binder = if params.is_empty() {
+                    "".to_string()
+                } else {
+                    format!("for<{}> ", params.join(", "))
+                };
+                format!("{}{}: {}",
+                        binder,
+                        ty.map_or("_".to_string(), type_to_string),
+                        bounds_to_strings(array_field(inner, "bounds")).join(" + "))
+            }
+            Some(("region_predicate", inner)) | Some(("lifetime_predicate", inner)) => {
+                let bounds = array_field(inner, "outlives").into_iter()
+                    .chain(array_field(inner, "bounds").into_iter())
+                    .filter_map(|b| bound_to_string(b).or(b.as_str().map(|s| s.to_string())))
+                    .collect::<Vec<String>>();
+                format!("{}: {}",
+                        str_field(inner, "lifetime").unwrap_or("'_".to_string()),
+                        bounds.join(" + "))
+            }
+            Some(("eq_predicate", inner)) => {
+                let rhs = match field(inner, "rhs").and_then(tagged) {
+                    Some(("type", ty)) => ty.map_or("_".to_string(), type_to_string),
+                    _ => field(inner, "rhs").map_or("_".to_string(), type_to_string),
+                };
+                format!("{} = {}", field(inner, "lhs").map_or("_".to_string(), type_to_string),
+                        rhs)
+            }
+            _ => continue,
+        };
+        generics.where_predicates.push(printed);
+    }
+
+    generics
+}
+
+fn self_kind(ty: &Value) -> SelfKind {
+    let is_self = |ty: Option<&Value>| match ty.and_then(tagged) {
+        Some(("generic", name)) => name.and_then(|n| n.as_str()) == Some("Self"),
+        _ => false,
+    };
+
+    match tagged(ty) {
+        Some(("borrowed_ref", inner)) if is_self(field(inner, "type")) => {
+            let mutability = if bool_field(inner, &["is_mutable", "mutable"]) {
+                Mutability::Mutable
+            } else {
+                Mutability::Immutable
+            };
+            SelfKind::Region(str_field(inner, "lifetime"), mutability)
+        }
+        _ if is_self(Some(ty)) => SelfKind::Value(Mutability::Immutable),
+        _ => SelfKind::Explicit(type_to_string(ty), Mutability::Immutable),
+    }
+}
+
+fn fn_decl(sig: Option<&Value>) -> FnDecl {
+    let inputs = array_field(sig, "inputs").into_iter()
+        .filter_map(|input| input.as_array())
+        .filter(|input| input.len() == 2)
+        .map(|input| {
+            let name = input[0].as_str().unwrap_or("_");
+            if name == "self" {
+                FnArg::SelfArg(self_kind(&input[1]))
+            } else {
+                FnArg::Arg {
+                    pattern: name.to_string(),
+                    ty: type_to_string(&input[1]),
+                }
+            }
+        })
+        .collect();
+
+    let output = field(sig, "output").map(type_to_string).and_then(|output| {
+        if output == "()" { None } else { Some(output) }
+    });
+
+    FnDecl {
+        inputs: inputs,
+        output: output,
+        variadic: bool_field(sig, &["is_c_variadic", "c_variadic"]),
+    }
+}
+
+fn abi_from_name(name: &str) -> Abi {
+    match name.trim_matches('"') {
+        "Rust"                       => Abi::Rust,
+        "C" | "C-unwind"             => Abi::C,
+        "system" | "System"          => Abi::System,
+        "cdecl"                      => Abi::Cdecl,
+        "stdcall" | "Stdcall"        => Abi::Stdcall,
+        "fastcall" | "Fastcall"      => Abi::Fastcall,
+        "vectorcall" | "Vectorcall"  => Abi::Vectorcall,
+        "thiscall" | "Thiscall"      => Abi::Thiscall,
+        "aapcs" | "Aapcs"            => Abi::Aapcs,
+        "win64" | "Win64"            => Abi::Win64,
+        "sysv64" | "SysV64"          => Abi::SysV64,
+        "ptx-kernel"                 => Abi::PtxKernel,
+        "msp430-interrupt"           => Abi::Msp430Interrupt,
+        "x86-interrupt"              => Abi::X86Interrupt,
+        "rust-intrinsic"             => Abi::RustIntrinsic,
+        "rust-call"                  => Abi::RustCall,
+        "platform-intrinsic"         => Abi::PlatformIntrinsic,
+        "unadjusted"                 => Abi::Unadjusted,
+        other                        => {
+            warn!("Unknown ABI {}, treating it as C", other);
+            Abi::C
+        }
+    }
+}
+
+/// Reads the ABI of a function, which is a quoted string in older versions of the format and an
+/// object like `{"C": {"unwind": false}}` or `{"Other": "\"efiapi\""}` in newer ones.
+fn abi(value: Option<&Value>) -> Abi {
+    match value.and_then(tagged) {
+        Some(("Other", Some(name))) => abi_from_name(name.as_str().unwrap_or("C")),
+        Some((name, _)) => abi_from_name(name),
+        None => Abi::Rust,
+    }
+}
+
+/// Reads the qualifiers of a function. Very old versions of the format list them as strings.
+fn fn_header(inner: Option<&Value>) -> (Unsafety, Constness, Abi) {
+    let header = field(inner, "header");
+    let (is_unsafe, is_const) = match header.and_then(|h| h.as_array()) {
+        Some(qualifiers) => (qualifiers.iter().any(|q| q.as_str() == Some("unsafe")),
+                             qualifiers.iter().any(|q| q.as_str() == Some("const"))),
+        None => (bool_field(header, &["is_unsafe", "unsafe_"]),
+                 bool_field(header, &["is_const", "const_"])),
+    };
+
+    let unsafety = if is_unsafe { Unsafety::Unsafe } else { Unsafety::Normal };
+    let constness = if is_const { Constness::Const } else { Constness::NotConst };
+    (unsafety, constness, abi(field(header, "abi").or(field(inner, "abi"))))
+}
+
+fn method_sig(inner: Option<&Value>) -> MethodSig {
+    let (unsafety, constness, abi) = fn_header(inner);
+    MethodSig {
+        generics: generics(field(inner, "generics")),
+        unsafety: unsafety,
+        constness: constness,
+        abi: abi,
+        decl: fn_decl(field(inner, "sig").or(field(inner, "decl"))),
+    }
+}
+
+fn function(inner: Option<&Value>, kind: FnKind, impl_header: Option<ImplHeader>) -> Function {
+    let sig = method_sig(inner);
+    Function {
+        has_receiver: sig.decl.has_receiver(),
+        decl: sig.decl,
+        generics: sig.generics,
+        unsafety: sig.unsafety,
+        constness: sig.constness,
+        abi: sig.abi,
+        kind: kind,
+        impl_header: impl_header,
+        return_links: Vec::new(),
+    }
+}
+
+fn visibility(item: &Value) -> Visibility {
+    match item.get("visibility").and_then(tagged) {
+        Some(("public", _)) => Visibility::Public,
+        Some(("crate", _)) => Visibility::Crate,
+        Some(("restricted", inner)) => {
+            match str_field(inner, "path").as_ref().map(|p| p.trim_left_matches("::")) {
+                Some("crate") => Visibility::Crate,
+                Some("super") => Visibility::Super,
+                Some("self")  => Visibility::Private,
+                Some(path)    => Visibility::InPath(ModPath::from(path.to_string())),
+                None          => Visibility::Private,
+            }
+        }
+        _ => Visibility::Inherited,
+    }
+}
+
+fn is_public(item: &Value) -> bool {
+    visibility(item) == Visibility::Public
+}
+
+fn attributes(item: &Value) -> Attributes {
+    let mut attrs = Attributes::new();
+
+    if let Some(docs) = str_field(Some(item), "docs") {
+        attrs.doc_strings = docs.lines().map(|line| line.to_string()).collect();
+    }
+
+    if let Some(deprecation) = field(Some(item), "deprecation") {
+        attrs.deprecation = Some(Deprecation {
+            since: str_field(Some(deprecation), "since"),
+            note: str_field(Some(deprecation), "note"),
+        });
+    }
+
+    // Attributes are only read when rustdoc prints them as source.
+    for attr in array_field(Some(item), "attrs").into_iter().filter_map(|a| a.as_str()) {
+        if let Some(caps) = MUST_USE_REGEX.captures(attr) {
+            attrs.must_use = Some(caps.get(2).map_or("", |m| m.as_str()).to_string());
+        }
+        let attr_name = attr.trim_left_matches("#[")
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .next()
+            .unwrap_or("");
+        if CODEGEN_ATTRIBUTES.contains(&attr_name) {
+            attrs.codegen_attrs.push(attr.to_string());
+        }
+        if attr.starts_with("#[doc(") {
+            attrs.hidden |= attr.contains("hidden");
+            attrs.aliases.extend(ALIAS_REGEX.captures_iter(attr)
+                                 .map(|caps| caps[1].to_string()));
+        }
+    }
+
+    attrs
+}
+
+fn is_non_exhaustive(item: &Value) -> bool {
+    array_field(Some(item), "attrs").into_iter()
+        .filter_map(|a| a.as_str())
+        .any(|attr| attr.trim() == "#[non_exhaustive]")
+}
+
+fn source_span(item: &Value) -> Option<SourceSpan> {
+    let span = field(Some(item), "span");
+    let line = |key: &str| {
+        field(span, key).and_then(|pos| pos.get(0)).and_then(|l| l.as_u64()).map(|l| l as usize)
+    };
+
+    match (str_field(span, "filename"), line("begin"), line("end")) {
+        (Some(file), Some(lo_line), Some(hi_line)) => Some(SourceSpan {
+            file: PathBuf::from(file),
+            lo_line: lo_line,
+            hi_line: hi_line,
+        }),
+        _ => None,
+    }
+}
+
+fn macro_matchers(source: &str) -> Vec<String> {
+    // rustdoc prints each rule of a `macro_rules!` on a line of its own, like
+    // `($x:expr) => { ... };`.
+    source.lines()
+        .filter_map(|line| line.find("=>").map(|end| line[..end].trim().to_string()))
+        .filter(|matcher| !matcher.is_empty())
+        .collect()
+}
+
+fn id_key(id: &Value) -> Option<String> {
+    match *id {
+        Value::String(ref id) => Some(id.clone()),
+        Value::Number(ref id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Walks the items of a crate in rustdoc's JSON, starting at the root module.
+struct Importer<'a> {
+    index: &'a Map<String, Value>,
+    paths: Option<&'a Map<String, Value>>,
+    crate_info: CrateInfo,
+    documents: Vec<Documentation>,
+    reexports: Vec<Reexport>,
+
+    /// The items of this crate that are re-exported, with the paths they're defined at. Those
+    /// defined in private modules aren't found by walking the public ones.
+    reexported_items: Vec<(&'a Value, ModPath)>,
+
+    /// Items of kinds that can't be documented yet.
+    skipped: Vec<SkippedItem>,
+}
+
+impl<'a> Importer<'a> {
+    /// Records an item of a kind that can't be documented yet, like `trait_alias`.
+    fn skip(&mut self, item: &Value, kind: &str, path: ModPath) {
+        self.skipped.push(SkippedItem {
+            kind: kind.replace('_', " "),
+            path: path,
+            source: source_span(item),
+            reason: format!("rustdoc JSON items of kind {} aren't supported", kind),
+        });
+    }
+
+    fn item(&self, id: &Value) -> Option<&'a Value> {
+        let index = self.index;
+        id_key(id).and_then(|key| index.get(&key))
+    }
+
+    /// The full path of an item of this crate, from the path summaries of the JSON.
+    fn local_path(&self, id: Option<&Value>) -> Option<ModPath> {
+        match self.summary_path(id) {
+            Some((0, path)) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// The name of the crate an item of another crate is in and the item's full path, which
+    /// starts with that name.
+    fn external_path(&self, id: Option<&Value>) -> Option<(String, ModPath)> {
+        match self.summary_path(id) {
+            Some((0, _)) | None => None,
+            Some((_, path)) => path.head().map(|krate| (krate.identifier, path)),
+        }
+    }
+
+    /// The id of the crate an item is in and its full path, from the path summaries of the JSON.
+    fn summary_path(&self, id: Option<&Value>) -> Option<(u64, ModPath)> {
+        let summary = match (self.paths, id.and_then(id_key)) {
+            (Some(paths), Some(key)) => paths.get(&key),
+            _ => None,
+        };
+
+        let crate_id = match field(summary, "crate_id").and_then(|c| c.as_u64()) {
+            Some(crate_id) => crate_id,
+            None => return None,
+        };
+        let segments: Vec<String> = array_field(summary, "path").into_iter()
+            .filter_map(|s| s.as_str().map(|s| s.to_string()))
+            .collect();
+        if segments.is_empty() {
+            None
+        } else {
+            Some((crate_id, ModPath::from(segments.join("::"))))
+        }
+    }
+
+    fn new_doc(&self, item: &Value, name: String, path: ModPath,
+               visibility: Visibility, inner_data: DocInnerData) -> Documentation {
+        Documentation {
+            name: name,
+            attrs: attributes(item),
+            crate_info: self.crate_info.clone(),
+            mod_path: path,
+            inner_data: inner_data,
+            visibility: Some(visibility),
+            source: source_span(item),
+            links: DocRelatedItems::new(),
+            disambiguator: 0,
+            signature: None,
+        }
+    }
+
+    fn import_module(&mut self, item: &'a Value, inner: Option<&'a Value>,
+                     name: String, path: ModPath, is_crate: bool) {
+        let mut links = DocRelatedItems::new();
+
+        for id in array_field(inner, "items") {
+            let child = match self.item(id) {
+                Some(child) => child,
+                None => continue,
+            };
+            if !is_public(child) {
+                continue;
+            }
+
+            let (kind, child_inner) = match item_kind(child) {
+                Some(kind) => kind,
+                None => continue,
+            };
+
+            match kind {
+                "use" | "import" => {
+                    self.add_reexport(&path, child_inner);
+                    continue;
+                }
+                // Impls are imported with the types they are for, and extern crates aren't
+                // documented, like when parsing the source.
+                "impl" | "extern_crate" => continue,
+                _ => (),
+            }
+
+            let child_name = match str_field(Some(child), "name") {
+                Some(name) => name,
+                None => continue,
+            };
+            let mut child_path = path.clone();
+            child_path.push_string(child_name.clone());
+
+            if let Some(doc_type) = self.import_item(child, kind, child_inner,
+                                                     child_name.clone(), child_path.clone()) {
+                links.entry(doc_type).or_insert(Vec::new())
+                    .push(DocLink::new(child_name, child_path));
+            }
+        }
+
+        let mut doc = self.new_doc(item, name, path, visibility(item),
+                                   ModuleDoc(Module { is_crate: is_crate }));
+        doc.links = links;
+        self.documents.push(doc);
+    }
+
+    fn add_reexport(&mut self, module: &ModPath, inner: Option<&'a Value>) {
+        let id = field(inner, "id");
+        let (target, krate) = match self.local_path(id) {
+            Some(target) => {
+                if let Some(item) = id.and_then(|id| self.item(id)) {
+                    self.reexported_items.push((item, target.clone()));
+                }
+                (target, None)
+            },
+            None => match self.external_path(id) {
+                Some((krate, target)) => (target, Some(krate)),
+                None => return,
+            },
+        };
+
+        // The name of an import is the one it's imported under.
+        let alias = str_field(inner, "name")
+            .and_then(|name| match target.name() {
+                Some(ref segment) if segment.identifier == name => None,
+                _                                             => Some(name),
+            });
+        let glob = bool_field(inner, &["is_glob", "glob"]);
+
+        self.reexports.push(Reexport {
+            module: module.clone(),
+            target: target,
+            glob: glob,
+            krate: krate,
+            alias: if glob { None } else { alias },
+        });
+    }
+
+    /// Imports the re-exported items that weren't found inside public modules, like the items of
+    /// private modules that are only reachable through a `pub use`. Importing them can find more
+    /// re-exports, which are imported in turn.
+    fn import_reexported_items(&mut self) {
+        let mut i = 0;
+        while i < self.reexported_items.len() {
+            let (item, path) = self.reexported_items[i].clone();
+            i += 1;
+
+            if self.documents.iter().any(|doc| doc.mod_path == path) {
+                continue;
+            }
+            let (kind, inner) = match item_kind(item) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            let name = match path.name() {
+                Some(segment) => segment.identifier,
+                None => continue,
+            };
+
+            match kind {
+                "module" => self.import_module(item, inner, name, path, false),
+                "use" | "import" | "impl" => (),
+                _ => {
+                    self.import_item(item, kind, inner, name, path);
+                },
+            }
+        }
+    }
+
+    /// Imports an item and the items inside it, returning the category it is listed under in its
+    /// module.
+    fn import_item(&mut self, item: &'a Value, kind: &str, inner: Option<&'a Value>,
+                   name: String, path: ModPath) -> Option<DocType> {
+        let vis = visibility(item);
+        let (doc_type, inner_data, links) = match kind {
+            "module" => {
+                self.import_module(item, inner, name, path, false);
+                return Some(DocType::Module);
+            }
+            "function" | "method" => {
+                let func = function(inner, FnKind::ItemFn, None);
+                (DocType::Function, FnDoc(func), DocRelatedItems::new())
+            }
+            "struct" => {
+                let (kind, fields, blocks, links) = self.import_struct(inner, &path);
+                let struct_ = Struct {
+                    kind: kind,
+                    generics: generics(field(inner, "generics")),
+                    fields: fields,
+                    impls: blocks,
+                    non_exhaustive: is_non_exhaustive(item),
+                };
+                (DocType::Struct, StructDoc(struct_), links)
+            }
+            "union" => {
+                let fields = self.struct_fields(array_field(inner, "fields"), false);
+                let mut links = field_links(&fields);
+                let (blocks, impl_links) = self.import_impls(array_field(inner, "impls"), &path);
+                links.extend(impl_links);
+                let union_ = Union {
+                    generics: generics(field(inner, "generics")),
+                    fields: fields,
+                    impls: blocks,
+                };
+                (DocType::Union, UnionDoc(union_), links)
+            }
+            "enum" => {
+                let variants = self.variants(array_field(inner, "variants"));
+                let mut links = DocRelatedItems::new();
+                links.insert(DocType::Variant, variants.iter()
+                             .map(|variant| DocLink::new(variant.to_string(), ModPath::new()))
+                             .collect());
+                let (blocks, impl_links) = self.import_impls(array_field(inner, "impls"), &path);
+                links.extend(impl_links);
+                let enum_ = Enum {
+                    generics: generics(field(inner, "generics")),
+                    variants: variants,
+                    impls: blocks,
+                    non_exhaustive: is_non_exhaustive(item),
+                };
+                (DocType::Enum, EnumDoc(enum_), links)
+            }
+            "trait" => {
+                let mut links = self.import_trait_items(array_field(inner, "items"), &path);
+                let supertraits = self.supertraits(array_field(inner, "bounds"));
+                if !supertraits.is_empty() {
+                    links.insert(DocType::Supertrait, supertraits);
+                }
+                let trait_ = Trait {
+                    unsafety: if bool_field(inner, &["is_unsafe", "unsafe"]) {
+                        Unsafety::Unsafe
+                    } else {
+                        Unsafety::Normal
+                    },
+                    generics: generics(field(inner, "generics")),
+                    bounds: bounds_to_strings(array_field(inner, "bounds")),
+                };
+                (DocType::Trait, TraitDoc(trait_), links)
+            }
+            "constant" => {
+                let constant = Constant {
+                    ty: Ty::from_name(field(inner, "type").map_or("_".to_string(), type_to_string)),
+                    expr: str_field(field(inner, "const"), "expr")
+                        .or(str_field(inner, "expr"))
+                        .unwrap_or("_".to_string()),
+                };
+                (DocType::Const, ConstDoc(constant), DocRelatedItems::new())
+            }
+            "static" => {
+                let mutability = if bool_field(inner, &["is_mutable", "mutable"]) {
+                    Mutability::Mutable
+                } else {
+                    Mutability::Immutable
+                };
+                let static_ = Static {
+                    ty: Ty::from_name(field(inner, "type").map_or("_".to_string(), type_to_string)),
+                    mutability: mutability,
+                    expr: str_field(inner, "expr").and_then(|expr| {
+                        if expr.is_empty() { None } else { Some(expr) }
+                    }),
+                    abi: None,
+                };
+                (DocType::Static, StaticDoc(static_), DocRelatedItems::new())
+            }
+            "type_alias" | "typedef" => {
+                let typedef = Typedef {
+                    generics: generics(field(inner, "generics")),
+                    ty: Ty::from_name(field(inner, "type").map_or("_".to_string(), type_to_string)),
+                };
+                (DocType::Typedef, TypedefDoc(typedef), DocRelatedItems::new())
+            }
+            "macro" => {
+                let source = inner.and_then(|v| v.as_str()).unwrap_or("");
+                let macro_ = MacroRules {
+                    matchers: macro_matchers(source),
+                };
+                (DocType::Macro, MacroDoc(macro_), DocRelatedItems::new())
+            }
+            other => {
+                self.skip(item, other, path);
+                return None;
+            }
+        };
+
+        let mut doc = self.new_doc(item, name, path, vis, inner_data);
+        doc.links = links;
+        self.documents.push(doc);
+        Some(doc_type)
+    }
+
+    fn import_struct(&mut self, inner: Option<&'a Value>, path: &ModPath)
+                     -> (StructKind, Vec<StructField>, Vec<ImplBlock>, DocRelatedItems) {
+        let (kind, fields) = match field(inner, "kind").and_then(tagged) {
+            Some(("plain", plain)) => {
+                (StructKind::Named, self.struct_fields(array_field(plain, "fields"), false))
+            }
+            Some(("tuple", Some(fields))) => {
+                let fields = fields.as_array().map_or(Vec::new(), |f| f.iter().collect());
+                (StructKind::Tuple, self.struct_fields(fields, true))
+            }
+            Some(_) => (StructKind::Unit, Vec::new()),
+            // Older versions of the format.
+            None => {
+                let kind = match str_field(inner, "struct_type") {
+                    Some(ref t) if t == "tuple" => StructKind::Tuple,
+                    Some(ref t) if t == "unit"  => StructKind::Unit,
+                    _                           => StructKind::Named,
+                };
+                (kind, self.struct_fields(array_field(inner, "fields"), kind == StructKind::Tuple))
+            }
+        };
+
+        let mut links = field_links(&fields);
+        let (blocks, impl_links) = self.import_impls(array_field(inner, "impls"), path);
+        links.extend(impl_links);
+        (kind, fields, blocks, links)
+    }
+
+    /// Reads the fields of a struct, union or variant. Fields rustdoc left out, like private
+    /// fields of tuple structs, are `null`.
+    fn struct_fields(&self, ids: Vec<&Value>, is_tuple: bool) -> Vec<StructField> {
+        ids.into_iter()
+            .filter_map(|id| {
+                if id.is_null() {
+                    return Some(StructField {
+                        ident: None,
+                        vis: Visibility::Inherited,
+                        ty: Ty::from_name("_".to_string()),
+                        attrs: Attributes::new(),
+                        path: ModPath::new(),
+                    });
+                }
+
+                // Variants in older versions of the format hold types instead of fields.
+                if id.is_object() {
+                    return Some(StructField {
+                        ident: None,
+                        vis: Visibility::Public,
+                        ty: Ty::from_name(type_to_string(id)),
+                        attrs: Attributes::new(),
+                        path: ModPath::new(),
+                    });
+                }
+
+                let item = self.item(id)?;
+                let ty = match item_kind(item) {
+                    Some(("struct_field", Some(ty))) => type_to_string(ty),
+                    _ => return None,
+                };
+                Some(StructField {
+                    ident: if is_tuple { None } else { str_field(Some(item), "name") },
+                    vis: visibility(item),
+                    ty: Ty::from_name(ty),
+                    attrs: attributes(item),
+                    path: ModPath::new(),
+                })
+            })
+            .collect()
+    }
+
+    fn variants(&self, ids: Vec<&Value>) -> Vec<Variant> {
+        ids.into_iter()
+            .filter_map(|id| self.item(id))
+            .filter_map(|item| {
+                let inner = match item_kind(item) {
+                    Some(("variant", inner)) => inner,
+                    _ => return None,
+                };
+
+                let (kind, data) = match field(inner, "kind")
+                    .or(field(inner, "variant_kind"))
+                    .and_then(tagged) {
+                    Some((kind, Some(data))) => (kind, Some(data)),
+                    Some((kind, None)) => (kind, field(inner, "variant_inner")),
+                    None => inner.and_then(tagged).unwrap_or(("plain", None)),
+                };
+
+                let kind = match kind {
+                    "tuple" => {
+                        let fields = data.and_then(|d| d.as_array())
+                            .map_or(Vec::new(), |f| f.iter().collect());
+                        VariantKind::Tuple(self.struct_fields(fields, true))
+                    }
+                    "struct" => {
+                        let fields = match data.and_then(|d| d.as_array()) {
+                            Some(fields) => fields.iter().collect(),
+                            None => array_field(data, "fields"),
+                        };
+                        VariantKind::Struct(self.struct_fields(fields, false))
+                    }
+                    _ => VariantKind::Unit,
+                };
+
+                Some(Variant {
+                    name: str_field(Some(item), "name").unwrap_or("_".to_string()),
+                    kind: kind,
+                    discriminant: str_field(field(inner, "discriminant"), "expr"),
+                    attrs: attributes(item),
+                })
+            })
+            .collect()
+    }
+
+    fn supertraits(&self, bounds: Vec<&Value>) -> Vec<DocLink> {
+        bounds.into_iter()
+            .filter_map(|bound| match tagged(bound) {
+                Some(("trait_bound", inner)) => field(inner, "trait"),
+                _ => None,
+            })
+            .filter_map(|trait_| {
+                self.local_path(field(Some(trait_), "id")).map(|path| {
+                    let name = path.0.last().map_or("".to_string(), |s| s.identifier.clone());
+                    DocLink::new(name, path)
+                })
+            })
+            .collect()
+    }
+
+    fn import_trait_items(&mut self, ids: Vec<&Value>, trait_path: &ModPath) -> DocRelatedItems {
+        let mut links = DocRelatedItems::new();
+
+        for (order, id) in ids.into_iter().enumerate() {
+            let item = match self.item(id) {
+                Some(item) => item,
+                None => continue,
+            };
+            let name = match str_field(Some(item), "name") {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let (category, node) = match item_kind(item) {
+                Some(("function", inner)) | Some(("method", inner)) => {
+                    let sig = method_sig(inner);
+                    if bool_field(inner, &["has_body"]) {
+                        (DocType::TraitItemProvidedMethod,
+                         TraitItemKind::Method(sig, Some("{ ... }".to_string())))
+                    } else {
+                        (DocType::TraitItemMethod, TraitItemKind::Method(sig, None))
+                    }
+                }
+                Some(("assoc_const", inner)) => {
+                    let ty = field(inner, "type").map_or("_".to_string(), type_to_string);
+                    let value = str_field(inner, "value").or(str_field(inner, "default"));
+                    (DocType::TraitItemConst, TraitItemKind::Const {
+                        ty: Ty::from_name(ty),
+                        default: value,
+                    })
+                }
+                Some(("assoc_type", inner)) => {
+                    let ty = field(inner, "type").or(field(inner, "default"))
+                        .map(|ty| Ty::from_name(type_to_string(ty)));
+                    let bounds = bounds_to_strings(array_field(inner, "bounds"));
+                    (DocType::TraitItemType, TraitItemKind::Type(bounds, ty))
+                }
+                Some((other, _)) => {
+                    let mut path = trait_path.clone();
+                    path.push_string(name.clone());
+                    self.skip(item, other, path);
+                    continue;
+                }
+                None => continue,
+            };
+
+            let mut path = trait_path.clone();
+            path.push_string(name.clone());
+
+            let doc = self.new_doc(item, name.clone(), path.clone(), Visibility::Inherited,
+                                   TraitItemDoc(TraitItem { node: node }));
+            self.documents.push(doc);
+            links.entry(category).or_insert(Vec::new())
+                .push(DocLink::new(name, path).with_order(order));
+        }
+
+        links
+    }
+
+    /// Imports the impl blocks of a type and the items in them. Impls rustdoc generated, like
+    /// those of auto traits and blanket impls, are skipped.
+    fn import_impls(&mut self, ids: Vec<&Value>, ty_path: &ModPath)
+                    -> (Vec<ImplBlock>, DocRelatedItems) {
+        let mut blocks = Vec::new();
+        let mut links = DocRelatedItems::new();
+        let mut offset = 0;
+
+        for id in ids {
+            let inner = match self.item(id).and_then(item_kind) {
+                Some(("impl", inner)) => inner,
+                _ => continue,
+            };
+            if bool_field(inner, &["is_synthetic", "synthetic", "is_negative", "negative"]) ||
+                field(inner, "blanket_impl").is_some() {
+                continue;
+            }
+
+            let header = ImplHeader {
+                unsafety: if bool_field(inner, &["is_unsafe", "unsafe"]) {
+                    Unsafety::Unsafe
+                } else {
+                    Unsafety::Normal
+                },
+                generics: generics(field(inner, "generics")),
+                trait_: field(inner, "trait").map(|t| path_to_string(Some(t))),
+                for_: Ty::from_name(field(inner, "for").map_or("_".to_string(), type_to_string)),
+            };
+            let is_trait_impl = header.trait_.is_some();
+
+            let mut items = DocRelatedItems::new();
+            let item_ids = array_field(inner, "items");
+            let count = item_ids.len();
+            for (order, item_id) in item_ids.into_iter().enumerate() {
+                let item = match self.item(item_id) {
+                    Some(item) => item,
+                    None => continue,
+                };
+                // The items of trait impls have the visibility of the trait.
+                if !is_trait_impl && !is_public(item) {
+                    continue;
+                }
+                let name = match str_field(Some(item), "name") {
+                    Some(name) => name,
+                    None => continue,
+                };
+                let mut path = ty_path.clone();
+                path.push_string(name.clone());
+
+                let (category, link) = match item_kind(item) {
+                    Some(("function", inner)) | Some(("method", inner)) => {
+                        let func = function(inner, FnKind::MethodFromImpl, Some(header.clone()));
+                        let doc = self.new_doc(item, name.clone(), path.clone(), visibility(item),
+                                               FnDoc(func));
+                        self.documents.push(doc);
+                        (DocType::Function, DocLink::new(name, path))
+                    }
+                    Some(("assoc_const", inner)) => {
+                        let constant = Constant {
+                            ty: Ty::from_name(field(inner, "type")
+                                              .map_or("_".to_string(), type_to_string)),
+                            expr: str_field(inner, "value")
+                                .or(str_field(inner, "default"))
+                                .unwrap_or("_".to_string()),
+                        };
+                        let doc = self.new_doc(item, name.clone(), path.clone(), visibility(item),
+                                               ConstDoc(constant));
+                        self.documents.push(doc);
+                        (DocType::AssocConst, DocLink::new(name, path))
+                    }
+                    // Associated types don't get documentation of their own yet, so they are
+                    // only listed by name.
+                    Some(("assoc_type", _)) => {
+                        (DocType::AssocType, DocLink::new(name, ModPath::new()))
+                    }
+                    _ => continue,
+                };
+
+                items.entry(category).or_insert(Vec::new()).push(link.with_order(order));
+            }
+
+            for (category, category_links) in items.iter() {
+                links.entry(category.clone()).or_insert(Vec::new())
+                    .extend(category_links.iter().cloned().map(|mut link| {
+                        link.order = link.order.map(|order| order + offset);
+                        link
+                    }));
+            }
+            offset += count;
+
+            let trait_link = self.local_path(field(field(inner, "trait"), "id")).map(|trait_path| {
+                let name = trait_path.0.last().map_or("".to_string(), |s| s.identifier.clone());
+                DocLink::new(name, trait_path)
+            });
+            if let Some(ref trait_link) = trait_link {
+                links.entry(DocType::TraitImpl).or_insert(Vec::new()).push(trait_link.clone());
+            }
+
+            blocks.push(ImplBlock {
+                header: header,
+                items: items,
+                trait_link: trait_link,
+                provided_methods: Vec::new(),
+                methods: Vec::new(),
+            });
+        }
+
+        (blocks, links)
+    }
+}
+
+fn field_links(fields: &[StructField]) -> DocRelatedItems {
+    let mut links = DocRelatedItems::new();
+    links.insert(DocType::StructField, fields.iter()
+                 .enumerate()
+                 .map(|(index, field)| DocLink::new(field.name(index), field.path.clone()))
+                 .collect());
+    links
+}
+
+fn import_crate(json: &Value, path: &Path) -> Result<(Vec<Documentation>, Vec<SkippedItem>)> {
+    let invalid = |reason: &str| ErrorKind::InvalidRustdocJson(path.to_path_buf(),
+                                                                reason.to_string());
+
+    let index = match json.get("index").and_then(|i| i.as_object()) {
+        Some(index) => index,
+        None => bail!(invalid("no item index")),
+    };
+    let root = match json.get("root").and_then(id_key).and_then(|key| index.get(&key)) {
+        Some(root) => root,
+        None => bail!(invalid("no root module")),
+    };
+    let name = match str_field(Some(root), "name") {
+        Some(name) => name,
+        None => bail!(invalid("the root module has no name")),
+    };
+
+    let crate_info = CrateInfo {
+        name: name.clone(),
+        version: str_field(Some(json), "crate_version").unwrap_or("0.0.0".to_string()),
+        lib_path: None,
+    };
+
+    let mut importer = Importer {
+        index: index,
+        paths: json.get("paths").and_then(|p| p.as_object()),
+        crate_info: crate_info,
+        documents: Vec::new(),
+        reexports: Vec::new(),
+        reexported_items: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    let inner = match item_kind(root) {
+        Some(("module", inner)) => inner,
+        _ => bail!(invalid("the root item isn't a module")),
+    };
+    importer.import_module(root, inner, name.clone(), ModPath::from(name), true);
+    importer.import_reexported_items();
+
+    let mut documents = importer.documents;
+    link_implementors(&mut documents);
+    link_provided_methods(&mut documents);
+    link_reexports(&mut documents, &importer.reexports);
+    disambiguate_duplicates(&mut documents);
+    link_methods(&mut documents);
+    resolve_intra_doc_links(&mut documents);
+    link_return_types(&mut documents);
+    fill_signatures(&mut documents);
+
+    Ok((documents, importer.skipped))
+}
+
+/// Converts the JSON written by `rustdoc --output-format=json` for a crate into documentation,
+/// without parsing the crate's source. Items of kinds that can't be represented are skipped with
+/// a warning.
+pub fn from_rustdoc_json<P: AsRef<Path>>(path: P) -> Result<Vec<Documentation>> {
+    let (documents, skipped) = from_rustdoc_json_with_skipped(path)?;
+    for item in skipped.iter() {
+        warn!("{}", item);
+    }
+    Ok(documents)
+}
+
+/// Like `from_rustdoc_json`, but returns the items that were skipped instead of reporting them.
+pub fn from_rustdoc_json_with_skipped<P: AsRef<Path>>(path: P)
+                                                      -> Result<(Vec<Documentation>,
+                                                                 Vec<SkippedItem>)> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .chain_err(|| ErrorKind::ReadFailed(path.to_path_buf()))?;
+    let json: Value = serde_json::from_reader(file)
+        .chain_err(|| ErrorKind::DeserializeFailed(path.to_path_buf()))?;
+
+    import_crate(&json, path)
+}
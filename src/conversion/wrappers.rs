@@ -1,10 +1,91 @@
+use std::fmt::{self, Display};
+
 use conversion::doc_containers::*;
 use document::ModPath;
 use generation::ast_ty_wrappers;
 
-#[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Generics {
+    pub params: Vec<GenericParam>,
+    pub where_predicates: Vec<String>,
+}
+
+impl Generics {
+    pub fn new() -> Generics {
+        Generics {
+            params: Vec::new(),
+            where_predicates: Vec::new(),
+        }
+    }
+
+    /// The where-clause of the item, prefixed with a space, or an empty string
+    /// if there are no predicates.
+    pub fn where_clause(&self) -> String {
+        if self.where_predicates.is_empty() {
+            "".to_string()
+        } else {
+            format!(" where {}", self.where_predicates.join(", "))
+        }
+    }
+}
+
+impl Display for Generics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.params.is_empty() {
+            return Ok(());
+        }
+        let params = self.params.iter().map(|p| p.to_string()).collect::<Vec<String>>();
+        write!(f, "<{}>", params.join(", "))
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GenericParam {
+    Lifetime {
+        name: String,
+        bounds: Vec<String>,
+    },
+    Type {
+        name: String,
+        bounds: Vec<String>,
+        default: Option<String>,
+    },
+    Const {
+        name: String,
+        ty: String,
+        default: Option<String>,
+    },
+}
 
+impl Display for GenericParam {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GenericParam::Lifetime { ref name, ref bounds } => {
+                write!(f, "{}", name)?;
+                if !bounds.is_empty() {
+                    write!(f, ": {}", bounds.join(" + "))?;
+                }
+                Ok(())
+            },
+            GenericParam::Type { ref name, ref bounds, ref default } => {
+                write!(f, "{}", name)?;
+                if !bounds.is_empty() {
+                    write!(f, ": {}", bounds.join(" + "))?;
+                }
+                if let Some(ref default) = *default {
+                    write!(f, " = {}", default)?;
+                }
+                Ok(())
+            },
+            GenericParam::Const { ref name, ref ty, ref default } => {
+                write!(f, "const {}: {}", name, ty)?;
+                if let Some(ref default) = *default {
+                    write!(f, " = {}", default)?;
+                }
+                Ok(())
+            },
+        }
+    }
 }
 
 #[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
@@ -14,15 +95,146 @@ pub struct VariantStruct {
 
 #[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Enum {
-    pub variants: DocRelatedItems,
+    pub generics: Generics,
+    pub variants: Vec<Variant>,
+    pub impls: Vec<ImplBlock>,
+
+    /// Whether the enum is marked `#[non_exhaustive]`, so matches on it outside its crate need a
+    /// wildcard arm.
+    pub non_exhaustive: bool,
+}
+
+#[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Variant {
+    pub name: String,
+    pub kind: VariantKind,
+    /// The explicit discriminant expression, as in `Foo = 5`.
+    pub discriminant: Option<String>,
+    pub attrs: ast_ty_wrappers::Attributes,
+}
+
+#[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum VariantKind {
+    Unit,
+    Tuple(Vec<StructField>),
+    Struct(Vec<StructField>),
+}
+
+impl Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        match self.kind {
+            VariantKind::Unit => (),
+            VariantKind::Tuple(ref fields) => {
+                let tys = fields.iter().map(|field| field.ty.name.clone()).collect::<Vec<String>>();
+                write!(f, "({})", tys.join(", "))?;
+            }
+            VariantKind::Struct(ref fields) => {
+                let fields = fields.iter()
+                    .map(|field| format!("{}: {}",
+                                         field.ident.clone().unwrap_or("_".to_string()),
+                                         field.ty.name))
+                    .collect::<Vec<String>>();
+                write!(f, " {{ {} }}", fields.join(", "))?;
+            }
+        }
+        if let Some(ref discriminant) = self.discriminant {
+            write!(f, " = {}", discriminant)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct MethodSig {
+    pub generics: Generics,
     pub unsafety: Unsafety,
     pub constness: Constness,
     pub abi: Abi,
-    pub header: String,
+    pub decl: FnDecl,
+}
+
+/// The arguments and return type of a function.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct FnDecl {
+    pub inputs: Vec<FnArg>,
+    /// The return type, if it isn't `()`.
+    pub output: Option<String>,
+    /// Whether the argument list ends with `...`, as in C functions.
+    pub variadic: bool,
+}
+
+impl FnDecl {
+    /// Whether the first argument is `self`, in any of its forms.
+    pub fn has_receiver(&self) -> bool {
+        match self.inputs.first() {
+            Some(&FnArg::SelfArg(..)) => true,
+            _                         => false,
+        }
+    }
+}
+
+impl Display for FnDecl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut inputs = self.inputs.iter().map(|i| i.to_string()).collect::<Vec<String>>();
+        if self.variadic {
+            inputs.push("...".to_string());
+        }
+        write!(f, "({})", inputs.join(", "))?;
+        if let Some(ref output) = self.output {
+            write!(f, " -> {}", output)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum FnArg {
+    /// The `self` receiver of a method.
+    SelfArg(SelfKind),
+    /// Any other argument. The pattern is kept as written, so bindings like `mut x` and
+    /// destructuring like `(a, b)` are preserved.
+    Arg {
+        pattern: String,
+        ty: String,
+    },
+}
+
+impl Display for FnArg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FnArg::SelfArg(ref kind) => write!(f, "{}", kind),
+            FnArg::Arg { ref pattern, ref ty } => write!(f, "{}: {}", pattern, ty),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum SelfKind {
+    /// `self` or `mut self`
+    Value(Mutability),
+    /// `&self`, `&'a mut self`, with the lifetime if there is one
+    Region(Option<String>, Mutability),
+    /// `self: Box<Self>`, with the type
+    Explicit(String, Mutability),
+}
+
+impl Display for SelfKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SelfKind::Value(ref mutability) => write!(f, "{}self", mutability.prefix()),
+            SelfKind::Region(ref lifetime, ref mutability) => {
+                write!(f, "&")?;
+                if let Some(ref lifetime) = *lifetime {
+                    write!(f, "{} ", lifetime)?;
+                }
+                write!(f, "{}self", mutability.prefix())
+            },
+            SelfKind::Explicit(ref ty, ref mutability) => {
+                write!(f, "{}self: {}", mutability.prefix(), ty)
+            },
+        }
+    }
 }
 
 // There are redundant enums because it isn't possible to derive
@@ -39,9 +251,31 @@ pub enum Constness {
     NotConst,
 }
 
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Mutability {
+    Mutable,
+    Immutable,
+}
+
+impl Mutability {
+    /// `mut ` for mutable bindings, or an empty string.
+    pub fn prefix(&self) -> &'static str {
+        match *self {
+            Mutability::Mutable   => "mut ",
+            Mutability::Immutable => "",
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Visibility {
     Public,
+    /// `pub(crate)`
+    Crate,
+    /// `pub(super)`
+    Super,
+    /// `pub(in some::path)`
+    InPath(ModPath),
     Private,
     Inherited,
 }
@@ -71,9 +305,122 @@ pub enum Abi {
     Thiscall
 }
 
+impl Abi {
+    /// The name of the ABI, as written in `extern "name"`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Abi::Cdecl             => "cdecl",
+            Abi::Stdcall           => "stdcall",
+            Abi::Fastcall          => "fastcall",
+            Abi::Vectorcall        => "vectorcall",
+            Abi::Aapcs             => "aapcs",
+            Abi::Win64             => "win64",
+            Abi::SysV64            => "sysv64",
+            Abi::PtxKernel         => "ptx-kernel",
+            Abi::Msp430Interrupt   => "msp430-interrupt",
+            Abi::X86Interrupt      => "x86-interrupt",
+            Abi::Rust              => "Rust",
+            Abi::C                 => "C",
+            Abi::System            => "system",
+            Abi::RustIntrinsic     => "rust-intrinsic",
+            Abi::RustCall          => "rust-call",
+            Abi::PlatformIntrinsic => "platform-intrinsic",
+            Abi::Unadjusted        => "unadjusted",
+            Abi::Thiscall          => "thiscall",
+        }
+    }
+}
+
 #[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Struct {
+    pub kind: ast_ty_wrappers::StructKind,
+    pub generics: Generics,
+    pub fields: Vec<StructField>,
+    pub impls: Vec<ImplBlock>,
+
+    /// Whether the struct is marked `#[non_exhaustive]`, so it can't be constructed with a struct
+    /// expression outside its crate.
+    pub non_exhaustive: bool,
+}
+
+#[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Union {
+    pub generics: Generics,
     pub fields: Vec<StructField>,
+    pub impls: Vec<ImplBlock>,
+}
+
+/// The part of an impl block before its items, like `impl<T: Clone> Foo<T>` or
+/// `unsafe impl Send for Foo`.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ImplHeader {
+    pub unsafety: Unsafety,
+    pub generics: Generics,
+    /// The implemented trait, with its type parameters.
+    pub trait_: Option<String>,
+    pub for_: ast_ty_wrappers::Ty,
+}
+
+impl Display for ImplHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.unsafety == Unsafety::Unsafe {
+            write!(f, "unsafe ")?;
+        }
+        write!(f, "impl{} ", self.generics)?;
+        if let Some(ref trait_) = self.trait_ {
+            write!(f, "{} for ", trait_)?;
+        }
+        write!(f, "{}{}", self.for_.name, self.generics.where_clause())
+    }
+}
+
+/// An impl block of a type, with the items it defines.
+#[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ImplBlock {
+    pub header: ImplHeader,
+    pub items: DocRelatedItems,
+
+    /// The implemented trait, if it's documented in the same crate.
+    pub trait_link: Option<DocLink>,
+
+    /// The provided methods of the implemented trait, once they're known from the trait's
+    /// documentation.
+    pub provided_methods: Vec<ProvidedMethod>,
+
+    /// The names of the block's functions that take `self`, once they're known from the
+    /// functions' documentation. The others are associated functions.
+    pub methods: Vec<String>,
+}
+
+impl ImplBlock {
+    /// Whether the block's function of the given name takes `self`.
+    pub fn is_method(&self, name: &str) -> bool {
+        self.methods.iter().any(|method| method == name)
+    }
+
+    /// Whether the block defines a method the trait provides a default for.
+    pub fn overrides(&self, name: &str) -> bool {
+        self.provided_methods.iter().any(|method| method.overridden && method.link.name == name)
+    }
+
+    /// The provided methods of the trait that the block doesn't define, so the trait's defaults
+    /// are used.
+    pub fn inherited_methods(&self) -> Vec<&DocLink> {
+        self.provided_methods.iter()
+            .filter(|method| !method.overridden)
+            .map(|method| &method.link)
+            .collect()
+    }
+}
+
+/// A provided method of the trait an impl block implements.
+#[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ProvidedMethod {
+    /// The trait's documentation of the method.
+    pub link: DocLink,
+
+    /// Whether the impl block defines the method itself instead of using the trait's default.
+    pub overridden: bool,
 }
 
 // TODO: Should these have their own documentation?
@@ -86,6 +433,25 @@ pub struct StructField {
     pub path: ModPath,
 }
 
+impl StructField {
+    /// The name of the field, or its position for the fields of tuple structs.
+    pub fn name(&self, index: usize) -> String {
+        match self.ident {
+            Some(ref ident) => ident.clone(),
+            None            => index.to_string(),
+        }
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.vis == Visibility::Public
+    }
+
+    /// The field with its visibility and type, like `pub name: Type` or `0: Type`.
+    pub fn declaration(&self, index: usize) -> String {
+        format!("{} {}: {}", self.vis, self.name(index), self.ty.name).trim().to_string()
+    }
+}
+
 #[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Module {
     pub is_crate: bool,
@@ -97,21 +463,63 @@ pub struct Constant {
     pub expr: String,
 }
 
+#[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Static {
+    pub ty: ast_ty_wrappers::Ty,
+    pub mutability: Mutability,
+    pub expr: Option<String>,
+    /// The ABI of the `extern` block the static was declared in, if any.
+    pub abi: Option<Abi>,
+}
+
+#[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MacroRules {
+    pub matchers: Vec<String>,
+}
+
+#[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Typedef {
+    pub generics: Generics,
+    pub ty: ast_ty_wrappers::Ty,
+}
+
 #[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Function {
-    pub header: String,
+    pub decl: FnDecl,
     pub generics: Generics,
     pub unsafety: Unsafety,
     pub constness: Constness,
     pub abi: Abi,
     pub kind: ast_ty_wrappers::FnKind,
+    /// The impl block containing a method, if it was defined in one.
+    pub impl_header: Option<ImplHeader>,
+
+    /// Whether the function takes `self`, which makes it a method instead of an associated
+    /// function like `new`.
+    pub has_receiver: bool,
+
+    /// The documented types named by the return type, like the method's own type for methods
+    /// returning `Self`. Filled in once the other items of the crate are known.
+    pub return_links: Vec<DocLink>,
 }
 
 #[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Trait {
     pub unsafety: Unsafety,
-    // pub generics: Generics,
-    // pub bounds: Vec<TyParamBound>,
+    pub generics: Generics,
+    /// The supertraits and other bounds on `Self`, as written.
+    pub bounds: Vec<String>,
+}
+
+impl Trait {
+    /// The bounds on `Self` prefixed with a colon, or an empty string if there are none.
+    pub fn bounds_clause(&self) -> String {
+        if self.bounds.is_empty() {
+            "".to_string()
+        } else {
+            format!(": {}", self.bounds.join(" + "))
+        }
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
@@ -121,16 +529,31 @@ pub struct TraitItem {
 
 #[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub enum TraitItemKind {
-    Const(ast_ty_wrappers::Ty, Option<String>),
-    Method(MethodSig),
-    Type(Option<ast_ty_wrappers::Ty>),
+    Const {
+        ty: ast_ty_wrappers::Ty,
+        /// The default value, which implementors of the trait can leave out.
+        default: Option<String>,
+    },
+    /// A method, along with the source of its default body if the trait provides one.
+    Method(MethodSig, Option<String>),
+    /// An associated type, with its bounds as written, like `?Sized`, and the default type if the
+    /// trait gives one.
+    Type(Vec<String>, Option<ast_ty_wrappers::Ty>),
     Macro(String),
 }
 
 impl TraitItemKind {
+    /// Whether this is a method with a default implementation.
+    pub fn is_provided_method(&self) -> bool {
+        match *self {
+            TraitItemKind::Method(_, Some(_)) => true,
+            _ => false,
+        }
+    }
+
     pub fn get_category_string(&self) -> &str {
         match *self {
-            TraitItemKind::Const(..)  => &"const",
+            TraitItemKind::Const {..} => &"const",
             TraitItemKind::Method(..) => &"fn",
             TraitItemKind::Type(..)   => &"type",
             TraitItemKind::Macro(..)  => &"macro",
@@ -0,0 +1,270 @@
+//! Comparison of the documentation generated for two versions of a crate, for reviewing changes
+//! to its public API.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::path::Path;
+
+use conversion::{DocInnerData, DocType, Documentation, Generics, StructField, Visibility};
+use store::{self, CorruptFile};
+use ::errors::*;
+
+/// An item that only exists in one of the compared versions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DiffItem {
+    pub path: String,
+    pub doc_type: DocType,
+    pub signature: Option<String>,
+
+    /// Whether the item isn't part of the crate's public API, so adding or removing it breaks
+    /// nothing.
+    pub private: bool,
+}
+
+/// An item that exists in both versions, but with a different signature or visibility.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ItemChange {
+    pub path: String,
+    pub doc_type: DocType,
+    pub old_signature: Option<String>,
+    pub new_signature: Option<String>,
+
+    /// The reasons the change breaks users of the item, like a public field becoming private.
+    /// Empty if the change isn't known to break anything.
+    pub breaking: Vec<String>,
+}
+
+impl ItemChange {
+    pub fn is_breaking(&self) -> bool {
+        !self.breaking.is_empty()
+    }
+}
+
+/// The differences between the documentation of two versions of a crate, sorted by path.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ApiDiff {
+    pub added: Vec<DiffItem>,
+    pub removed: Vec<DiffItem>,
+    pub modified: Vec<ItemChange>,
+
+    /// The documentation files that couldn't be read, so their items weren't compared.
+    pub unreadable: Vec<CorruptFile>,
+}
+
+impl ApiDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty() &&
+            self.unreadable.is_empty()
+    }
+
+    /// Whether updating to the new version can break users of the crate.
+    pub fn is_breaking(&self) -> bool {
+        self.breaking_count() > 0
+    }
+
+    /// The number of changes that can break users of the crate. Removed items do unless they were
+    /// private.
+    pub fn breaking_count(&self) -> usize {
+        self.removed.iter().filter(|item| !item.private).count() +
+            self.modified.iter().filter(|change| change.is_breaking()).count()
+    }
+}
+
+/// Identifies the same item across the two versions.
+type ItemKey = (String, DocType, usize);
+
+fn item_key(doc: &Documentation) -> ItemKey {
+    (doc.mod_path.to_string(), doc.get_type(), doc.disambiguator)
+}
+
+/// Signatures can span several lines, like those of structs, so they're compared and printed
+/// with their whitespace collapsed.
+fn flat_signature(doc: &Documentation) -> Option<String> {
+    doc.signature()
+        .map(|signature| signature.split_whitespace().collect::<Vec<&str>>().join(" "))
+}
+
+fn diff_item(doc: &Documentation) -> DiffItem {
+    DiffItem {
+        path: doc.mod_path.to_string(),
+        doc_type: doc.get_type(),
+        signature: flat_signature(doc),
+        private: doc.is_private(),
+    }
+}
+
+/// The generic parameters and fields of structs and unions.
+fn struct_parts(doc: &Documentation) -> Option<(&Generics, &[StructField])> {
+    match doc.inner_data {
+        DocInnerData::StructDoc(ref struct_) => Some((&struct_.generics, &struct_.fields)),
+        DocInnerData::UnionDoc(ref union_)   => Some((&union_.generics, &union_.fields)),
+        _                                    => None,
+    }
+}
+
+/// Finds the changes to an item that take something away from its users.
+fn breaking_reasons(old: &Documentation, new: &Documentation) -> Vec<String> {
+    let mut reasons = Vec::new();
+
+    if old.visibility == Some(Visibility::Public) && new.visibility != Some(Visibility::Public) {
+        reasons.push("no longer public".to_string());
+    }
+    if old.is_private() {
+        return reasons;
+    }
+
+    match (struct_parts(old), struct_parts(new)) {
+        // The signatures of structs also change with their private fields, so their parts are
+        // compared instead.
+        (Some((old_generics, old_fields)), Some((new_generics, new_fields))) => {
+            if old_generics != new_generics {
+                reasons.push("generic parameters or bounds changed".to_string());
+            }
+
+            for (i, field) in old_fields.iter().enumerate().filter(|&(_, f)| f.is_public()) {
+                let name = field.name(i);
+                let new_field = new_fields.iter()
+                    .enumerate()
+                    .find(|&(j, f)| f.name(j) == name);
+
+                match new_field {
+                    Some((_, f)) if !f.is_public() => {
+                        reasons.push(format!("field `{}` is no longer public", name))
+                    },
+                    Some((_, f)) if f.ty.name != field.ty.name => {
+                        reasons.push(format!("type of field `{}` changed", name))
+                    },
+                    Some(_) => (),
+                    None    => reasons.push(format!("public field `{}` was removed", name)),
+                }
+            }
+        },
+        _ => {
+            if flat_signature(old) != flat_signature(new) {
+                reasons.push("signature changed".to_string());
+            }
+        },
+    }
+
+    reasons
+}
+
+/// Compares the documentation of two versions of a crate.
+pub fn diff_docs(old: &[Documentation], new: &[Documentation]) -> ApiDiff {
+    let old_items: BTreeMap<ItemKey, &Documentation> =
+        old.iter().map(|doc| (item_key(doc), doc)).collect();
+    let new_items: BTreeMap<ItemKey, &Documentation> =
+        new.iter().map(|doc| (item_key(doc), doc)).collect();
+
+    let added = new_items.iter()
+        .filter(|&(key, _)| !old_items.contains_key(key))
+        .map(|(_, doc)| diff_item(doc))
+        .collect();
+
+    let removed = old_items.iter()
+        .filter(|&(key, _)| !new_items.contains_key(key))
+        .map(|(_, doc)| diff_item(doc))
+        .collect();
+
+    let modified = old_items.iter()
+        .filter_map(|(key, old_doc)| new_items.get(key).map(|new_doc| (old_doc, new_doc)))
+        .filter_map(|(old_doc, new_doc)| {
+            let old_signature = flat_signature(old_doc);
+            let new_signature = flat_signature(new_doc);
+            let breaking = breaking_reasons(old_doc, new_doc);
+
+            if old_signature == new_signature && breaking.is_empty() {
+                return None;
+            }
+
+            Some(ItemChange {
+                path: old_doc.mod_path.to_string(),
+                doc_type: old_doc.get_type(),
+                old_signature: old_signature,
+                new_signature: new_signature,
+                breaking: breaking,
+            })
+        })
+        .collect();
+
+    ApiDiff {
+        added: added,
+        removed: removed,
+        modified: modified,
+        unreadable: Vec::new(),
+    }
+}
+
+/// Compares two directories of generated documentation, like the store's directories for two
+/// versions of a crate. Files that can't be read are listed in the diff instead of failing it.
+pub fn diff_doc_dirs(old_dir: &Path, new_dir: &Path) -> Result<ApiDiff> {
+    let (old, mut unreadable) = store::read_docs_in_dir(old_dir)?;
+    let (new, new_unreadable) = store::read_docs_in_dir(new_dir)?;
+    unreadable.extend(new_unreadable);
+
+    let mut api_diff = diff_docs(&old, &new);
+    api_diff.unreadable = unreadable;
+    Ok(api_diff)
+}
+
+impl Display for DiffItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.signature {
+            Some(ref signature) => write!(f, "{}: {}", self.path, signature),
+            None                => write!(f, "{}", self.path),
+        }
+    }
+}
+
+impl Display for ItemChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+        if self.is_breaking() {
+            write!(f, " [breaking: {}]", self.breaking.join(", "))?;
+        }
+
+        if self.old_signature != self.new_signature {
+            let none = String::new();
+            write!(f, "\n      old: {}", self.old_signature.as_ref().unwrap_or(&none))?;
+            write!(f, "\n      new: {}", self.new_signature.as_ref().unwrap_or(&none))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ApiDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No API changes.");
+        }
+
+        let mut sections = Vec::new();
+
+        if !self.added.is_empty() {
+            let lines: Vec<String> = self.added.iter()
+                .map(|item| format!("  + {}", item))
+                .collect();
+            sections.push(format!("Added:\n{}", lines.join("\n")));
+        }
+        if !self.removed.is_empty() {
+            let lines: Vec<String> = self.removed.iter()
+                .map(|item| format!("  - {}", item))
+                .collect();
+            sections.push(format!("Removed:\n{}", lines.join("\n")));
+        }
+        if !self.modified.is_empty() {
+            let lines: Vec<String> = self.modified.iter()
+                .map(|change| format!("  ~ {}", change))
+                .collect();
+            sections.push(format!("Modified:\n{}", lines.join("\n")));
+        }
+        if !self.unreadable.is_empty() {
+            let lines: Vec<String> = self.unreadable.iter()
+                .map(|file| format!("  ! {}", file))
+                .collect();
+            sections.push(format!("Unreadable:\n{}", lines.join("\n")));
+        }
+
+        write!(f, "{}", sections.join("\n\n"))
+    }
+}
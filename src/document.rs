@@ -1,11 +1,14 @@
 use std::fmt::{self, Display};
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::slice;
 
 use syntax::ast;
-use syntax::codemap::{Span};
+use syntax::codemap::{CodeMap, Span, DUMMY_SP};
 use syntax::print::pprust;
 
+use paths;
+use ::errors::*;
+
 /// Represents a single portion of a full module path.
 #[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
 pub struct PathSegment {
@@ -17,6 +20,22 @@ pub struct PathSegment {
     // pub parameters: Option<P<PathParameters>>,
 }
 
+/// The identifier without the `r#` prefix of a raw identifier, like `async` for `r#async`. Raw
+/// identifiers are kept as written in paths and names, but are searched for by their bare name.
+pub fn unraw(identifier: &str) -> &str {
+    if identifier.starts_with("r#") {
+        &identifier[2..]
+    } else {
+        identifier
+    }
+}
+
+/// The path with the `r#` prefix of its raw identifiers removed, like `type::async` for
+/// `r#type::r#async`.
+pub fn unraw_path(path: &str) -> String {
+    path.split("::").map(unraw).collect::<Vec<&str>>().join("::")
+}
+
 impl Display for PathSegment {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.identifier)
@@ -100,7 +119,29 @@ impl ModPath {
     }
 
     pub fn to_filepath(&self) -> PathBuf {
-        self.0.iter().fold(PathBuf::new(), |res, s| res.join(s.identifier.clone()))
+        self.0.iter().fold(PathBuf::new(), |res, s| res.join(paths::sanitize_filename(&s.identifier)))
+    }
+
+    /// The inverse of `to_filepath`. Fails for paths that `to_filepath` couldn't have produced,
+    /// like absolute paths or ones with unescaped characters.
+    pub fn from_filepath<P: AsRef<Path>>(path: P) -> Result<ModPath> {
+        let path = path.as_ref();
+        let invalid = || ErrorKind::InvalidDocPath(path.to_path_buf());
+
+        let mut segments = Vec::new();
+        for component in path.components() {
+            let name = match component {
+                Component::Normal(name) => name.to_str().ok_or_else(&invalid)?,
+                _ => bail!(invalid()),
+            };
+            let identifier = paths::unsanitize_filename(name).ok_or_else(&invalid)?;
+            segments.push(PathSegment { identifier: identifier });
+        }
+
+        if segments.is_empty() {
+            bail!(invalid());
+        }
+        Ok(ModPath(segments))
     }
 
     pub fn segments(&self) -> slice::Iter<PathSegment> {
@@ -153,3 +194,39 @@ impl Display for CrateInfo {
         write!(f, "{}-{}", self.name, self.version)
     }
 }
+
+/// The location of an item's definition in its crate's source code.
+#[derive(Clone, Debug, Serialize, Deserialize, Hash, Eq, PartialEq)]
+pub struct SourceSpan {
+    pub file: PathBuf,
+
+    /// The first line of the definition, starting from 1.
+    pub lo_line: usize,
+
+    /// The last line of the definition, inclusive.
+    pub hi_line: usize,
+}
+
+impl SourceSpan {
+    /// Resolves a span from the AST into file and line information. Returns `None` for spans that
+    /// don't point into a source file, like those of generated items.
+    pub fn from_span(codemap: &CodeMap, span: Span) -> Option<SourceSpan> {
+        if span == DUMMY_SP {
+            return None;
+        }
+
+        let lo = codemap.lookup_char_pos(span.lo);
+        let hi = codemap.lookup_char_pos(span.hi);
+        Some(SourceSpan {
+            file: PathBuf::from(lo.file.name.clone()),
+            lo_line: lo.line,
+            hi_line: hi.line,
+        })
+    }
+}
+
+impl Display for SourceSpan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}-{}", self.file.display(), self.lo_line, self.hi_line)
+    }
+}
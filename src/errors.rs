@@ -1,3 +1,9 @@
+//! Errors are structured so that callers can match on the kind of failure with `Error::kind`,
+//! instead of parsing messages. Errors from the standard library and other crates are kept as the
+//! cause of the structured error.
+
+use std::path::PathBuf;
+
 error_chain! {
     errors {
         NoHomeDirectory {
@@ -22,6 +28,115 @@ error_chain! {
             description("crate could not be parsed")
             display("Failed to parse crate {}: {}", krate, err)
         }
+        StoreVersionMismatch(path: PathBuf, found: u32, expected: u32) {
+            description("doc store version mismatch")
+            display("Doc store version mismatch in {} (found {}, expected {}), please regenerate",
+                    path.display(), found, expected)
+        }
+        ReadFailed(path: PathBuf) {
+            description("file could not be read")
+            display("Could not read file {}", path.display())
+        }
+        WriteFailed(path: PathBuf) {
+            description("file could not be written")
+            display("Failed to write file {}", path.display())
+        }
+        DeserializeFailed(path: PathBuf) {
+            description("file could not be deserialized")
+            display("Could not deserialize file {}", path.display())
+        }
+        SerializeFailed(path: PathBuf) {
+            description("data could not be serialized")
+            display("Could not serialize data for {}", path.display())
+        }
+        CreateDirectoryFailed(path: PathBuf) {
+            description("directory could not be created")
+            display("Failed to create directory {}", path.display())
+        }
+        RemoveDirectoryFailed(path: PathBuf) {
+            description("directory could not be removed")
+            display("Failed to remove directory {}", path.display())
+        }
+        RemoveFileFailed(path: PathBuf) {
+            description("file could not be removed")
+            display("Failed to remove file {}", path.display())
+        }
+        InvalidDocPath(path: PathBuf) {
+            description("invalid documentation path")
+            display("Not a documentation path: {}", path.display())
+        }
+        DocLoadFailed(item: String) {
+            description("documentation could not be loaded")
+            display("Could not load documentation for {}", item)
+        }
+        InvalidJobCount(jobs: String) {
+            description("invalid number of jobs")
+            display("Invalid number of jobs: {}", jobs)
+        }
+        CrateNotFound(krate: String) {
+            description("crate not found in doc store")
+            display("No documentation for crate {} in the doc store", krate)
+        }
+        StoreLayoutMismatch(krate: String, stored: String, chosen: String) {
+            description("crate documentation stored in another layout")
+            display("The documentation for {} is stored in the {} layout, not {}; remove it with \
+                     --remove first to regenerate it",
+                    krate, stored, chosen)
+        }
+        BreakingApiChanges(count: usize) {
+            description("breaking API changes")
+            display("Found {} breaking API changes", count)
+        }
+        InvalidRustdocJson(path: PathBuf, reason: String) {
+            description("invalid rustdoc JSON")
+            display("Invalid rustdoc JSON in {}: {}", path.display(), reason)
+        }
+        SkippedItems(count: usize) {
+            description("items left out of the documentation")
+            display("{} items couldn't be documented", count)
+        }
+        CoverageTooLow(coverage: f64, threshold: f64) {
+            description("documentation coverage below threshold")
+            display("Documentation coverage of {:.1}% is below the required {:.1}%",
+                    coverage, threshold)
+        }
+        InvalidCoverageThreshold(value: String) {
+            description("invalid coverage threshold")
+            display("Invalid coverage threshold: {}", value)
+        }
+        InvalidRegex(pattern: String, reason: String) {
+            description("invalid regular expression")
+            display("Invalid regular expression {}: {}", pattern, reason)
+        }
+        UnknownDocType(name: String) {
+            description("unknown documentation type")
+            display("Unknown documentation type: {}", name)
+        }
+        InvalidMergeInput(value: String) {
+            description("invalid documentation to merge")
+            display("Expected FEATURES=DIRECTORY to merge, like std,serde=path/to/docs: {}", value)
+        }
+        NoCrateModule(path: PathBuf) {
+            description("no crate module in documentation")
+            display("No crate module in the documentation in {}", path.display())
+        }
+        NoMigration(from: u32, to: u32) {
+            description("no migration between doc store versions")
+            display("No migration of the doc store from format version {} to {}, please regenerate",
+                    from, to)
+        }
+        CorruptFiles(count: usize) {
+            description("corrupt documentation files")
+            display("Found {} corrupt documentation files, please regenerate", count)
+        }
+        ModuleNotFound(module: String) {
+            description("module not found in crate")
+            display("No module {} in the crate", module)
+        }
+        ModulesNeedOneCrate {
+            description("--module given for several crates")
+            display("--module can only be used when generating a single crate")
+        }
         NameEncodingError(name: String) {
             description("Failed to encode name: {}")
         }
@@ -0,0 +1,221 @@
+//! Full-text searching over the doc comments of stored documentation, for finding items by what
+//! their documentation says instead of by name.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use conversion::Documentation;
+use document::{CrateInfo, ModPath};
+use paths;
+use search::SearchFilter;
+use store::{CrateIndex, StoreLocation};
+
+use ::errors::*;
+
+/// The number of characters shown before the first match in a snippet.
+const SNIPPET_BEFORE: usize = 30;
+
+/// The number of characters shown after the first match in a snippet.
+const SNIPPET_AFTER: usize = 60;
+
+/// An inverted index of the words in the doc comments of every stored item. It is saved to its own
+/// file next to the name search index.
+#[derive(Serialize, Deserialize)]
+pub struct FullTextIndex {
+    /// The indexed items, with their doc comments for showing snippets.
+    documents: Vec<IndexedDoc>,
+
+    /// Each word, lowercased, mapped to the documents it appears in and how often.
+    /// "thread" => [Posting { doc: 3, count: 2 }, ...]
+    postings: BTreeMap<String, Vec<Posting>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexedDoc {
+    location: StoreLocation,
+    text: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Posting {
+    doc: usize,
+    count: usize,
+}
+
+/// An item whose doc comment contains every word of a full-text query.
+#[derive(Clone, Debug)]
+pub struct FullTextResult {
+    pub location: StoreLocation,
+
+    /// How many times the words of the query appear in the doc comment in total.
+    pub score: usize,
+
+    /// The part of the doc comment around the first match, on a single line.
+    pub snippet: String,
+}
+
+impl CrateIndex for FullTextIndex {
+    fn new() -> Self {
+        FullTextIndex {
+            documents: Vec::new(),
+            postings: BTreeMap::new(),
+        }
+    }
+
+    fn index_path() -> Result<PathBuf> {
+        paths::full_text_index_path()
+    }
+
+    fn insert_docs(&mut self, _crate_info: &CrateInfo, documents: &[Documentation]) {
+        let first_new = self.documents.len();
+        for document in documents.iter() {
+            let text = document.attrs.doc_body();
+            if !text.trim().is_empty() {
+                self.documents.push(IndexedDoc {
+                    location: document.to_store_location(),
+                    text: text,
+                });
+            }
+        }
+        self.add_postings(first_new);
+    }
+
+    fn append(&mut self, other: FullTextIndex) {
+        let first_new = self.documents.len();
+        self.documents.extend(other.documents);
+        self.add_postings(first_new);
+    }
+
+    /// The postings are only rebuilt if one of the crates was indexed, since removing documents
+    /// shifts the positions of those after them.
+    fn remove_crates(&mut self, crates: &[CrateInfo]) {
+        let len = self.documents.len();
+        self.documents.retain(|doc| {
+            !crates.iter().any(|krate| is_same_crate(&doc.location.crate_info, krate))
+        });
+        if self.documents.len() != len {
+            self.postings.clear();
+            self.add_postings(0);
+        }
+    }
+
+    fn remove_docs<F>(&mut self, crate_info: &CrateInfo, is_removed: F)
+        where F: Fn(&ModPath) -> bool
+    {
+        let len = self.documents.len();
+        self.documents.retain(|doc| {
+            !(is_same_crate(&doc.location.crate_info, crate_info) &&
+              is_removed(&doc.location.mod_path))
+        });
+        if self.documents.len() != len {
+            self.postings.clear();
+            self.add_postings(0);
+        }
+    }
+}
+
+impl FullTextIndex {
+    /// Adds the postings of the documents from position `first` on.
+    fn add_postings(&mut self, first: usize) {
+        for (i, doc) in self.documents.iter().enumerate().skip(first) {
+            let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+            for word in words(&doc.text) {
+                *counts.entry(word).or_insert(0) += 1;
+            }
+            for (word, count) in counts.into_iter() {
+                self.postings.entry(word)
+                    .or_insert(Vec::new())
+                    .push(Posting { doc: i, count: count });
+            }
+        }
+    }
+
+    /// Finds the items matching the filter whose doc comments contain every word of the query.
+    /// Returns at most `limit` results, the ones mentioning the query's words most often first.
+    pub fn search(&self, query: &str, limit: usize, filter: &SearchFilter) -> Vec<FullTextResult> {
+        let terms = words(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scores: BTreeMap<usize, usize> = BTreeMap::new();
+        for (i, term) in terms.iter().enumerate() {
+            let postings = match self.postings.get(term) {
+                Some(postings) => postings,
+                None           => return Vec::new(),
+            };
+
+            let mut next = BTreeMap::new();
+            for posting in postings.iter() {
+                if i == 0 {
+                    next.insert(posting.doc, posting.count);
+                } else if let Some(score) = scores.get(&posting.doc) {
+                    next.insert(posting.doc, score + posting.count);
+                }
+            }
+            scores = next;
+        }
+
+        let mut results: Vec<FullTextResult> = scores.into_iter()
+            .map(|(i, score)| (&self.documents[i], score))
+            .filter(|&(doc, _)| filter.matches(&doc.location))
+            .map(|(doc, score)| FullTextResult {
+                location: doc.location.clone(),
+                score: score,
+                snippet: snippet(&doc.text, &terms[0]),
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.score.cmp(&a.score)
+                .then_with(|| a.location.mod_path.to_string().cmp(&b.location.mod_path.to_string()))
+        });
+        results.truncate(limit);
+        results
+    }
+}
+
+fn is_same_crate(a: &CrateInfo, b: &CrateInfo) -> bool {
+    a.name == b.name && a.version == b.version
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The lowercased words of some text, in order.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !is_word_char(c))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// The part of the text around the first occurrence of the word, with its whitespace collapsed
+/// and ellipses where it was cut.
+fn snippet(text: &str, word: &str) -> String {
+    let flat = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let chars: Vec<char> = flat.chars().collect();
+    let lower: Vec<char> = chars.iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let word: Vec<char> = word.chars().collect();
+
+    let found = (0..chars.len()).find(|&i| {
+        lower[i..].starts_with(&word) &&
+            (i == 0 || !is_word_char(lower[i - 1])) &&
+            lower.get(i + word.len()).map_or(true, |c| !is_word_char(*c))
+    });
+    let position = found.unwrap_or(0);
+
+    let start = position.saturating_sub(SNIPPET_BEFORE);
+    let end = (position + word.len() + SNIPPET_AFTER).min(chars.len());
+    let mut snippet: String = chars[start..end].iter().collect();
+    if start > 0 {
+        snippet = format!("...{}", snippet.trim_left());
+    }
+    if end < chars.len() {
+        snippet = format!("{}...", snippet.trim_right());
+    }
+    snippet
+}
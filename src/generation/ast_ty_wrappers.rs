@@ -1,11 +1,13 @@
 use std::collections::HashMap;
+use std::env;
 use std::{slice, vec};
 
 use syntax::ast;
 use syntax::abi;
 use syntax::print::pprust;
 
-use document::ModPath;
+use document::{ModPath, SourceSpan};
+use super::ty_printer;
 
 // FIXME: Duplication from librustdoc
 pub struct ListAttributesIter<'a> {
@@ -63,26 +65,227 @@ impl<I: IntoIterator<Item=ast::NestedMetaItem>> NestedAttributesExt for I {
     }
 }
 
+/// The contents of a `#[deprecated]` attribute.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Deprecation {
+    pub since: Option<String>,
+    pub note: Option<String>,
+}
+
+impl Deprecation {
+    fn from_ast(attr: &ast::Attribute) -> Deprecation {
+        let mut deprecation = Deprecation {
+            since: None,
+            note: None,
+        };
+
+        // #[deprecated = "note"]
+        if let Some(note) = attr.value_str() {
+            deprecation.note = Some(note.to_string());
+        }
+
+        // #[deprecated(since = "1.0", note = "note")]
+        if let Some(items) = attr.meta_item_list() {
+            for item in items.iter() {
+                let value = item.value_str().map(|v| v.to_string());
+                if item.check_name("since") {
+                    deprecation.since = value;
+                } else if item.check_name("note") {
+                    deprecation.note = value;
+                }
+            }
+        }
+
+        deprecation
+    }
+}
+
+/// The stability of an item, from the `#[stable]` and `#[unstable]` attributes of crates that
+/// track their stability like the standard library does.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Stability {
+    /// `#[stable(feature = "x", since = "1.0")]`
+    Stable {
+        since: Option<String>,
+    },
+
+    /// `#[unstable(feature = "x", issue = "123")]`
+    Unstable {
+        feature: Option<String>,
+        issue: Option<String>,
+    },
+}
+
+impl Stability {
+    fn from_ast(attr: &ast::Attribute) -> Stability {
+        let mut since = None;
+        let mut feature = None;
+        let mut issue = None;
+        if let Some(items) = attr.meta_item_list() {
+            for item in items.iter() {
+                let value = item.value_str().map(|v| v.to_string());
+                if item.check_name("since") {
+                    since = value;
+                } else if item.check_name("feature") {
+                    feature = value;
+                } else if item.check_name("issue") {
+                    issue = value;
+                }
+            }
+        }
+
+        if attr.check_name("unstable") {
+            Stability::Unstable {
+                feature: feature,
+                issue: issue,
+            }
+        } else {
+            Stability::Stable {
+                since: since,
+            }
+        }
+    }
+
+    pub fn is_unstable(&self) -> bool {
+        match *self {
+            Stability::Unstable { .. } => true,
+            Stability::Stable { .. }   => false,
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Attributes {
     pub doc_strings: Vec<String>,
+    pub deprecation: Option<Deprecation>,
+    pub stability: Option<Stability>,
+
+    /// The predicates of the `#[cfg(...)]` attributes on the item, like `feature = "x"`.
+    pub cfgs: Vec<String>,
+
+    /// The message of a `#[must_use]` attribute, which is empty if it has none.
+    pub must_use: Option<String>,
+
+    /// Whether the item is marked `#[doc(hidden)]`. Hidden items are only documented when asked
+    /// for.
+    pub hidden: bool,
+
+    /// Other names the item can be found by, from `#[doc(alias = "...")]` attributes.
+    pub aliases: Vec<String>,
+
+    /// Attributes that affect code generation or layout, like `#[inline]` or `#[repr(C)]`, as
+    /// they were written. Only those in `CODEGEN_ATTRIBUTES` are kept.
+    pub codegen_attrs: Vec<String>,
+
+    /// The features of the generations the item was found in, if its documentation was merged
+    /// from generations with different features and it was missing from some of them.
+    pub feature_sets: Vec<Vec<String>>,
+
+    /// The features of each generation the item was found in with its signature there, if its
+    /// documentation was merged and its signature depends on the features.
+    pub feature_signatures: Vec<(Vec<String>, String)>,
+
+    /// Whether the item is inside a private module and isn't re-exported, so other crates can't
+    /// use it even if it's `pub`.
+    pub unreachable: bool,
 }
 
+/// The attributes worth showing with an item's signature, because they change how it can be
+/// used from other code or other languages. `#[non_exhaustive]` is explained by a notice instead.
+pub const CODEGEN_ATTRIBUTES: &[&str] = &[
+    "cold",
+    "export_name",
+    "inline",
+    "link_name",
+    "link_section",
+    "naked",
+    "no_mangle",
+    "repr",
+    "target_feature",
+    "track_caller",
+    "used",
+];
+
 impl Attributes {
     pub fn new() -> Attributes {
         Attributes {
             doc_strings: Vec::new(),
+            deprecation: None,
+            stability: None,
+            cfgs: Vec::new(),
+            must_use: None,
+            hidden: false,
+            aliases: Vec::new(),
+            codegen_attrs: Vec::new(),
+            feature_sets: Vec::new(),
+            feature_signatures: Vec::new(),
+            unreachable: false,
         }
     }
 
+    /// The whole doc comment, as Markdown.
+    pub fn doc_body(&self) -> String {
+        self.doc_strings.join("\n")
+    }
+
+    /// The first paragraph of the doc comment, which ends at the first blank line, for showing
+    /// in summaries. Returns `None` for items without documentation.
+    pub fn summary_paragraph(&self) -> Option<String> {
+        let body = self.doc_body();
+        let paragraph = body.lines()
+            .skip_while(|line| line.trim().is_empty())
+            .take_while(|line| !line.trim().is_empty())
+            .collect::<Vec<&str>>()
+            .join("\n");
+
+        if paragraph.is_empty() {
+            None
+        } else {
+            Some(paragraph)
+        }
+    }
+
+    /// The features named by `feature = "x"` in the item's `#[cfg(...)]` attributes, along with
+    /// those of the generations it was found in if it was merged, for searching by feature.
+    pub fn features(&self) -> Vec<String> {
+        let mut features = Vec::new();
+        for cfg in self.cfgs.iter() {
+            let mut rest = &cfg[..];
+            while let Some(start) = rest.find("feature") {
+                rest = rest[start + "feature".len()..].trim_left();
+                if !rest.starts_with('=') {
+                    continue;
+                }
+                rest = rest[1..].trim_left();
+                if !rest.starts_with('"') {
+                    continue;
+                }
+                let end = match rest[1..].find('"') {
+                    Some(end) => end + 1,
+                    None      => break,
+                };
+                features.push(rest[1..end].to_string());
+                rest = &rest[end + 1..];
+            }
+        }
+        for set in self.feature_sets.iter() {
+            features.extend(set.iter().cloned());
+        }
+
+        features.sort();
+        features.dedup();
+        features
+    }
+
     pub fn from_ast(attrs: &[ast::Attribute]) -> Attributes {
         let mut doc_strings = vec![];
         let mut sp = None;
         let other_attrs: Vec<ast::Attribute> = attrs.iter().filter_map(|attr| {
+            let sugared = attr.is_sugared_doc;
             attr.with_desugared_doc(|attr| {
                 if let Some(value) = attr.value_str() {
                     if attr.check_name("doc") {
-                        doc_strings.push(value.to_string());
+                        doc_strings.extend(doc_lines(&value.to_string(), sugared));
                         if sp.is_none() {
                             sp = Some(attr.span);
                         }
@@ -93,8 +296,50 @@ impl Attributes {
                 Some(attr.clone())
             })
         }).collect();
+        let deprecation = other_attrs.iter()
+            .find(|attr| attr.check_name("deprecated"))
+            .map(Deprecation::from_ast);
+
+        let stability = other_attrs.iter()
+            .find(|attr| attr.check_name("stable") || attr.check_name("unstable"))
+            .map(Stability::from_ast);
+
+        let cfgs = other_attrs.iter()
+            .filter(|attr| attr.check_name("cfg"))
+            .filter_map(|attr| attr.meta_item_list())
+            .flat_map(|items| items.into_iter())
+            .map(|item| pprust::to_string(|s| s.print_meta_list_item(&item)))
+            .collect();
+
+        let must_use = other_attrs.iter()
+            .find(|attr| attr.check_name("must_use"))
+            .map(|attr| attr.value_str().map_or(String::new(), |value| value.to_string()));
+
+        let hidden = other_attrs.lists("doc").has_word("hidden");
+
+        let aliases = other_attrs.lists("doc")
+            .filter(|item| item.check_name("alias"))
+            .filter_map(|item| item.value_str())
+            .map(|alias| alias.to_string())
+            .collect();
+
+        let codegen_attrs = other_attrs.iter()
+            .filter(|attr| CODEGEN_ATTRIBUTES.iter().any(|name| attr.check_name(name)))
+            .map(|attr| pprust::attribute_to_string(attr))
+            .collect();
+
         Attributes {
             doc_strings: doc_strings,
+            deprecation: deprecation,
+            stability: stability,
+            cfgs: cfgs,
+            must_use: must_use,
+            hidden: hidden,
+            aliases: aliases,
+            codegen_attrs: codegen_attrs,
+            feature_sets: Vec::new(),
+            feature_signatures: Vec::new(),
+            unreachable: false,
             //other_attrs: other_attrs,
         }
     }
@@ -106,6 +351,21 @@ impl Attributes {
     }
 }
 
+/// The lines of the text of a `doc` attribute. Doc comments keep the space after their `///` or
+/// `//!` when they're turned into attributes, which is removed so they line up with the text of
+/// any `#[doc = "..."]` attributes mixed in with them.
+fn doc_lines(text: &str, sugared: bool) -> Vec<String> {
+    text.split('\n')
+        .map(|line| {
+            if sugared && line.starts_with(' ') {
+                line[1..].to_string()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
 #[derive(Clone, Debug)]
 pub struct StructField {
     type_: ast::Ty,
@@ -114,12 +374,27 @@ pub struct StructField {
 
 #[derive(Clone, Debug)]
 pub struct Struct {
+    pub ident: ast::Ident,
+    pub id: NodeId,
+    pub vis: ast::Visibility,
+    pub kind: StructKind,
+    pub fields: Vec<ast::StructField>,
+    pub generics: ast::Generics,
+    pub attrs: Vec<ast::Attribute>,
+    pub path: ModPath,
+    pub source: Option<SourceSpan>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Union {
     pub ident: ast::Ident,
     pub id: NodeId,
     pub vis: ast::Visibility,
     pub fields: Vec<ast::StructField>,
+    pub generics: ast::Generics,
     pub attrs: Vec<ast::Attribute>,
     pub path: ModPath,
+    pub source: Option<SourceSpan>,
 }
 
 #[derive(Clone, Debug)]
@@ -128,12 +403,15 @@ pub struct Function {
     pub unsafety: ast::Unsafety,
     pub constness: ast::Constness,
     pub decl: ast::FnDecl,
-    // TODO: Generics
+    pub generics: ast::Generics,
     pub vis: ast::Visibility,
     pub abi: abi::Abi,
     pub attrs: Vec<ast::Attribute>,
     pub kind: FnKind,
+    /// The impl block containing a method, if it was defined in one.
+    pub impl_header: Option<ImplHeader>,
     pub path: ModPath,
+    pub source: Option<SourceSpan>,
 }
 
 #[derive(Clone, Debug)]
@@ -142,16 +420,21 @@ pub struct Module {
     pub vis: ast::Visibility,
     pub imports: Vec<Import>,
     pub structs: Vec<Struct>,
+    pub unions: Vec<Union>,
     pub fns: Vec<Function>,
     pub mods: Vec<Module>,
     pub consts: Vec<Constant>,
+    pub statics: Vec<Static>,
+    pub typedefs: Vec<Typedef>,
     pub enums: Vec<Enum>,
     pub impls: Vec<Impl>,
     pub traits: Vec<Trait>,
     pub def_traits: Vec<DefaultImpl>,
+    pub macros: Vec<MacroRules>,
     pub is_crate: bool,
     pub attrs: Vec<ast::Attribute>,
     pub path: ModPath,
+    pub source: Option<SourceSpan>,
 
     /// A mapping from identifers that are 'use'd within this module to the full
     /// namespace they resolve to.
@@ -166,15 +449,20 @@ impl Module {
             attrs:      Vec::new(),
             imports:    Vec::new(),
             structs:    Vec::new(),
+            unions:     Vec::new(),
             fns:        Vec::new(),
             mods:       Vec::new(),
             consts:     Vec::new(),
+            statics:    Vec::new(),
+            typedefs:   Vec::new(),
             enums:      Vec::new(),
             impls:      Vec::new(),
             traits:     Vec::new(),
             def_traits: Vec::new(),
+            macros:     Vec::new(),
             is_crate:   false,
             path:       ModPath::new(),
+            source:     None,
             namespaces_to_paths: HashMap::new(),
         }
 
@@ -203,15 +491,24 @@ pub struct Trait {
     pub items: Vec<TraitItem>,
     pub ident: ast::Ident,
     pub unsafety: ast::Unsafety,
+    pub generics: ast::Generics,
+    pub bounds: ast::TyParamBounds,
+
+    /// The names of the traits in `bounds` and the paths they resolve to within the crate. The
+    /// paths are empty for traits that couldn't be resolved or are defined in other crates.
+    /// Filled in once the whole module has been visited.
+    pub supertraits: Vec<(String, ModPath)>,
     pub vis: ast::Visibility,
     pub attrs: Vec<ast::Attribute>,
     pub path: ModPath,
+    pub source: Option<SourceSpan>,
 }
 #[derive(Clone, Debug)]
 pub struct TraitItem {
     pub ident: ast::Ident,
     pub attrs: Vec<ast::Attribute>,
     pub path: ModPath,
+    pub source: Option<SourceSpan>,
     pub node: ast::TraitItemKind,
 }
 #[derive(Clone, Debug)]
@@ -219,8 +516,10 @@ pub struct Enum {
     pub ident: ast::Ident,
     pub vis: ast::Visibility,
     pub variants: Vec<ast::Variant>,
+    pub generics: ast::Generics,
     pub attrs: Vec<ast::Attribute>,
     pub path: ModPath,
+    pub source: Option<SourceSpan>,
 }
 
 #[derive(Clone, Debug)]
@@ -239,17 +538,97 @@ pub struct Constant {
     pub vis: ast::Visibility,
     pub attrs: Vec<ast::Attribute>,
     pub path: ModPath,
+    pub source: Option<SourceSpan>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Static {
+    pub type_: Ty,
+    pub mutability: ast::Mutability,
+    /// The initializer, which statics declared in `extern` blocks don't have.
+    pub expr: Option<ast::Expr>,
+    /// The ABI of the `extern` block the static was declared in, if any.
+    pub abi: Option<abi::Abi>,
+    pub ident: ast::Ident,
+    pub vis: ast::Visibility,
+    pub attrs: Vec<ast::Attribute>,
+    pub path: ModPath,
+    pub source: Option<SourceSpan>,
+}
+
+/// A declarative macro defined with `macro_rules!`.
+#[derive(Clone, Debug)]
+pub struct MacroRules {
+    pub ident: ast::Ident,
+    /// The matcher of each of the macro's rules, like `($x:expr)`.
+    pub matchers: Vec<String>,
+    pub exported: bool,
+    pub attrs: Vec<ast::Attribute>,
+    pub path: ModPath,
+    pub source: Option<SourceSpan>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Typedef {
+    pub type_: Ty,
+    pub generics: ast::Generics,
+    pub ident: ast::Ident,
+    pub vis: ast::Visibility,
+    pub attrs: Vec<ast::Attribute>,
+    pub path: ModPath,
+    pub source: Option<SourceSpan>,
 }
 
 #[derive(Clone, Debug)]
 pub struct Impl {
     pub unsafety: ast::Unsafety,
-    //pub generics: ast::Generics,
+    pub generics: ast::Generics,
     pub trait_: Option<ast::TraitRef>,
+    /// The name of the implemented trait and its path, as for `Trait::supertraits`. Filled in
+    /// when the impl is added to its module.
+    pub trait_link: Option<(String, ModPath)>,
     pub for_: ast::Ty,
     pub items: Vec<ast::ImplItem>,
     pub attrs: Vec<ast::Attribute>,
+    /// The path of the type the impl is for. Until the impl is added to its module, this is the
+    /// path of the module.
     pub path: ModPath,
+    pub source: Option<SourceSpan>,
+}
+
+impl Impl {
+    pub fn header(&self) -> ImplHeader {
+        ImplHeader {
+            unsafety: self.unsafety,
+            generics: self.generics.clone(),
+            trait_: self.trait_.clone(),
+            for_: self.for_.clone(),
+        }
+    }
+}
+
+/// The part of an impl block before its items, like `impl<T: Clone> Foo<T>`.
+#[derive(Clone, Debug)]
+pub struct ImplHeader {
+    pub unsafety: ast::Unsafety,
+    pub generics: ast::Generics,
+    pub trait_: Option<ast::TraitRef>,
+    pub for_: ast::Ty,
+}
+
+/// A `pub use` of an item or, for globs, of every public item in a module.
+#[derive(Clone, Debug)]
+pub struct Reexport {
+    /// The module containing the `pub use`.
+    pub module: ModPath,
+    /// The full path of the re-exported item, or of the module for globs. For items of other
+    /// crates, the path starts with the name of that crate.
+    pub target: ModPath,
+    pub glob: bool,
+    /// The crate the re-exported item is in, if it's an `extern crate` of this one.
+    pub krate: Option<String>,
+    /// The name the item is re-exported under, if it's renamed with `as`.
+    pub alias: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -264,11 +643,34 @@ pub struct Import {
     pub path: ast::ViewPath,
 }
 
+/// How the fields of a struct are declared.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum StructKind {
+    /// `struct Point { x: f64, y: f64 }`
+    Named,
+    /// `struct Meters(f64);`
+    Tuple,
+    /// `struct Unit;`
+    Unit,
+}
+
+impl StructKind {
+    pub fn from_ast(data: &ast::VariantData) -> StructKind {
+        match *data {
+            ast::VariantData::Struct(..) => StructKind::Named,
+            ast::VariantData::Tuple(..)  => StructKind::Tuple,
+            ast::VariantData::Unit(..)   => StructKind::Unit,
+        }
+    }
+}
+
 #[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum FnKind {
     ItemFn,
     MethodFromImpl,
     MethodFromTrait,
+    /// A function declared in an `extern` block.
+    ForeignFn,
 }
 
 // These structs have importance in the initial AST visit, because all impls for
@@ -290,12 +692,45 @@ pub struct Ty {
     pub name: String,
 }
 
+impl Ty {
+    /// A type known only by its printed name, like one read from rustdoc's JSON output.
+    pub fn from_name(name: String) -> Ty {
+        Ty {
+            id: NodeId::from(ast::DUMMY_NODE_ID),
+            name: name,
+        }
+    }
+}
+
 impl From<ast::Ty> for Ty {
     fn from(ty: ast::Ty) -> Self{
         Ty {
             id: NodeId::from(ty.id),
-            name: pprust::ty_to_string(&ty),
+            name: ty_to_string(&ty),
         }
     }
 }
 
+/// The environment variable used to choose how deeply types may nest inside each other before the
+/// rest of them is shown as `...`.
+const MAX_TYPE_DEPTH_VAR: &str = "OXIDOC_MAX_TYPE_DEPTH";
+
+/// How deeply types may nest if `OXIDOC_MAX_TYPE_DEPTH` isn't set.
+pub const DEFAULT_MAX_TYPE_DEPTH: usize = 16;
+
+lazy_static! {
+    static ref MAX_TYPE_DEPTH: usize = env::var(MAX_TYPE_DEPTH_VAR).ok()
+        .and_then(|depth| depth.parse().ok())
+        .unwrap_or(DEFAULT_MAX_TYPE_DEPTH);
+}
+
+/// How deeply types may nest inside each other, read from `OXIDOC_MAX_TYPE_DEPTH` once per run.
+pub fn max_type_depth() -> usize {
+    *MAX_TYPE_DEPTH
+}
+
+/// Prints a type, with the types nested more than `max_type_depth()` deep inside it shown as
+/// `...`.
+pub fn ty_to_string(ty: &ast::Ty) -> String {
+    ty_printer::ty_to_string(ty, max_type_depth())
+}
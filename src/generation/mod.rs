@@ -1,27 +1,145 @@
 pub mod ast_ty_wrappers;
 mod io_support;
+pub mod plan;
+mod progress;
+mod source_stamp;
+pub mod ty_printer;
 pub mod visitor;
 
 use std;
+use std::cmp;
+use std::collections::HashSet;
 use std::env;
+use std::fmt;
+use std::sync::Arc;
+use std::thread;
 use std::path::{Path, PathBuf};
 use std::fs::{read_dir, remove_dir_all};
 
-use store::Store;
+use completion::CompletionIndex;
+use full_text::FullTextIndex;
+use merge::{self, SignatureConflict};
+use store::{self, CrateIndex, Store};
 use syntax::ast;
 use syntax::diagnostics::plugin::DiagnosticBuilder;
 use syntax::parse::{self, ParseSess};
-use syntax::codemap::FilePathMapping;
+use syntax::codemap::{CodeMap, FilePathMapping};
 
 use document::*;
-use conversion::{Convert, Context, Documentation};
+use conversion::{self, Convert, Context, DocLink, DocType, Documentation};
 use paths;
 use store::Docset;
 use toml_util;
+use self::plan::GenerationPlan;
+use self::progress::Progress;
+use self::source_stamp::SourceStamp;
 use self::visitor::OxidocVisitor;
 
+use env_logger::LogBuilder;
+use log::LogLevelFilter;
+use num_cpus;
+
 use ::errors::*;
 
+/// How much is reported while generating documentation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Verbosity {
+    /// Only errors.
+    Quiet,
+    /// Which crates are being generated, and warnings about items that were left out.
+    Normal,
+    /// Also each module as it's documented, and a progress bar while saving.
+    Verbose,
+}
+
+impl Verbosity {
+    /// The level oxidoc logs at with this verbosity when `RUST_LOG` isn't set.
+    pub fn log_level(&self) -> LogLevelFilter {
+        match *self {
+            Verbosity::Quiet   => LogLevelFilter::Error,
+            Verbosity::Normal  => LogLevelFilter::Warn,
+            Verbosity::Verbose => LogLevelFilter::Info,
+        }
+    }
+
+    /// A logger for oxidoc's own messages at `log_level`, with the directives of `RUST_LOG`, if
+    /// given, on top. Only oxidoc's level is set, so its dependencies log at the levels `RUST_LOG`
+    /// gives them, or not at all.
+    pub fn log_builder(&self, rust_log: Option<&str>) -> LogBuilder {
+        let mut builder = LogBuilder::new();
+        builder.filter(Some("oxidoc"), self.log_level());
+        if let Some(spec) = rust_log {
+            builder.parse(spec);
+        }
+        builder
+    }
+}
+
+/// Options controlling how documentation is generated.
+#[derive(Clone, Debug)]
+pub struct GenerateOptions {
+    /// Regenerate documentation even for crates whose sources haven't changed.
+    pub force: bool,
+
+    /// The maximum number of threads used for saving documentation. Parsing and converting a crate
+    /// always happen on one thread, since `syntex_syntax` interns the identifiers of the AST per
+    /// thread.
+    pub jobs: usize,
+
+    /// List related items in the order they appear in the source, instead of sorting them by
+    /// name.
+    pub source_order: bool,
+
+    /// Document items marked `#[doc(hidden)]`, along with the contents of hidden modules.
+    pub document_hidden: bool,
+
+    /// Document items that aren't public, like `pub(crate)` items and private functions.
+    pub document_private: bool,
+
+    /// Delete the documentation of items that no longer exist in the source.
+    pub prune: bool,
+
+    /// Fail instead of only warning when items have to be left out of the documentation.
+    pub strict: bool,
+
+    /// Print the files that would be written instead of writing them, leaving the store as it
+    /// is.
+    pub dry_run: bool,
+
+    /// Also write a JSON copy of each document next to its `.odoc` file, for tools that can't read
+    /// bincode. `store::migrate` reads these copies to bring the documentation up to date when
+    /// the format changes.
+    pub json_copies: bool,
+
+    /// Only document the items under these module paths, like `serde::de`, along with the modules
+    /// containing them. The crate's name can be left out. Everything is documented if it's empty.
+    pub modules: Vec<String>,
+
+    pub verbosity: Verbosity,
+}
+
+impl GenerateOptions {
+    pub fn new() -> Self {
+        GenerateOptions {
+            force: false,
+            jobs: num_cpus::get(),
+            source_order: false,
+            document_hidden: false,
+            document_private: false,
+            prune: true,
+            strict: false,
+            dry_run: false,
+            json_copies: false,
+            modules: Vec::new(),
+            verbosity: Verbosity::Normal,
+        }
+    }
+
+    fn is_quiet(&self) -> bool {
+        self.verbosity == Verbosity::Quiet
+    }
+}
+
 fn parse_crate_from_path<'a, T: ?Sized + AsRef<Path>>(path: &T,
                                                       parse_session: &'a ParseSess)
                                                       -> std::result::Result<ast::Crate, Option<DiagnosticBuilder<'a>>> {
@@ -36,32 +154,162 @@ fn parse_crate_from_path<'a, T: ?Sized + AsRef<Path>>(path: &T,
     }
 }
 
-pub fn generate_all_docs() -> Result<()> {
-    debug!("Regenerating all documentation.");
-    generate_crate_registry_docs()?;
+/// The entries the crates generated in a run add to the full-text and completion indices.
+/// Rewriting the indices takes longer the more crates they hold, so a run that generates several
+/// crates saves them once at the end instead of after each crate.
+struct SearchIndices {
+    full_text: FullTextIndex,
+    completions: CompletionIndex,
+    crates: Vec<CrateInfo>,
+
+    /// The crates only some modules of were generated. Their other entries are kept.
+    updated: Vec<(CrateInfo, ModuleSelection)>,
+    cleared: bool,
+}
 
-    if generate_stdlib_docs().is_err() {
-        println!("The environment variable RUST_SRC_PATH was not set or malformed. Documentation \
-                  for std won't be generated.");
+impl SearchIndices {
+    fn new() -> SearchIndices {
+        SearchIndices {
+            full_text: FullTextIndex::new(),
+            completions: CompletionIndex::new(),
+            crates: Vec::new(),
+            updated: Vec::new(),
+            cleared: false,
+        }
     }
 
-    Ok(())
+    /// Drops the entries of every crate when saving, for when the whole store is regenerated.
+    fn clear(&mut self) {
+        self.cleared = true;
+    }
+
+    fn add_docs(&mut self, crate_info: &CrateInfo, documents: &[Documentation]) {
+        self.full_text.add_docs(crate_info, documents);
+        self.completions.add_docs(crate_info, documents);
+        if !self.crates.contains(crate_info) {
+            self.crates.push(crate_info.clone());
+        }
+    }
+
+    /// Adds the documents generated for the selected modules of a crate, replacing only the
+    /// crate's entries for the selected items.
+    fn update_docs(&mut self,
+                   crate_info: &CrateInfo,
+                   documents: &[Documentation],
+                   selection: ModuleSelection) {
+        self.full_text.insert_docs(crate_info, documents);
+        self.completions.insert_docs(crate_info, documents);
+        self.updated.push((crate_info.clone(), selection));
+    }
+
+    /// Writes the entries of the generated crates to the indices on disk. The indices are loaded
+    /// again while holding the store's lock, so the crates other processes added to them since the
+    /// run started are kept.
+    fn save(self) -> Result<()> {
+        if self.crates.is_empty() && self.updated.is_empty() && !self.cleared {
+            return Ok(());
+        }
+
+        let _lock = store::StoreLock::acquire()?;
+        save_index(self.full_text, &self.crates, &self.updated, self.cleared)?;
+        save_index(self.completions, &self.crates, &self.updated, self.cleared)
+    }
 }
 
-pub fn generate_crate_registry_docs() -> Result<()> {
-    let path = paths::doc_registry_path()?;
+/// Merges the entries of the generated crates into the index on disk, or replaces it with them if
+/// it was cleared.
+fn save_index<I: CrateIndex>(index: I,
+                             crates: &[CrateInfo],
+                             updated: &[(CrateInfo, ModuleSelection)],
+                             cleared: bool) -> Result<()> {
+    let mut saved = if cleared { I::new() } else { I::load_or_new() };
+    for &(ref crate_info, ref selection) in updated.iter() {
+        saved.remove_docs(crate_info, |path| selection.replaces(path));
+    }
+    saved.merge(index, crates);
+    saved.save()
+}
+
+/// Runs a generation with the search indices, saving them afterwards. They're saved even if it
+/// fails, since the crates saved to the store before the failure were added to them; a crate
+/// whose generation failed wasn't, and keeps its earlier entries.
+fn with_search_indices<T, F>(generate: F) -> Result<T>
+    where F: FnOnce(&mut SearchIndices) -> Result<T>
+{
+    let mut indices = SearchIndices::new();
+    let result = generate(&mut indices);
+    let saved = indices.save();
+    let value = result?;
+    saved?;
+    Ok(value)
+}
 
-    remove_dir_all(path);
+/// What saving a crate's documentation did.
+enum Generated {
+    /// The documentation was saved, giving the store it was added to.
+    Saved(Store),
+    /// With `options.dry_run` set, the files that would have been written.
+    Planned(GenerationPlan),
+}
+
+impl Generated {
+    fn into_plan(self) -> Option<GenerationPlan> {
+        match self {
+            Generated::Saved(_) => None,
+            Generated::Planned(plan) => Some(plan),
+        }
+    }
+}
+
+/// Generates the documentation of every crate in the registry and the standard library. With
+/// `options.dry_run` set, returns what would be written for each crate instead.
+pub fn generate_all_docs(options: &GenerateOptions) -> Result<Vec<GenerationPlan>> {
+    debug!("Regenerating all documentation.");
+    with_search_indices(|indices| {
+        let mut plans = generate_crate_registry_docs_with(options, indices)?;
+
+        match generate_stdlib_docs_with(options, indices) {
+            Ok(stdlib_plans) => plans.extend(stdlib_plans),
+            Err(_) => if !options.is_quiet() {
+                println!("The environment variable RUST_SRC_PATH was not set or malformed. \
+                          Documentation for std won't be generated.");
+            },
+        }
 
+        Ok(plans)
+    })
+}
+
+pub fn generate_crate_registry_docs(options: &GenerateOptions) -> Result<Vec<GenerationPlan>> {
+    with_search_indices(|indices| generate_crate_registry_docs_with(options, indices))
+}
+
+fn generate_crate_registry_docs_with(options: &GenerateOptions,
+                                     indices: &mut SearchIndices)
+                                     -> Result<Vec<GenerationPlan>> {
+    // The registry is regenerated from scratch, so crates that were removed from it don't linger
+    // in the store.
+    if !options.dry_run {
+        let path = paths::doc_registry_path()?;
+        remove_dir_all(path);
+        indices.clear();
+    }
+
+    let mut plans = Vec::new();
     for src_dir in paths::iter_crate_source_paths()
         .chain_err(|| "Could not iterate cargo registry src directories")?
     {
-        generate_docs_for_path(src_dir)?;
+        plans.extend(generate_docs_for_path_with(src_dir, options, indices)?);
     }
-    Ok(())
+    Ok(plans)
 }
 
-pub fn generate_stdlib_docs() -> Result<()> {
+pub fn generate_stdlib_docs(options: &GenerateOptions) -> Result<Vec<GenerationPlan>> {
+    with_search_indices(|indices| generate_stdlib_docs_with(options, indices))
+}
+
+fn generate_stdlib_docs_with(options: &GenerateOptions,
+                             indices: &mut SearchIndices) -> Result<Vec<GenerationPlan>> {
     let rust_src_dir = env::var("RUST_SRC_PATH")
         .chain_err(|| format!("RUST_SRC_PATH was not set when trying to generate stdlib docs."))?;
 
@@ -83,22 +331,30 @@ pub fn generate_stdlib_docs() -> Result<()> {
         }
     }
 
+    let mut plans = Vec::new();
     for path in paths {
         // BUG: ICE when attempting to parse rustdoc. Just skip parsing librustdoc.
         if !path.display().to_string().contains("librustdoc") {
-            generate_docs_for_path(path)?;
+            plans.extend(generate_docs_for_path_with(path, options, indices)?);
         }
     }
-    Ok(())
+    Ok(plans)
 }
 
-pub fn generate_docs_for_path(src_dir: PathBuf) -> Result<()> {
-    cache_doc_for_crate(&src_dir).
+/// Generates the documentation of the crate in a directory. With `options.dry_run` set, returns
+/// what would be written instead.
+pub fn generate_docs_for_path(src_dir: PathBuf,
+                              options: &GenerateOptions) -> Result<Option<GenerationPlan>> {
+    with_search_indices(|indices| generate_docs_for_path_with(src_dir, options, indices))
+}
+
+fn generate_docs_for_path_with(src_dir: PathBuf,
+                               options: &GenerateOptions,
+                               indices: &mut SearchIndices) -> Result<Option<GenerationPlan>> {
+    cache_doc_for_crate(&src_dir, options, indices).
         chain_err(|| format!("Unable to generate documentation \
                               for directory {}",
-                             &src_dir.display()))?;
-
-    Ok(())
+                             &src_dir.display()))
 }
 
 fn get_crate_info(crate_path: &PathBuf) -> Result<CrateInfo> {
@@ -116,30 +372,63 @@ fn get_crate_info(crate_path: &PathBuf) -> Result<CrateInfo> {
 }
 
 /// Generates cached Rustdoc information for the given crate.
-/// Expects the crate root directory as an argument.
-fn cache_doc_for_crate(crate_path: &PathBuf) -> Result<()> {
+/// Expects the crate root directory as an argument. With `options.dry_run` set, returns what would
+/// be written instead.
+fn cache_doc_for_crate(crate_path: &PathBuf,
+                       options: &GenerateOptions,
+                       indices: &mut SearchIndices) -> Result<Option<GenerationPlan>> {
     let info = get_crate_info(crate_path)?;
 
-    println!("Generating documentation for {}", &info);
+    let old_stamp = SourceStamp::load(&info).ok();
+    let (stamp, up_to_date) = source_stamp::check_sources(crate_path, old_stamp.as_ref())?;
+    if up_to_date && !options.force && !options.dry_run {
+        if !options.is_quiet() {
+            println!("Documentation for {} is up to date", &info);
+        }
+        stamp.save(&info)?;
+        return Ok(None);
+    }
 
-    let krate = match parse_crate(crate_path, &info) {
+    if !options.is_quiet() {
+        println!("Generating documentation for {}", &info);
+    }
+
+    let parse_session = ParseSess::new(FilePathMapping::empty());
+    let krate = match parse_crate(crate_path, &info, &parse_session) {
         Ok(k) => k,
         Err(_) => {
-            println!("No crate entry point found \
-                      (nonstandard paths are unsupported)");
-            return Ok(())
+            if !options.is_quiet() {
+                println!("No crate entry point found \
+                          (nonstandard paths are unsupported)");
+            }
+            return Ok(None)
         }
     };
 
-    let mut store = generate_doc_cache(krate, info)
+    let generated = generate_doc_cache_with(krate, info.clone(), parse_session.codemap(), options,
+                                            indices)
         .chain_err(|| "Failed to generate doc cache")?;
+    let mut store = match generated {
+        Generated::Saved(store) => store,
+        Generated::Planned(plan) => return Ok(Some(plan)),
+    };
 
     store.save()
-        .chain_err(|| "Couldn't save oxidoc data for module")
+        .chain_err(|| "Couldn't save oxidoc data for module")?;
+
+    // The rest of the crate still has to be generated the next time.
+    if !options.modules.is_empty() {
+        return Ok(None);
+    }
+
+    stamp.save(&info)
+        .chain_err(|| format!("Couldn't save source stamp for {}", &info))?;
+    Ok(None)
 }
 
-fn parse_crate(crate_path: &PathBuf, crate_info: &CrateInfo) -> Result<ast::Crate> {
-    let parse_session = ParseSess::new(FilePathMapping::empty());
+fn parse_crate(crate_path: &PathBuf,
+               crate_info: &CrateInfo,
+               parse_session: &ParseSess) -> Result<ast::Crate> {
     let lib_path = crate_info.lib_path.clone().unwrap_or("src/lib.rs".to_string());
 
     // TODO: This has to handle multiple [[bin]] targets.
@@ -152,7 +441,7 @@ fn parse_crate(crate_path: &PathBuf, crate_info: &CrateInfo) -> Result<ast::Crat
         }
     }
 
-    let krate = match parse_crate_from_path(main_path.as_path(), &parse_session) {
+    let krate = match parse_crate_from_path(main_path.as_path(), parse_session) {
         Ok(k) => k,
         Err(e) => bail!(ErrorKind::CrateParseError(crate_info.name.clone(), format!("{:?}", e))),
     };
@@ -160,39 +449,447 @@ fn parse_crate(crate_path: &PathBuf, crate_info: &CrateInfo) -> Result<ast::Crat
     Ok(krate)
 }
 
-pub fn generate_crate_docs(krate: ast::Crate, crate_info: CrateInfo) -> Result<Vec<Documentation>> {
+/// An item that was found in the source but left out of the documentation, like an associated
+/// type of an impl or the items generated by a macro invocation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SkippedItem {
+    /// What kind of item it is, like `macro invocation`.
+    pub kind: String,
+    /// The path of the item, or of the module or type it's in if it has no name of its own.
+    pub path: ModPath,
+    pub source: Option<SourceSpan>,
+    pub reason: String,
+}
+
+impl fmt::Display for SkippedItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Skipped {} in {}", self.kind, self.path)?;
+        if let Some(ref source) = self.source {
+            write!(f, " ({})", source)?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+/// Generates the documentation of a crate. Items that have to be left out are logged as warnings,
+/// or make generation fail if `options.strict` is set.
+pub fn generate_crate_docs(krate: ast::Crate,
+                           crate_info: CrateInfo,
+                           codemap: &CodeMap,
+                           options: &GenerateOptions) -> Result<Vec<Documentation>> {
+    let (documents, skipped) = generate_crate_docs_with_skipped(krate, crate_info, codemap,
+                                                                options)?;
+    report_skipped(&skipped, options)?;
+    Ok(documents)
+}
+
+/// Logs the items that were left out as warnings, and fails if `options.strict` is set and there
+/// are any.
+fn report_skipped(skipped: &[SkippedItem], options: &GenerateOptions) -> Result<()> {
+    for item in skipped.iter() {
+        warn!("{}", item);
+    }
+    if options.strict && !skipped.is_empty() {
+        bail!(ErrorKind::SkippedItems(skipped.len()));
+    }
+    Ok(())
+}
+
+/// Like `generate_crate_docs`, but returns the items that were left out instead of reporting them.
+pub fn generate_crate_docs_with_skipped(krate: ast::Crate,
+                                        crate_info: CrateInfo,
+                                        codemap: &CodeMap,
+                                        options: &GenerateOptions)
+                                        -> Result<(Vec<Documentation>, Vec<SkippedItem>)> {
     let crate_doc_path = paths::crate_doc_path(&crate_info)
         .chain_err(|| format!("Unable to get crate doc path for crate: {}",
                               &crate_info.name))?;
 
-    let mut v = OxidocVisitor::new(crate_info.clone());
+    let mut v = OxidocVisitor::new(crate_info.clone(), codemap);
+    v.document_hidden = options.document_hidden;
+    v.document_private = options.document_private;
+    v.modules = options.modules.clone();
     v.visit_crate(krate);
     let context = Context::new(crate_doc_path.clone(),
                                crate_info,
                                v.impls_for_ty.clone());
-    Ok(v.convert(&context))
+    let mut documents = v.convert(&context);
+    conversion::link_implementors(&mut documents);
+    conversion::link_provided_methods(&mut documents);
+    conversion::link_reexports(&mut documents, &v.reexports);
+    conversion::move_exported_macros(&mut documents);
+    conversion::mark_unreachable(&mut documents, &v.reexports);
+    if !options.document_private {
+        documents = conversion::remove_private(documents);
+    }
+    conversion::disambiguate_duplicates(&mut documents);
+    conversion::link_methods(&mut documents);
+    conversion::resolve_intra_doc_links(&mut documents);
+    conversion::link_return_types(&mut documents);
+    conversion::fill_signatures(&mut documents);
+
+    let mut documents = select_modules(documents, &options.modules)?;
+    if !options.source_order {
+        for doc in documents.iter_mut() {
+            doc.sort_links();
+        }
+    }
+
+    Ok((documents, v.skipped))
+}
+
+/// The segments of a module path given to `GenerateOptions::modules`, without the crate's name or
+/// `crate` at the start. The crate's name on its own is a module of that name, like `foo::foo`,
+/// since selecting the whole crate is the same as selecting nothing.
+fn module_filter_segments<'a>(module: &'a str, crate_name: &str) -> Vec<&'a str> {
+    let mut segments: Vec<&str> = module.split("::")
+        .map(|segment| unraw(segment.trim()))
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let starts_with_crate = match segments.first() {
+        Some(&"crate") => true,
+        Some(first) => *first == crate_name && segments.len() > 1,
+        None => false,
+    };
+    if starts_with_crate {
+        segments.remove(0);
+    }
+    segments
+}
+
+/// The segments of an item's path after the crate's name.
+fn path_segments_in_crate(path: &ModPath) -> Vec<&str> {
+    path.segments().skip(1).map(|segment| unraw(&segment.identifier)).collect()
+}
+
+/// Whether the item at `path` is the module given to `GenerateOptions::modules` or inside it.
+fn is_in_module(path: &ModPath, module: &str) -> bool {
+    let crate_name = path.head().map_or("".to_string(), |head| head.identifier);
+    let module = module_filter_segments(module, &crate_name);
+    let path = path_segments_in_crate(path);
+    path.len() >= module.len() && path.iter().zip(module.iter()).all(|(a, b)| a == b)
+}
+
+/// Whether the module at `path` contains the module given to `GenerateOptions::modules`.
+fn contains_module(path: &ModPath, module: &str) -> bool {
+    let crate_name = path.head().map_or("".to_string(), |head| head.identifier);
+    let module = module_filter_segments(module, &crate_name);
+    let path = path_segments_in_crate(path);
+    path.len() < module.len() && path.iter().zip(module.iter()).all(|(a, b)| a == b)
+}
+
+/// The items whose documentation is replaced when only some modules of a crate are generated: the
+/// items in the modules, including those that were removed from them, and the generated stubs of
+/// the modules containing them.
+struct ModuleSelection {
+    modules: Vec<String>,
+    generated: HashSet<ModPath>,
+}
+
+impl ModuleSelection {
+    fn new(modules: &[String], documents: &[Documentation]) -> ModuleSelection {
+        ModuleSelection {
+            modules: modules.to_vec(),
+            generated: documents.iter().map(|doc| doc.mod_path.clone()).collect(),
+        }
+    }
+
+    fn replaces(&self, path: &ModPath) -> bool {
+        self.generated.contains(path) ||
+            self.modules.iter().any(|module| is_in_module(path, module))
+    }
+}
+
+/// Keeps only the documentation of the items in the given modules, along with the modules
+/// containing them, so their paths resolve. The containing modules are kept as stubs that only
+/// list the kept items. Fails if one of the modules isn't in the crate.
+fn select_modules(documents: Vec<Documentation>, modules: &[String]) -> Result<Vec<Documentation>> {
+    if modules.is_empty() {
+        return Ok(documents);
+    }
+
+    for module in modules.iter() {
+        if !documents.iter().any(|doc| is_in_module(&doc.mod_path, module)) {
+            bail!(ErrorKind::ModuleNotFound(module.clone()));
+        }
+    }
+
+    let (mut selected, rest): (Vec<Documentation>, Vec<Documentation>) = documents.into_iter()
+        .partition(|doc| modules.iter().any(|module| is_in_module(&doc.mod_path, module)));
+
+    let mut stubs: Vec<Documentation> = rest.into_iter()
+        .filter(|doc| {
+            doc.get_type() == DocType::Module &&
+                modules.iter().any(|module| contains_module(&doc.mod_path, module))
+        })
+        .collect();
+
+    let kept: HashSet<ModPath> = selected.iter()
+        .chain(stubs.iter())
+        .map(|doc| doc.mod_path.clone())
+        .collect();
+    for stub in stubs.iter_mut() {
+        let links = std::mem::replace(&mut stub.links, conversion::DocRelatedItems::new());
+        stub.links = links.into_iter()
+            .map(|(category, mut links)| {
+                links.retain(|link| kept.contains(&link.path));
+                (category, links)
+            })
+            .filter(|&(_, ref links)| !links.is_empty())
+            .collect();
+    }
+
+    selected.extend(stubs);
+    Ok(selected)
+}
+
+/// Adds the links of a containing module's stub to the module's documentation from an earlier
+/// generation, so items added to the selected modules are listed without losing the others. The
+/// stub is used as it is if there's no earlier documentation.
+fn merge_stub(stub: Documentation, options: &GenerateOptions) -> Documentation {
+    let stored = Documentation::load(&stub.crate_info, &stub.mod_path, stub.get_type(),
+                                     stub.disambiguator);
+    let mut stored = match stored {
+        Ok(stored) => stored,
+        Err(_) => return stub,
+    };
+
+    for (category, links) in stub.links.into_iter() {
+        let stored_links = stored.links.entry(category).or_insert(Vec::new());
+        for link in links.into_iter() {
+            if !stored_links.iter().any(|stored_link| stored_link.path == link.path) {
+                stored_links.push(link);
+            }
+        }
+    }
+    if !options.source_order {
+        stored.sort_links();
+    }
+    stored
+}
+
+/// A related-item link whose target documentation couldn't be found in the store.
+#[derive(Clone, Debug)]
+pub struct DanglingLink {
+    pub source: ModPath,
+    pub category: DocType,
+    pub link: DocLink,
 }
 
-pub fn make_docset(documents: Vec<Documentation>) -> Result<Docset> {
-    for doc in &documents {
-        debug!("p: {}", doc.mod_path);
-        doc.save()?;
+/// Checks that every related-item link in the given documentation points to a saved `.odoc` file.
+/// Dangling links are logged as warnings and returned, so callers can fail on them.
+pub fn check_doc_links(documents: &[Documentation]) -> Vec<DanglingLink> {
+    let mut dangling = Vec::new();
+    for doc in documents.iter() {
+        for (category, link) in doc.dangling_links() {
+            warn!("Dangling link from {} to {} ({})", doc.mod_path, link.path, category);
+            dangling.push(DanglingLink {
+                source: doc.mod_path.clone(),
+                category: category,
+                link: link.clone(),
+            });
+        }
     }
+    dangling
+}
+
+/// Saves the documents using up to `jobs` threads, drawing a progress bar if `show_progress` is set.
+/// Only saving is spread over threads, since the parsed crate the documents are converted from
+/// can't be shared between them. With `json_copies`, a JSON copy of each document is written next
+/// to it.
+fn save_documents(documents: Vec<Documentation>,
+                  jobs: usize,
+                  json_copies: bool,
+                  show_progress: bool) -> Result<Vec<Documentation>> {
+    let count = documents.len();
+    let jobs = cmp::max(1, cmp::min(jobs, count));
+    let chunk_size = (count + jobs - 1) / jobs;
+    let documents = Arc::new(documents);
+    let progress = Arc::new(Progress::new(count, show_progress));
+
+    let workers: Vec<thread::JoinHandle<Result<()>>> = (0..jobs)
+        .map(|i| {
+            let documents = documents.clone();
+            let progress = progress.clone();
+            thread::spawn(move || {
+                let start = cmp::min(i * chunk_size, count);
+                let end = cmp::min(start + chunk_size, count);
+                for doc in documents[start..end].iter() {
+                    debug!("p: {}", doc.mod_path);
+                    doc.save()
+                        .and_then(|_| if json_copies { doc.save_json() } else { Ok(()) })
+                        .chain_err(|| format!("Could not save doc {}", doc.mod_path))?;
+                    progress.tick();
+                }
+                Ok(())
+            })
+        })
+        .collect();
+
+    let results: Vec<_> = workers.into_iter().map(|worker| worker.join()).collect();
+    progress.finish();
+    for result in results {
+        match result {
+            Ok(result) => result?,
+            Err(_) => bail!("Documentation saving thread panicked"),
+        }
+    }
+
+    match Arc::try_unwrap(documents) {
+        Ok(documents) => Ok(documents),
+        Err(_) => bail!("Documentation is still in use by a saving thread"),
+    }
+}
+
+pub fn make_docset(documents: Vec<Documentation>, jobs: usize) -> Result<Docset> {
+    make_docset_with_progress(documents, jobs, false, false).map(|(docset, _)| docset)
+}
+
+fn make_docset_with_progress(documents: Vec<Documentation>,
+                             jobs: usize,
+                             json_copies: bool,
+                             show_progress: bool) -> Result<(Docset, Vec<Documentation>)> {
+    let documents = save_documents(documents, jobs, json_copies, show_progress)?;
+    info!("Saved {} items", documents.len());
+
+    check_doc_links(&documents);
 
     let mut docset = Docset::new();
-    docset.add_docs(documents)?;
+    docset.add_docs(&documents);
+
+    Ok((docset, documents))
+}
 
-    Ok(docset)
+/// Generates documentation for the given crate. With `options.dry_run` set, nothing is written and
+/// the store is returned as it is.
+pub fn generate_doc_cache(krate: ast::Crate,
+                          crate_info: CrateInfo,
+                          codemap: &CodeMap,
+                          options: &GenerateOptions) -> Result<Store> {
+    let generated = with_search_indices(|indices| {
+        generate_doc_cache_with(krate, crate_info, codemap, options, indices)
+    })?;
+    match generated {
+        Generated::Saved(store) => Ok(store),
+        Generated::Planned(_) => Ok(Store::load()),
+    }
+}
+
+/// Generates documentation for the given crate, adding it to the search indices.
+fn generate_doc_cache_with(krate: ast::Crate,
+                           crate_info: CrateInfo,
+                           codemap: &CodeMap,
+                           options: &GenerateOptions,
+                           indices: &mut SearchIndices) -> Result<Generated> {
+    let documents = generate_crate_docs(krate, crate_info.clone(), codemap, options)?;
+    save_doc_cache(documents, crate_info, options, indices)
 }
 
-/// Generates documentation for the given crate.
-pub fn generate_doc_cache(krate: ast::Crate, crate_info: CrateInfo) -> Result<Store> {
-    let documents = generate_crate_docs(krate, crate_info.clone())?;
-    let docset = make_docset(documents)?;
+/// Saves a crate's documentation and adds it to the store and search indices, which are left for
+/// the caller to save. The crate is only added to the indices once it's saved. With
+/// `options.dry_run` set, only plans the files that would be written.
+fn save_doc_cache(documents: Vec<Documentation>,
+                  crate_info: CrateInfo,
+                  options: &GenerateOptions,
+                  indices: &mut SearchIndices) -> Result<Generated> {
+    store::check_crate_layout(&crate_info)?;
+
+    // The stubs of the modules containing the selected ones would replace their full
+    // documentation from an earlier generation.
+    let documents: Vec<Documentation> = if options.modules.is_empty() {
+        documents
+    } else {
+        let modules = &options.modules;
+        documents.into_iter()
+            .map(|doc| if modules.iter().any(|module| is_in_module(&doc.mod_path, module)) {
+                doc
+            } else {
+                merge_stub(doc, options)
+            })
+            .collect()
+    };
+
+    if options.dry_run {
+        return Ok(Generated::Planned(GenerationPlan::new(&documents)?));
+    }
+
+    let written = documents.iter()
+        .map(|doc| doc.to_store_location().to_filepath())
+        .collect::<Result<HashSet<PathBuf>>>()?;
+
+    let show_progress = options.verbosity == Verbosity::Verbose;
+    let selected_modules = !options.modules.is_empty();
+    let (docset, documents) = make_docset_with_progress(documents, options.jobs,
+                                                        options.json_copies, show_progress)?;
+    store::save_crate_metadata(&crate_info)?;
+
+    // The documentation of the items that weren't generated isn't stale, and stays searchable.
+    if selected_modules {
+        let selection = ModuleSelection::new(&options.modules, &documents);
+        let store = store::update_docset(crate_info.clone(), &documents,
+                                         |path| selection.replaces(path))?;
+        indices.update_docs(&crate_info, &documents, selection);
+        return Ok(Generated::Saved(store));
+    }
+
+    if options.prune {
+        let crate_doc_path = paths::crate_doc_path(&crate_info)?;
+        let pruned = store::prune_stale_docs(&crate_doc_path, &written)?;
+        if pruned > 0 {
+            info!("Removed {} stale documentation files for {}", pruned, crate_info);
+        }
+    }
 
-    let mut store = Store::load();
-    store.add_docset(crate_info, docset);
-    store.save()?;
+    let store = store::save_docset(crate_info.clone(), docset)?;
+    indices.add_docs(&crate_info, &documents);
+    Ok(Generated::Saved(store))
+}
+
+/// Merges the documentation generated for a crate with different features, given as the features
+/// of each generation and the directory it was saved to, and saves it to the store in place of the
+/// crate's documentation. Returns the items whose signatures depend on the features, and with
+/// `options.dry_run` set, what would be written.
+pub fn generate_merged_docs(dirs: &[(Vec<String>, PathBuf)],
+                            options: &GenerateOptions)
+                            -> Result<(Vec<SignatureConflict>, Option<GenerationPlan>)> {
+    let merged = merge::merge_doc_dirs(dirs)?;
+
+    let crate_info = match merged.documents.iter().find(|doc| doc.mod_path.0.len() == 1) {
+        Some(doc) => doc.crate_info.clone(),
+        None => bail!(ErrorKind::NoCrateModule(dirs.first().map_or(PathBuf::new(),
+                                                                   |&(_, ref dir)| dir.clone()))),
+    };
+
+    let generated = with_search_indices(|indices| {
+        save_doc_cache(merged.documents, crate_info, options, indices)
+    })?;
+    Ok((merged.conflicts, generated.into_plan()))
+}
+
+/// Generates documentation for a crate from the JSON written by `rustdoc --output-format=json`,
+/// instead of parsing its source. Items of kinds that can't be imported are reported like those
+/// left out when parsing the source. With `options.dry_run` set, returns what would be written.
+pub fn generate_docs_from_rustdoc_json(json_path: &Path,
+                                       options: &GenerateOptions)
+                                       -> Result<Option<GenerationPlan>> {
+    let (mut documents, skipped) = conversion::from_rustdoc_json_with_skipped(json_path)?;
+    report_skipped(&skipped, options)?;
+
+    if !options.source_order {
+        for doc in documents.iter_mut() {
+            doc.sort_links();
+        }
+    }
+
+    let crate_info = match documents.iter().find(|doc| doc.mod_path.0.len() == 1) {
+        Some(doc) => doc.crate_info.clone(),
+        None => bail!(ErrorKind::InvalidRustdocJson(json_path.to_path_buf(),
+                                                    "no crate module".to_string())),
+    };
 
-    Ok(store)
+    let documents = select_modules(documents, &options.modules)?;
+    let generated = with_search_indices(|indices| {
+        save_doc_cache(documents, crate_info, options, indices)
+    })?;
+    Ok(generated.into_plan())
 }
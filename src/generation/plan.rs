@@ -0,0 +1,96 @@
+//! The files a generation would write, for checking a crate's documentation with `--dry-run`
+//! before anything is saved.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use conversion::{DocType, Documentation};
+use document::ModPath;
+
+use ::errors::*;
+
+/// A documentation file that would be written.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub mod_path: ModPath,
+    pub doc_type: DocType,
+
+    /// Whether a file already exists at the path, from an earlier generation.
+    pub exists: bool,
+}
+
+/// The documentation files a generation would write.
+#[derive(Clone, Debug)]
+pub struct GenerationPlan {
+    pub files: Vec<PlannedFile>,
+}
+
+impl GenerationPlan {
+    /// Plans the files for the given documents, in the order they would be saved.
+    pub fn new(documents: &[Documentation]) -> Result<Self> {
+        let mut files = Vec::new();
+        for doc in documents.iter() {
+            let path = doc.to_store_location().to_filepath()?;
+            files.push(PlannedFile {
+                exists: path.is_file(),
+                path: path,
+                mod_path: doc.mod_path.clone(),
+                doc_type: doc.get_type(),
+            });
+        }
+
+        Ok(GenerationPlan {
+            files: files,
+        })
+    }
+
+    /// The files that would be overwritten by one another, as each path with the items that would
+    /// be saved to it. Only the last item saved to a path would be kept.
+    pub fn collisions(&self) -> Vec<(PathBuf, Vec<&PlannedFile>)> {
+        let mut by_path: BTreeMap<&PathBuf, Vec<&PlannedFile>> = BTreeMap::new();
+        for file in self.files.iter() {
+            by_path.entry(&file.path).or_insert(Vec::new()).push(file);
+        }
+
+        by_path.into_iter()
+            .filter(|&(_, ref files)| files.len() > 1)
+            .map(|(path, files)| (path.clone(), files))
+            .collect()
+    }
+
+    /// The files that would replace ones written by an earlier generation.
+    pub fn overwrites(&self) -> Vec<&PlannedFile> {
+        self.files.iter().filter(|file| file.exists).collect()
+    }
+}
+
+impl fmt::Display for PlannedFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({} {})", self.path.display(), self.doc_type.short_name(), self.mod_path)?;
+        if self.exists {
+            write!(f, " [overwrites]")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GenerationPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for file in self.files.iter() {
+            writeln!(f, "{}", file)?;
+        }
+
+        let collisions = self.collisions();
+        for &(ref path, ref files) in collisions.iter() {
+            let items: Vec<String> = files.iter().map(|file| file.mod_path.to_string()).collect();
+            writeln!(f, "Collision: {} would be written by {}", path.display(), items.join(", "))?;
+        }
+
+        write!(f, "{} files would be written, {} of them over existing files, with {} collisions",
+               self.files.len(),
+               self.overwrites().len(),
+               collisions.len())
+    }
+}
@@ -0,0 +1,54 @@
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use atty;
+
+/// A count of the documents saved so far, drawn as a bar on stderr while saving a large crate.
+pub struct Progress {
+    total: usize,
+    saved: Mutex<usize>,
+    enabled: bool,
+}
+
+const BAR_WIDTH: usize = 40;
+
+impl Progress {
+    /// Creates a progress bar for saving `total` documents. The bar is only drawn if `enabled` is
+    /// set and stderr is a terminal, so it never ends up in redirected output.
+    pub fn new(total: usize, enabled: bool) -> Self {
+        Progress {
+            total: total,
+            saved: Mutex::new(0),
+            enabled: enabled && atty::is(atty::Stream::Stderr),
+        }
+    }
+
+    /// Records that one more document was saved and redraws the bar.
+    pub fn tick(&self) {
+        let mut saved = match self.saved.lock() {
+            Ok(saved) => saved,
+            Err(_)    => return,
+        };
+        *saved += 1;
+
+        if self.enabled {
+            self.draw(*saved);
+        }
+    }
+
+    /// Moves past the bar, once saving is done.
+    pub fn finish(&self) {
+        if self.enabled {
+            let _ = writeln!(io::stderr(), "");
+        }
+    }
+
+    fn draw(&self, saved: usize) {
+        let filled = if self.total == 0 { BAR_WIDTH } else { saved * BAR_WIDTH / self.total };
+        let _ = write!(io::stderr(), "\r[{}{}] {}/{} items saved",
+                       "=".repeat(filled),
+                       " ".repeat(BAR_WIDTH - filled),
+                       saved,
+                       self.total);
+    }
+}
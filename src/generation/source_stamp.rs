@@ -0,0 +1,149 @@
+//! Records the state of a crate's source files at the time its documentation was generated, so
+//! regeneration can be skipped for crates that haven't changed.
+
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use document::CrateInfo;
+use paths;
+use store::{self, Fnv1aHasher};
+
+use ::errors::*;
+
+const SOURCE_STAMP_FILENAME: &str = "source_stamp";
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+pub struct SourceStamp {
+    /// The latest modification time of any source file, in seconds since the Unix epoch.
+    pub mtime: u64,
+
+    /// A hash of the paths of all source files. Deleting a file doesn't change the modification
+    /// time of the others, so this is compared too before trusting `mtime`.
+    pub paths_hash: u64,
+
+    /// A hash of the paths and contents of all source files.
+    ///
+    /// The hashes are stored, so they're computed with FNV-1a, which gives the same hash with
+    /// every Rust version.
+    pub hash: u64,
+}
+
+impl SourceStamp {
+    fn stamp_path(crate_info: &CrateInfo) -> Result<PathBuf> {
+        let crate_doc_path = paths::crate_doc_path(crate_info)?;
+        Ok(crate_doc_path.join(SOURCE_STAMP_FILENAME))
+    }
+
+    pub fn load(crate_info: &CrateInfo) -> Result<SourceStamp> {
+        let path = SourceStamp::stamp_path(crate_info)?;
+        store::deserialize_object(path)
+    }
+
+    pub fn save(&self, crate_info: &CrateInfo) -> Result<()> {
+        let path = SourceStamp::stamp_path(crate_info)?;
+        store::serialize_object(self, path)
+    }
+}
+
+/// Computes the stamp of the sources of the crate at `crate_path`, and whether documentation
+/// generated from the sources described by `old_stamp` is still up to date.
+///
+/// The contents of the sources are only hashed if their modification times or the set of files
+/// changed.
+pub fn check_sources(crate_path: &Path,
+                     old_stamp: Option<&SourceStamp>) -> Result<(SourceStamp, bool)> {
+    let files = source_files(crate_path)?;
+    let mtime = latest_mtime(&files)?;
+    let paths_hash = paths_hash(crate_path, &files);
+
+    if let Some(old) = old_stamp {
+        if old.mtime == mtime && old.paths_hash == paths_hash {
+            let stamp = SourceStamp {
+                mtime: mtime,
+                paths_hash: paths_hash,
+                hash: old.hash,
+            };
+            return Ok((stamp, true));
+        }
+    }
+
+    let stamp = SourceStamp {
+        mtime: mtime,
+        paths_hash: paths_hash,
+        hash: content_hash(crate_path, &files)?,
+    };
+    let up_to_date = old_stamp.map_or(false, |old| old.hash == stamp.hash);
+
+    Ok((stamp, up_to_date))
+}
+
+/// Finds all Rust source files and the manifest of a crate, in a stable order.
+fn source_files(crate_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![crate_path.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir)
+            .chain_err(|| format!("Couldn't read source directory {}", dir.display()))?;
+
+        for entry in entries {
+            let entry = entry.chain_err(|| format!("Couldn't read source directory {}", dir.display()))?;
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            if path.is_dir() {
+                if !name.starts_with(".") && name != "target" {
+                    dirs.push(path);
+                }
+            } else if name.ends_with(".rs") || name == "Cargo.toml" {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+fn latest_mtime(files: &[PathBuf]) -> Result<u64> {
+    let mut latest = 0;
+    for file in files {
+        let modified = fs::metadata(file)
+            .and_then(|m| m.modified())
+            .chain_err(|| format!("Couldn't get modification time of {}", file.display()))?;
+        let secs = modified.duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        if secs > latest {
+            latest = secs;
+        }
+    }
+    Ok(latest)
+}
+
+fn paths_hash(crate_path: &Path, files: &[PathBuf]) -> u64 {
+    let mut hasher = Fnv1aHasher::new();
+    for file in files {
+        let relative = file.strip_prefix(crate_path).unwrap_or(file);
+        hasher.write(relative.to_string_lossy().as_bytes());
+    }
+    hasher.finish()
+}
+
+fn content_hash(crate_path: &Path, files: &[PathBuf]) -> Result<u64> {
+    let mut hasher = Fnv1aHasher::new();
+    for file in files {
+        let relative = file.strip_prefix(crate_path).unwrap_or(file);
+        hasher.write(relative.to_string_lossy().as_bytes());
+
+        let mut data = Vec::new();
+        File::open(file)
+            .and_then(|mut f| f.read_to_end(&mut data))
+            .chain_err(|| format!("Couldn't read source file {}", file.display()))?;
+        hasher.write(&data);
+    }
+    Ok(hasher.finish())
+}
@@ -0,0 +1,310 @@
+//! Prints types the way `pprust` does, but with the types nested too deeply inside them shown as
+//! `...`. The depth is counted while walking the type, so even huge generated types are printed
+//! without recursing deeper than the limit.
+
+use std::cmp;
+
+use syntax::abi;
+use syntax::ast;
+use syntax::print::pprust;
+use syntax::ptr::P;
+
+/// Prints a type, showing the types nested more than `max_depth` deep inside it as `...`, like
+/// `Vec<Option<...>>` for a depth of 2.
+pub fn ty_to_string(ty: &ast::Ty, max_depth: usize) -> String {
+    let mut printer = TyPrinter::new(max_depth);
+    printer.ty(ty);
+    printer.out
+}
+
+/// Prints a bound, like `Iterator<Item = u32>`, with the types inside it limited like
+/// `ty_to_string`.
+pub fn bound_to_string(bound: &ast::TyParamBound, max_depth: usize) -> String {
+    let mut printer = TyPrinter::new(max_depth);
+    printer.bound(bound);
+    printer.out
+}
+
+/// Prints a predicate of a where-clause, like `T: Clone`, with the types inside it limited like
+/// `ty_to_string`.
+pub fn where_predicate_to_string(predicate: &ast::WherePredicate, max_depth: usize) -> String {
+    let mut printer = TyPrinter::new(max_depth);
+    printer.where_predicate(predicate);
+    printer.out
+}
+
+struct TyPrinter {
+    depth: usize,
+    max_depth: usize,
+    out: String,
+}
+
+impl TyPrinter {
+    fn new(max_depth: usize) -> TyPrinter {
+        TyPrinter {
+            depth: 0,
+            max_depth: cmp::max(max_depth, 1),
+            out: String::new(),
+        }
+    }
+
+    fn push(&mut self, s: &str) {
+        self.out.push_str(s);
+    }
+
+    fn ty(&mut self, ty: &ast::Ty) {
+        if self.depth >= self.max_depth {
+            self.push("...");
+            return;
+        }
+
+        self.depth += 1;
+        self.ty_kind(ty);
+        self.depth -= 1;
+    }
+
+    fn tys(&mut self, tys: &[P<ast::Ty>]) {
+        for (i, ty) in tys.iter().enumerate() {
+            if i > 0 {
+                self.push(", ");
+            }
+            self.ty(ty);
+        }
+    }
+
+    fn ty_kind(&mut self, ty: &ast::Ty) {
+        match ty.node {
+            ast::TyKind::Slice(ref inner) => {
+                self.push("[");
+                self.ty(inner);
+                self.push("]");
+            },
+            ast::TyKind::Array(ref inner, ref len) => {
+                self.push("[");
+                self.ty(inner);
+                self.push("; ");
+                self.push(&pprust::expr_to_string(len));
+                self.push("]");
+            },
+            ast::TyKind::Ptr(ref mut_ty) => {
+                self.push(match mut_ty.mutbl {
+                    ast::Mutability::Mutable   => "*mut ",
+                    ast::Mutability::Immutable => "*const ",
+                });
+                self.ty(&mut_ty.ty);
+            },
+            ast::TyKind::Rptr(ref lifetime, ref mut_ty) => {
+                self.push("&");
+                if let Some(ref lifetime) = *lifetime {
+                    self.lifetime(lifetime);
+                    self.push(" ");
+                }
+                if mut_ty.mutbl == ast::Mutability::Mutable {
+                    self.push("mut ");
+                }
+                self.ty(&mut_ty.ty);
+            },
+            ast::TyKind::Never => self.push("!"),
+            ast::TyKind::Tup(ref tys) => {
+                self.push("(");
+                self.tys(tys);
+                if tys.len() == 1 {
+                    self.push(",");
+                }
+                self.push(")");
+            },
+            ast::TyKind::Paren(ref inner) => {
+                self.push("(");
+                self.ty(inner);
+                self.push(")");
+            },
+            ast::TyKind::Path(None, ref path) => self.path(path),
+            ast::TyKind::Path(Some(ref qself), ref path) => {
+                self.push("<");
+                self.ty(&qself.ty);
+                if qself.position > 0 {
+                    self.push(" as ");
+                    let trait_path = ast::Path {
+                        span: path.span,
+                        segments: path.segments[..qself.position].to_vec(),
+                    };
+                    self.path(&trait_path);
+                }
+                self.push(">");
+                for segment in path.segments[qself.position..].iter() {
+                    self.push("::");
+                    self.path_segment(segment);
+                }
+            },
+            ast::TyKind::TraitObject(ref bounds) => self.bounds(bounds),
+            ast::TyKind::ImplTrait(ref bounds) => {
+                self.push("impl ");
+                self.bounds(bounds);
+            },
+            ast::TyKind::BareFn(ref bare_fn) => {
+                if !bare_fn.lifetimes.is_empty() {
+                    self.lifetime_defs(&bare_fn.lifetimes);
+                    self.push(" ");
+                }
+                if bare_fn.unsafety == ast::Unsafety::Unsafe {
+                    self.push("unsafe ");
+                }
+                if bare_fn.abi != abi::Abi::Rust {
+                    self.push(&format!("extern \"{}\" ", bare_fn.abi.name()));
+                }
+                self.push("fn(");
+                for (i, arg) in bare_fn.decl.inputs.iter().enumerate() {
+                    if i > 0 {
+                        self.push(", ");
+                    }
+                    self.ty(&arg.ty);
+                }
+                if bare_fn.decl.variadic {
+                    self.push(", ...");
+                }
+                self.push(")");
+                if let ast::FunctionRetTy::Ty(ref output) = bare_fn.decl.output {
+                    self.push(" -> ");
+                    self.ty(output);
+                }
+            },
+            // The rest don't have types inside them.
+            _ => self.push(&pprust::ty_to_string(ty)),
+        }
+    }
+
+    /// Prints a path. The root segment of global paths like `::std::fmt` prints as nothing, so the
+    /// path starts with `::`.
+    fn path(&mut self, path: &ast::Path) {
+        for (i, segment) in path.segments.iter().enumerate() {
+            if i > 0 {
+                self.push("::");
+            }
+            self.path_segment(segment);
+        }
+    }
+
+    fn path_segment(&mut self, segment: &ast::PathSegment) {
+        let name = pprust::ident_to_string(segment.identifier);
+        if name != "{{root}}" {
+            self.push(&name);
+        }
+
+        let parameters = match segment.parameters {
+            Some(ref parameters) => parameters,
+            None => return,
+        };
+        match **parameters {
+            ast::PathParameters::AngleBracketed(ref data) => {
+                if data.lifetimes.is_empty() && data.types.is_empty() && data.bindings.is_empty() {
+                    return;
+                }
+                self.push("<");
+                let mut first = true;
+                for lifetime in data.lifetimes.iter() {
+                    if !first {
+                        self.push(", ");
+                    }
+                    first = false;
+                    self.lifetime(lifetime);
+                }
+                for ty in data.types.iter() {
+                    if !first {
+                        self.push(", ");
+                    }
+                    first = false;
+                    self.ty(ty);
+                }
+                for binding in data.bindings.iter() {
+                    if !first {
+                        self.push(", ");
+                    }
+                    first = false;
+                    self.push(&pprust::ident_to_string(binding.ident));
+                    self.push(" = ");
+                    self.ty(&binding.ty);
+                }
+                self.push(">");
+            },
+            ast::PathParameters::Parenthesized(ref data) => {
+                self.push("(");
+                self.tys(&data.inputs);
+                self.push(")");
+                if let Some(ref output) = data.output {
+                    self.push(" -> ");
+                    self.ty(output);
+                }
+            },
+        }
+    }
+
+    fn bounds(&mut self, bounds: &[ast::TyParamBound]) {
+        for (i, bound) in bounds.iter().enumerate() {
+            if i > 0 {
+                self.push(" + ");
+            }
+            self.bound(bound);
+        }
+    }
+
+    fn bound(&mut self, bound: &ast::TyParamBound) {
+        match *bound {
+            ast::TyParamBound::TraitTyParamBound(ref poly_trait, modifier) => {
+                if modifier == ast::TraitBoundModifier::Maybe {
+                    self.push("?");
+                }
+                if !poly_trait.bound_lifetimes.is_empty() {
+                    self.lifetime_defs(&poly_trait.bound_lifetimes);
+                    self.push(" ");
+                }
+                self.path(&poly_trait.trait_ref.path);
+            },
+            ast::TyParamBound::RegionTyParamBound(ref lifetime) => self.lifetime(lifetime),
+        }
+    }
+
+    fn where_predicate(&mut self, predicate: &ast::WherePredicate) {
+        match *predicate {
+            ast::WherePredicate::BoundPredicate(ref bound) => {
+                if !bound.bound_lifetimes.is_empty() {
+                    self.lifetime_defs(&bound.bound_lifetimes);
+                    self.push(" ");
+                }
+                self.ty(&bound.bounded_ty);
+                self.push(": ");
+                self.bounds(&bound.bounds);
+            },
+            ast::WherePredicate::RegionPredicate(ref region) => {
+                self.lifetime(&region.lifetime);
+                self.push(": ");
+                for (i, lifetime) in region.bounds.iter().enumerate() {
+                    if i > 0 {
+                        self.push(" + ");
+                    }
+                    self.lifetime(lifetime);
+                }
+            },
+            ast::WherePredicate::EqPredicate(ref eq) => {
+                self.ty(&eq.lhs_ty);
+                self.push(" = ");
+                self.ty(&eq.rhs_ty);
+            },
+        }
+    }
+
+    fn lifetime(&mut self, lifetime: &ast::Lifetime) {
+        self.push(&pprust::to_string(|s| s.print_lifetime(lifetime)));
+    }
+
+    /// Prints the lifetimes of a higher-ranked bound, like `for<'a>`.
+    fn lifetime_defs(&mut self, lifetimes: &[ast::LifetimeDef]) {
+        self.push("for<");
+        for (i, def) in lifetimes.iter().enumerate() {
+            if i > 0 {
+                self.push(", ");
+            }
+            self.lifetime(&def.lifetime);
+        }
+        self.push(">");
+    }
+}
@@ -1,11 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use syntax::abi;
 use syntax::ast;
+use syntax::attr;
+use syntax::codemap::{CodeMap, Span};
+use syntax::parse::token;
 use syntax::print::pprust;
 use syntax::symbol::keywords;
+use syntax::tokenstream::{TokenStream, TokenTree};
 
 use document::*;
+use generation::{contains_module, is_in_module, SkippedItem};
 use generation::ast_ty_wrappers::*;
 
 /// Visits the AST starting at a crate and creates a tree of documentation
@@ -15,22 +20,92 @@ use generation::ast_ty_wrappers::*;
 /// Does not implement "Visitor" since this design allows passing in found Items
 /// as arguments instead of maintaining a global stack of Items and looking at
 /// the last one found.
-pub struct OxidocVisitor {
+pub struct OxidocVisitor<'a> {
+    pub codemap: &'a CodeMap,
     pub current_scope: ModPath,
     pub crate_info: CrateInfo,
     pub crate_module: Module,
     pub name_for_ty: HashMap<NodeId, ast::Ident>,
     pub impls_for_ty: HashMap<ModPath, Vec<Impl>>,
+    pub reexports: Vec<Reexport>,
+
+    /// Items that were found but can't be documented yet.
+    pub skipped: Vec<SkippedItem>,
+
+    /// Impls whose type couldn't be resolved, with the name of the type. Only those for types
+    /// the crate documents are skipped items, which is known once the whole crate is visited.
+    unresolved_impls: Vec<(String, SkippedItem)>,
+
+    /// The paths that `extern crate` items make other crates available at, like `crate::serde`,
+    /// and the names of those crates.
+    pub extern_crates: HashMap<ModPath, String>,
+
+    /// Visit items marked `#[doc(hidden)]`, which are skipped by default.
+    pub document_hidden: bool,
+
+    /// Visit items that aren't public, which are skipped by default.
+    pub document_private: bool,
+
+    /// Only visit the items in these modules, and what leads to them, like
+    /// `GenerateOptions::modules`. Everything is visited if it's empty.
+    pub modules: Vec<String>,
 }
 
-impl OxidocVisitor {
-    pub fn new(crate_info: CrateInfo) -> OxidocVisitor {
+impl<'a> OxidocVisitor<'a> {
+    pub fn new(crate_info: CrateInfo, codemap: &'a CodeMap) -> OxidocVisitor<'a> {
         OxidocVisitor {
+            codemap: codemap,
             crate_module: Module::new(None),
             current_scope: ModPath::new(),
             crate_info: crate_info,
             name_for_ty: HashMap::new(),
             impls_for_ty: HashMap::new(),
+            reexports: Vec::new(),
+            skipped: Vec::new(),
+            unresolved_impls: Vec::new(),
+            extern_crates: HashMap::new(),
+            document_hidden: false,
+            document_private: false,
+            modules: Vec::new(),
+        }
+    }
+
+    /// Whether the module at `path` is in one of the selected modules, so all of its items are
+    /// visited.
+    fn is_selected(&self, path: &ModPath) -> bool {
+        self.modules.is_empty() || self.modules.iter().any(|module| is_in_module(path, module))
+    }
+
+    /// Whether an item of a module containing the selected ones is visited: the item if it's
+    /// selected, the modules leading to the selected ones, and the uses and impls, which may be
+    /// for the selected items.
+    fn is_visited_outside_selection(&self, item: &ast::Item) -> bool {
+        let path = self.current_scope.append_ident(item.ident);
+        match item.node {
+            ast::ItemKind::Use(..) | ast::ItemKind::Impl(..) => true,
+            ast::ItemKind::Mod(..) => {
+                self.modules.iter().any(|module| {
+                    is_in_module(&path, module) || contains_module(&path, module)
+                })
+            },
+            _ => self.is_selected(&path),
+        }
+    }
+
+    fn is_visible(&self, attrs: &[ast::Attribute]) -> bool {
+        self.document_hidden || !attrs.lists("doc").has_word("hidden")
+    }
+
+    fn source_span(&self, span: Span) -> Option<SourceSpan> {
+        SourceSpan::from_span(self.codemap, span)
+    }
+
+    fn skipped_item(&self, kind: &str, path: ModPath, span: Span, reason: String) -> SkippedItem {
+        SkippedItem {
+            kind: kind.to_string(),
+            path: path,
+            source: self.source_span(span),
+            reason: reason,
         }
     }
 
@@ -42,65 +117,106 @@ impl OxidocVisitor {
             vis: item.vis.clone(),
             attrs: item.attrs.clone(),
             path: for_path.append_ident(item.ident),
+            source: self.source_span(item.span),
         }
     }
 
-    fn visit_impl_method(&self, item: &ast::ImplItem, for_path: &ModPath, sig: &ast::MethodSig) -> Function {
+    fn visit_impl_method(&self, item: &ast::ImplItem, imp: &Impl,
+                         for_path: &ModPath, sig: &ast::MethodSig) -> Function {
         // In this case, the final segment of the ModPath is used as the type
         // the item is implemented on.
         Function {
             ident: item.ident,
             decl: (*sig.decl).clone(),
+            generics: sig.generics.clone(),
             unsafety: sig.unsafety.clone(),
             constness: sig.constness.node.clone(),
             vis: item.vis.clone(),
             abi: sig.abi.clone(),
             attrs: item.attrs.clone(),
             kind: FnKind::MethodFromImpl,
+            impl_header: Some(imp.header()),
             path: for_path.append_ident(item.ident),
+            source: self.source_span(item.span),
         }
     }
 
-    fn visit_impl_item(&self, module: &mut Module, item: &ast::ImplItem, for_path: &ModPath) {
+    fn visit_impl_item(&self, module: &mut Module, item: &ast::ImplItem, imp: &Impl,
+                       for_path: &ModPath, skipped: &mut Vec<SkippedItem>) {
         match item.node {
             ast::ImplItemKind::Const(ref ty, ref expr) => {
                 let c = self.visit_impl_const(item, for_path, ty, expr);
                 module.consts.push(c);
             },
             ast::ImplItemKind::Method(ref sig, _) => {
-                let f = self.visit_impl_method(item, for_path, sig);
+                let f = self.visit_impl_method(item, imp, for_path, sig);
                 module.fns.push(f);
             },
             // TODO: Handle types and macros
-            ast::ImplItemKind::Type(ref _ty) => (),
-            ast::ImplItemKind::Macro(ref _mac) => (),
+            ast::ImplItemKind::Type(ref _ty) => {
+                skipped.push(self.skipped_item("associated type",
+                                               for_path.append_ident(item.ident),
+                                               item.span,
+                                               "associated types of impls aren't documented"
+                                                   .to_string()));
+            },
+            ast::ImplItemKind::Macro(ref mac) => {
+                skipped.push(self.skipped_item("macro invocation",
+                                               for_path.clone(),
+                                               item.span,
+                                               macro_skip_reason(mac)));
+            },
         }
     }
 
-    fn add_impl(&mut self, module: &mut Module, imp: Impl) {
+    fn add_impl(&mut self, module: &mut Module, mut imp: Impl) {
+        imp.trait_link = imp.trait_.as_ref().and_then(|t| resolve_trait(module, &t.path));
+
         if let ast::TyKind::Path(_, path) = imp.for_.node.clone() {
             let namespaced_path = ModPath::from(path.clone());
             if let Some(full_path) = module.resolve_use(&namespaced_path) {
                 debug!("Full path for {}: {}", namespaced_path, full_path);
-                for item in &imp.items {
-                    self.visit_impl_item(module, &item, &full_path);
+                let mut skipped = Vec::new();
+                // The items of trait impls are as public as the trait, whatever they're marked.
+                let is_inherent = imp.trait_.is_none();
+                for item in imp.items.iter().filter(|item| {
+                    self.is_visible(&item.attrs) &&
+                        (!is_inherent || self.document_private ||
+                         item.vis == ast::Visibility::Public)
+                }) {
+                    self.visit_impl_item(module, &item, &imp, &full_path, &mut skipped);
                 }
+                self.skipped.extend(skipped);
+                // Items are linked from the type they belong to, so they are namespaced under
+                // its path rather than the module's.
+                imp.path = full_path.clone();
                 self.impls_for_ty.entry(full_path.clone()).or_insert(Vec::new()).push(imp);
-            } else {
+            } else if let Some(name) = namespaced_path.0.last().map(|s| s.identifier.clone()) {
                 debug!("No type found for impl {}", namespaced_path);
+                self.unresolved_impls.push((name, SkippedItem {
+                    kind: "impl".to_string(),
+                    path: imp.path.clone(),
+                    source: imp.source.clone(),
+                    reason: format!("the type `{}` couldn't be resolved", namespaced_path),
+                }));
             }
+        } else {
+            // Impls for references, tuples and the like have no page to be listed on.
+            debug!("Impl for {} isn't documented", pprust::ty_to_string(&imp.for_));
         }
     }
 
     fn visit_enum_def(&self, item: &ast::Item,
                       enum_def: &ast::EnumDef,
-                      _generics: &ast::Generics) -> Enum {
+                      generics: &ast::Generics) -> Enum {
         Enum {
             ident: item.ident,
             vis: item.vis.clone(),
             variants: enum_def.variants.clone(),
+            generics: generics.clone(),
             attrs: item.attrs.clone(),
             path: self.current_scope.append_ident(item.ident),
+            source: self.source_span(item.span),
         }
     }
 
@@ -109,17 +225,20 @@ impl OxidocVisitor {
                 ast_unsafety: ast::Unsafety,
                 ast_constness: ast::Constness,
                 ast_abi: abi::Abi,
-                _generics: &ast::Generics) -> Function {
+                generics: &ast::Generics) -> Function {
         Function {
             ident: item.ident,
             decl: fn_decl.clone(),
+            generics: generics.clone(),
             unsafety: ast_unsafety,
             constness: ast_constness,
             vis: item.vis.clone(),
             abi: ast_abi,
             attrs: item.attrs.clone(),
             kind: FnKind::ItemFn,
+            impl_header: None,
             path: self.current_scope.append_ident(item.ident),
+            source: self.source_span(item.span),
         }
     }
 
@@ -134,59 +253,173 @@ impl OxidocVisitor {
             vis: item.vis.clone(),
             attrs: item.attrs.clone(),
             path: self.current_scope.append_ident(item.ident),
+            source: self.source_span(item.span),
+        }
+    }
+
+    fn visit_static(&self, item: &ast::Item,
+                    ast_ty: &ast::Ty,
+                    ast_mutability: ast::Mutability,
+                    ast_expr: &ast::Expr,
+    ) -> Static {
+        Static {
+            ident: item.ident,
+            type_: Ty::from(ast_ty.clone()),
+            mutability: ast_mutability,
+            expr: Some(ast_expr.clone()),
+            abi: None,
+            vis: item.vis.clone(),
+            attrs: item.attrs.clone(),
+            path: self.current_scope.append_ident(item.ident),
+            source: self.source_span(item.span),
+        }
+    }
+
+    fn visit_foreign_item(&self, module: &mut Module, item: &ast::ForeignItem, abi: abi::Abi) {
+        let path = self.current_scope.append_ident(item.ident);
+        let source = self.source_span(item.span);
+
+        match item.node {
+            ast::ForeignItemKind::Fn(ref decl, ref generics) => {
+                module.fns.push(Function {
+                    ident: item.ident,
+                    decl: (**decl).clone(),
+                    generics: generics.clone(),
+                    // Calling a foreign function is always unsafe.
+                    unsafety: ast::Unsafety::Unsafe,
+                    constness: ast::Constness::NotConst,
+                    vis: item.vis.clone(),
+                    abi: abi,
+                    attrs: item.attrs.clone(),
+                    kind: FnKind::ForeignFn,
+                    impl_header: None,
+                    path: path,
+                    source: source,
+                });
+            },
+            ast::ForeignItemKind::Static(ref ty, is_mutable) => {
+                let mutability = if is_mutable {
+                    ast::Mutability::Mutable
+                } else {
+                    ast::Mutability::Immutable
+                };
+                module.statics.push(Static {
+                    ident: item.ident,
+                    type_: Ty::from((**ty).clone()),
+                    mutability: mutability,
+                    expr: None,
+                    abi: Some(abi),
+                    vis: item.vis.clone(),
+                    attrs: item.attrs.clone(),
+                    path: path,
+                    source: source,
+                });
+            },
+        }
+    }
+
+    fn visit_typedef(&self, item: &ast::Item,
+                     ast_ty: &ast::Ty,
+                     ast_generics: &ast::Generics) -> Typedef {
+        Typedef {
+            ident: item.ident,
+            type_: Ty::from(ast_ty.clone()),
+            generics: ast_generics.clone(),
+            vis: item.vis.clone(),
+            attrs: item.attrs.clone(),
+            path: self.current_scope.append_ident(item.ident),
+            source: self.source_span(item.span),
+        }
+    }
+
+    fn visit_macro_def(&self, item: &ast::Item, def: &ast::MacroDef) -> MacroRules {
+        MacroRules {
+            ident: item.ident,
+            matchers: macro_matchers(def),
+            exported: attr::contains_name(&item.attrs, "macro_export"),
+            attrs: item.attrs.clone(),
+            path: self.current_scope.append_ident(item.ident),
+            source: self.source_span(item.span),
         }
     }
 
     fn visit_struct(&self, item: &ast::Item,
                     variant_data: &ast::VariantData,
-                    _ast_generics: &ast::Generics) -> Struct {
+                    ast_generics: &ast::Generics) -> Struct {
         Struct {
             ident: item.ident,
             id: NodeId::from(item.id),
             vis: item.vis.clone(),
             fields: variant_data.fields().iter().cloned().collect(),
+            generics: ast_generics.clone(),
             attrs: item.attrs.clone(),
             path: self.current_scope.append_ident(item.ident),
+            source: self.source_span(item.span),
         }
 
     }
 
+    fn visit_union(&self, item: &ast::Item,
+                   variant_data: &ast::VariantData,
+                   ast_generics: &ast::Generics) -> Union {
+        Union {
+            ident: item.ident,
+            id: NodeId::from(item.id),
+            vis: item.vis.clone(),
+            kind: StructKind::from_ast(variant_data),
+            fields: variant_data.fields().iter().cloned().collect(),
+            generics: ast_generics.clone(),
+            attrs: item.attrs.clone(),
+            path: self.current_scope.append_ident(item.ident),
+            source: self.source_span(item.span),
+        }
+    }
+
     fn visit_trait(&self, item: &ast::Item,
                    ast_unsafety: ast::Unsafety,
-                   _ast_generics: &ast::Generics,
+                   ast_generics: &ast::Generics,
+                   ast_bounds: &ast::TyParamBounds,
                    trait_items: &Vec<ast::TraitItem>) -> Trait {
         Trait {
-            items: trait_items.iter().cloned().map(|ti| {
+            items: trait_items.iter().filter(|ti| self.is_visible(&ti.attrs)).cloned().map(|ti| {
                 TraitItem {
                     ident: ti.ident,
                     attrs: ti.attrs.clone(),
                     path: ModPath::join(&self.current_scope.append_ident(item.ident),
                                         &ModPath::from(ti.ident)),
                     node: ti.node,
+                    source: self.source_span(ti.span),
                 }
             }).collect(),
             ident: item.ident,
             unsafety: ast_unsafety,
+            generics: ast_generics.clone(),
+            bounds: ast_bounds.clone(),
+            supertraits: Vec::new(),
             vis: item.vis.clone(),
             attrs: item.attrs.clone(),
             path: self.current_scope.append_ident(item.ident),
+            source: self.source_span(item.span),
         }
     }
 
     fn visit_impl(&self, item: &ast::Item,
                   ast_unsafety: ast::Unsafety,
                   _ast_defaultness: &ast::Defaultness,
-                  _ast_generics: &ast::Generics,
+                  ast_generics: &ast::Generics,
                   ast_trait_ref: &Option<ast::TraitRef>,
                   ast_ty: &ast::Ty,
                   items: &Vec<ast::ImplItem>) -> Impl {
         Impl {
             unsafety: ast_unsafety,
+            generics: ast_generics.clone(),
             trait_: ast_trait_ref.clone(),
+            trait_link: None,
             for_: ast_ty.clone(),
-            items: items.clone(),
+            items: items.iter().filter(|item| self.is_visible(&item.attrs)).cloned().collect(),
             attrs: item.attrs.clone(),
-            path: self.current_scope.append_ident(item.ident)
+            path: self.current_scope.clone(),
+            source: self.source_span(item.span),
         }
     }
 
@@ -200,16 +433,91 @@ impl OxidocVisitor {
         }
     }
 
-    fn add_uses(&self, module: &mut Module,
-                _item: &ast::Item,
+    /// Resolves the path of a `use` to a full path, treating paths that don't start with `self`
+    /// or `super` as relative to the crate root.
+    fn absolute_use_path(&self, path: &ModPath) -> ModPath {
+        let crate_root = ModPath(self.current_scope.0.iter().take(1).cloned().collect());
+        let mut segments = path.segments();
+
+        let mut result = match segments.next().map(|s| s.identifier.as_str()) {
+            Some("self")                     => self.current_scope.clone(),
+            Some("super")                    => self.current_scope.parent()
+                .unwrap_or(self.current_scope.clone()),
+            Some("{{root}}") | Some("crate") => crate_root,
+            Some(first)                      => {
+                let mut result = crate_root;
+                result.push_string(first.to_string());
+                result
+            },
+            None                             => crate_root,
+        };
+
+        for segment in segments {
+            if segment.identifier == "super" {
+                result.pop();
+            } else {
+                result.push(segment.clone());
+            }
+        }
+        result
+    }
+
+    /// Splits a path starting with an `extern crate` into the name of that crate and the path of
+    /// the item in it.
+    fn extern_crate_path(&self, path: &ModPath) -> Option<(String, ModPath)> {
+        let segments: Vec<PathSegment> = path.segments().cloned().collect();
+        (1..segments.len()).filter_map(|len| {
+            let prefix = ModPath(segments[..len].to_vec());
+            self.extern_crates.get(&prefix).map(|krate| {
+                let mut target = ModPath::from(krate.clone());
+                for segment in segments[len..].iter() {
+                    target.push(segment.clone());
+                }
+                (krate.clone(), target)
+            })
+        }).next()
+    }
+
+    fn add_reexport(&mut self,
+                    item: &ast::Item,
+                    path: &ModPath,
+                    glob: bool,
+                    alias: Option<String>) {
+        if item.vis != ast::Visibility::Public {
+            return;
+        }
+
+        let target = self.absolute_use_path(path);
+        let (krate, target) = match self.extern_crate_path(&target) {
+            Some((krate, target)) => (Some(krate), target),
+            None                  => (None, target),
+        };
+
+        let reexport = Reexport {
+            module: self.current_scope.clone(),
+            target: target,
+            glob: glob,
+            krate: krate,
+            alias: alias,
+        };
+        self.reexports.push(reexport);
+    }
+
+    fn add_uses(&mut self, module: &mut Module,
+                use_item: &ast::Item,
                 import: &ast::ViewPath) {
         // TODO: This will take some work to resolve globbed imports from
         // external crates.
         match import.node {
             ast::ViewPath_::ViewPathSimple(ident, ref path) => {
-                module.add_use(&ident, ModPath::from(path.clone()));
+                let path = ModPath::from(path.clone());
+                let alias = rename_of(&path, &ident);
+                self.add_reexport(use_item, &path, false, alias);
+                module.add_use(&ident, path);
             },
             ast::ViewPath_::ViewPathGlob(ref path) => {
+                self.add_reexport(use_item, &ModPath::from(path.clone()), true, None);
+
                 // FIXME: Get all the keywords for this namespace. One would
                 // have to look into stores of dependencies that are already
                 // saved and get the list of namespaces there.
@@ -234,15 +542,18 @@ impl OxidocVisitor {
                         },
                     };
 
-                    let full_path = if path.segments.last().unwrap().identifier == ident {
-                        // This was originally the 'self' keyword, so
+                    let full_path = if item.node.name == keywords::SelfValue.ident() {
                         // std::fmt::{self} becomes
                         // path = std::fmt, ident = fmt
                         ModPath::from(path.clone())
                     } else {
+                        // The original name is used, since the item may be
+                        // renamed.
                         ModPath::join(&ModPath::from(path.clone()),
-                                      &ModPath::from(ident))
+                                      &ModPath::from(item.node.name))
                     };
+                    let alias = rename_of(&full_path, &ident);
+                    self.add_reexport(use_item, &full_path, false, alias);
                     module.add_use(&ident, full_path);
                 }
             }
@@ -258,6 +569,15 @@ impl OxidocVisitor {
                 let c = self.visit_const(item, ty, expr);
                 module.consts.push(c);
             }
+            ast::ItemKind::Static(ref ty, mutability, ref expr) => {
+                let s = self.visit_static(item, ty, mutability, expr);
+                module.statics.push(s);
+            }
+            ast::ItemKind::Ty(ref ty, ref generics) => {
+                let t = self.visit_typedef(item, ty, generics);
+                module.add_use(&item.ident, t.path.clone());
+                module.typedefs.push(t);
+            }
             ast::ItemKind::Fn(ref decl, unsafety, constness,
                               abi, ref generics, _) => {
                 let f = self.visit_fn(item, &*decl,
@@ -284,13 +604,18 @@ impl OxidocVisitor {
                 module.structs.push(s);
             },
             ast::ItemKind::Union(ref variant_data, ref generics) => {
-                // TODO when unions become stable?
+                let u = self.visit_union(item,
+                                         variant_data,
+                                         generics);
+                module.add_use(&item.ident, u.path.clone());
+                module.unions.push(u);
             },
             ast::ItemKind::Trait(unsafety, ref generics,
                                  ref param_bounds, ref trait_items) => {
                 let t = self.visit_trait(item,
                                          unsafety, generics,
-                                         trait_items);
+                                         param_bounds, trait_items);
+                module.add_use(&item.ident, t.path.clone());
                 module.traits.push(t);
             },
             ast::ItemKind::DefaultImpl(unsafety, ref trait_ref) => {
@@ -306,29 +631,59 @@ impl OxidocVisitor {
                                         ty, items);
                 module.impls.push(i);
             },
-            ast::ItemKind::Ty(..) |
-            ast::ItemKind::Static(..) |
+            ast::ItemKind::ForeignMod(ref foreign_mod) => {
+                for foreign_item in &foreign_mod.items {
+                    if (foreign_item.vis == ast::Visibility::Public || self.document_private) &&
+                        self.is_visible(&foreign_item.attrs) {
+                        self.visit_foreign_item(module, foreign_item, foreign_mod.abi);
+                    }
+                }
+            },
             ast::ItemKind::Mac(..) |
-            ast::ItemKind::ExternCrate(..) |
-            ast::ItemKind::ForeignMod(..) => (),
+            ast::ItemKind::ExternCrate(..) => (),
             ast::ItemKind::GlobalAsm(..) => (),
-            ast::ItemKind::MacroDef(..) => (),
+            ast::ItemKind::MacroDef(ref def) => {
+                let m = self.visit_macro_def(item, def);
+                module.macros.push(m);
+            },
         }
     }
 
     fn visit_module(&mut self, attrs: Vec<ast::Attribute>, m: &ast::Mod,
                     mod_name: Option<ast::Ident>) -> Module {
-        debug!("visiting module");
         let mut module = Module::new(mod_name);
         module.attrs = attrs.clone();
+        module.source = self.source_span(m.inner);
 
         let current_module_scope = current_module_scope(self, mod_name);
         self.current_scope.push_string(current_module_scope);
         module.path = self.current_scope.clone();
+        info!("Documenting module {}", module.path);
 
+        // `extern crate` items are private, and uses can come before them.
         for item in &m.items {
-            if should_visit_item(&item) {
+            if let ast::ItemKind::ExternCrate(original_name) = item.node {
+                let name = original_name.unwrap_or(item.ident.name);
+                self.extern_crates.insert(self.current_scope.append_ident(item.ident),
+                                          name.as_str().to_string());
+            }
+        }
+
+        let selected = self.is_selected(&self.current_scope);
+        for item in &m.items {
+            if !selected && !self.is_visited_outside_selection(item) {
+                continue;
+            }
+            if should_visit_item(&item, self.document_private) && self.is_visible(&item.attrs) {
                 self.visit_item(item, &mut module);
+            } else if let ast::ItemKind::Mac(ref mac) = item.node {
+                // Macro invocations have no visibility of their own, but may expand to public
+                // items.
+                let skipped = self.skipped_item("macro invocation",
+                                                self.current_scope.clone(),
+                                                item.span,
+                                                macro_skip_reason(mac));
+                self.skipped.push(skipped);
             }
         }
 
@@ -338,6 +693,13 @@ impl OxidocVisitor {
             self.add_impl(&mut module, impl_);
         }
 
+        // Supertraits can be defined or imported after the trait that uses them, so they are
+        // resolved after the rest of the module.
+        for i in 0..module.traits.len() {
+            let supertraits = resolve_supertraits(&module, &module.traits[i].bounds);
+            module.traits[i].supertraits = supertraits;
+        }
+
         module
     }
 
@@ -347,26 +709,94 @@ impl OxidocVisitor {
                                               &krate.module,
                                               None);
         self.crate_module.is_crate = true;
+
+        // Impls for types of other crates, like `Vec<T>`, or for private types aren't missing
+        // anything, so only those for the crate's public types are reported.
+        let mut public_types = HashSet::new();
+        add_public_type_names(&self.crate_module, &mut public_types);
+        for (name, skipped) in self.unresolved_impls.drain(..) {
+            if public_types.contains(&name) {
+                self.skipped.push(skipped);
+            }
+        }
+    }
+}
+
+/// Adds the names of the public structs, unions, enums and type aliases of the module and its
+/// submodules.
+fn add_public_type_names(module: &Module, names: &mut HashSet<String>) {
+    let is_public = |vis: &ast::Visibility| *vis == ast::Visibility::Public;
+    names.extend(module.structs.iter()
+                 .filter(|s| is_public(&s.vis))
+                 .map(|s| pprust::ident_to_string(s.ident)));
+    names.extend(module.unions.iter()
+                 .filter(|u| is_public(&u.vis))
+                 .map(|u| pprust::ident_to_string(u.ident)));
+    names.extend(module.enums.iter()
+                 .filter(|e| is_public(&e.vis))
+                 .map(|e| pprust::ident_to_string(e.ident)));
+    names.extend(module.typedefs.iter()
+                 .filter(|t| is_public(&t.vis))
+                 .map(|t| pprust::ident_to_string(t.ident)));
+
+    for child in module.mods.iter() {
+        add_public_type_names(child, names);
     }
 }
 
-fn should_visit_item(item: &ast::Item) -> bool {
-    // TODO: Until "pub use" works, public reexports may not be visited, so just visit all modules
-    // to find them.
+fn should_visit_item(item: &ast::Item, document_private: bool) -> bool {
+    // Private modules are visited for the items they re-export with `pub use`. The items that
+    // aren't re-exported are marked unreachable after conversion.
+    // extern blocks have no visibility, so their items are checked one by one
     let is_module = match item.node {
-        ast::ItemKind::Mod(..) => true,
+        ast::ItemKind::Mod(..) |
+        ast::ItemKind::ForeignMod(..) => true,
         _ => false,
     };
 
-    let is_hidden = item.attrs.lists("doc").has_word("hidden");
-
-    // methods in impls inherit the visibility of the parent
+    // methods in impls inherit the visibility of the parent, and macros are public if they are
+    // exported
     let is_public = match item.node {
         ast::ItemKind::Impl(..) => true,
+        ast::ItemKind::MacroDef(..) => attr::contains_name(&item.attrs, "macro_export"),
         _ => item.vis == ast::Visibility::Public,
     };
 
-    !is_hidden && (is_module || is_public)
+    is_module || is_public || document_private
+}
+
+fn macro_skip_reason(mac: &ast::Mac) -> String {
+    format!("items generated by `{}!` aren't documented", pprust::path_to_string(&mac.node.path))
+}
+
+/// The matchers of the rules of a `macro_rules!` definition. Each rule is a delimited matcher,
+/// `=>`, a delimited body and an optional `;`.
+fn macro_matchers(def: &ast::MacroDef) -> Vec<String> {
+    let stream: TokenStream = def.tokens.clone().into();
+    let mut matchers = Vec::new();
+    let mut expect_matcher = true;
+
+    for tree in stream.trees() {
+        match tree {
+            TokenTree::Delimited(..) if expect_matcher => {
+                matchers.push(pprust::tts_to_string(&[tree.clone()]));
+                expect_matcher = false;
+            },
+            TokenTree::Token(_, token::Semi) => expect_matcher = true,
+            _ => (),
+        }
+    }
+
+    matchers
+}
+
+/// The name an item is imported under, if a `use` renames it with `as`.
+fn rename_of(path: &ModPath, ident: &ast::Ident) -> Option<String> {
+    let name = pprust::ident_to_string(*ident);
+    match path.name() {
+        Some(ref segment) if segment.identifier == name => None,
+        _                                             => Some(name),
+    }
 }
 
 fn current_module_scope(visitor: &OxidocVisitor, mod_name: Option<ast::Ident>) -> String {
@@ -376,3 +806,31 @@ fn current_module_scope(visitor: &OxidocVisitor, mod_name: Option<ast::Ident>) -
         visitor.crate_info.name.clone()
     }
 }
+
+/// Finds the name of the trait at the given path and resolves it against the uses of the module.
+/// Traits outside the crate get an empty path, since they can't be linked to.
+fn resolve_trait(module: &Module, path: &ast::Path) -> Option<(String, ModPath)> {
+    let path = ModPath::from(path.clone());
+    let name = match path.name() {
+        Some(segment) => segment.identifier,
+        None          => return None,
+    };
+    let resolved = match module.resolve_use(&path) {
+        Some(full_path) if full_path.head() == module.path.head() => full_path,
+        _ => ModPath::new(),
+    };
+    Some((name, resolved))
+}
+
+/// Resolves the traits among the given bounds, ignoring lifetimes and `?Sized`.
+fn resolve_supertraits(module: &Module, bounds: &ast::TyParamBounds) -> Vec<(String, ModPath)> {
+    bounds.iter().filter_map(|bound| {
+        match *bound {
+            ast::TyParamBound::TraitTyParamBound(ref poly_trait_ref,
+                                                 ast::TraitBoundModifier::None) => {
+                resolve_trait(module, &poly_trait_ref.trait_ref.path)
+            },
+            _ => None,
+        }
+    }).collect()
+}
@@ -0,0 +1,67 @@
+//! Syntax highlighting of Rust code for terminal output.
+
+use std::env;
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Style, Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::as_24_bit_terminal_escaped;
+
+/// The environment variable used to select the highlighting theme.
+const THEME_VAR: &str = "OXIDOC_THEME";
+
+const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+struct Highlighter {
+    syntax_set: SyntaxSet,
+    theme: Theme,
+}
+
+impl Highlighter {
+    fn new() -> Self {
+        let mut syntax_set = SyntaxSet::load_defaults_nonewlines();
+        syntax_set.link_syntaxes();
+
+        let mut themes = ThemeSet::load_defaults().themes;
+        let name = env::var(THEME_VAR).unwrap_or(DEFAULT_THEME.to_string());
+        let theme = match themes.remove(&name) {
+            Some(theme) => theme,
+            None => {
+                warn!("Unknown highlighting theme {}, using {}", name, DEFAULT_THEME);
+                themes.remove(DEFAULT_THEME).unwrap()
+            }
+        };
+
+        Highlighter {
+            syntax_set: syntax_set,
+            theme: theme,
+        }
+    }
+
+    fn highlight(&self, code: &str) -> String {
+        let syntax = match self.syntax_set.find_syntax_by_extension("rs") {
+            Some(syntax) => syntax,
+            None => return code.to_string(),
+        };
+
+        let mut lines = HighlightLines::new(syntax, &self.theme);
+        code.lines()
+            .map(|line| {
+                let ranges: Vec<(Style, &str)> = lines.highlight(line);
+                as_24_bit_terminal_escaped(&ranges[..], false) + "\x1b[0m"
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+thread_local! {
+    // Loading the syntax definitions is slow, so it's only done once.
+    static HIGHLIGHTER: Highlighter = Highlighter::new();
+}
+
+/// Highlights Rust code with ANSI escapes. The theme can be changed with the `OXIDOC_THEME`
+/// environment variable.
+pub fn highlight_rust(code: &str) -> String {
+    HIGHLIGHTER.with(|h| h.highlight(code))
+}
@@ -9,25 +9,41 @@ extern crate serde_derive;
 
 extern crate clap;
 extern crate ansi_term;
+extern crate atty;
 extern crate bincode;
 extern crate cursive;
 extern crate env_logger;
+extern crate num_cpus;
 extern crate regex;
 extern crate serde;
+extern crate serde_json;
 extern crate strsim;
+extern crate syntect;
 extern crate syntex_syntax as syntax;
 extern crate toml;
 extern crate term_size;
 extern crate catmark;
+extern crate pulldown_cmark;
+extern crate zstd;
 
 #[cfg(unix)]
 extern crate pager;
 
+mod colorize;
+pub mod completion;
 pub mod conversion;
+pub mod diff;
 pub mod generation;
 pub mod document;
 pub mod driver;
+pub mod full_text;
+mod highlight;
 pub mod markup;
+pub mod merge;
+pub mod migration;
+pub mod search;
+pub mod site;
+pub mod stats;
 pub mod store;
 mod toml_util;
 pub mod tui;
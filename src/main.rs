@@ -6,26 +6,39 @@ extern crate log;
 extern crate clap;
 
 extern crate ansi_term;
+extern crate atty;
 extern crate bincode;
 extern crate cursive;
-extern crate env_logger;
 extern crate regex;
 extern crate serde;
+extern crate serde_json;
 extern crate syntex_syntax as syntax;
 extern crate toml;
 extern crate catmark;
+extern crate term_size;
 
 extern crate oxidoc;
 
-use std::path::PathBuf;
+use std::env;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches};
+use oxidoc::completion::{CompletionIndex, ExportFormat};
+use oxidoc::diff;
+use oxidoc::stats;
+use oxidoc::conversion::Documentation;
 use oxidoc::driver::Driver;
-use oxidoc::generation;
+use oxidoc::generation::{self, GenerateOptions, Verbosity};
+use oxidoc::generation::plan::GenerationPlan;
 use oxidoc::errors::*;
-use oxidoc::store::StoreLocation;
-use oxidoc::markup::Format;
-use oxidoc::store::Store;
+use oxidoc::document::CrateInfo;
+use oxidoc::store;
+use oxidoc::full_text::FullTextIndex;
+use oxidoc::markup::{self, FormatOptions};
+use oxidoc::search::{self, JsonResult, SearchFilter, SearchMode};
+use oxidoc::site;
+use oxidoc::store::{CrateIndex, Store, StoreLocation};
 
 #[cfg(unix)]
 extern crate pager;
@@ -54,16 +67,265 @@ fn app<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true)
                 .alias("generate"),
         )
+        .arg(
+            Arg::with_name("rustdoc-json")
+                .long("rustdoc-json")
+                .value_name("FILE")
+                .help(
+                    "Generates oxidoc info from the JSON written by rustdoc's \
+                    --output-format=json instead of parsing the crate",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("merge")
+                .long("merge")
+                .value_name("FEATURES=DIR")
+                .help(
+                    "Merges the documentation generated for a crate with different features, \
+                     like 'std=one/dir std,serde=other/dir', and saves it to the store",
+                )
+                .takes_value(true)
+                .multiple(true)
+                .min_values(2),
+        )
+        .arg(
+            Arg::with_name("remove")
+                .long("remove")
+                .value_name("CRATE")
+                .help(
+                    "Removes the documentation for a crate from the store, either for all \
+                    versions ('serde') or a single one ('serde-1.0.0')",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("verify")
+                .long("verify")
+                .value_name("CRATE")
+                .help(
+                    "Checks that the documentation files of a crate can be read back, listing \
+                    the corrupt ones, either for all versions ('serde') or a single one \
+                    ('serde-1.0.0')",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("migrate")
+                .long("migrate")
+                .value_name("VERSION")
+                .help(
+                    "Rewrites the documentation written with an older format version in the \
+                    current one instead of regenerating it, keeping the originals in a backup \
+                    directory",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("export-completions")
+                .long("export-completions")
+                .value_name("FILE")
+                .help(
+                    "Writes the full path, kind and signature of every stored item to a file, \
+                    for editors to offer completions from",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("export-format")
+                .long("export-format")
+                .value_name("FORMAT")
+                .help("The format of the exported completions (json, binary)")
+                .possible_values(&["json", "binary"])
+                .default_value("json")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("site")
+                .long("site")
+                .value_names(&["CRATE", "DIR"])
+                .help(
+                    "Writes a browsable HTML site for a crate's documentation to a directory, \
+                    either for all versions ('serde') or a single one ('serde-1.0.0')",
+                )
+                .takes_value(true)
+                .number_of_values(2),
+        )
+        .arg(
+            Arg::with_name("diff")
+                .long("diff")
+                .value_names(&["OLD_DIR", "NEW_DIR"])
+                .help(
+                    "Compares the documentation generated for two versions of a crate and lists \
+                    the added, removed and changed items, failing if any change is breaking",
+                )
+                .takes_value(true)
+                .number_of_values(2),
+        )
+        .arg(
+            Arg::with_name("stats")
+                .long("stats")
+                .value_name("CRATE")
+                .help(
+                    "Shows how many of a crate's public items are documented, by type, either for \
+                    all versions ('serde') or a single one ('serde-1.0.0')",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("min-coverage")
+                .long("min-coverage")
+                .value_name("PERCENT")
+                .requires("stats")
+                .help("Fails if less than this percentage of the items shown by --stats are \
+                       documented")
+                .takes_value(true),
+        )
+        .arg(Arg::with_name("force").short("f").long("force").help(
+            "Regenerates documentation even if the crate sources haven't changed",
+        ))
+        .arg(Arg::with_name("dry-run").long("dry-run").help(
+            "Lists the documentation files generation would write, and any that would be \
+            overwritten, without writing anything",
+        ))
+        .arg(
+            Arg::with_name("module")
+                .long("module")
+                .value_name("PATH")
+                .help(
+                    "Only generates documentation for the items under a module, like \
+                    'serde::de', along with the modules containing it. Can be given several times",
+                )
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("type")
+                .long("type")
+                .value_name("TYPE")
+                .help(
+                    "Only shows results of the given types, separated by commas (fn, mod, struct, \
+                    enum, union, trait, const, static, type, ...)",
+                )
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("jobs")
+                .short("j")
+                .long("jobs")
+                .value_name("N")
+                .help("Number of threads to use when saving generated documentation")
+                .takes_value(true),
+        )
+        .arg(Arg::with_name("no-prune").long("no-prune").help(
+            "Keeps the documentation of items that were removed from the source",
+        ))
+        .arg(Arg::with_name("json-copies").long("json-copies").help(
+            "Also writes the documentation as JSON, which later versions can migrate it from",
+        ))
+        .arg(Arg::with_name("strict").long("strict").help(
+            "Fails if any items can't be documented, instead of only warning about them",
+        ))
+        .arg(Arg::with_name("verbose").short("v").long("verbose").help(
+            "Reports each module while generating, with a progress bar while saving",
+        ))
+        .arg(Arg::with_name("quiet").short("q").long("quiet").conflicts_with("verbose").help(
+            "Only reports errors while generating",
+        ))
+        .arg(Arg::with_name("document-private-items").long("document-private-items").help(
+            "Also generates documentation for items that aren't public",
+        ))
+        .arg(Arg::with_name("document-hidden").long("document-hidden").help(
+            "Also generates documentation for items marked #[doc(hidden)]",
+        ))
+        .arg(Arg::with_name("source-order").long("source-order").help(
+            "Lists related items and members in the order they were declared",
+        ))
+        .arg(Arg::with_name("hyperlinks").long("hyperlinks").help(
+            "Makes source locations and related items clickable, in terminals that support it",
+        ))
         .arg(Arg::with_name("pager").short("p").long("pager").help(
-            "Automatically pages output",
+            "Always pages output, even if it fits on the screen",
+        ))
+        .arg(Arg::with_name("no-pager").long("no-pager").conflicts_with("pager").help(
+            "Never pages output",
+        ))
+        .arg(Arg::with_name("stable-only").long("stable-only").help(
+            "Leaves out items marked #[unstable]",
+        ))
+        .arg(
+            Arg::with_name("feature")
+                .long("feature")
+                .value_name("FEATURE")
+                .help("Only shows items that are only available with the given feature")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(Arg::with_name("regex").long("regex").conflicts_with("full-text").help(
+            "Searches for items whose name or full path matches the query as a regular expression",
+        ))
+        .arg(Arg::with_name("full-text").long("full-text").help(
+            "Searches the doc comments of items for the words of the query, instead of their names",
+        ))
+        .arg(
+            Arg::with_name("match")
+                .long("match")
+                .value_name("MODE")
+                .help("Matches the query against the names of items exactly, ignoring case or as \
+                       the start of their names, instead of against their module paths (exact, \
+                       insensitive, prefix)")
+                .possible_values(&["exact", "insensitive", "prefix"])
+                .conflicts_with_all(&["regex", "full-text"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("resolve")
+                .long("resolve")
+                .conflicts_with_all(&["regex", "full-text", "match"])
+                .help("Shows the item whose path ends with the query, like 'HashMap::insert', \
+                       asking which one to show if several do"),
+        )
+        .arg(Arg::with_name("short").short("s").long("short").help(
+            "Prints a one-line summary of each result",
         ))
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .value_name("FORMAT")
+                .help("Prints search results as text or as one JSON object per line (text, json)")
+                .possible_values(&["text", "json"])
+                .default_value("text")
+                .takes_value(true),
+        )
         .arg(Arg::with_name("query").index(1))
 }
 
+fn verbosity(matches: &ArgMatches) -> Verbosity {
+    if matches.is_present("quiet") {
+        Verbosity::Quiet
+    } else if matches.is_present("verbose") {
+        Verbosity::Verbose
+    } else {
+        Verbosity::Normal
+    }
+}
+
+/// Logs oxidoc's own messages at the level for the given verbosity, unless `RUST_LOG` asks for
+/// something else. Those of its dependencies are left out.
+fn init_logging(verbosity: Verbosity) {
+    let rust_log = env::var("RUST_LOG").ok();
+    verbosity.log_builder(rust_log.as_ref().map(|spec| spec.as_str())).init().unwrap();
+}
+
 fn main() {
-    env_logger::init().unwrap();
+    let matches = app().get_matches();
+    init_logging(verbosity(&matches));
 
-    if let Err(ref e) = run() {
+    if let Err(ref e) = run(&matches) {
         error!("error: {}", e);
 
         for e in e.iter().skip(1) {
@@ -78,25 +340,258 @@ fn main() {
     }
 }
 
-fn generate(arg: Option<&str>) -> Result<()> {
-    match arg {
-        Some("all") => generation::generate_all_docs(),
-        Some("crates") => generation::generate_crate_registry_docs(),
-        Some("std") => generation::generate_stdlib_docs(),
-        Some(x) => generation::generate_docs_for_path(PathBuf::from(x)),
+fn generate(arg: Option<&str>, options: &GenerateOptions) -> Result<()> {
+    // A module path only names a module of one crate.
+    let several_crates = arg == Some("all") || arg == Some("crates") || arg == Some("std");
+    if several_crates && !options.modules.is_empty() {
+        bail!(ErrorKind::ModulesNeedOneCrate);
+    }
+
+    let plans = match arg {
+        Some("all") => generation::generate_all_docs(options)?,
+        Some("crates") => generation::generate_crate_registry_docs(options)?,
+        Some("std") => generation::generate_stdlib_docs(options)?,
+        Some(x) => generation::generate_docs_for_path(PathBuf::from(x), options)?
+            .into_iter()
+            .collect::<Vec<GenerationPlan>>(),
         None => bail!(ErrorKind::NoCrateDirectoryProvided),
+    };
+    // Only dry runs return plans.
+    for plan in plans.iter() {
+        println!("{}", plan);
     }
+    Ok(())
 }
 
-fn run() -> Result<()> {
-    let matches = app().get_matches();
+/// Finds the stored versions of a crate given either its name or its name and version.
+fn find_crates(krate: &str) -> Result<Vec<CrateInfo>> {
+    let crates: Vec<CrateInfo> = store::list_crates()?
+        .into_iter()
+        .filter(|info| info.name == krate || info.to_string() == krate)
+        .collect();
+
+    if crates.is_empty() {
+        bail!(ErrorKind::CrateNotFound(krate.to_string()));
+    }
+    Ok(crates)
+}
+
+fn remove_crate(krate: &str) -> Result<()> {
+    let crates = find_crates(krate)?;
+
+    for info in crates.iter() {
+        store::remove_crate(info)?;
+        println!("Removed documentation for {}", info);
+    }
+
+    Ok(())
+}
+
+fn verify_crate(krate: &str) -> Result<()> {
+    let crates = find_crates(krate)?;
+
+    let mut corrupt = 0;
+    for info in crates.iter() {
+        let files = store::verify(info)?;
+        for file in files.iter() {
+            println!("{}", file);
+        }
+        println!("Verified documentation for {}, {} corrupt files", info, files.len());
+        corrupt += files.len();
+    }
+
+    if corrupt > 0 {
+        bail!(ErrorKind::CorruptFiles(corrupt));
+    }
+    Ok(())
+}
+
+fn generate_site(krate: &str, dir: &Path) -> Result<()> {
+    let crates = find_crates(krate)?;
+
+    for info in crates.iter() {
+        let generated = site::generate_site(info, dir)?;
+        println!("Wrote {} pages for {}, starting at {}",
+                 generated.pages,
+                 info,
+                 generated.index.display());
+    }
+    Ok(())
+}
+
+fn migrate_store(version: &str) -> Result<()> {
+    let from = version.parse::<u32>()
+        .chain_err(|| format!("Invalid format version: {}", version))?;
+    let report = store::migrate(from, store::FORMAT_VERSION)?;
+
+    for file in report.failed.iter() {
+        println!("{}", file);
+    }
+    println!("Migrated {} files from format version {} to {}, {} already up to date, {} failed",
+             report.migrated,
+             from,
+             store::FORMAT_VERSION,
+             report.current,
+             report.failed.len());
+    println!("The original files were copied to {}", report.backup_dir.display());
+    for crate_info in report.unindexed.iter() {
+        println!("{} {} wasn't fully migrated and has to be regenerated",
+                 crate_info.name, crate_info.version);
+    }
+    Ok(())
+}
+
+fn export_completions(path: &Path, format: ExportFormat) -> Result<()> {
+    let index = CompletionIndex::load()
+        .chain_err(|| "Couldn't load the completion index, try regenerating your documentation")?;
+    index.export(path, format)?;
+    println!("Exported {} items to {}", index.items().len(), path.display());
+    Ok(())
+}
+
+/// Reads the features and directory of a generation to merge, like `std,serde=path/to/docs`.
+fn parse_merge_input(input: &str) -> Result<(Vec<String>, PathBuf)> {
+    let mut parts = input.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(features), Some(dir)) if !dir.is_empty() => {
+            let features = features.split(',')
+                .map(|feature| feature.trim().to_string())
+                .filter(|feature| !feature.is_empty())
+                .collect();
+            Ok((features, PathBuf::from(dir)))
+        }
+        _ => bail!(ErrorKind::InvalidMergeInput(input.to_string())),
+    }
+}
+
+fn merge_docs(inputs: &[&str], options: &GenerateOptions) -> Result<()> {
+    let dirs = inputs.iter()
+        .map(|input| parse_merge_input(input))
+        .collect::<Result<Vec<(Vec<String>, PathBuf)>>>()?;
+
+    let (conflicts, plan) = generation::generate_merged_docs(&dirs, options)?;
+    if let Some(plan) = plan {
+        println!("{}", plan);
+    }
+    for conflict in conflicts.iter() {
+        println!("{}", conflict);
+    }
+    println!("Merged {} generations, {} items differ between features",
+             dirs.len(),
+             conflicts.len());
+    Ok(())
+}
+
+fn print_api_diff(old_dir: &Path, new_dir: &Path) -> Result<()> {
+    let api_diff = diff::diff_doc_dirs(old_dir, new_dir)?;
+    println!("{}", api_diff);
+
+    let breaking = api_diff.breaking_count();
+    if breaking > 0 {
+        bail!(ErrorKind::BreakingApiChanges(breaking));
+    }
+    if !api_diff.unreadable.is_empty() {
+        bail!(ErrorKind::CorruptFiles(api_diff.unreadable.len()));
+    }
+    Ok(())
+}
+
+fn print_stats(krate: &str, min_coverage: Option<f64>) -> Result<()> {
+    let crates = find_crates(krate)?;
+
+    let mut lowest = 100.0;
+    for (i, info) in crates.iter().enumerate() {
+        let stats = stats::crate_stats(info)?;
+        if i > 0 {
+            println!("");
+        }
+        println!("{}\n{}", info, stats);
+        lowest = stats.coverage().min(lowest);
+    }
+
+    if let Some(threshold) = min_coverage {
+        if lowest < threshold {
+            bail!(ErrorKind::CoverageTooLow(lowest, threshold));
+        }
+    }
+    Ok(())
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
     if matches.is_present("version") {
         println!("oxidoc {}", crate_version!());
         return Ok(());
     }
 
-    if matches.is_present("generate") {
-        return generate(matches.value_of("generate"));
+    if matches.is_present("generate") || matches.is_present("rustdoc-json") ||
+        matches.is_present("merge") {
+        let mut options = GenerateOptions::new();
+        options.force = matches.is_present("force");
+        options.source_order = matches.is_present("source-order");
+        options.document_hidden = matches.is_present("document-hidden");
+        options.document_private = matches.is_present("document-private-items");
+        options.prune = !matches.is_present("no-prune");
+        options.strict = matches.is_present("strict");
+        options.dry_run = matches.is_present("dry-run");
+        options.json_copies = matches.is_present("json-copies");
+        if let Some(modules) = matches.values_of("module") {
+            options.modules = modules.map(|module| module.to_string()).collect();
+        }
+        options.verbosity = verbosity(matches);
+        if let Some(jobs) = matches.value_of("jobs") {
+            options.jobs = jobs.parse()
+                .chain_err(|| ErrorKind::InvalidJobCount(jobs.to_string()))?;
+        }
+        if let Some(inputs) = matches.values_of("merge") {
+            return merge_docs(&inputs.collect::<Vec<&str>>(), &options);
+        }
+        if let Some(json_path) = matches.value_of("rustdoc-json") {
+            let plan = generation::generate_docs_from_rustdoc_json(Path::new(json_path), &options)?;
+            if let Some(plan) = plan {
+                println!("{}", plan);
+            }
+            return Ok(());
+        }
+        return generate(matches.value_of("generate"), &options);
+    }
+
+    if let Some(krate) = matches.value_of("remove") {
+        return remove_crate(krate);
+    }
+
+    if let Some(krate) = matches.value_of("verify") {
+        return verify_crate(krate);
+    }
+
+    if let Some(version) = matches.value_of("migrate") {
+        return migrate_store(version);
+    }
+
+    if let Some(path) = matches.value_of("export-completions") {
+        let format = match matches.value_of("export-format") {
+            Some("binary") => ExportFormat::Binary,
+            _              => ExportFormat::Json,
+        };
+        return export_completions(Path::new(path), format);
+    }
+
+    if let Some(krate) = matches.value_of("stats") {
+        let min_coverage = match matches.value_of("min-coverage") {
+            Some(value) => Some(value.trim_right_matches('%').parse::<f64>()
+                .chain_err(|| ErrorKind::InvalidCoverageThreshold(value.to_string()))?),
+            None => None,
+        };
+        return print_stats(krate, min_coverage);
+    }
+
+    if let Some(values) = matches.values_of("site") {
+        let values: Vec<&str> = values.collect();
+        return generate_site(values[0], Path::new(values[1]));
+    }
+
+    if let Some(dirs) = matches.values_of("diff") {
+        let dirs: Vec<&str> = dirs.collect();
+        return print_api_diff(Path::new(dirs[0]), Path::new(dirs[1]));
     }
 
     if matches.is_present("tui") {
@@ -107,8 +602,89 @@ fn run() -> Result<()> {
             None => bail!(ErrorKind::NoSearchQuery),
         };
 
-        let enable_pager = matches.is_present("pager");
-        print_search_query(query, enable_pager)
+        let mut filter = SearchFilter::new();
+        if let Some(types) = matches.values_of("type") {
+            for name in types.flat_map(|t| t.split(',')) {
+                filter.doc_types.push(name.trim().parse()?);
+            }
+        }
+        filter.stable_only = matches.is_present("stable-only");
+        if let Some(features) = matches.values_of("feature") {
+            filter.features.extend(features.map(|feature| feature.to_string()));
+        }
+
+        if matches.is_present("full-text") {
+            return print_full_text_search(query, &filter);
+        }
+
+        let kind = if matches.is_present("regex") {
+            QueryKind::Regex
+        } else {
+            match matches.value_of("match").and_then(SearchMode::from_name) {
+                Some(mode) => QueryKind::Name(mode),
+                None => QueryKind::Path,
+            }
+        };
+
+        if matches.value_of("output") == Some("json") {
+            return print_search_json(query, kind, &filter);
+        }
+
+        let paging = if matches.is_present("pager") {
+            Paging::Always
+        } else if matches.is_present("no-pager") {
+            Paging::Never
+        } else {
+            Paging::Auto
+        };
+        let short = matches.is_present("short");
+        let mut format_options = FormatOptions::new();
+        format_options.source_order = matches.is_present("source-order");
+        format_options.hyperlinks = matches.is_present("hyperlinks") &&
+            markup::hyperlinks_supported();
+
+        if matches.is_present("resolve") {
+            return print_resolved_query(query, &filter, paging, short, &format_options);
+        }
+
+        print_search_query(query, kind, &filter, paging, short, &format_options)
+    }
+}
+
+/// How a search query is matched against the stored items.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum QueryKind {
+    /// Against module paths.
+    Path,
+    /// Against the names of items, in the given mode.
+    Name(SearchMode),
+    /// As a regular expression against the names and full paths of items.
+    Regex,
+}
+
+/// When search results are shown through a pager.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Paging {
+    Always,
+    /// Only when stdout is a terminal and the output doesn't fit on the screen.
+    Auto,
+    Never,
+}
+
+impl Paging {
+    fn should_page(&self, output: &[String]) -> bool {
+        match *self {
+            Paging::Always => true,
+            Paging::Never  => false,
+            Paging::Auto   => {
+                let height = match term_size::dimensions() {
+                    Some((_, h)) => h,
+                    None => return false,
+                };
+                let lines: usize = output.iter().map(|result| result.lines().count()).sum();
+                atty::is(atty::Stream::Stdout) && lines > height
+            }
+        }
     }
 }
 
@@ -122,44 +698,171 @@ fn setup_pager() {
     Pager::with_pager(&executable).setup();
 }
 
+/// The pager command, from `OXIDOC_PAGER` or `PAGER`. `less -R` is used by default, since it
+/// keeps the colors of the output.
 #[cfg(unix)]
 fn get_pager_executable() -> String {
-    if let Ok(pager) = std::env::var("PAGER") {
-        return pager.to_string();
+    for var in &["OXIDOC_PAGER", "PAGER"] {
+        match std::env::var(var) {
+            Ok(ref pager) if !pager.trim().is_empty() => return pager.to_string(),
+            _ => (),
+        }
     }
 
-    // Linux and BSD systems doesn't support "-r" option but macOS supports
-    // For linux `less` shows better results (with control chars)
-    #[cfg(target_os = "macos")]
-    let executable = "more -r";
+    "less -R".to_string()
+}
 
-    #[cfg(not(target_os = "macos"))]
-    let executable = "less";
+/// Loads the documentation of the first ten items that match the query and the filter.
+fn search_results(query: &str,
+                  kind: QueryKind,
+                  filter: &SearchFilter) -> Result<Vec<Documentation>> {
+    let locations = match kind {
+        QueryKind::Name(mode) => search::find_by_name(query, mode, 10, filter),
+        QueryKind::Regex => search::find_by_regex(query, 10, filter)?,
+        QueryKind::Path => {
+            let store = Store::load();
+            store.lookup_name(query)
+                .into_iter()
+                .filter(|location| filter.matches(location))
+                .take(10)
+                .cloned()
+                .collect()
+        }
+    };
 
-    return executable.to_string();
+    locations.iter()
+        .map(|location| Driver::get_doc(location))
+        .collect()
 }
 
-fn print_search_query(query: &str, enable_pager: bool) -> Result<()> {
-    let store = Store::load();
-    // search::add_search_paths(store.all_locations());
+/// Prints each search result as a JSON object on its own line. Nothing is printed if there are no
+/// results.
+fn print_search_json(query: &str, kind: QueryKind, filter: &SearchFilter) -> Result<()> {
+    for doc in search_results(query, kind, filter)? {
+        let line = serde_json::to_string(&JsonResult::new(&doc))
+            .chain_err(|| format!("Couldn't serialize search result {}", doc.mod_path))?;
+        println!("{}", line);
+    }
+
+    Ok(())
+}
 
-    let results: Vec<&StoreLocation> = store.lookup_name(query).into_iter().take(10).collect();
+/// Prints the items whose doc comments mention the words of the query, with the part of the doc
+/// comment that does.
+fn print_full_text_search(query: &str, filter: &SearchFilter) -> Result<()> {
+    let index = FullTextIndex::load()
+        .chain_err(|| "Couldn't load the full-text index, try regenerating your documentation")?;
+    let results = index.search(query, 10, filter);
 
     if results.is_empty() {
         println!("No results for \"{}\".", query);
         return Ok(());
     }
 
-    let formatted: Vec<String> = results
+    for result in results.iter() {
+        println!("{} ({})", result.location.mod_path, location_kind(&result.location));
+        println!("    {}", result.snippet);
+    }
+
+    Ok(())
+}
+
+fn print_search_query(query: &str,
+                      kind: QueryKind,
+                      filter: &SearchFilter,
+                      paging: Paging,
+                      short: bool,
+                      format_options: &FormatOptions) -> Result<()> {
+    let results = search_results(query, kind, filter)?;
+
+    if results.is_empty() {
+        match kind {
+            QueryKind::Regex => println!("No results for /{}/.", query),
+            _                => println!("No results for \"{}\".", query),
+        }
+        return Ok(());
+    }
+
+    print_docs(results, paging, short, format_options)
+}
+
+/// Shows the documentation of the item the query names, matching it against the end of the paths
+/// of items, like `HashMap::insert`. If several items match, they're listed with their full paths
+/// and types, and the user is asked to choose one if stdin is a terminal.
+fn print_resolved_query(query: &str,
+                        filter: &SearchFilter,
+                        paging: Paging,
+                        short: bool,
+                        format_options: &FormatOptions) -> Result<()> {
+    let candidates: Vec<StoreLocation> = search::resolve_query(query)
         .into_iter()
-        .map(|location| {
-            let result = Driver::get_doc(&location).unwrap();
+        .filter(|location| filter.matches(location))
+        .collect();
+
+    let chosen = match candidates.len() {
+        0 => {
+            println!("No results for \"{}\".", query);
+            return Ok(());
+        },
+        1 => 0,
+        count => {
+            for (i, location) in candidates.iter().enumerate() {
+                println!("{:>3}. {} ({})", i + 1, location.mod_path, location_kind(location));
+            }
+            if !atty::is(atty::Stream::Stdin) {
+                return Ok(());
+            }
+            match choose_item(count)? {
+                Some(i) => i,
+                None => return Ok(()),
+            }
+        },
+    };
+
+    let doc = Driver::get_doc(&candidates[chosen])?;
+    print_docs(vec![doc], paging, short, format_options)
+}
+
+/// The type of a listed item, followed by whether it's deprecated, like `fn, deprecated`.
+fn location_kind(location: &StoreLocation) -> String {
+    if location.deprecated {
+        format!("{}, deprecated", location.doc_type)
+    } else {
+        location.doc_type.to_string()
+    }
+}
+
+/// Asks for the number of one of the `count` items listed. Returns the index of the item, or
+/// `None` if the answer isn't the number of one.
+fn choose_item(count: usize) -> Result<Option<usize>> {
+    print!("Choose an item (1-{}): ", count);
+    io::stdout().flush().chain_err(|| "Couldn't write to stdout")?;
 
-            result.format().to_string()
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).chain_err(|| "Couldn't read from stdin")?;
+    match answer.trim().parse::<usize>() {
+        Ok(number) if number >= 1 && number <= count => Ok(Some(number - 1)),
+        _ => Ok(None),
+    }
+}
+
+/// Prints the documentation, through a pager if `paging` says so.
+fn print_docs(results: Vec<Documentation>,
+              paging: Paging,
+              short: bool,
+              format_options: &FormatOptions) -> Result<()> {
+    let formatted: Vec<String> = results
+        .into_iter()
+        .map(|result| {
+            if short {
+                result.summary_line()
+            } else {
+                result.format_with(format_options).to_string()
+            }
         })
         .collect();
 
-    if enable_pager {
+    if paging.should_page(&formatted) {
         setup_pager();
     }
 
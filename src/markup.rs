@@ -1,16 +1,30 @@
+use std::collections::HashMap;
+use std::env;
 use std::fmt;
+use std::path::Path;
 
 use ansi_term::Style;
+use atty;
+use colorize;
 use catmark::{self, OutputKind};
+use pulldown_cmark::{self, Event, Parser};
+use regex::Regex;
 use conversion::*;
 use document::ModPath;
-use generation::ast_ty_wrappers::{FnKind, Attributes};
+use generation::ast_ty_wrappers::{FnKind, Attributes, Deprecation, Stability, StructKind};
+use highlight;
+use merge;
+use store::StoreLocation;
 use term_size;
 
 pub enum Markup {
     Header(String),
     Section(String),
     Block(String),
+    /// Rust code, which is highlighted when color is enabled.
+    Code(String),
+    /// An item signature, whose keywords, types and lifetimes are colored when color is enabled.
+    Signature(String),
     Markdown(String),
     Rule(usize),
     LineBreak,
@@ -25,49 +39,379 @@ fn get_term_width() -> u16 {
     }
 }
 
-impl fmt::Display for Markup {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let string = match *self {
-            Header(ref text) => {
-                Style::new()
-                    .bold()
-                    .paint(format!("==== {}", text))
-                    .to_string()
-            }
-            Section(ref text) => {
-                Style::new()
-                    .bold()
-                    .paint(format!("== {}", text))
-                    .to_string()
+lazy_static! {
+    static ref ANSI_ESCAPE_REGEX: Regex = Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").unwrap();
+}
+
+/// Whether output should be styled with ANSI escapes by default. Styling is disabled when stdout
+/// isn't a terminal, or when `NO_COLOR` is set.
+fn color_enabled() -> bool {
+    env::var_os("NO_COLOR").is_none() && atty::is(atty::Stream::Stdout)
+}
+
+/// Whether stdout is likely to support OSC 8 hyperlinks. Terminals without support are meant to
+/// ignore the escapes, but to be safe they're only used where styling is, and never for dumb
+/// terminals.
+pub fn hyperlinks_supported() -> bool {
+    color_enabled() && env::var("TERM").map_or(true, |term| term != "dumb")
+}
+
+/// Makes the text a hyperlink to the URL, with an OSC 8 escape sequence.
+fn hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// The `file://` URL of a path. Relative paths are taken from the current directory.
+fn file_url(path: &Path) -> String {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        match env::current_dir() {
+            Ok(dir) => dir.join(path),
+            Err(_)  => path.to_path_buf(),
+        }
+    };
+
+    format!("file://{}", percent_encode(&path.to_string_lossy()))
+}
+
+/// The URL of a relative path, with its components separated by `/` on every platform.
+pub fn relative_url(path: &Path) -> String {
+    let url = path.components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<String>>()
+        .join("/");
+    percent_encode(&url)
+}
+
+/// Encodes the bytes that can't be part of a URL path as is. Stored files are named with `%`
+/// escapes, like `fn.r%23async.odoc`, so the `%` is encoded too.
+fn percent_encode(path: &str) -> String {
+    let mut url = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => {
+                url.push(byte as char)
             }
-            Block(ref text) => text.clone(),
-            Markdown(ref md) => {
-                let width = get_term_width();
-                catmark::render_ansi(md, width, OutputKind::Color)
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+fn bold(text: String, color: bool) -> String {
+    if color {
+        Style::new().bold().paint(text).to_string()
+    } else {
+        text
+    }
+}
+
+fn render_code(code: &str, color: bool) -> String {
+    let code = if color {
+        highlight::highlight_rust(code)
+    } else {
+        code.to_string()
+    };
+
+    code.lines().map(|line| format!("  {}", line)).collect::<Vec<String>>().join("\n")
+}
+
+fn render_signature(signature: &str, color: bool) -> String {
+    signature.lines()
+        .map(|line| if color {
+            format!("  {}", colorize::colorize_signature(line))
+        } else {
+            format!("  {}", line)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Whether a fenced code block with the given info string contains Rust code. Like in rustdoc,
+/// blocks without a language are assumed to be Rust.
+fn is_rust_fence(info: &str) -> bool {
+    let info = info.trim();
+    info.is_empty() || info.split(',').any(|attr| attr.trim() == "rust")
+}
+
+/// Splits Markdown into the prose and the Rust code blocks in it, so the code can be highlighted
+/// separately. Lines hidden from rustdoc examples with `# ` are dropped.
+fn split_rust_blocks(md: &str) -> Vec<Markup> {
+    let mut parts = Vec::new();
+    let mut text = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+
+    for line in md.lines() {
+        let trimmed = line.trim_left();
+        let is_fence = trimmed.starts_with("```");
+
+        code = match code {
+            Some(mut lines) => {
+                if is_fence {
+                    parts.push(Code(lines.join("\n")));
+                    None
+                } else {
+                    if !(trimmed == "#" || trimmed.starts_with("# ")) {
+                        lines.push(line);
+                    }
+                    Some(lines)
+                }
+            }
+            None => {
+                if is_fence && is_rust_fence(&trimmed[3..]) {
+                    parts.push(Markdown(text.join("\n")));
+                    text.clear();
+                    Some(Vec::new())
+                } else {
+                    text.push(line);
+                    None
+                }
+            }
+        };
+    }
+
+    match code {
+        // An unterminated block is left as it was written.
+        Some(lines) => {
+            text.push("```");
+            text.extend(lines);
+        }
+        None => (),
+    }
+    parts.push(Markdown(text.join("\n")));
+
+    parts
+}
+
+/// Whether the line starts a Markdown list item, like `- item` or `1. item`. Returns the width
+/// of the marker and the space after it.
+fn list_marker_len(line: &str) -> Option<usize> {
+    for marker in &["- ", "* ", "+ ", "• "] {
+        if line.starts_with(marker) {
+            return Some(marker.chars().count());
+        }
+    }
+
+    let digits = line.find(|c: char| !c.is_digit(10)).unwrap_or(0);
+    if digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") ")) {
+        return Some(digits + 2);
+    }
+    None
+}
+
+/// Whether the line can't be joined with the lines around it, like a heading, a table row or an
+/// indented code block.
+fn is_standalone_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty() ||
+        line.starts_with("    ") ||
+        line.starts_with('\t') ||
+        trimmed.starts_with('#') ||
+        trimmed.starts_with('>') ||
+        trimmed.starts_with('|') ||
+        trimmed.starts_with("```") ||
+        trimmed.chars().all(|c| c == '-' || c == '=' || c == '*' || c == '_')
+}
+
+/// Joins the lines of hard-wrapped paragraphs and list items, so they can be wrapped again to the
+/// width of the terminal. Code blocks and other lines that aren't prose are kept as they are.
+fn unwrap_paragraphs(md: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_fence = false;
+    let mut joinable = false;
+
+    for line in md.lines() {
+        if line.trim_left().starts_with("```") {
+            in_fence = !in_fence;
+            lines.push(line.to_string());
+            joinable = false;
+            continue;
+        }
+
+        if in_fence || is_standalone_line(line) {
+            lines.push(line.to_string());
+            joinable = false;
+            continue;
+        }
+
+        let starts_item = list_marker_len(line.trim_left()).is_some();
+        if joinable && !starts_item {
+            if let Some(last) = lines.last_mut() {
+                last.push(' ');
+                last.push_str(line.trim());
             }
+        } else {
+            lines.push(line.to_string());
+        }
+        // Lines ending in two spaces or a backslash end with a hard line break.
+        joinable = !(line.ends_with("  ") || line.ends_with('\\'));
+    }
+
+    lines.join("\n")
+}
+
+fn visible_width(text: &str) -> usize {
+    ANSI_ESCAPE_REGEX.replace_all(text, "").chars().count()
+}
+
+/// Wraps a rendered line at spaces so it fits in `width` columns, not counting ANSI escapes.
+/// Continuation lines are indented to the start of the text, so list items stay aligned. Words
+/// longer than the width are left on lines of their own.
+fn wrap_rendered_line(line: &str, width: usize) -> String {
+    if visible_width(line) <= width {
+        return line.to_string();
+    }
+
+    let start = line.find(|c: char| c != ' ').unwrap_or(line.len());
+    let plain = ANSI_ESCAPE_REGEX.replace_all(&line[start..], "").into_owned();
+    let indent = start + list_marker_len(&plain).unwrap_or(0);
+
+    let mut lines = Vec::new();
+    let mut current = line[..start].to_string();
+    let mut current_width = start;
+    let mut has_words = false;
+
+    for word in line[start..].split(' ').filter(|word| !word.is_empty()) {
+        let word_width = visible_width(word);
+        if has_words && current_width + 1 + word_width > width {
+            lines.push(current);
+            current = " ".repeat(indent);
+            current_width = indent;
+            has_words = false;
+        }
+        if has_words {
+            current.push(' ');
+            current_width += 1;
+        }
+        current.push_str(word);
+        current_width += word_width;
+        has_words = true;
+    }
+    lines.push(current);
+
+    lines.join("\n")
+}
+
+/// Renders prose and the code blocks that aren't Rust, wrapping the prose to `width` columns.
+fn render_prose(md: &str, width: usize, color: bool) -> String {
+    let mut rendered = Vec::new();
+    let mut prose = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+
+    let render = |md: &str, wrap: bool| {
+        let text = catmark::render_ansi(md, width as u16, OutputKind::Color);
+        let text = if color {
+            text
+        } else {
+            ANSI_ESCAPE_REGEX.replace_all(&text, "").into_owned()
+        };
+        if wrap {
+            text.lines()
+                .map(|line| wrap_rendered_line(line, width))
+                .collect::<Vec<String>>()
+                .join("\n")
+        } else {
+            text
+        }
+    };
+
+    for line in md.lines() {
+        let is_fence = line.trim_left().starts_with("```");
+        code = match code {
+            Some(mut lines) => {
+                lines.push(line);
+                if is_fence {
+                    rendered.push(render(&lines.join("\n"), false));
+                    None
+                } else {
+                    Some(lines)
+                }
+            }
+            None if is_fence => {
+                if !prose.is_empty() {
+                    rendered.push(render(&unwrap_paragraphs(&prose.join("\n")), true));
+                    prose.clear();
+                }
+                Some(vec![line])
+            }
+            None => {
+                prose.push(line);
+                None
+            }
+        };
+    }
+
+    // An unterminated block is rendered with the prose, as it was written.
+    if let Some(lines) = code {
+        prose.extend(lines);
+    }
+    if !prose.is_empty() || rendered.is_empty() {
+        rendered.push(render(&unwrap_paragraphs(&prose.join("\n")), true));
+    }
+
+    rendered.join("\n")
+}
+
+/// Renders Markdown for the terminal, reflowing prose to the terminal's width. Code blocks aren't
+/// wrapped.
+fn render_markdown(md: &str, color: bool) -> String {
+    render_markdown_with_width(md, get_term_width() as usize, color)
+}
+
+fn render_markdown_with_width(md: &str, width: usize, color: bool) -> String {
+    split_rust_blocks(md).iter()
+        .map(|part| match *part {
+            Markdown(ref text) => render_prose(text, width, color),
+            ref other => other.render(color),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+impl Markup {
+    /// Renders the markup for the terminal, styled with ANSI escapes if `color` is set.
+    pub fn render(&self, color: bool) -> String {
+        match *self {
+            Header(ref text) => bold(format!("==== {}", text), color),
+            Section(ref text) => bold(format!("== {}", text), color),
+            Block(ref text) => text.clone(),
+            Code(ref code) => render_code(code, color),
+            Signature(ref signature) => render_signature(signature, color),
+            Markdown(ref md) => render_markdown(md, color),
             Rule(ref count) => "-".repeat(*count),
             LineBreak => "".to_string(),
-        };
-        write!(f, "{}", string)
+        }
+    }
+}
+
+impl fmt::Display for Markup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(color_enabled()))
     }
 }
 
 /// A formatted piece of documentation made up of individual markup pieces.
 pub struct MarkupDoc {
     pub parts: Vec<Markup>,
+
+    /// Whether the pieces are styled with ANSI escapes when displayed.
+    pub color: bool,
 }
 
 impl MarkupDoc {
     pub fn new(parts: Vec<Markup>) -> Self {
-        MarkupDoc { parts: parts }
+        MarkupDoc {
+            parts: parts,
+            color: color_enabled(),
+        }
     }
 }
 
 impl fmt::Display for MarkupDoc {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for part in self.parts.iter() {
-            part.fmt(f)?;
-            write!(f, "\n")?;
+            write!(f, "{}\n", part.render(self.color))?;
         }
         Ok(())
     }
@@ -78,13 +422,51 @@ pub trait Format {
     fn format(&self) -> MarkupDoc;
 }
 
+/// Options for how documentation is formatted for the terminal.
+#[derive(Clone, Debug)]
+pub struct FormatOptions {
+    /// List the consts, types and methods of traits and types together, in the order they were
+    /// declared, instead of by kind.
+    pub source_order: bool,
+
+    /// Signature lines longer than this have their where-clauses broken onto separate lines, one
+    /// predicate per line. Defaults to the width of the terminal.
+    pub signature_width: usize,
+
+    /// Make the source location and related items hyperlinks to the source file and their
+    /// documentation files, for terminals that support OSC 8 hyperlinks.
+    pub hyperlinks: bool,
+
+    /// Style the output with ANSI escapes. Defaults to whether stdout is a terminal and `NO_COLOR`
+    /// isn't set.
+    pub color: bool,
+}
+
+impl FormatOptions {
+    pub fn new() -> Self {
+        FormatOptions {
+            source_order: false,
+            // The signature is indented by two spaces.
+            signature_width: (get_term_width() as usize).saturating_sub(2),
+            hyperlinks: false,
+            color: color_enabled(),
+        }
+    }
+}
+
 impl Format for Documentation {
     fn format(&self) -> MarkupDoc {
+        self.format_with(&FormatOptions::new())
+    }
+}
+
+impl Documentation {
+    pub fn format_with(&self, options: &FormatOptions) -> MarkupDoc {
         let header = doc_header(self);
         let info = doc_inner_info(self);
-        let signature = doc_signature(self);
+        let signature = doc_signature(self, options);
         let body = doc_body(self);
-        let related_items = doc_related_items(self);
+        let related_items = doc_related_items(self, options);
 
         let mut result = Vec::new();
         result.extend(header.parts);
@@ -93,7 +475,9 @@ impl Format for Documentation {
         result.extend(body.parts);
         result.extend(related_items.parts);
 
-        MarkupDoc::new(result)
+        let mut markup = MarkupDoc::new(result);
+        markup.color = options.color;
+        markup
     }
 }
 
@@ -105,42 +489,662 @@ impl Format for ModPath {
 
 impl Format for Attributes {
     fn format(&self) -> MarkupDoc {
-        let body = self.doc_strings.join("\n");
+        let body = self.doc_body();
 
         MarkupDoc::new(vec![Markdown(body)])
     }
 }
 
-fn doc_header(data: &Documentation) -> MarkupDoc {
-    let name = match data.inner_data {
+impl Documentation {
+    /// Renders the documentation as a Markdown document. Related items are listed with links to
+    /// their `.odoc` files, relative to the crate's documentation directory.
+    pub fn to_markdown(&self) -> String {
+        let mut parts = Vec::new();
+
+        parts.push(format!("# {} {}", doc_kind_name(self), self.mod_path));
+        parts.push(format!("*{}*", self.crate_info));
+
+        if let Some(ref deprecation) = self.attrs.deprecation {
+            parts.push(format!("> {}", deprecation_notice("**Deprecated**", deprecation)));
+        }
+
+        if let Some(declaration) = declaration_string(self) {
+            parts.push(format!("```rust\n{}\n```", declaration));
+        }
+
+        if let Some(header) = parent_impl(self) {
+            parts.push(format!("*From `{}`*", header));
+        }
+
+        // Code blocks can't hold links, so the returned types are linked after the signature.
+        let returns = return_links(self).iter()
+            .map(|&(ref category, link)| markdown_link(link, category, self))
+            .collect::<Vec<String>>();
+        if !returns.is_empty() {
+            parts.push(format!("*Returns {}*", returns.join(", ")));
+        }
+
+        if let Some(availability) = availability_notice(&self.attrs) {
+            parts.push(format!("*{}*", availability));
+        }
+
+        for &(ref features, ref signature) in self.attrs.feature_signatures.iter() {
+            parts.push(format!("*With features {}:*\n```rust\n{}\n```",
+                               merge::describe_features(features),
+                               signature));
+        }
+
+        if let Some(stability) = stability_notice(&self.attrs) {
+            parts.push(format!("*{}*", stability));
+        }
+
+        if let Some(must_use) = must_use_notice(&self.attrs) {
+            parts.push(format!("*{}*", must_use));
+        }
+
+        if let Some(non_exhaustive) = non_exhaustive_notice(self) {
+            parts.push(format!("*{}*", non_exhaustive));
+        }
+
+        if self.attrs.hidden {
+            parts.push(format!("*{}*", HIDDEN_NOTICE));
+        }
+
+        if self.is_private() {
+            parts.push(format!("*{}*", PRIVATE_NOTICE));
+        }
+
+        let body = self.attrs.doc_body();
+        if !body.trim().is_empty() {
+            parts.push(body);
+        }
+
+        for category in self.listed_categories() {
+            if category == DocType::Impl {
+                if let Some(impls) = doc_impls(self) {
+                    let blocks = impls.iter()
+                        .map(|block| {
+                            let groups = impl_block_groups(block).iter()
+                                .map(|&(heading, ref items)| {
+                                    let list = items.iter()
+                                        .map(|&(ref item_category, link)| {
+                                            format!("{}{}",
+                                                    markdown_list_item(link, item_category, self),
+                                                    impl_item_note(block, item_category, link))
+                                        })
+                                        .collect::<Vec<String>>()
+                                        .join("\n");
+                                    match heading {
+                                        Some(heading) => format!("#### {}\n\n{}", heading, list),
+                                        None          => list,
+                                    }
+                                })
+                                .collect::<Vec<String>>();
+                            format!("### `{}`\n\n{}", block.header, groups.join("\n\n"))
+                        })
+                        .collect::<Vec<String>>();
+                    parts.push(format!("## {}\n\n{}", category, blocks.join("\n\n")));
+                }
+                continue;
+            }
+
+            if let Some(items) = self.subitems_in_category(&category) {
+                let list = match (&category, doc_fields(self), doc_variants(self)) {
+                    (&DocType::StructField, Some(fields), _) => {
+                        fields.iter()
+                            .enumerate()
+                            .map(|(i, field)| markdown_field_item(field, i))
+                            .collect::<Vec<String>>()
+                    },
+                    (&DocType::Variant, _, Some(variants)) => {
+                        variants.iter().map(markdown_variant_item).collect::<Vec<String>>()
+                    },
+                    _ => {
+                        items.iter()
+                            .map(|link| markdown_list_item(link, &category, self))
+                            .collect::<Vec<String>>()
+                    },
+                };
+                parts.push(format!("## {}\n\n{}", category, list.join("\n")));
+            }
+        }
+
+        parts.join("\n\n") + "\n"
+    }
+
+    /// Renders the signature and the first sentence of the doc comment on a single line,
+    /// truncated to the width of the terminal.
+    pub fn summary_line(&self) -> String {
+        self.summary_line_with_width(get_term_width() as usize)
+    }
+
+    /// Like `summary_line`, but truncated to the given number of characters.
+    pub fn summary_line_with_width(&self, width: usize) -> String {
+        let mut signature = match signature_string_with(self, Some(SUMMARY_VALUE_WIDTH)) {
+            Some(signature) => signature.split_whitespace().collect::<Vec<&str>>().join(" "),
+            None => format!("{} {}", doc_kind_name(self), self.mod_path),
+        };
+        if self.attrs.deprecation.is_some() {
+            signature.push_str(" (deprecated)");
+        }
+
+        let line = match self.summary() {
+            Some(sentence) => format!("{}  — {}", signature, sentence),
+            None => signature,
+        };
+        truncate_line(&line, width)
+    }
+
+    /// The first sentence of the doc comment, if there is one.
+    pub fn summary(&self) -> Option<String> {
+        self.attrs.summary_paragraph().and_then(|paragraph| first_sentence(&paragraph))
+    }
+
+    /// The declaration of the item, like `pub fn new() -> Self`, or `None` for crates.
+    pub fn signature(&self) -> Option<String> {
+        signature_string(self)
+    }
+
+    /// Builds the declaration of the item from its structured data, for filling in `signature`.
+    pub fn build_signature(&self) -> Option<String> {
+        signature_string_with(self, None)
+    }
+
+    /// Renders the documentation as a self-contained HTML fragment. Related items link to the
+    /// `.html` pages at the same relative paths as their `.odoc` files.
+    pub fn to_html(&self) -> String {
+        self.to_html_with_links(&HashMap::new())
+    }
+
+    /// Like `to_html`, with the intra-doc links of the doc comment whose targets are keys of
+    /// `urls` linking to the given URLs. See `conversion::rewrite_intra_doc_links`.
+    pub fn to_html_with_links(&self, urls: &HashMap<String, String>) -> String {
+        let mut parts = Vec::new();
+
+        parts.push(format!("<h1>{} {}</h1>",
+                           doc_kind_name(self),
+                           escape_html(&self.mod_path.to_string())));
+        parts.push(format!("<p class=\"crate\">{}</p>",
+                           escape_html(&self.crate_info.to_string())));
+
+        if let Some(ref deprecation) = self.attrs.deprecation {
+            parts.push(format!("<p class=\"deprecated\">{}</p>",
+                               escape_html(&deprecation_notice("Deprecated", deprecation))));
+        }
+
+        if let Some(declaration) = declaration_string(self) {
+            let declaration: String = split_return_types(&declaration, self).into_iter()
+                .map(|(text, link)| match link.and_then(|(category, link)| {
+                    html_link_url(link, &category, self)
+                }) {
+                    Some(url) => format!("<a href=\"{}\">{}</a>", url, escape_html(text)),
+                    None      => escape_html(text),
+                })
+                .collect();
+            parts.push(format!("<pre class=\"rust\">{}</pre>", declaration));
+        }
+
+        if let Some(header) = parent_impl(self) {
+            parts.push(format!("<p class=\"impl\">From <code>{}</code></p>",
+                               escape_html(&header.to_string())));
+        }
+
+        if let Some(availability) = availability_notice(&self.attrs) {
+            parts.push(format!("<p class=\"availability\">{}</p>", escape_html(&availability)));
+        }
+
+        for &(ref features, ref signature) in self.attrs.feature_signatures.iter() {
+            parts.push(format!("<p class=\"feature-signature\">With features {}:</p>",
+                               escape_html(&merge::describe_features(features))));
+            parts.push(format!("<pre class=\"rust\">{}</pre>", escape_html(signature)));
+        }
+
+        if let Some(stability) = stability_notice(&self.attrs) {
+            parts.push(format!("<p class=\"stability\">{}</p>", escape_html(&stability)));
+        }
+
+        if let Some(must_use) = must_use_notice(&self.attrs) {
+            parts.push(format!("<p class=\"must-use\">{}</p>", escape_html(&must_use)));
+        }
+
+        if let Some(non_exhaustive) = non_exhaustive_notice(self) {
+            parts.push(format!("<p class=\"non-exhaustive\">{}</p>", non_exhaustive));
+        }
+
+        if self.attrs.hidden {
+            parts.push(format!("<p class=\"hidden\">{}</p>", HIDDEN_NOTICE));
+        }
+
+        if self.is_private() {
+            parts.push(format!("<p class=\"private\">{}</p>", PRIVATE_NOTICE));
+        }
+
+        let body = rewrite_intra_doc_links(&self.attrs.doc_body(), urls);
+        if !body.trim().is_empty() {
+            parts.push(format!("<div class=\"docblock\">\n{}</div>", markdown_to_html(&body)));
+        }
+
+        for category in self.listed_categories() {
+            if category == DocType::Impl {
+                if let Some(impls) = doc_impls(self) {
+                    parts.push(format!("<h2>{}</h2>", category));
+                    for block in impls {
+                        let groups = impl_block_groups(block).iter()
+                            .map(|&(heading, ref items)| {
+                                let list = items.iter()
+                                    .map(|&(ref item_category, link)| {
+                                        let note = impl_item_note(block, item_category, link);
+                                        html_list_item_with_note(link, item_category, self, note)
+                                    })
+                                    .collect::<Vec<String>>()
+                                    .join("\n");
+                                match heading {
+                                    Some(heading) => {
+                                        format!("<h4>{}</h4>\n<ul>\n{}\n</ul>", heading, list)
+                                    },
+                                    None => format!("<ul>\n{}\n</ul>", list),
+                                }
+                            })
+                            .collect::<Vec<String>>()
+                            .join("\n");
+                        parts.push(format!("<h3><code>{}</code></h3>\n{}",
+                                           escape_html(&block.header.to_string()),
+                                           groups));
+                    }
+                }
+                continue;
+            }
+
+            if let Some(items) = self.subitems_in_category(&category) {
+                let list = match (&category, doc_fields(self), doc_variants(self)) {
+                    (&DocType::StructField, Some(fields), _) => {
+                        fields.iter()
+                            .enumerate()
+                            .map(|(i, field)| html_field_item(field, i))
+                            .collect::<Vec<String>>()
+                    },
+                    (&DocType::Variant, _, Some(variants)) => {
+                        variants.iter().map(html_variant_item).collect::<Vec<String>>()
+                    },
+                    _ => {
+                        items.iter()
+                            .map(|link| html_list_item(link, &category, self))
+                            .collect::<Vec<String>>()
+                    },
+                }.join("\n");
+                parts.push(format!("<h2>{}</h2>\n<ul>\n{}\n</ul>", category, list));
+            }
+        }
+
+        format!("<div class=\"oxidoc\">\n{}\n</div>\n", parts.join("\n"))
+    }
+}
+
+/// Escapes the characters that have a meaning in HTML, for showing text as it is.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&'  => escaped.push_str("&amp;"),
+            '<'  => escaped.push_str("&lt;"),
+            '>'  => escaped.push_str("&gt;"),
+            '"'  => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _    => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders Markdown to HTML. Raw HTML in the Markdown is escaped instead of passed through.
+fn markdown_to_html(markdown: &str) -> String {
+    let events = Parser::new(markdown).map(|event| {
+        match event {
+            Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+            other => other,
+        }
+    });
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+/// The first sentence of a paragraph, which ends at the first `. `. Sentences can be wrapped over
+/// several lines, so the lines are joined first.
+fn first_sentence(paragraph: &str) -> Option<String> {
+    let line = paragraph.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ");
+    let sentence = match line.find(". ") {
+        Some(pos) => &line[..pos + 1],
+        None => &line[..],
+    };
+
+    let sentence = sentence.trim();
+    if sentence.is_empty() {
+        None
+    } else {
+        Some(sentence.to_string())
+    }
+}
+
+/// Shortens the line to at most `width` characters, ending it with an ellipsis if it was cut.
+fn truncate_line(line: &str, width: usize) -> String {
+    if line.chars().count() <= width || width == 0 {
+        return line.to_string();
+    }
+    let mut truncated: String = line.chars().take(width - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn html_field_item(field: &StructField, index: usize) -> String {
+    let field_doc = field.attrs.doc_body();
+    if field_doc.trim().is_empty() {
+        format!("<li><code>{}</code></li>", escape_html(&field.declaration(index)))
+    } else {
+        format!("<li><code>{}</code>\n{}</li>",
+                escape_html(&field.declaration(index)),
+                markdown_to_html(&field_doc))
+    }
+}
+
+fn html_variant_item(variant: &Variant) -> String {
+    let mut item = format!("<li><code>{}</code>", escape_html(&variant.to_string()));
+    let variant_doc = variant.attrs.doc_body();
+    if !variant_doc.trim().is_empty() {
+        item.push_str("\n");
+        item.push_str(&markdown_to_html(&variant_doc));
+    }
+
+    let fields = documented_variant_fields(variant);
+    if !fields.is_empty() {
+        let list = fields.iter()
+            .map(|&(i, field)| html_field_item(field, i))
+            .collect::<Vec<String>>()
+            .join("\n");
+        item.push_str(&format!("\n<ul>\n{}\n</ul>", list));
+    }
+    item.push_str("</li>");
+    item
+}
+
+fn html_list_item(link: &DocLink, category: &DocType, data: &Documentation) -> String {
+    html_list_item_with_note(link, category, data, "")
+}
+
+/// Like `html_list_item`, with a note after the item's name, like ` (provided)`.
+fn html_list_item_with_note(link: &DocLink,
+                            category: &DocType,
+                            data: &Documentation,
+                            note: &str) -> String {
+    format!("<li>{}{}</li>", html_link(link, category, data), note)
+}
+
+/// The item's name as code, linking to its page if it has one.
+fn html_link(link: &DocLink, category: &DocType, data: &Documentation) -> String {
+    let name = escape_html(&link.shown_name());
+    match html_link_url(link, category, data) {
+        Some(url) => format!("<a href=\"{}\"><code>{}</code></a>", url, name),
+        None      => format!("<code>{}</code>", name),
+    }
+}
+
+/// The escaped URL of the page of a listed item, relative to the crate's pages, if it has one.
+fn html_link_url(link: &DocLink, category: &DocType, data: &Documentation) -> Option<String> {
+    resolve_link(link, category, data).map(|location| {
+        let page = location.relative_filepath_from(&data.crate_info).with_extension("html");
+        escape_html(&relative_url(&page))
+    })
+}
+
+/// A list item with the field's declaration, followed by its documentation indented under it.
+fn markdown_field_item(field: &StructField, index: usize) -> String {
+    let mut item = format!("- `{}`", field.declaration(index));
+    for line in field.attrs.doc_body().trim().lines() {
+        item.push_str("\n  ");
+        item.push_str(line.trim());
+    }
+    item
+}
+
+/// Like `markdown_field_item`, with the documented fields of struct variants nested under the
+/// variant.
+fn markdown_variant_item(variant: &Variant) -> String {
+    let mut item = format!("- `{}`", variant);
+    for line in variant.attrs.doc_body().trim().lines() {
+        item.push_str("\n  ");
+        item.push_str(line.trim());
+    }
+    for (i, field) in documented_variant_fields(variant) {
+        for line in markdown_field_item(field, i).lines() {
+            item.push_str("\n  ");
+            item.push_str(line);
+        }
+    }
+    item
+}
+
+fn markdown_list_item(link: &DocLink, category: &DocType, data: &Documentation) -> String {
+    format!("- {}", markdown_link(link, category, data))
+}
+
+/// The item's name as code, linking to its documentation file if it has one.
+fn markdown_link(link: &DocLink, category: &DocType, data: &Documentation) -> String {
+    match resolve_link(link, category, data) {
+        Some(location) => {
+            format!("[`{}`]({})",
+                    link.shown_name(),
+                    location.relative_filepath_from(&data.crate_info).display())
+        }
+        None => format!("`{}`", link.shown_name()),
+    }
+}
+
+/// The location a listed item links to. Items without documentation of their own and items in
+/// other crates that haven't been generated are listed by name only.
+fn resolve_link(link: &DocLink, category: &DocType, data: &Documentation) -> Option<StoreLocation> {
+    if link.has_target() {
+        link.resolve(&data.crate_info, category)
+    } else {
+        None
+    }
+}
+
+fn doc_kind_name(data: &Documentation) -> &'static str {
+    match data.inner_data {
         DocInnerData::FnDoc(..) => "Function",
         DocInnerData::StructDoc(..) => "Struct",
+        DocInnerData::UnionDoc(..) => "Union",
         DocInnerData::ConstDoc(..) => "Constant",
+        DocInnerData::StaticDoc(..) => "Static",
+        DocInnerData::TypedefDoc(..) => "Type Definition",
         DocInnerData::EnumDoc(..) => "Enum",
         DocInnerData::TraitDoc(..) => "Trait",
         DocInnerData::TraitItemDoc(..) => "Trait Item",
+        DocInnerData::MacroDoc(..) => "Macro",
         DocInnerData::ModuleDoc(ref module) => if module.is_crate { "Crate" } else { "Module" },
-    };
+    }
+}
 
-    MarkupDoc::new(vec![
+fn doc_header(data: &Documentation) -> MarkupDoc {
+    let mut parts = vec![
         Block(format!("({})", data.crate_info)),
-        Header(format!("{} {}", name, data.mod_path)),
-    ])
+        Header(format!("{} {}", doc_kind_name(data), data.mod_path)),
+    ];
+
+    if let Some(ref deprecation) = data.attrs.deprecation {
+        parts.push(Section(deprecation_notice("DEPRECATED", deprecation)));
+    }
+
+    MarkupDoc::new(parts)
+}
+
+fn deprecation_notice(label: &str, deprecation: &Deprecation) -> String {
+    let mut notice = label.to_string();
+    if let Some(ref since) = deprecation.since {
+        notice.push_str(&format!(" since {}", since));
+    }
+    if let Some(ref note) = deprecation.note {
+        notice.push_str(&format!(": {}", note));
+    }
+    notice
 }
 
 fn doc_body(data: &Documentation) -> MarkupDoc {
     data.attrs.format()
 }
 
-fn doc_related_items(data: &Documentation) -> MarkupDoc {
-    MarkupDoc::new(vec![])
+/// How an item is introduced when items of different kinds are listed together.
+fn member_label(category: &DocType) -> &'static str {
+    match *category {
+        DocType::AssocConst | DocType::TraitItemConst => "const",
+        DocType::AssocType | DocType::TraitItemType   => "type",
+        DocType::Macro | DocType::TraitItemMacro      => "macro",
+        _                                             => "fn",
+    }
+}
+
+fn doc_related_items(data: &Documentation, options: &FormatOptions) -> MarkupDoc {
+    let mut parts = Vec::new();
+
+    let members = if options.source_order {
+        data.member_categories()
+    } else {
+        Vec::new()
+    };
+    let mut members_listed = false;
+
+    for category in data.listed_categories() {
+        if members.contains(&category) {
+            let items = data.subitems_in_source_order(&members);
+            if !members_listed && !items.is_empty() {
+                let lines = items.iter()
+                    .map(|&(ref item_category, link)| {
+                        let provided = if *item_category == DocType::TraitItemProvidedMethod {
+                            " (provided)"
+                        } else {
+                            ""
+                        };
+                        format!("  {} {}{}",
+                                member_label(item_category),
+                                link_name(link, item_category, data, options),
+                                provided)
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                parts.push(LineBreak);
+                let section = match data.get_type() {
+                    DocType::Trait => "Trait Items",
+                    _              => "Associated Items",
+                };
+                parts.push(Section(section.to_string()));
+                parts.push(Block(lines));
+            }
+            members_listed = true;
+            continue;
+        }
+
+        if category == DocType::Impl {
+            if let Some(impls) = doc_impls(data) {
+                parts.push(LineBreak);
+                parts.push(Section(category.to_string()));
+                for block in impls {
+                    let mut lines = vec![format!("  {}", block.header)];
+                    for &(heading, ref items) in impl_block_groups(block).iter() {
+                        let indent = match heading {
+                            Some(heading) => {
+                                lines.push(format!("    {}:", heading));
+                                "      "
+                            },
+                            None => "    ",
+                        };
+                        lines.extend(items.iter()
+                                     .map(|&(ref item_category, link)| {
+                                         let name = link_name(link, item_category, data, options);
+                                         format!("{}{}{}",
+                                                 indent,
+                                                 name,
+                                                 impl_item_note(block, item_category, link))
+                                     }));
+                    }
+                    parts.push(Block(lines.join("\n")));
+                }
+            }
+            continue;
+        }
+
+        if let Some(items) = data.subitems_in_category(&category) {
+            parts.push(LineBreak);
+            parts.push(Section(category.to_string()));
+
+            match (&category, doc_fields(data), doc_variants(data)) {
+                (&DocType::StructField, Some(fields), _) => {
+                    for (i, field) in fields.iter().enumerate() {
+                        parts.push(Block(format!("  {}", field.declaration(i))));
+                        let field_doc = field.attrs.doc_body();
+                        if !field_doc.trim().is_empty() {
+                            parts.push(Markdown(field_doc));
+                        }
+                    }
+                },
+                (&DocType::Variant, _, Some(variants)) => {
+                    let lines = variants.iter()
+                        .map(variant_lines)
+                        .collect::<Vec<String>>()
+                        .join("\n");
+                    parts.push(Block(lines));
+                },
+                _ => {
+                    let names = items.iter()
+                        .map(|link| format!("  {}", link_name(link, &category, data, options)))
+                        .collect::<Vec<String>>()
+                        .join("\n");
+                    parts.push(Block(names));
+                },
+            }
+        }
+    }
+
+    MarkupDoc::new(parts)
+}
+
+/// The name of a listed item, as a hyperlink to its documentation file if hyperlinks are enabled
+/// and the item has one.
+fn link_name(link: &DocLink, category: &DocType, data: &Documentation, options: &FormatOptions)
+             -> String {
+    if !options.hyperlinks {
+        return link.shown_name();
+    }
+
+    match resolve_link(link, category, data).map(|location| location.to_filepath()) {
+        Some(Ok(path)) => hyperlink(&file_url(&path), &link.shown_name()),
+        _              => link.shown_name(),
+    }
+}
+
+/// Where the item is defined, like `Defined in src/lib.rs:10-24`, with the location as a
+/// hyperlink to the source file if hyperlinks are enabled.
+fn source_notice(data: &Documentation, options: &FormatOptions) -> Option<String> {
+    data.source.as_ref().map(|source| {
+        let location = source.to_string();
+        if options.hyperlinks {
+            format!("Defined in {}", hyperlink(&file_url(&source.file), &location))
+        } else {
+            format!("Defined in {}", location)
+        }
+    })
 }
 
 fn doc_inner_info(data: &Documentation) -> MarkupDoc {
     let markup = match data.inner_data {
         DocInnerData::FnDoc(ref func) => {
-            match func.kind {
-                FnKind::MethodFromImpl => Header(format!(
+            match (&func.kind, &func.impl_header) {
+                (&FnKind::MethodFromImpl, &Some(ref header)) => Header(format!("From {}", header)),
+                (&FnKind::MethodFromImpl, &None) => Header(format!(
                     "Impl on type {}",
                     data.mod_path.parent().unwrap()
                 )),
@@ -151,7 +1155,11 @@ fn doc_inner_info(data: &Documentation) -> MarkupDoc {
             Header(format!("From trait {}", data.mod_path.parent().unwrap()))
         }
         DocInnerData::StructDoc(..) |
+        DocInnerData::UnionDoc(..) |
         DocInnerData::ConstDoc(..) |
+        DocInnerData::StaticDoc(..) |
+        DocInnerData::TypedefDoc(..) |
+        DocInnerData::MacroDoc(..) |
         DocInnerData::EnumDoc(..) |
         DocInnerData::TraitDoc(..) |
         DocInnerData::ModuleDoc(..) => LineBreak,
@@ -159,7 +1167,19 @@ fn doc_inner_info(data: &Documentation) -> MarkupDoc {
     MarkupDoc::new(vec![markup])
 }
 
-fn doc_signature(data: &Documentation) -> MarkupDoc {
+/// The most characters of the value of a constant or static shown in summary lines. Longer values
+/// are cut off, and only shown in full in the item's own documentation.
+const SUMMARY_VALUE_WIDTH: usize = 40;
+
+/// The signature of the documented item, including its visibility. Crates have no signature.
+/// Documents whose signature wasn't filled in during generation have it built on the spot.
+fn signature_string(data: &Documentation) -> Option<String> {
+    data.signature.clone().or_else(|| data.build_signature())
+}
+
+/// Builds the signature of the documented item from its data, with the values of constants and
+/// statics shortened to at most `value_width` characters if given.
+fn signature_string_with(data: &Documentation, value_width: Option<usize>) -> Option<String> {
     let vis_string = match data.visibility {
         Some(ref v) => v.to_string(),
         None => "".to_string(),
@@ -168,69 +1188,675 @@ fn doc_signature(data: &Documentation) -> MarkupDoc {
     let header = match data.inner_data {
         DocInnerData::ModuleDoc(ref module) => {
             if module.is_crate {
-                return MarkupDoc::new(vec![Rule(10), LineBreak]);
+                return None;
             } else {
                 doc_module(data)
             }
         }
         DocInnerData::FnDoc(ref func) => doc_fn(data, func),
-        DocInnerData::EnumDoc(..) => doc_enum(data),
-        DocInnerData::StructDoc(..) => doc_struct(data),
-        DocInnerData::ConstDoc(ref konst) => doc_const(data, konst),
-        DocInnerData::TraitDoc(..) => doc_trait(data),
+        DocInnerData::EnumDoc(ref enum_) => doc_enum(data, enum_),
+        DocInnerData::StructDoc(ref struct_) => doc_struct(data, struct_),
+        DocInnerData::UnionDoc(ref union_) => doc_union(data, union_),
+        DocInnerData::ConstDoc(ref konst) => doc_const(data, konst, value_width),
+        DocInnerData::StaticDoc(ref statik) => doc_static(data, statik, value_width),
+        DocInnerData::TypedefDoc(ref typedef) => doc_typedef(data, typedef),
+        DocInnerData::TraitDoc(ref trait_) => doc_trait(data, trait_),
         DocInnerData::TraitItemDoc(ref item) => doc_trait_item(data, item),
+        DocInnerData::MacroDoc(ref mac) => return Some(doc_macro(data, mac)),
     };
 
-    MarkupDoc::new(vec![
-        Rule(10),
-        LineBreak,
-        Block(format!("  {} {}", vis_string, header)),
-        LineBreak,
-        Rule(10),
-        LineBreak,
-    ])
+    Some(format!("{} {}", vis_string, header).trim().to_string())
+}
+
+/// The signature with the item's codegen attributes, like `#[repr(C)]`, on the lines above it.
+fn declaration_string(data: &Documentation) -> Option<String> {
+    signature_string(data).map(|signature| {
+        let mut lines = data.attrs.codegen_attrs.clone();
+        lines.push(signature);
+        lines.join("\n")
+    })
+}
+
+fn doc_signature(data: &Documentation, options: &FormatOptions) -> MarkupDoc {
+    let mut parts = match declaration_string(data) {
+        Some(signature) => {
+            vec![
+                Rule(10),
+                LineBreak,
+                Signature(link_return_types(&wrap_where_clauses(&signature,
+                                                                options.signature_width),
+                                            data,
+                                            options)),
+                LineBreak,
+                Rule(10),
+                LineBreak,
+            ]
+        }
+        None => vec![Rule(10), LineBreak],
+    };
+
+    if let Some(availability) = availability_notice(&data.attrs) {
+        parts.push(Block(availability));
+        parts.push(LineBreak);
+    }
+
+    for &(ref features, ref signature) in data.attrs.feature_signatures.iter() {
+        parts.push(Block(format!("With features {}:", merge::describe_features(features))));
+        parts.push(LineBreak);
+        parts.push(Signature(wrap_where_clauses(signature, options.signature_width)));
+        parts.push(LineBreak);
+    }
+
+    if let Some(stability) = stability_notice(&data.attrs) {
+        parts.push(Block(stability));
+        parts.push(LineBreak);
+    }
+
+    if let Some(must_use) = must_use_notice(&data.attrs) {
+        parts.push(Block(must_use));
+        parts.push(LineBreak);
+    }
+
+    if let Some(non_exhaustive) = non_exhaustive_notice(data) {
+        parts.push(Block(non_exhaustive.to_string()));
+        parts.push(LineBreak);
+    }
+
+    if data.attrs.hidden {
+        parts.push(Block(HIDDEN_NOTICE.to_string()));
+        parts.push(LineBreak);
+    }
+
+    if data.is_private() {
+        parts.push(Block(PRIVATE_NOTICE.to_string()));
+        parts.push(LineBreak);
+    }
+
+    if let Some(source) = source_notice(data, options) {
+        parts.push(Block(source));
+        parts.push(LineBreak);
+    }
+
+    MarkupDoc::new(parts)
+}
+
+/// Breaks the where-clauses of signature lines longer than `width` onto their own lines, with
+/// one predicate per line, like rustfmt does. Shorter lines are kept as they are.
+fn wrap_where_clauses(signature: &str, width: usize) -> String {
+    signature.lines()
+        .map(|line| if line.chars().count() > width {
+            wrap_where_clause(line)
+        } else {
+            line.to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn wrap_where_clause(line: &str) -> String {
+    let start = match line.find(" where ") {
+        Some(start) => start,
+        None => return line.to_string(),
+    };
+    let indent: String = line.chars().take_while(|c| c.is_whitespace()).collect();
+    let clause = &line[start + " where ".len()..];
+
+    // The where-clause is followed by the item's body, if any.
+    let (predicates, end) = if clause.ends_with(" {}") {
+        (&clause[..clause.len() - 3], "{}")
+    } else if clause.ends_with(" {") {
+        (&clause[..clause.len() - 2], "{")
+    } else if clause.ends_with(';') {
+        (&clause[..clause.len() - 1], ";")
+    } else {
+        (clause, "")
+    };
+
+    let predicates = split_top_level(predicates);
+    let mut lines = vec![line[..start].to_string(), format!("{}where", indent)];
+    for (i, predicate) in predicates.iter().enumerate() {
+        let separator = if i + 1 == predicates.len() && end == ";" { ";" } else { "," };
+        lines.push(format!("{}    {}{}", indent, predicate, separator));
+    }
+    if end == "{" || end == "{}" {
+        lines.push(format!("{}{}", indent, end));
+    }
+    lines.join("\n")
+}
+
+/// Splits a list at the commas that aren't nested inside brackets, like those between
+/// `T: Fn(A, B) -> C` and `U: Into<(A, B)>`.
+fn split_top_level(list: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0i32;
+    let mut prev = ' ';
+
+    for c in list.chars() {
+        match c {
+            '<' | '(' | '[' | '{' => depth += 1,
+            // The `>` of `->` doesn't close anything.
+            '>' if prev == '-' => (),
+            '>' | ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                prev = c;
+                continue;
+            }
+            _ => (),
+        }
+        current.push(c);
+        prev = c;
+    }
+
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+/// Shown for `#[doc(hidden)]` items, which are only documented when asked for.
+const HIDDEN_NOTICE: &str = "Hidden from the public documentation";
+
+/// Shown for items that aren't public, which are only documented when asked for.
+const PRIVATE_NOTICE: &str = "Private: not part of the crate's public API";
+
+/// Describes a `#[stable]` or `#[unstable]` attribute, like "Unstable (feature x)" or "Stable
+/// since 1.0".
+fn stability_notice(attrs: &Attributes) -> Option<String> {
+    attrs.stability.as_ref().map(|stability| match *stability {
+        Stability::Stable { since: Some(ref since) } => format!("Stable since {}", since),
+        Stability::Stable { since: None } => "Stable".to_string(),
+        Stability::Unstable { ref feature, ref issue } => {
+            let mut details = Vec::new();
+            if let Some(ref feature) = *feature {
+                details.push(format!("feature {}", feature));
+            }
+            if let Some(ref issue) = *issue {
+                details.push(format!("issue {}", issue));
+            }
+            if details.is_empty() {
+                "Unstable".to_string()
+            } else {
+                format!("Unstable ({})", details.join(", "))
+            }
+        }
+    })
+}
+
+/// Describes a `#[must_use]` attribute, with its message if it has one.
+fn must_use_notice(attrs: &Attributes) -> Option<String> {
+    attrs.must_use.as_ref().map(|message| if message.is_empty() {
+        "Must be used".to_string()
+    } else {
+        format!("Must be used: {}", message)
+    })
+}
+
+/// Describes what `#[non_exhaustive]` means for a struct or enum marked with it.
+fn non_exhaustive_notice(data: &Documentation) -> Option<&'static str> {
+    match data.inner_data {
+        DocInnerData::StructDoc(ref struct_) if struct_.non_exhaustive => {
+            Some("Non-exhaustive: more fields may be added, so it can't be constructed with a \
+                  struct expression outside its crate")
+        }
+        DocInnerData::EnumDoc(ref enum_) if enum_.non_exhaustive => {
+            Some("Non-exhaustive: more variants may be added, so matches on it outside its crate \
+                  must include a wildcard arm")
+        }
+        _ => None,
+    }
+}
+
+/// Describes the `#[cfg]` conditions an item is available under, like "Available on feature `x`
+/// only".
+fn availability_notice(attrs: &Attributes) -> Option<String> {
+    let mut notices = Vec::new();
+
+    if !attrs.cfgs.is_empty() {
+        let conditions = attrs.cfgs.iter()
+            .map(|cfg| describe_cfg(cfg))
+            .collect::<Vec<String>>()
+            .join(" and ");
+        notices.push(format!("Available on {} only", conditions));
+    }
+
+    // Set when documentation generated with different features was merged.
+    if !attrs.feature_sets.is_empty() {
+        let sets = attrs.feature_sets.iter()
+            .map(|features| merge::describe_features(features))
+            .collect::<Vec<String>>()
+            .join(" or ");
+        notices.push(format!("Available with features {} only", sets));
+    }
+
+    if notices.is_empty() {
+        None
+    } else {
+        Some(notices.join(". "))
+    }
+}
+
+fn describe_cfg(cfg: &str) -> String {
+    let mut parts = cfg.splitn(2, '=').map(|part| part.trim());
+    match (parts.next(), parts.next()) {
+        (Some("feature"), Some(value)) => format!("feature `{}`", value.trim_matches('"')),
+        _ => format!("`{}`", cfg),
+    }
 }
 
 fn doc_module(data: &Documentation) -> String {
     format!("mod {}", data.mod_path)
 }
 
+/// The qualifiers written before `fn`, in the order Rust requires, like `const unsafe extern "C" `.
+/// The Rust ABI is left out, since it is the default.
+fn fn_qualifiers(constness: &Constness, unsafety: &Unsafety, abi: &Abi) -> String {
+    let mut qualifiers = String::new();
+    if *constness == Constness::Const {
+        qualifiers.push_str("const ");
+    }
+    if *unsafety == Unsafety::Unsafe {
+        qualifiers.push_str("unsafe ");
+    }
+    if *abi != Abi::Rust {
+        qualifiers.push_str(&format!("extern \"{}\" ", abi.name()));
+    }
+    qualifiers
+}
+
 fn doc_fn(data: &Documentation, func: &Function) -> String {
-    format!("fn {} {}", data.name, func.header)
+    format!("{}fn {}{}{}{}",
+            fn_qualifiers(&func.constness, &func.unsafety, &func.abi),
+            data.name,
+            func.generics,
+            func.decl,
+            func.generics.where_clause())
+}
+
+fn doc_enum(data: &Documentation, enum_: &Enum) -> String {
+    let header = format!("enum {}{}{}",
+                         data.name,
+                         enum_.generics,
+                         enum_.generics.where_clause());
+    if enum_.variants.is_empty() {
+        return format!("{} {{}}", header);
+    }
+
+    let variants = enum_.variants.iter()
+        .map(|variant| format!("    {},", variant))
+        .collect::<Vec<String>>()
+        .join("\n");
+    format!("{} {{\n{}\n}}", header, variants)
+}
+
+/// The body of a struct or union with named fields. Private fields are summarized in a comment.
+fn named_fields_body(fields: &[StructField]) -> String {
+    let mut lines: Vec<String> = fields.iter()
+        .enumerate()
+        .filter(|&(_, field)| field.is_public())
+        .map(|(i, field)| format!("    {},", field.declaration(i)))
+        .collect();
+
+    if fields.iter().any(|field| !field.is_public()) {
+        if lines.is_empty() {
+            return "{ /* private fields */ }".to_string();
+        }
+        lines.push("    /* private fields */".to_string());
+    }
+    format!("{{\n{}\n}}", lines.join("\n"))
+}
+
+fn doc_struct(data: &Documentation, struct_: &Struct) -> String {
+    let header = format!("struct {}{}", data.name, struct_.generics);
+    let where_clause = struct_.generics.where_clause();
+
+    match struct_.kind {
+        StructKind::Unit => format!("{}{};", header, where_clause),
+        StructKind::Tuple => {
+            let fields = struct_.fields.iter()
+                .map(|field| if field.is_public() {
+                    format!("pub {}", field.ty.name)
+                } else {
+                    "_".to_string()
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+            format!("{}({}){};", header, fields, where_clause)
+        }
+        StructKind::Named if struct_.fields.is_empty() => {
+            format!("{}{} {{}}", header, where_clause)
+        }
+        StructKind::Named => {
+            format!("{}{} {}", header, where_clause, named_fields_body(&struct_.fields))
+        }
+    }
+}
+
+fn doc_union(data: &Documentation, union_: &Union) -> String {
+    format!("union {}{}{} {}",
+            data.name,
+            union_.generics,
+            union_.generics.where_clause(),
+            named_fields_body(&union_.fields))
+}
+
+/// The impl block a method was defined in.
+fn parent_impl(data: &Documentation) -> Option<&ImplHeader> {
+    match data.inner_data {
+        DocInnerData::FnDoc(ref func) => func.impl_header.as_ref(),
+        _                             => None,
+    }
+}
+
+/// The documented types named by a function's return type, with the categories to resolve them
+/// in. Return type links always carry the type of the item they point to.
+fn return_links(data: &Documentation) -> Vec<(DocType, &DocLink)> {
+    match data.inner_data {
+        DocInnerData::FnDoc(ref func) => {
+            func.return_links.iter()
+                .map(|link| (link.target_type(&DocType::Struct), link))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Where the return type of a function's signature starts, after the `->` that isn't inside the
+/// parameters, like those of `fn(u8) -> u8` parameters.
+fn return_type_start(signature: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut prev = ' ';
+    for (i, c) in signature.char_indices() {
+        match c {
+            '(' | '[' | '<' => depth += 1,
+            '>' if prev == '-' => if depth == 0 {
+                return Some(i + 1);
+            },
+            ')' | ']' | '>' => depth -= 1,
+            _ => (),
+        }
+        prev = c;
+    }
+    None
+}
+
+/// Splits a function's signature into the names of the documented types in its return type and
+/// the text around them, pairing each name with its link and the category to resolve it in.
+/// `Self` names the type the method is defined on. The return type ends at the where-clause.
+fn split_return_types<'a>(signature: &'a str, data: &'a Documentation)
+                          -> Vec<(&'a str, Option<(DocType, &'a DocLink)>)> {
+    let links = return_links(data);
+    let start = match return_type_start(signature) {
+        Some(start) if !links.is_empty() => start,
+        _ => return vec![(signature, None)],
+    };
+    let self_path = data.mod_path.parent();
+
+    let mut pieces = Vec::new();
+    let mut text_start = 0;
+    let mut rest = start;
+    while rest < signature.len() {
+        let len = signature[rest..].find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(signature.len() - rest);
+        if len == 0 {
+            rest += signature[rest..].chars().next().map_or(1, |c| c.len_utf8());
+            continue;
+        }
+
+        let name = &signature[rest..rest + len];
+        if name == "where" {
+            break;
+        }
+        // Lifetimes, like the `a` of `&'a Self`, aren't types.
+        let found = if signature[..rest].ends_with('\'') {
+            None
+        } else if name == "Self" {
+            links.iter().find(|&&(_, link)| Some(&link.path) == self_path.as_ref())
+        } else {
+            links.iter().find(|&&(_, link)| link.name == name)
+        };
+        if let Some(&(ref category, link)) = found {
+            pieces.push((&signature[text_start..rest], None));
+            pieces.push((name, Some((category.clone(), link))));
+            text_start = rest + len;
+        }
+        rest += len;
+    }
+    pieces.push((&signature[text_start..], None));
+    pieces
 }
 
-fn doc_enum(data: &Documentation) -> String {
-    format!("enum {}", data.name)
+/// Makes the documented types in the return type of a function's signature hyperlinks to their
+/// documentation files, if hyperlinks are enabled.
+fn link_return_types(signature: &str, data: &Documentation, options: &FormatOptions) -> String {
+    if !options.hyperlinks {
+        return signature.to_string();
+    }
+
+    split_return_types(signature, data).into_iter()
+        .map(|(text, link)| {
+            let path = link.and_then(|(category, link)| resolve_link(link, &category, data))
+                .and_then(|location| location.to_filepath().ok());
+            match path {
+                Some(path) => hyperlink(&file_url(&path), text),
+                None       => text.to_string(),
+            }
+        })
+        .collect()
 }
 
-fn doc_struct(data: &Documentation) -> String {
-    format!("struct {} {{ /* fields omitted */ }}", data.name)
+/// The impl blocks of a struct, union or enum, if it has any.
+fn doc_impls(data: &Documentation) -> Option<&Vec<ImplBlock>> {
+    let impls = match data.inner_data {
+        DocInnerData::StructDoc(ref struct_) => &struct_.impls,
+        DocInnerData::UnionDoc(ref union_)   => &union_.impls,
+        DocInnerData::EnumDoc(ref enum_)     => &enum_.impls,
+        _                                    => return None,
+    };
+    if impls.is_empty() {
+        None
+    } else {
+        Some(impls)
+    }
 }
 
-fn doc_const(data: &Documentation, konst: &Constant) -> String {
-    format!("const {}: {} = {}", data.name, konst.ty.name, konst.expr)
+/// The items of an impl block with their categories, constants and types first. The provided
+/// methods of the trait that the block doesn't define come last, linking to the trait's
+/// documentation of them.
+fn impl_block_items(block: &ImplBlock) -> Vec<(DocType, &DocLink)> {
+    let order = [DocType::AssocConst, DocType::AssocType, DocType::Function, DocType::Macro];
+    let mut items: Vec<(DocType, &DocLink)> = order.iter()
+        .flat_map(|category| {
+            block.items.get(category).into_iter()
+                .flat_map(|links| links.iter())
+                .map(move |link| (category.clone(), link))
+        })
+        .collect();
+    items.extend(block.inherited_methods().into_iter()
+                 .map(|link| (DocType::TraitItemProvidedMethod, link)));
+    items
 }
 
-fn doc_trait(data: &Documentation) -> String {
-    format!("trait {} {{ /* fields omitted */ }}", data.name)
+/// Items of an impl block listed together, under a heading if the block's other items have one.
+type ImplItemGroup<'a> = (Option<&'static str>, Vec<(DocType, &'a DocLink)>);
+
+/// The items of an impl block in the order of `impl_block_items`, grouped under headings if the
+/// block has both associated functions and methods: constants, types and macros first without a
+/// heading, then the associated functions, then the methods.
+fn impl_block_groups(block: &ImplBlock) -> Vec<ImplItemGroup> {
+    let mut others = Vec::new();
+    let mut associated_fns = Vec::new();
+    let mut methods = Vec::new();
+    for (category, link) in impl_block_items(block).into_iter() {
+        let group = match category {
+            DocType::Function if block.is_method(&link.name) => &mut methods,
+            DocType::Function                                => &mut associated_fns,
+            DocType::TraitItemProvidedMethod                 => &mut methods,
+            _                                                => &mut others,
+        };
+        group.push((category, link));
+    }
+
+    if associated_fns.is_empty() || methods.is_empty() {
+        return vec![(None, impl_block_items(block))];
+    }
+
+    let mut groups = Vec::new();
+    if !others.is_empty() {
+        groups.push((None, others));
+    }
+    groups.push((Some("Associated Functions"), associated_fns));
+    groups.push((Some("Methods"), methods));
+    groups
+}
+
+/// Tells the methods of a trait impl that replace one of the trait's provided methods apart from
+/// the ones using the trait's default.
+fn impl_item_note(block: &ImplBlock, category: &DocType, link: &DocLink) -> &'static str {
+    match *category {
+        DocType::TraitItemProvidedMethod                 => " (provided)",
+        DocType::Function if block.overrides(&link.name) => " (overridden)",
+        _                                                => "",
+    }
+}
+
+/// The variants of an enum, if the documentation is for one.
+fn doc_variants(data: &Documentation) -> Option<&Vec<Variant>> {
+    match data.inner_data {
+        DocInnerData::EnumDoc(ref enum_) => Some(&enum_.variants),
+        _                                => None,
+    }
+}
+
+/// The fields of a struct variant that have doc comments, with their indices.
+fn documented_variant_fields(variant: &Variant) -> Vec<(usize, &StructField)> {
+    match variant.kind {
+        VariantKind::Struct(ref fields) => {
+            fields.iter()
+                .enumerate()
+                .filter(|&(_, field)| !field.attrs.doc_body().trim().is_empty())
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// A variant followed by the first sentence of its doc comment, and the same for each of its
+/// documented fields on the lines under it.
+fn variant_lines(variant: &Variant) -> String {
+    let with_summary = |item: String, attrs: &Attributes, indent: &str| {
+        match attrs.summary_paragraph().and_then(|paragraph| first_sentence(&paragraph)) {
+            Some(sentence) => format!("{}{}  — {}", indent, item, sentence),
+            None => format!("{}{}", indent, item),
+        }
+    };
+
+    let mut lines = vec![with_summary(variant.to_string(), &variant.attrs, "  ")];
+    for (i, field) in documented_variant_fields(variant) {
+        lines.push(with_summary(field.name(i), &field.attrs, "      "));
+    }
+    lines.join("\n")
+}
+
+/// The fields of a struct or union, if the documentation is for one.
+fn doc_fields(data: &Documentation) -> Option<&Vec<StructField>> {
+    match data.inner_data {
+        DocInnerData::StructDoc(ref struct_) => Some(&struct_.fields),
+        DocInnerData::UnionDoc(ref union_)   => Some(&union_.fields),
+        _                                    => None,
+    }
+}
+
+fn doc_const(data: &Documentation, konst: &Constant, value_width: Option<usize>) -> String {
+    format!("const {}: {} = {}", data.name, konst.ty.name, value_string(&konst.expr, value_width))
+}
+
+/// The value of a constant or static as it was written, or on one line and cut to `width`
+/// characters if given.
+fn value_string(expr: &str, width: Option<usize>) -> String {
+    match width {
+        Some(width) => truncate_line(&expr.split_whitespace().collect::<Vec<&str>>().join(" "),
+                                     width),
+        None => expr.to_string(),
+    }
+}
+
+fn doc_static(data: &Documentation, statik: &Static, value_width: Option<usize>) -> String {
+    let abi = match statik.abi {
+        Some(ref abi) => format!("extern \"{}\" ", abi.name()),
+        None => "".to_string(),
+    };
+    let expr = match statik.expr {
+        Some(ref expr) => format!(" = {}", value_string(expr, value_width)),
+        None => "".to_string(),
+    };
+    format!("{}static {}{}: {}{}",
+            abi,
+            statik.mutability.prefix(),
+            data.name,
+            statik.ty.name,
+            expr)
+}
+
+fn doc_typedef(data: &Documentation, typedef: &Typedef) -> String {
+    format!("type {}{} = {}", data.name, typedef.generics, typedef.ty.name)
+}
+
+/// Macros are always shown without a visibility, since `#[macro_export]` isn't written as one.
+fn doc_macro(data: &Documentation, mac: &MacroRules) -> String {
+    if mac.matchers.is_empty() {
+        return format!("macro_rules! {} {{ }}", data.name);
+    }
+
+    let rules = mac.matchers.iter()
+        .map(|matcher| format!("    {} => {{ ... }};", matcher))
+        .collect::<Vec<String>>()
+        .join("\n");
+    format!("macro_rules! {} {{\n{}\n}}", data.name, rules)
+}
+
+fn doc_trait(data: &Documentation, trait_: &Trait) -> String {
+    format!("trait {}{}{}{} {{ /* fields omitted */ }}",
+            data.name,
+            trait_.generics,
+            trait_.bounds_clause(),
+            trait_.generics.where_clause())
 }
 
 fn doc_trait_item(data: &Documentation, item: &TraitItem) -> String {
     let item_string = match item.node {
-        TraitItemKind::Const(ref ty, ref expr) => {
-            let expr_string = match *expr {
-                Some(ref e) => e.clone(),
-                None => "".to_string(),
+        TraitItemKind::Const { ref ty, ref default } => {
+            match *default {
+                Some(ref default) => format!("const {}: {} = {};", data.name, ty.name, default),
+                None => format!("const {}: {};", data.name, ty.name),
+            }
+        }
+        TraitItemKind::Method(ref sig, ref body) => {
+            let body_string = match *body {
+                Some(_) => " { ... }",
+                None => ";",
             };
-            format!("const {}: {} = {}", data.name, ty.name, expr_string)
+            format!("{}fn {}{}{}{}{}",
+                    fn_qualifiers(&sig.constness, &sig.unsafety, &sig.abi),
+                    data.name,
+                    sig.generics,
+                    sig.decl,
+                    sig.generics.where_clause(),
+                    body_string)
         }
-        TraitItemKind::Method(ref sig) => format!("fn {} {}", data.name, sig.header),
-        TraitItemKind::Type(ref ty) => {
+        TraitItemKind::Type(ref bounds, ref ty) => {
+            let bounds_string = if bounds.is_empty() {
+                "".to_string()
+            } else {
+                format!(": {}", bounds.join(" + "))
+            };
             let ty_string = match *ty {
-                Some(ref t) => t.name.clone(),
+                Some(ref t) => format!(" = {}", t.name),
                 None => "".to_string(),
             };
-            format!("type {}", ty_string)
+            format!("type {}{}{};", data.name, bounds_string, ty_string)
         }
         TraitItemKind::Macro(ref mac) => format!("macro {} {}", data.name, mac),
     };
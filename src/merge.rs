@@ -0,0 +1,215 @@
+//! Merging of the documentation generated for a crate with different features enabled, for a
+//! complete picture of its API across features.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+
+use conversion::{DocInnerData, DocLink, DocRelatedItems, DocType, Documentation, ImplBlock};
+use store;
+use ::errors::*;
+
+/// The documentation generated for a crate with a certain set of features enabled.
+pub struct Generation {
+    pub features: Vec<String>,
+    pub documents: Vec<Documentation>,
+}
+
+/// An item whose signature depends on the enabled features.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignatureConflict {
+    pub path: String,
+    pub doc_type: DocType,
+
+    /// The features of each generation the item was found in, with its signature there.
+    pub signatures: Vec<(Vec<String>, Option<String>)>,
+}
+
+/// The union of several generations of a crate's documentation.
+pub struct MergedDocs {
+    /// Every item found in any of the generations. Items missing from some of them are tagged
+    /// with the feature sets of the generations they were found in.
+    pub documents: Vec<Documentation>,
+
+    /// The items whose signatures differ between generations. The merged documentation keeps the
+    /// signature from the first generation the item was found in, and lists the others in its
+    /// `feature_signatures` attribute.
+    pub conflicts: Vec<SignatureConflict>,
+}
+
+type ItemKey = (String, DocType, usize);
+
+struct MergedItem {
+    document: Documentation,
+    found_in: Vec<usize>,
+    signatures: Vec<(Vec<String>, Option<String>)>,
+}
+
+/// Signatures are compared with their whitespace collapsed, since they can span several lines.
+fn flat_signature(doc: &Documentation) -> Option<String> {
+    doc.signature()
+        .map(|signature| signature.split_whitespace().collect::<Vec<&str>>().join(" "))
+}
+
+/// Whether two links point to the same item. Their positions can differ between generations,
+/// since items missing from one shift the ones after them.
+fn same_target(a: &DocLink, b: &DocLink) -> bool {
+    a.path == b.path && a.doc_type == b.doc_type && a.disambiguator == b.disambiguator
+}
+
+fn merge_related_items(into: &mut DocRelatedItems, from: DocRelatedItems) {
+    for (category, links) in from.into_iter() {
+        let merged = into.entry(category).or_insert(Vec::new());
+        for link in links.into_iter() {
+            if !merged.iter().any(|existing| same_target(existing, &link)) {
+                merged.push(link);
+            }
+        }
+    }
+}
+
+/// Adds the impl blocks of the same type in another generation. Blocks with the same header are
+/// taken for the same block, and get the items of both, like methods that only exist with some
+/// features.
+fn merge_impls(into: &mut Vec<ImplBlock>, from: Vec<ImplBlock>) {
+    for block in from.into_iter() {
+        let header = block.header.to_string();
+        let existing = match into.iter_mut().find(|b| b.header.to_string() == header) {
+            Some(existing) => existing,
+            None => {
+                into.push(block);
+                continue;
+            }
+        };
+
+        let ImplBlock { items, trait_link, provided_methods, methods, .. } = block;
+        merge_related_items(&mut existing.items, items);
+        if existing.trait_link.is_none() {
+            existing.trait_link = trait_link;
+        }
+        for method in provided_methods.into_iter() {
+            match existing.provided_methods.iter_mut().find(|m| m.link.name == method.link.name) {
+                Some(provided) => provided.overridden |= method.overridden,
+                None           => existing.provided_methods.push(method),
+            }
+        }
+        for method in methods.into_iter() {
+            if !existing.methods.contains(&method) {
+                existing.methods.push(method);
+            }
+        }
+    }
+}
+
+/// Adds the related items and impl blocks of the same item in another generation, like the items
+/// of a module that only exist with some features.
+fn merge_links(into: &mut Documentation, from: Documentation) {
+    merge_related_items(&mut into.links, from.links);
+
+    match (&mut into.inner_data, from.inner_data) {
+        (&mut DocInnerData::StructDoc(ref mut into), DocInnerData::StructDoc(from)) => {
+            merge_impls(&mut into.impls, from.impls)
+        },
+        (&mut DocInnerData::UnionDoc(ref mut into), DocInnerData::UnionDoc(from)) => {
+            merge_impls(&mut into.impls, from.impls)
+        },
+        (&mut DocInnerData::EnumDoc(ref mut into), DocInnerData::EnumDoc(from)) => {
+            merge_impls(&mut into.impls, from.impls)
+        },
+        _ => (),
+    }
+}
+
+/// Merges the documentation generated for a crate with different features enabled.
+pub fn merge_generations(generations: Vec<Generation>) -> MergedDocs {
+    let feature_sets: Vec<Vec<String>> = generations.iter()
+        .map(|generation| generation.features.clone())
+        .collect();
+    let mut items: BTreeMap<ItemKey, MergedItem> = BTreeMap::new();
+
+    for (i, generation) in generations.into_iter().enumerate() {
+        for doc in generation.documents.into_iter() {
+            let key = (doc.mod_path.to_string(), doc.get_type(), doc.disambiguator);
+            let signature = (generation.features.clone(), flat_signature(&doc));
+
+            if let Some(item) = items.get_mut(&key) {
+                item.found_in.push(i);
+                item.signatures.push(signature);
+                merge_links(&mut item.document, doc);
+                continue;
+            }
+
+            items.insert(key, MergedItem {
+                document: doc,
+                found_in: vec![i],
+                signatures: vec![signature],
+            });
+        }
+    }
+
+    let mut documents = Vec::new();
+    let mut conflicts = Vec::new();
+    for ((path, doc_type, _), item) in items.into_iter() {
+        let MergedItem { mut document, found_in, signatures } = item;
+
+        let differs = signatures.iter().any(|&(_, ref signature)| *signature != signatures[0].1);
+        if differs {
+            document.attrs.feature_signatures = signatures.iter()
+                .filter_map(|&(ref features, ref signature)| {
+                    signature.as_ref().map(|signature| (features.clone(), signature.clone()))
+                })
+                .collect();
+            conflicts.push(SignatureConflict {
+                path: path,
+                doc_type: doc_type,
+                signatures: signatures,
+            });
+        }
+
+        if found_in.len() < feature_sets.len() {
+            document.attrs.feature_sets = found_in.iter()
+                .map(|&i| feature_sets[i].clone())
+                .collect();
+        }
+        documents.push(document);
+    }
+
+    MergedDocs {
+        documents: documents,
+        conflicts: conflicts,
+    }
+}
+
+/// Merges directories of generated documentation, each given with the features it was generated
+/// with.
+pub fn merge_doc_dirs(dirs: &[(Vec<String>, PathBuf)]) -> Result<MergedDocs> {
+    let mut generations = Vec::new();
+    for &(ref features, ref dir) in dirs.iter() {
+        let documents = store::iter_docs_in_dir(dir)
+            .collect::<Result<Vec<Documentation>>>()?;
+        generations.push(Generation {
+            features: features.clone(),
+            documents: documents,
+        });
+    }
+
+    Ok(merge_generations(generations))
+}
+
+/// Describes a set of features, like `[std, serde]`.
+pub fn describe_features(features: &[String]) -> String {
+    format!("[{}]", features.join(", "))
+}
+
+impl Display for SignatureConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}) differs between features:", self.path, self.doc_type)?;
+        let none = String::new();
+        for &(ref features, ref signature) in self.signatures.iter() {
+            write!(f, "\n      {}: {}",
+                   describe_features(features),
+                   signature.as_ref().unwrap_or(&none))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+//! The changes to each version of the format of stored documentation, for bringing documentation
+//! written by an older oxidoc up to date without regenerating it from source that may no longer be
+//! available. See `store::migrate`.
+//!
+//! Bincoded files can only be read with the exact shape they were written with, so documents are
+//! migrated from the JSON copies written next to them by `Documentation::save_json`. Each
+//! migration rewrites the JSON of a document from one version to the next, giving the fields that
+//! were added the value they would have had, or a default when it can't be known.
+
+use std::mem;
+
+use serde_json::Value;
+
+use ::errors::*;
+
+/// The changes from one format version to the next.
+pub struct Migration {
+    pub from: u32,
+
+    /// What the version changed, for reporting.
+    pub description: &'static str,
+
+    migrate: fn(&mut Value),
+}
+
+impl Migration {
+    pub fn to(&self) -> u32 {
+        self.from + 1
+    }
+}
+
+/// Every known migration, ordered by the version they migrate from.
+static MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 30,
+        description: "the stability of items",
+        migrate: add_stability,
+    },
+    Migration {
+        from: 31,
+        description: "precomputed signatures",
+        migrate: add_signature,
+    },
+    Migration {
+        from: 32,
+        description: "methods told apart from associated functions",
+        migrate: add_receivers,
+    },
+    Migration {
+        from: 33,
+        description: "the bounds of associated types",
+        migrate: add_assoc_type_bounds,
+    },
+    Migration {
+        from: 34,
+        description: "re-exports from crates that weren't generated yet",
+        migrate: only_store_changed,
+    },
+    Migration {
+        from: 35,
+        description: "the names of renamed re-exports",
+        migrate: add_link_aliases,
+    },
+    Migration {
+        from: 36,
+        description: "the signatures of merged items with each set of features",
+        migrate: add_feature_signatures,
+    },
+    Migration {
+        from: 37,
+        description: "items of private modules that aren't re-exported",
+        migrate: add_unreachable,
+    },
+    Migration {
+        from: 38,
+        description: "glob re-exports of other crates' modules",
+        migrate: add_link_globs,
+    },
+    Migration {
+        from: 39,
+        description: "how files are compressed, in their header",
+        migrate: only_header_changed,
+    },
+    Migration {
+        from: 40,
+        description: "exported macros at the crate root, in `macro-` files",
+        migrate: move_macros,
+    },
+    Migration {
+        from: 41,
+        description: "the features of items, for searching by feature",
+        migrate: only_store_changed,
+    },
+    Migration {
+        from: 42,
+        description: "deprecated items flagged in search results",
+        migrate: only_store_changed,
+    },
+];
+
+/// The migrations to apply, in order, to bring a document from one version to another. Fails if
+/// any version in between has no migration.
+pub fn migrations(from: u32, to: u32) -> Result<Vec<&'static Migration>> {
+    if from >= to {
+        bail!(ErrorKind::NoMigration(from, to));
+    }
+
+    let mut steps = Vec::new();
+    for version in from..to {
+        match MIGRATIONS.iter().find(|migration| migration.from == version) {
+            Some(migration) => steps.push(migration),
+            None            => bail!(ErrorKind::NoMigration(from, to)),
+        }
+    }
+    Ok(steps)
+}
+
+/// Rewrites the JSON of a document written with the `from` version of the format into the `to`
+/// version.
+pub fn migrate_document(document: &mut Value, from: u32, to: u32) -> Result<()> {
+    for migration in migrations(from, to)? {
+        (migration.migrate)(document);
+    }
+    Ok(())
+}
+
+/// Sets a field of an object if it's missing, leaving other values alone.
+fn insert_default(value: &mut Value, field: &str, default: Value) {
+    if let Value::Object(ref mut object) = *value {
+        if !object.contains_key(field) {
+            object.insert(field.to_string(), default);
+        }
+    }
+}
+
+/// The data inside `inner_data` of a document, if it's of the given variant.
+fn inner_data<'a>(document: &'a mut Value, variant: &str) -> Option<&'a mut Value> {
+    document.get_mut("inner_data").and_then(|inner| inner.get_mut(variant))
+}
+
+fn add_stability(document: &mut Value) {
+    if let Some(attrs) = document.get_mut("attrs") {
+        insert_default(attrs, "stability", Value::Null);
+    }
+}
+
+fn add_signature(document: &mut Value) {
+    // A missing signature is built from the rest of the document when it's shown.
+    insert_default(document, "signature", Value::Null);
+}
+
+fn add_receivers(document: &mut Value) {
+    if let Some(function) = inner_data(document, "FnDoc") {
+        let has_receiver = function.get("decl")
+            .and_then(|decl| decl.get("inputs"))
+            .and_then(|inputs| inputs.get(0))
+            .map_or(false, |input| input.get("SelfArg").is_some());
+        insert_default(function, "has_receiver", Value::Bool(has_receiver));
+    }
+
+    // Which functions are methods is only known once every document of the crate is migrated,
+    // so the lists are filled in by `conversion::link_methods` afterwards.
+    for variant in ["StructDoc", "EnumDoc", "UnionDoc"].iter() {
+        let impls = inner_data(document, variant).and_then(|data| data.get_mut("impls"));
+        if let Some(&mut Value::Array(ref mut impls)) = impls {
+            for block in impls.iter_mut() {
+                insert_default(block, "methods", Value::Array(Vec::new()));
+            }
+        }
+    }
+}
+
+fn add_assoc_type_bounds(document: &mut Value) {
+    let node = inner_data(document, "TraitItemDoc").and_then(|item| item.get_mut("node"));
+    if let Some(&mut Value::Object(ref mut node)) = node {
+        // `Type(default)` became `Type(bounds, default)`.
+        if let Some(ty) = node.get_mut("Type") {
+            if !ty.is_array() {
+                let default = mem::replace(ty, Value::Null);
+                *ty = Value::Array(vec![Value::Array(Vec::new()), default]);
+            }
+        }
+    }
+}
+
+fn only_store_changed(_: &mut Value) {
+    // Only the shape of the store changed, and it's rebuilt from the documents.
+}
+
+fn only_header_changed(_: &mut Value) {
+    // The documents are the same, and are written with the new header when they're saved.
+}
+
+fn move_macros(_: &mut Value) {
+    // Which module lists a macro only changes once every document of the crate is migrated, so
+    // the macros are moved by `conversion::move_exported_macros` afterwards, and saved where
+    // their new path and prefix put them.
+}
+
+fn add_link_aliases(document: &mut Value) {
+    insert_link_default(document, "alias", Value::Null);
+}
+
+/// Sets a field of every link in the value if it's missing.
+fn insert_link_default(value: &mut Value, field: &str, default: Value) {
+    // Links are found by their fields, since they're in many places of a document.
+    match *value {
+        Value::Object(ref mut object) => {
+            let is_link = ["name", "path", "disambiguator", "krate"].iter()
+                .all(|field| object.contains_key(*field));
+            if is_link && !object.contains_key(field) {
+                object.insert(field.to_string(), default.clone());
+            }
+            for (_, child) in object.iter_mut() {
+                insert_link_default(child, field, default.clone());
+            }
+        },
+        Value::Array(ref mut array) => {
+            for child in array.iter_mut() {
+                insert_link_default(child, field, default.clone());
+            }
+        },
+        _ => (),
+    }
+}
+
+fn add_feature_signatures(document: &mut Value) {
+    // Only merged documents have them, and those are merged again from their generations.
+    if let Some(attrs) = document.get_mut("attrs") {
+        insert_default(attrs, "feature_signatures", Value::Array(Vec::new()));
+    }
+}
+
+fn add_unreachable(document: &mut Value) {
+    // Private modules were only looked into when documenting private items, so the items found in
+    // them are taken to be public like before.
+    if let Some(attrs) = document.get_mut("attrs") {
+        insert_default(attrs, "unreachable", Value::Bool(false));
+    }
+}
+
+fn add_link_globs(document: &mut Value) {
+    // Glob re-exports of other crates' modules weren't listed before.
+    insert_link_default(document, "glob", Value::Bool(false));
+}
@@ -1,10 +1,24 @@
 use std::{env, fs, io, result};
+use std::ffi::OsString;
 use std::path::{PathBuf};
+use std::sync::RwLock;
 use document::CrateInfo;
 use ::errors::*;
 
 const STORE_FILENAME: &str = "store";
 
+/// The environment variable used to choose the directory documentation is stored in.
+const STORE_VAR: &str = "OXIDOC_STORE";
+
+const SEARCH_INDEX_FILENAME: &str = "search_index";
+const FULL_TEXT_INDEX_FILENAME: &str = "full_text_index";
+const COMPLETION_INDEX_FILENAME: &str = "completion_index";
+const STORE_LOCK_FILENAME: &str = "store.lock";
+
+lazy_static! {
+    static ref STORE_ROOT_OVERRIDE: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
 pub fn home_dir() -> Result<PathBuf> {
     if let Some(home_dir) = env::home_dir() {
         Ok(home_dir)
@@ -19,8 +33,32 @@ fn make_registry_path(dir: &str) -> Result<PathBuf> {
     Ok(home_dir.as_path().join(".cargo").join("registry").join(dir))
 }
 
+/// Makes documentation be stored in the given directory from now on, taking precedence over the
+/// `OXIDOC_STORE` environment variable. Passing `None` removes the override. Only meant for tests,
+/// which can't change the environment of the other tests running with them.
+#[doc(hidden)]
+pub fn set_store_root(path: Option<PathBuf>) {
+    *STORE_ROOT_OVERRIDE.write().unwrap() = path;
+}
+
+/// Picks the store directory from the override, the value of `OXIDOC_STORE` and the default, in
+/// that order.
+fn choose_store_root(override_path: Option<PathBuf>,
+                     env_value: Option<OsString>) -> Result<PathBuf> {
+    if let Some(path) = override_path {
+        return Ok(path);
+    }
+
+    match env_value {
+        Some(ref value) if !value.is_empty() => Ok(PathBuf::from(value)),
+        _ => make_registry_path("doc"),
+    }
+}
+
+/// The directory documentation is stored in. Defaults to `~/.cargo/registry/doc`.
 pub fn doc_registry_path() -> Result<PathBuf> {
-    make_registry_path("doc")
+    let override_path = STORE_ROOT_OVERRIDE.read().unwrap().clone();
+    choose_store_root(override_path, env::var_os(STORE_VAR))
 }
 
 pub fn src_registry_path() -> Result<PathBuf> {
@@ -33,6 +71,31 @@ pub fn store_file_path() -> Result<PathBuf> {
     Ok(registry_path)
 }
 
+pub fn search_index_path() -> Result<PathBuf> {
+    let mut registry_path = doc_registry_path()?;
+    registry_path.push(SEARCH_INDEX_FILENAME);
+    Ok(registry_path)
+}
+
+pub fn full_text_index_path() -> Result<PathBuf> {
+    let mut registry_path = doc_registry_path()?;
+    registry_path.push(FULL_TEXT_INDEX_FILENAME);
+    Ok(registry_path)
+}
+
+pub fn completion_index_path() -> Result<PathBuf> {
+    let mut registry_path = doc_registry_path()?;
+    registry_path.push(COMPLETION_INDEX_FILENAME);
+    Ok(registry_path)
+}
+
+/// The lock file held while the files shared by every crate in the store are rewritten.
+pub fn store_lock_path() -> Result<PathBuf> {
+    let mut registry_path = doc_registry_path()?;
+    registry_path.push(STORE_LOCK_FILENAME);
+    Ok(registry_path)
+}
+
 /// Obtains the base output path for a crate's documentation.
 pub fn crate_doc_path(crate_info: &CrateInfo) -> Result<PathBuf> {
     let registry_path = doc_registry_path()?;
@@ -43,6 +106,48 @@ pub fn crate_doc_path(crate_info: &CrateInfo) -> Result<PathBuf> {
     Ok(path)
 }
 
+/// Makes an item name safe to use as a file or directory name, by percent-encoding the UTF-8 bytes
+/// of every character that isn't an ASCII letter, digit, `_` or `-`. The encoding is reversible,
+/// so distinct names never map to the same file.
+pub fn sanitize_filename(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars() {
+        if (c as u32) < 0x80 && c.is_alphanumeric() || c == '_' || c == '-' {
+            result.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                result.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    result
+}
+
+/// Reverses `sanitize_filename`. Returns `None` if the name contains characters that
+/// `sanitize_filename` would have escaped, or escapes that aren't valid UTF-8.
+pub fn unsanitize_filename(name: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c == '%' {
+            let hex: String = chars.by_ref().take(2).collect();
+            if hex.len() != 2 {
+                return None;
+            }
+            match u8::from_str_radix(&hex, 16) {
+                Ok(byte) => bytes.push(byte),
+                Err(_) => return None,
+            }
+        } else if (c as u32) < 0x80 && c.is_alphanumeric() || c == '_' || c == '-' {
+            bytes.push(c as u8);
+        } else {
+            return None;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
 pub fn iter_crate_source_paths() -> Result<Vec<PathBuf>> {
     let mut paths = Vec::new();
 
@@ -82,3 +187,43 @@ fn validate_crate_src_path(src: result::Result<fs::DirEntry, io::Error>) -> Opti
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename("Vec"), "Vec");
+        assert_eq!(sanitize_filename("snake_case-name"), "snake_case-name");
+        assert_eq!(sanitize_filename("a/b:c"), "a%2Fb%3Ac");
+        assert_eq!(sanitize_filename("Vec<T>"), "Vec%3CT%3E");
+        assert_eq!(sanitize_filename("100%"), "100%25");
+        assert_eq!(sanitize_filename("ñ"), "%C3%B1");
+        assert_eq!(sanitize_filename(".."), "%2E%2E");
+    }
+
+    #[test]
+    fn test_unsanitize_filename() {
+        for name in &["Vec", "a/b:c", "Vec<T>", "100%", "ñ", ".."] {
+            assert_eq!(unsanitize_filename(&sanitize_filename(name)).unwrap(), *name);
+        }
+        assert_eq!(unsanitize_filename("a.b"), None);
+        assert_eq!(unsanitize_filename("%2"), None);
+        assert_eq!(unsanitize_filename("%ZZ"), None);
+        assert_eq!(unsanitize_filename("%FF"), None);
+    }
+
+    #[test]
+    fn test_choose_store_root() {
+        let default = make_registry_path("doc").unwrap();
+        let from_env = Some(OsString::from("/tmp/env_store"));
+
+        assert_eq!(choose_store_root(None, None).unwrap(), default);
+        assert_eq!(choose_store_root(None, Some(OsString::new())).unwrap(), default);
+        assert_eq!(choose_store_root(None, from_env.clone()).unwrap(),
+                   PathBuf::from("/tmp/env_store"));
+        assert_eq!(choose_store_root(Some(PathBuf::from("/tmp/override")), from_env).unwrap(),
+                   PathBuf::from("/tmp/override"));
+    }
+}
@@ -0,0 +1,499 @@
+//! Fuzzy searching over the names and module paths of stored documentation.
+
+use std::cmp::Ordering;
+
+use regex::Regex;
+
+use conversion::{DocLink, DocType, Documentation};
+use document::unraw;
+use store::{self, Store, StoreLocation};
+use ::errors::*;
+
+/// Scores how well a candidate string matches a search query. Implement this to change how
+/// fuzzy search results are ranked.
+pub trait Scorer {
+    /// Returns a score between 0.0 and 1.0, higher being a better match, or `None` if the
+    /// candidate doesn't match at all.
+    fn score(&self, query: &str, candidate: &str) -> Option<f64>;
+}
+
+/// Matches candidates that contain all the characters of the query in order, ignoring case.
+/// Consecutive matches, matches at the start of words and shorter candidates score higher.
+pub struct SubsequenceScorer;
+
+impl Scorer for SubsequenceScorer {
+    fn score(&self, query: &str, candidate: &str) -> Option<f64> {
+        let query: Vec<char> = query.chars().map(lowercase).collect();
+        if query.is_empty() {
+            return None;
+        }
+
+        let mut matched = 0;
+        let mut points = 0;
+        let mut prev_matched = false;
+        let mut prev_char = None;
+        let mut candidate_len = 0;
+
+        for c in candidate.chars() {
+            candidate_len += 1;
+
+            let is_word_start = match prev_char {
+                None => true,
+                Some(p) => p == '_' || p == ':' || (c.is_uppercase() && !p.is_uppercase()),
+            };
+            prev_char = Some(c);
+
+            if matched < query.len() && lowercase(c) == query[matched] {
+                matched += 1;
+                points += 1;
+                if prev_matched {
+                    points += 1;
+                }
+                if is_word_start {
+                    points += 1;
+                }
+                prev_matched = true;
+            } else {
+                prev_matched = false;
+            }
+        }
+
+        if matched < query.len() {
+            return None;
+        }
+
+        let quality = points as f64 / (query.len() * 3) as f64;
+        let coverage = query.len() as f64 / candidate_len as f64;
+        Some((quality + coverage) / 2.0)
+    }
+}
+
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Matches candidates equal to the query. Every match scores 1.0.
+pub struct ExactScorer;
+
+impl Scorer for ExactScorer {
+    fn score(&self, query: &str, candidate: &str) -> Option<f64> {
+        if !query.is_empty() && query == candidate {
+            Some(1.0)
+        } else {
+            None
+        }
+    }
+}
+
+/// Matches candidates that contain the query, ignoring case. Matches at the start of the
+/// candidate score highest, then matches at the start of a word, like `Map` in `HashMap`, then
+/// any others. Within each group, shorter candidates score higher, so an equal name scores 1.0.
+pub struct SubstringScorer;
+
+impl Scorer for SubstringScorer {
+    fn score(&self, query: &str, candidate: &str) -> Option<f64> {
+        if query.is_empty() {
+            return None;
+        }
+
+        let lower_query = query.to_lowercase();
+        let lower_candidate = candidate.to_lowercase();
+        let start = match lower_candidate.find(&lower_query) {
+            Some(start) => start,
+            None        => return None,
+        };
+
+        let position = if start == 0 {
+            1.0
+        } else if is_word_start(&lower_candidate, candidate, start) {
+            0.5
+        } else {
+            0.0
+        };
+        let coverage = lower_query.len() as f64 / lower_candidate.len() as f64;
+        Some((position + coverage) / 2.0)
+    }
+}
+
+/// Whether the byte offset `start` of the lowercased candidate begins a word of the original one,
+/// after a `_` or a `:`, or at an uppercase letter.
+fn is_word_start(lower_candidate: &str, candidate: &str, start: usize) -> bool {
+    let prev = lower_candidate[..start].chars().next_back();
+    if prev == Some('_') || prev == Some(':') {
+        return true;
+    }
+    // Lowercasing can change the length of a string, so only look at the original's case when
+    // the offsets agree.
+    lower_candidate.len() == candidate.len() && candidate.is_char_boundary(start) &&
+        candidate[start..].chars().next().map_or(false, |c| c.is_uppercase())
+}
+
+/// Matches candidates that start with the query, ignoring case, for completing names. Candidates
+/// starting with the query in the same case score higher than ones that only match when case is
+/// ignored, and shorter candidates score higher than longer ones.
+pub struct PrefixScorer;
+
+impl Scorer for PrefixScorer {
+    fn score(&self, query: &str, candidate: &str) -> Option<f64> {
+        if query.is_empty() || !candidate.to_lowercase().starts_with(&query.to_lowercase()) {
+            return None;
+        }
+
+        let coverage = query.len() as f64 / candidate.len().max(query.len()) as f64;
+        if candidate.starts_with(query) {
+            Some((1.0 + coverage) / 2.0)
+        } else {
+            Some(coverage / 2.0)
+        }
+    }
+}
+
+/// Matches candidates against a regular expression, ignoring the query it's given. Candidates the
+/// match covers more of score higher, so `^new` ranks `new` above `new_with_capacity`.
+pub struct RegexScorer {
+    regex: Regex,
+}
+
+impl RegexScorer {
+    /// Compiles the pattern, failing with an error describing what's wrong with it if it isn't a
+    /// valid regular expression.
+    pub fn new(pattern: &str) -> Result<RegexScorer> {
+        match Regex::new(pattern) {
+            Ok(regex) => Ok(RegexScorer { regex: regex }),
+            Err(e)    => bail!(ErrorKind::InvalidRegex(pattern.to_string(), e.to_string())),
+        }
+    }
+}
+
+impl Scorer for RegexScorer {
+    fn score(&self, _query: &str, candidate: &str) -> Option<f64> {
+        if candidate.is_empty() {
+            return None;
+        }
+
+        self.regex.find_iter(candidate)
+            .map(|found| found.end() - found.start())
+            .max()
+            .map(|len| len.max(1) as f64 / candidate.len().max(1) as f64)
+    }
+}
+
+/// How `name_search` matches the query against stored items.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SearchMode {
+    /// The name, an alias or the full path of the item equals the query.
+    Exact,
+    /// The name or an alias of the item contains the query, ignoring case. See `SubstringScorer`
+    /// for how results are ranked.
+    CaseInsensitive,
+    /// The name or the full path of the item starts with the query, ignoring case, like `Hash`
+    /// for `HashMap` and `HashSet`. See `PrefixScorer` for how results are ranked.
+    Prefix,
+}
+
+impl Default for SearchMode {
+    fn default() -> Self {
+        SearchMode::CaseInsensitive
+    }
+}
+
+impl SearchMode {
+    /// Parses the names used on the command line: `exact`, `insensitive` or `prefix`.
+    pub fn from_name(name: &str) -> Option<SearchMode> {
+        match name {
+            "exact"       => Some(SearchMode::Exact),
+            "insensitive" => Some(SearchMode::CaseInsensitive),
+            "prefix"      => Some(SearchMode::Prefix),
+            _             => None,
+        }
+    }
+
+    /// Whether the full module path of an item is matched against the query, besides its name and
+    /// aliases. Every item of a module contains the module's name, so substring matches on paths
+    /// would bury the items actually named like the query.
+    fn matches_paths(&self) -> bool {
+        *self != SearchMode::CaseInsensitive
+    }
+}
+
+/// Restricts search results to certain kinds of items.
+#[derive(Clone, Debug)]
+pub struct SearchFilter {
+    /// The types of documentation to include. All types are included if this is empty. Types are
+    /// compared by their short names, so `Const` also includes the consts of traits and impls.
+    pub doc_types: Vec<DocType>,
+
+    /// Leave out items marked `#[unstable]`.
+    pub stable_only: bool,
+
+    /// Only include items that are only available with one of these features, like those behind
+    /// `#[cfg(feature = "x")]`. All items are included if this is empty.
+    pub features: Vec<String>,
+}
+
+impl SearchFilter {
+    pub fn new() -> Self {
+        SearchFilter {
+            doc_types: Vec::new(),
+            stable_only: false,
+            features: Vec::new(),
+        }
+    }
+
+    pub fn matches(&self, location: &StoreLocation) -> bool {
+        let short_name = location.doc_type.short_name();
+        let type_matches = self.doc_types.is_empty() ||
+            self.doc_types.iter().any(|t| t.short_name() == short_name);
+        let feature_matches = self.features.is_empty() ||
+            self.features.iter().any(|feature| location.features.contains(feature));
+        type_matches && feature_matches && !(self.stable_only && location.unstable)
+    }
+}
+
+/// A search result in the form printed by `--output json`, for scripts and editors.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct JsonResult {
+    pub name: String,
+    pub path: String,
+    #[serde(rename = "type")]
+    pub doc_type: String,
+    /// The first sentence of the item's doc comment.
+    pub summary: Option<String>,
+    /// Whether the item is marked `#[deprecated]`.
+    pub deprecated: bool,
+}
+
+impl JsonResult {
+    pub fn new(doc: &Documentation) -> Self {
+        JsonResult {
+            name: doc.name.clone(),
+            path: doc.mod_path.to_string(),
+            doc_type: doc.get_type().short_name().to_string(),
+            summary: doc.summary(),
+            deprecated: doc.attrs.deprecation.is_some(),
+        }
+    }
+}
+
+/// Scores a stored item by the best of its name, its aliases and, if `match_path` is set, its full
+/// module path.
+fn score_location<S: Scorer>(scorer: &S,
+                             query: &str,
+                             location: &StoreLocation,
+                             match_path: bool) -> Option<f64> {
+    let mut candidates = vec![location.name.clone()];
+    if unraw(&location.name) != location.name {
+        candidates.push(unraw(&location.name).to_string());
+    }
+    if match_path {
+        candidates.push(location.mod_path.to_string());
+    }
+    candidates.extend(location.aliases.iter().cloned());
+
+    candidates.iter()
+        .filter_map(|candidate| scorer.score(query, candidate))
+        .fold(None, |best: Option<f64>, score| Some(best.map_or(score, |b| b.max(score))))
+}
+
+/// Searches the given store for items matching the query and filter, using the given scorer.
+/// Returns at most `limit` results, best matches first.
+pub fn fuzzy_search_with<S: Scorer>(store: &Store,
+                                    query: &str,
+                                    limit: usize,
+                                    filter: &SearchFilter,
+                                    scorer: &S) -> Vec<(DocLink, f64)> {
+    fuzzy_search_locations(&store.all_locations(), query, limit, filter, scorer)
+}
+
+/// Like `fuzzy_search_with`, but searches the given locations, like those of a search index.
+pub fn fuzzy_search_locations<S: Scorer>(locations: &[StoreLocation],
+                                         query: &str,
+                                         limit: usize,
+                                         filter: &SearchFilter,
+                                         scorer: &S) -> Vec<(DocLink, f64)> {
+    rank_locations(locations, query, limit, filter, scorer, true)
+}
+
+/// Scores the locations matching the filter and returns links to at most `limit` of them, best
+/// matches first.
+fn rank_locations<S: Scorer>(locations: &[StoreLocation],
+                             query: &str,
+                             limit: usize,
+                             filter: &SearchFilter,
+                             scorer: &S,
+                             match_path: bool) -> Vec<(DocLink, f64)> {
+    to_links(rank_store_locations(locations, query, limit, filter, scorer, match_path))
+}
+
+fn to_links(results: Vec<(&StoreLocation, f64)>) -> Vec<(DocLink, f64)> {
+    results.into_iter()
+        .map(|(loc, score)| (loc.to_doc_link(), score))
+        .collect()
+}
+
+/// Like `rank_locations`, but returns the locations themselves.
+fn rank_store_locations<'a, S: Scorer>(locations: &'a [StoreLocation],
+                                       query: &str,
+                                       limit: usize,
+                                       filter: &SearchFilter,
+                                       scorer: &S,
+                                       match_path: bool) -> Vec<(&'a StoreLocation, f64)> {
+    let mut results: Vec<(&StoreLocation, f64)> = locations.iter()
+        .filter(|loc| filter.matches(loc))
+        .filter_map(|loc| {
+            score_location(scorer, query, loc, match_path).map(|score| (loc, score))
+        })
+        .collect();
+
+    // Ties are broken by path so the order doesn't depend on the store's hash maps.
+    results.sort_by(|&(ref a, a_score), &(ref b, b_score)| {
+        b_score.partial_cmp(&a_score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.mod_path.to_string().cmp(&b.mod_path.to_string()))
+            .then_with(|| a.disambiguator.cmp(&b.disambiguator))
+    });
+
+    results.truncate(limit);
+    results
+}
+
+/// The locations of every stored item, from the search index if there is one.
+fn stored_locations() -> Vec<StoreLocation> {
+    match store::load_search_index() {
+        Ok(index) => index.locations,
+        Err(_)    => Store::load().all_locations(),
+    }
+}
+
+/// Searches the documentation store for items matching the query and filter. Returns at most
+/// `limit` results, best matches first. The search index is used if there is one, falling back
+/// to the full store.
+pub fn fuzzy_search(query: &str, limit: usize, filter: &SearchFilter) -> Vec<(DocLink, f64)> {
+    match store::load_search_index() {
+        Ok(index) => fuzzy_search_locations(&index.locations, query, limit, filter,
+                                            &SubsequenceScorer),
+        Err(_)    => fuzzy_search_with(&Store::load(), query, limit, filter, &SubsequenceScorer),
+    }
+}
+
+/// Searches the given locations, like those of a search index, for items matching the query in
+/// the given mode. Returns at most `limit` results, best matches first, with ties broken by path.
+pub fn name_search_locations(locations: &[StoreLocation],
+                             query: &str,
+                             mode: SearchMode,
+                             limit: usize,
+                             filter: &SearchFilter) -> Vec<(DocLink, f64)> {
+    to_links(rank_by_name(locations, query, mode, limit, filter))
+}
+
+fn rank_by_name<'a>(locations: &'a [StoreLocation],
+                    query: &str,
+                    mode: SearchMode,
+                    limit: usize,
+                    filter: &SearchFilter) -> Vec<(&'a StoreLocation, f64)> {
+    let match_path = mode.matches_paths();
+    match mode {
+        SearchMode::Exact => {
+            rank_store_locations(locations, query, limit, filter, &ExactScorer, match_path)
+        }
+        SearchMode::CaseInsensitive => {
+            rank_store_locations(locations, query, limit, filter, &SubstringScorer, match_path)
+        }
+        SearchMode::Prefix => {
+            rank_store_locations(locations, query, limit, filter, &PrefixScorer, match_path)
+        }
+    }
+}
+
+/// Like `name_search_locations`, but searches the documentation store, using the search index if
+/// there is one.
+pub fn name_search(query: &str,
+                   mode: SearchMode,
+                   limit: usize,
+                   filter: &SearchFilter) -> Vec<(DocLink, f64)> {
+    name_search_locations(&stored_locations(), query, mode, limit, filter)
+}
+
+/// Like `name_search`, but returns the locations of the results, for loading their
+/// documentation.
+pub fn find_by_name(query: &str,
+                    mode: SearchMode,
+                    limit: usize,
+                    filter: &SearchFilter) -> Vec<StoreLocation> {
+    let locations = stored_locations();
+    rank_by_name(&locations, query, mode, limit, filter).into_iter()
+        .map(|(loc, _)| loc.clone())
+        .collect()
+}
+
+/// Searches the given locations, like those of a search index, for items whose name or full
+/// module path matches the regular expression, like `^new` or `::io::.*Error$`. Returns at most
+/// `limit` results, the ones the match covers more of first, or an error if the pattern isn't a
+/// valid regular expression.
+pub fn regex_search_locations(locations: &[StoreLocation],
+                              pattern: &str,
+                              limit: usize,
+                              filter: &SearchFilter) -> Result<Vec<(DocLink, f64)>> {
+    let scorer = RegexScorer::new(pattern)?;
+    Ok(rank_locations(locations, pattern, limit, filter, &scorer, true))
+}
+
+/// Like `regex_search_locations`, but searches the documentation store, using the search index
+/// if there is one.
+pub fn regex_search(pattern: &str,
+                    limit: usize,
+                    filter: &SearchFilter) -> Result<Vec<(DocLink, f64)>> {
+    regex_search_locations(&stored_locations(), pattern, limit, filter)
+}
+
+/// Like `regex_search`, but returns the locations of the results, for loading their
+/// documentation.
+pub fn find_by_regex(pattern: &str,
+                     limit: usize,
+                     filter: &SearchFilter) -> Result<Vec<StoreLocation>> {
+    let scorer = RegexScorer::new(pattern)?;
+    let locations = stored_locations();
+    let results = rank_store_locations(&locations, pattern, limit, filter, &scorer, true);
+    Ok(results.into_iter().map(|(loc, _)| loc.clone()).collect())
+}
+
+/// Finds the items whose paths end with the segments of the query, like `insert` or
+/// `HashMap::insert`, ignoring case. An ambiguous query returns every candidate, sorted by path,
+/// so the user can be asked to choose one. If the query is the full path of an item, also ignoring
+/// case, only the items with that path are returned.
+pub fn resolve_partial_path(locations: &[StoreLocation], query: &str) -> Vec<StoreLocation> {
+    let query_segments: Vec<String> = query.split("::")
+        .map(|seg| unraw(seg).to_lowercase())
+        .collect();
+
+    let mut results: Vec<(StoreLocation, bool)> = locations.iter()
+        .filter_map(|loc| {
+            let segments: Vec<String> = loc.mod_path.segments()
+                .map(|seg| unraw(&seg.identifier).to_lowercase())
+                .collect();
+            if segments.ends_with(&query_segments) {
+                Some((loc.clone(), segments.len() == query_segments.len()))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    if results.iter().any(|&(_, exact)| exact) {
+        results.retain(|&(_, exact)| exact);
+    }
+
+    let mut results: Vec<StoreLocation> = results.into_iter().map(|(loc, _)| loc).collect();
+    results.sort_by(|a, b| {
+        a.mod_path.to_string().cmp(&b.mod_path.to_string())
+            .then_with(|| a.doc_type.to_string().cmp(&b.doc_type.to_string()))
+            .then_with(|| a.disambiguator.cmp(&b.disambiguator))
+    });
+    results
+}
+
+/// Like `resolve_partial_path`, but searches the documentation store, using the search index if
+/// there is one.
+pub fn resolve_query(query: &str) -> Vec<StoreLocation> {
+    resolve_partial_path(&stored_locations(), query)
+}
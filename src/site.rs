@@ -0,0 +1,218 @@
+//! A browsable HTML site for a crate's stored documentation, with a page for each item, an index
+//! of every item and a sidebar with the crate's module tree. Pages link to each other with
+//! relative URLs and carry their own styles, so the site can be opened from disk or hosted
+//! anywhere.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use conversion::{self, DocInnerData, DocType, Documentation};
+use document::{CrateInfo, ModPath};
+use markup::{self, escape_html};
+use store::{self, StoreLocation};
+
+use ::errors::*;
+
+/// The name of the page listing every item of a crate, in the crate's directory of the site.
+pub const INDEX_FILENAME: &str = "index.html";
+
+const STYLE: &str = "
+body { margin: 0; display: flex; font-family: sans-serif; line-height: 1.5; color: #222; }
+nav.sidebar { flex: 0 0 16em; min-height: 100vh; padding: 1em; background: #f5f5f5;
+              border-right: 1px solid #ddd; overflow-x: auto; }
+nav.sidebar ul { list-style: none; margin: 0; padding-left: 1em; }
+nav.sidebar > ul { padding-left: 0; }
+nav.sidebar .current > a { font-weight: bold; }
+main { flex: 1; max-width: 60em; padding: 1em 2em; }
+a { color: #2a6ebb; text-decoration: none; }
+a:hover { text-decoration: underline; }
+pre, code { font-family: monospace; }
+pre { padding: 0.8em; background: #f5f5f5; overflow-x: auto; }
+p.crate { color: #666; }
+p.deprecated, p.stability, p.must-use, p.non-exhaustive, p.hidden, p.private {
+    padding: 0.4em 0.8em; background: #fff5d6; }
+";
+
+/// The pages written for a crate by `generate_site`.
+#[derive(Clone, Debug)]
+pub struct GeneratedSite {
+    /// The index page of the crate.
+    pub index: PathBuf,
+
+    /// The number of pages written, including the index.
+    pub pages: usize,
+}
+
+/// Writes a page for each stored item of a crate, and an index page, to the crate's directory
+/// under `out_dir`, like `out_dir/serde-1.0.0`. Generating several crates into the same directory
+/// makes the links between them work too.
+///
+/// Each page is at the same path relative to the crate's directory as the item's `.odoc` file in
+/// the store, so the links of `Documentation::to_html` lead to it. Intra-doc links in doc
+/// comments are resolved against the crate's other items.
+pub fn generate_site(crate_info: &CrateInfo, out_dir: &Path) -> Result<GeneratedSite> {
+    let mut documents = store::iter_docs(crate_info).collect::<Result<Vec<Documentation>>>()?;
+    documents.sort_by(|a, b| a.mod_path.to_string().cmp(&b.mod_path.to_string()));
+
+    let site_dir = out_dir.join(crate_info.to_path_prefix());
+    let link_targets = conversion::intra_doc_link_targets(&documents);
+
+    for (doc, targets) in documents.iter().zip(link_targets.iter()) {
+        let urls = targets.iter()
+            .filter_map(|&(ref target, ref doc_type, ref link)| {
+                link.resolve(&doc.crate_info, doc_type)
+                    .map(|location| (target.clone(), page_url(&location, crate_info)))
+            })
+            .collect::<HashMap<String, String>>();
+
+        let page = page_path(&doc.to_store_location(), crate_info);
+        let html = page_html(&page,
+                             &doc.mod_path.to_string(),
+                             &sidebar_html(&documents, crate_info, Some(&doc.mod_path)),
+                             &doc.to_html_with_links(&urls));
+        write_page(&site_dir.join(&page), &html)?;
+    }
+
+    let index = PathBuf::from(INDEX_FILENAME);
+    let html = page_html(&index,
+                         &crate_info.to_string(),
+                         &sidebar_html(&documents, crate_info, None),
+                         &index_html(&documents, crate_info));
+    write_page(&site_dir.join(&index), &html)?;
+
+    Ok(GeneratedSite {
+        index: site_dir.join(index),
+        pages: documents.len() + 1,
+    })
+}
+
+/// The path of an item's page relative to the crate's directory of the site.
+fn page_path(location: &StoreLocation, crate_info: &CrateInfo) -> PathBuf {
+    location.relative_filepath_from(crate_info).with_extension("html")
+}
+
+/// The URL of an item's page relative to the crate's directory of the site, which every page
+/// sets as its base URL.
+fn page_url(location: &StoreLocation, crate_info: &CrateInfo) -> String {
+    markup::relative_url(&page_path(location, crate_info))
+}
+
+/// The URL of the crate's directory of the site relative to a page in it.
+fn base_url(page: &Path) -> String {
+    let depth = page.components().count().saturating_sub(1);
+    if depth == 0 {
+        "./".to_string()
+    } else {
+        "../".repeat(depth)
+    }
+}
+
+fn page_html(page: &Path, title: &str, sidebar: &str, content: &str) -> String {
+    format!("<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<base href=\"{}\">
+<title>{}</title>
+<style>{}</style>
+</head>
+<body>
+{}
+<main>
+{}</main>
+</body>
+</html>
+",
+            base_url(page),
+            escape_html(title),
+            STYLE,
+            sidebar,
+            content)
+}
+
+/// The sidebar shown on every page, with the crate's module tree. The module of the current item
+/// is highlighted.
+fn sidebar_html(documents: &[Documentation],
+                crate_info: &CrateInfo,
+                current: Option<&ModPath>) -> String {
+    let modules: Vec<&Documentation> = documents.iter()
+        .filter(|doc| match doc.inner_data {
+            DocInnerData::ModuleDoc(..) => true,
+            _                           => false,
+        })
+        .collect();
+
+    let current_module = current.map(|path| {
+        let is_module = modules.iter().any(|module| module.mod_path == *path);
+        if is_module {
+            path.clone()
+        } else {
+            path.parent().unwrap_or(path.clone())
+        }
+    });
+
+    let roots = modules.iter()
+        .filter(|module| module.mod_path.0.len() == 1)
+        .map(|module| module_tree_html(module, &modules, crate_info, current_module.as_ref()))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    format!("<nav class=\"sidebar\">\n<p><a href=\"{}\">{}</a></p>\n<ul>\n{}\n</ul>\n</nav>",
+            INDEX_FILENAME,
+            escape_html(&crate_info.to_string()),
+            roots)
+}
+
+fn module_tree_html(module: &Documentation,
+                    modules: &[&Documentation],
+                    crate_info: &CrateInfo,
+                    current: Option<&ModPath>) -> String {
+    let children = modules.iter()
+        .filter(|child| child.mod_path.parent().as_ref() == Some(&module.mod_path))
+        .map(|child| module_tree_html(child, modules, crate_info, current))
+        .collect::<Vec<String>>();
+
+    let class = if current == Some(&module.mod_path) { " class=\"current\"" } else { "" };
+    let mut item = format!("<li{}><a href=\"{}\">{}</a>",
+                           class,
+                           escape_html(&page_url(&module.to_store_location(), crate_info)),
+                           escape_html(&module.name));
+    if !children.is_empty() {
+        item.push_str(&format!("\n<ul>\n{}\n</ul>", children.join("\n")));
+    }
+    item.push_str("</li>");
+    item
+}
+
+/// Every item of the crate, by type.
+fn index_html(documents: &[Documentation], crate_info: &CrateInfo) -> String {
+    let mut by_type: BTreeMap<DocType, Vec<&Documentation>> = BTreeMap::new();
+    for doc in documents.iter() {
+        by_type.entry(doc.get_type()).or_insert(Vec::new()).push(doc);
+    }
+
+    let mut parts = vec![format!("<h1>{}</h1>", escape_html(&crate_info.to_string()))];
+    for (doc_type, docs) in by_type.iter() {
+        let list = docs.iter()
+            .map(|doc| {
+                format!("<li><a href=\"{}\"><code>{}</code></a></li>",
+                        escape_html(&page_url(&doc.to_store_location(), crate_info)),
+                        escape_html(&doc.mod_path.to_string()))
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        parts.push(format!("<h2>{}</h2>\n<ul>\n{}\n</ul>", doc_type, list));
+    }
+
+    format!("<div class=\"oxidoc\">\n{}\n</div>\n", parts.join("\n"))
+}
+
+fn write_page(path: &Path, html: &str) -> Result<()> {
+    store::create_dir_all(path.parent().unwrap())?;
+
+    File::create(path)
+        .and_then(|mut file| file.write_all(html.as_bytes()))
+        .chain_err(|| ErrorKind::WriteFailed(path.to_path_buf()))
+}
@@ -0,0 +1,88 @@
+//! Counts of how many of a crate's items have documentation, like rustc's `missing_docs` lint but
+//! computed from the store.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+use conversion::{DocType, Documentation};
+use document::CrateInfo;
+use store;
+use ::errors::*;
+
+/// How many items of one type there are, and how many of them have doc comments.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TypeStats {
+    pub total: usize,
+    pub documented: usize,
+}
+
+/// The documentation coverage of a crate's public items, by type.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DocStats {
+    pub by_type: BTreeMap<DocType, TypeStats>,
+}
+
+impl DocStats {
+    pub fn total(&self) -> TypeStats {
+        self.by_type.values().fold(TypeStats::default(), |sum, stats| {
+            TypeStats {
+                total: sum.total + stats.total,
+                documented: sum.documented + stats.documented,
+            }
+        })
+    }
+
+    /// The percentage of items with doc comments. A crate without items counts as fully
+    /// documented.
+    pub fn coverage(&self) -> f64 {
+        self.total().coverage()
+    }
+}
+
+impl TypeStats {
+    pub fn coverage(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.documented as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+fn is_documented(doc: &Documentation) -> bool {
+    doc.attrs.doc_strings.iter().any(|doc_string| !doc_string.trim().is_empty())
+}
+
+/// Counts the public items of the given documentation, and how many of them are documented. Items
+/// with restricted visibility, like `pub(crate)`, aren't part of the public API, so they're left
+/// out, but trait items are counted like their trait.
+pub fn doc_stats(docs: &[Documentation]) -> DocStats {
+    let mut stats = DocStats::default();
+    for doc in docs.iter().filter(|doc| !doc.is_private()) {
+        let entry = stats.by_type.entry(doc.get_type()).or_insert_with(TypeStats::default);
+        entry.total += 1;
+        if is_documented(doc) {
+            entry.documented += 1;
+        }
+    }
+    stats
+}
+
+/// Counts the documented items of a crate in the store.
+pub fn crate_stats(crate_info: &CrateInfo) -> Result<DocStats> {
+    let docs = store::iter_docs(crate_info).collect::<Result<Vec<Documentation>>>()?;
+    Ok(doc_stats(&docs))
+}
+
+impl Display for DocStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (doc_type, stats) in self.by_type.iter() {
+            writeln!(f, "{:<24} {:>6}/{:<6} {:>5.1}%",
+                     doc_type.to_string(), stats.documented, stats.total, stats.coverage())?;
+        }
+
+        let total = self.total();
+        write!(f, "{:<24} {:>6}/{:<6} {:>5.1}%",
+               "Total", total.documented, total.total, total.coverage())
+    }
+}
@@ -1,37 +1,260 @@
-use conversion::Documentation;
-use std::collections::{HashMap, HashSet};
+use conversion::{self, Documentation};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::env;
 use std::fmt;
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
+use std::fs::{self, File, OpenOptions};
+use std::hash::Hasher;
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use bincode::{self, Infinite};
 use serde::de::DeserializeOwned;
 use serde::ser::Serialize;
+use serde_json;
 use strsim::levenshtein;
+use zstd;
 
-use conversion::DocType;
+use conversion::{DocLink, DocType};
 use document::CrateInfo;
-use document::ModPath;
+use document::{ModPath, unraw, unraw_path};
+use completion::CompletionIndex;
+use full_text::FullTextIndex;
+use migration;
 use paths;
 use ::errors::*;
 
-fn create_or_open_file<T: AsRef<Path>>(path: T) -> Result<File> {
-    let path_as = path.as_ref();
-    if !path_as.exists() {
-        OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open(path_as)
-            .chain_err(|| format!("Could not create file {}", path_as.display()))
-    } else {
-        OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(path_as)
-            .chain_err(|| format!("Could not open file {}", path_as.display()))
+#[doc(hidden)]
+pub use paths::set_store_root;
+
+/// Bytes at the start of every bincoded file written by oxidoc.
+const FORMAT_MAGIC: &[u8] = b"ODOC";
+
+/// The version of the serialized format, written after the magic bytes. This must be bumped
+/// whenever `Documentation`, `DocInnerData` or `Store` change shape, so stale files are rejected
+/// instead of being deserialized into garbage.
+pub const FORMAT_VERSION: u32 = 43;
+
+/// The length of the magic bytes and the format version, which every version of the format starts
+/// with.
+const FORMAT_VERSION_END: usize = 8;
+
+/// The length of the header, which ends with how the data after it is compressed.
+const FORMAT_HEADER_LEN: usize = 9;
+
+/// The compression byte of the header of uncompressed data.
+const UNCOMPRESSED: u8 = 0;
+
+/// The compression byte of the header of data compressed with zstd.
+const ZSTD_COMPRESSED: u8 = 1;
+
+fn format_header(compression: u8) -> Vec<u8> {
+    let mut header = FORMAT_MAGIC.to_vec();
+    for i in 0..4 {
+        header.push((FORMAT_VERSION >> (i * 8)) as u8);
     }
+    header.push(compression);
+    header
+}
+
+/// The format version in the header of a file's data, or 0 for files from before the header was
+/// introduced.
+fn format_version(data: &[u8]) -> u32 {
+    if data.len() < FORMAT_VERSION_END || &data[..FORMAT_MAGIC.len()] != FORMAT_MAGIC {
+        return 0;
+    }
+
+    data[FORMAT_MAGIC.len()..FORMAT_VERSION_END].iter()
+        .enumerate()
+        .fold(0u32, |acc, (i, b)| acc | ((*b as u32) << (i * 8)))
+}
+
+/// Returns the compression byte of a valid format header and the data following it. The path is
+/// only used for errors.
+fn strip_format_header<'a>(data: &'a [u8], path: &Path) -> Result<(u8, &'a [u8])> {
+    let version = format_version(data);
+    if version != FORMAT_VERSION {
+        bail!(ErrorKind::StoreVersionMismatch(path.to_path_buf(), version, FORMAT_VERSION));
+    }
+    if data.len() < FORMAT_HEADER_LEN {
+        bail!(ErrorKind::DeserializeFailed(path.to_path_buf()));
+    }
+
+    Ok((data[FORMAT_VERSION_END], &data[FORMAT_HEADER_LEN..]))
+}
+
+/// The length of the CRC-32 of the data that ends every bincoded file.
+const CHECKSUM_LEN: usize = 4;
+
+/// CRC-32 (IEEE 802.3), for telling files that were only partly written or were damaged on disk
+/// apart from valid ones before they're deserialized.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = 0u32.wrapping_sub(crc & 1);
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn checksum_trailer(data: &[u8]) -> Vec<u8> {
+    let crc = crc32(data);
+    (0..CHECKSUM_LEN).map(|i| (crc >> (i * 8)) as u8).collect()
+}
+
+/// Returns the data before a checksum trailer matching it. The path is only used for errors.
+fn strip_checksum<'a>(data: &'a [u8], path: &Path) -> Result<&'a [u8]> {
+    let corrupt = || -> Error {
+        Error::from("the checksum doesn't match, so the file is truncated or corrupt")
+            .chain_err(|| ErrorKind::DeserializeFailed(path.to_path_buf()))
+    };
+
+    if data.len() < CHECKSUM_LEN {
+        return Err(corrupt());
+    }
+
+    let (body, trailer) = data.split_at(data.len() - CHECKSUM_LEN);
+    if trailer != checksum_trailer(body).as_slice() {
+        return Err(corrupt());
+    }
+    Ok(body)
+}
+
+const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+
+/// The zstd compression level for written files, taken from `OXIDOC_COMPRESSION_LEVEL`. A level of
+/// 0 disables compression.
+fn compression_level() -> i32 {
+    env::var("OXIDOC_COMPRESSION_LEVEL").ok()
+        .and_then(|level| level.parse::<i32>().ok())
+        .unwrap_or(DEFAULT_COMPRESSION_LEVEL)
+}
+
+/// Compresses the data at the configured level. Returns the compression byte for the header along
+/// with the data.
+fn compress(data: Vec<u8>) -> Result<(u8, Vec<u8>)> {
+    let level = compression_level();
+    if level <= 0 {
+        return Ok((UNCOMPRESSED, data));
+    }
+    let compressed = zstd::stream::encode_all(data.as_slice(), level)
+        .chain_err(|| "Could not compress data")?;
+    Ok((ZSTD_COMPRESSED, compressed))
+}
+
+/// Decompresses data according to the compression byte of its header.
+fn decompress(compression: u8, data: &[u8]) -> Result<Vec<u8>> {
+    match compression {
+        UNCOMPRESSED => Ok(data.to_vec()),
+        ZSTD_COMPRESSED => {
+            zstd::stream::decode_all(data)
+                .chain_err(|| "Could not decompress data")
+        },
+        _ => bail!("unknown compression {} in the header", compression),
+    }
+}
+
+/// Like `fs::create_dir_all`, but doesn't fail if another thread creates the directory at the
+/// same time.
+pub fn create_dir_all<P: AsRef<Path>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    match fs::create_dir_all(path) {
+        Ok(()) => Ok(()),
+        Err(_) if path.is_dir() => Ok(()),
+        Err(e) => Err(e).chain_err(|| ErrorKind::CreateDirectoryFailed(path.to_path_buf())),
+    }
+}
+
+/// Used with the process ID to give each temporary file a unique name.
+static TEMP_FILE_COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// A name for a temporary file next to the path that no other thread or process uses, since the
+/// process ID is unique among running processes and the counter within the process.
+fn temp_file_path(path: &Path) -> PathBuf {
+    let count = TEMP_FILE_COUNTER.fetch_add(1, Ordering::SeqCst);
+    let filename = path.file_name().map_or("".into(), |f| f.to_string_lossy().into_owned());
+
+    path.with_file_name(format!(".{}.{}-{}.tmp", filename, process::id(), count))
+}
+
+/// How long the store's lock file can go unchanged before it's taken to be left behind by an
+/// oxidoc process that was killed while holding it.
+const STALE_LOCK_SECS: u64 = 600;
+
+/// How long to wait before trying to take the store's lock again.
+const LOCK_RETRY_MILLIS: u64 = 50;
+
+/// An advisory lock on the files shared by every crate in the store, like the store index and the
+/// search indices. It is held while they're loaded, changed and written back, so oxidoc processes
+/// generating documentation at the same time don't lose each other's crates. The lock is a file in
+/// the store that only one process can create, and it's removed again when this is dropped.
+pub struct StoreLock {
+    path: PathBuf,
+}
+
+impl StoreLock {
+    /// Takes the lock, waiting for any other process holding it to release it.
+    pub fn acquire() -> Result<StoreLock> {
+        let path = paths::store_lock_path()?;
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let _ = write!(file, "{}", process::id());
+                    return Ok(StoreLock { path: path });
+                },
+                Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    if is_stale_lock(&path) {
+                        warn!("Removing the lock {} left behind by another process",
+                              path.display());
+                        let _ = fs::remove_file(&path);
+                    } else {
+                        thread::sleep(Duration::from_millis(LOCK_RETRY_MILLIS));
+                    }
+                },
+                Err(e) => return Err(e).chain_err(|| ErrorKind::WriteFailed(path.clone())),
+            }
+        }
+    }
+}
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn is_stale_lock(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map_or(false, |age| age.as_secs() > STALE_LOCK_SECS)
+}
+
+/// Writes a file by writing a temporary file next to it and renaming it into place, so readers
+/// never see partially written data, even if several processes write the same file at once.
+fn write_file_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let temp_path = temp_file_path(path);
+
+    let result = File::create(&temp_path)
+        .and_then(|mut file| file.write_all(bytes).and_then(|_| file.sync_all()))
+        .and_then(|_| fs::rename(&temp_path, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result.chain_err(|| ErrorKind::WriteFailed(path.to_path_buf()))
 }
 
 pub fn deserialize_object<S, T>(path: T) -> Result<S>
@@ -41,12 +264,16 @@ pub fn deserialize_object<S, T>(path: T) -> Result<S>
     let path_as = path.as_ref();
     let mut data: Vec<u8> = Vec::new();
     let mut bincoded_file = File::open(&path_as)
-        .chain_err(|| format!("Could not open file {}", path_as.display()))?;
+        .chain_err(|| ErrorKind::ReadFailed(path_as.to_path_buf()))?;
 
     bincoded_file.read_to_end(&mut data)
-        .chain_err(|| format!("Failed to read file {}", path_as.display()))?;
-    let result = bincode::deserialize(data.as_slice())
-        .chain_err(|| format!("Could not deserialize file at {}", path_as.display()))?;
+        .chain_err(|| ErrorKind::ReadFailed(path_as.to_path_buf()))?;
+    let (compression, body) = strip_format_header(data.as_slice(), path_as)?;
+    let body = strip_checksum(body, path_as)?;
+    let body = decompress(compression, body)
+        .chain_err(|| ErrorKind::DeserializeFailed(path_as.to_path_buf()))?;
+    let result = bincode::deserialize(body.as_slice())
+        .chain_err(|| ErrorKind::DeserializeFailed(path_as.to_path_buf()))?;
 
     Ok(result)
 }
@@ -58,12 +285,663 @@ pub fn serialize_object<S, T>(data: &S, path: T) -> Result<()>
     let path_as = path.as_ref();
 
     let data = bincode::serialize(data, Infinite)
-        .chain_err(|| format!("Could not serialize data for {}", path_as.display()))?;
+        .chain_err(|| ErrorKind::SerializeFailed(path_as.to_path_buf()))?;
+    let (compression, data) = compress(data)
+        .chain_err(|| ErrorKind::SerializeFailed(path_as.to_path_buf()))?;
+    let mut bytes = format_header(compression);
+    let trailer = checksum_trailer(&data);
+    bytes.extend(data);
+    bytes.extend(trailer);
+
+    write_file_atomically(path_as, bytes.as_slice())
+}
+
+pub fn deserialize_object_json<S, T>(path: T) -> Result<S>
+    where S: DeserializeOwned,
+          T: AsRef<Path>
+{
+    let path_as = path.as_ref();
+    let json_file = File::open(&path_as)
+        .chain_err(|| ErrorKind::ReadFailed(path_as.to_path_buf()))?;
+
+    let result = serde_json::from_reader(json_file)
+        .chain_err(|| ErrorKind::DeserializeFailed(path_as.to_path_buf()))?;
+
+    Ok(result)
+}
+
+pub fn serialize_object_json<S, T>(data: &S, path: T) -> Result<()>
+    where S: Serialize,
+          T: AsRef<Path>
+{
+    let path_as = path.as_ref();
+
+    let data = serde_json::to_vec_pretty(data)
+        .chain_err(|| ErrorKind::SerializeFailed(path_as.to_path_buf()))?;
+
+    write_file_atomically(path_as, data.as_slice())
+}
+
+/// An index of what the stored crates document, like the full-text and completion indices, saved
+/// to its own file next to the name search index. The entries of each crate version are replaced
+/// as a whole when it's regenerated.
+pub trait CrateIndex: Serialize + DeserializeOwned + Sized {
+    /// An index without any entries.
+    fn new() -> Self;
+
+    /// The path of the index's file.
+    fn index_path() -> Result<PathBuf>;
+
+    /// Adds the entries of a crate's documents, without removing any it already has.
+    fn insert_docs(&mut self, crate_info: &CrateInfo, documents: &[Documentation]);
+
+    /// Moves the entries of `other` into this index, without removing any it already has.
+    fn append(&mut self, other: Self);
+
+    /// Removes the entries of the given crate versions.
+    fn remove_crates(&mut self, crates: &[CrateInfo]);
+
+    /// Removes a crate version's entries for the items whose paths match `is_removed`.
+    fn remove_docs<F>(&mut self, crate_info: &CrateInfo, is_removed: F)
+        where F: Fn(&ModPath) -> bool;
+
+    /// Loads the index written by the last documentation generation.
+    fn load() -> Result<Self> {
+        deserialize_object(Self::index_path()?)
+    }
+
+    /// Loads the index, or starts an empty one if none was written yet.
+    fn load_or_new() -> Self {
+        Self::load().unwrap_or_else(|_| Self::new())
+    }
+
+    fn save(&self) -> Result<()> {
+        serialize_object(self, Self::index_path()?)
+    }
+
+    /// Adds the entries of a crate's documents, replacing those of any earlier generation of the
+    /// same crate version.
+    fn add_docs(&mut self, crate_info: &CrateInfo, documents: &[Documentation]) {
+        self.remove_crate(crate_info);
+        self.insert_docs(crate_info, documents);
+    }
+
+    /// Removes a crate version's entries.
+    fn remove_crate(&mut self, crate_info: &CrateInfo) {
+        self.remove_crates(&[crate_info.clone()]);
+    }
+
+    /// Replaces the entries of the given crates with those in `other`, an index of the same crates
+    /// made since this one was loaded.
+    fn merge(&mut self, other: Self, crates: &[CrateInfo]) {
+        self.remove_crates(crates);
+        self.append(other);
+    }
+}
+
+/// The name of the file in each crate's documentation directory describing the crate and how
+/// its documentation was generated.
+const CRATE_META_FILENAME: &str = "meta.json";
+
+/// Information about a crate's generated documentation.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct CrateMeta {
+    pub crate_info: CrateInfo,
+
+    /// The output of `rustc --version` at generation time, if rustc could be run.
+    pub rustc_version: Option<String>,
+
+    /// When the documentation was generated, in seconds since the Unix epoch.
+    pub generated_at: u64,
+
+    /// How the documentation files are laid out. Metadata written before the layout could be
+    /// chosen has none, since those crates were always prefixed.
+    #[serde(default)]
+    pub layout: StoreLayout,
+}
+
+impl CrateMeta {
+    pub fn new(crate_info: CrateInfo) -> Self {
+        let generated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        CrateMeta {
+            crate_info: crate_info,
+            rustc_version: rustc_version(),
+            generated_at: generated_at,
+            layout: StoreLayout::from_env(),
+        }
+    }
+}
+
+fn rustc_version() -> Option<String> {
+    let rustc = env::var("RUSTC").unwrap_or("rustc".to_string());
+    let output = match Command::new(rustc).arg("--version").output() {
+        Ok(output) => output,
+        Err(_) => return None,
+    };
+
+    if output.status.success() {
+        String::from_utf8(output.stdout).ok().map(|v| v.trim().to_string())
+    } else {
+        None
+    }
+}
+
+/// Writes the metadata file for a crate's documentation, which also marks the crate as present
+/// in the store.
+pub fn save_crate_metadata(crate_info: &CrateInfo) -> Result<()> {
+    let crate_doc_path = paths::crate_doc_path(crate_info)?;
+    create_dir_all(&crate_doc_path)?;
+
+    let meta = CrateMeta::new(crate_info.clone());
+    serialize_object_json(&meta, crate_doc_path.join(CRATE_META_FILENAME))?;
+    forget_crate_list();
+    Ok(())
+}
+
+/// Fails if the crate's documentation in the store was generated with another layout than the one
+/// chosen now. Its files would be written next to the old ones, which pruning would then delete as
+/// stale, so the crate has to be removed before it's regenerated in the new layout.
+pub fn check_crate_layout(crate_info: &CrateInfo) -> Result<()> {
+    if let Ok(meta) = crate_metadata(crate_info) {
+        let layout = StoreLayout::from_env();
+        if meta.layout != layout {
+            bail!(ErrorKind::StoreLayoutMismatch(crate_info.to_string(),
+                                                 meta.layout.name().to_string(),
+                                                 layout.name().to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Reads the metadata of a crate's documentation.
+pub fn crate_metadata(crate_info: &CrateInfo) -> Result<CrateMeta> {
+    let crate_doc_path = paths::crate_doc_path(crate_info)?;
+    deserialize_object_json(crate_doc_path.join(CRATE_META_FILENAME))
+}
+
+/// Lists the crates that have documentation in the store, sorted by name and version. Directories
+/// without readable metadata are skipped.
+pub fn list_crates() -> Result<Vec<CrateInfo>> {
+    let registry_path = paths::doc_registry_path()?;
+    if !registry_path.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&registry_path)
+        .chain_err(|| ErrorKind::ReadFailed(registry_path.clone()))?;
+
+    let mut crates = Vec::new();
+    for entry in entries {
+        let path = match entry {
+            Ok(entry) => entry.path().join(CRATE_META_FILENAME),
+            Err(_) => continue,
+        };
+        if !path.is_file() {
+            continue;
+        }
+
+        match deserialize_object_json::<CrateMeta, _>(&path) {
+            Ok(meta) => crates.push(meta.crate_info),
+            Err(e) => warn!("Skipping {}: {}", path.display(), e),
+        }
+    }
+
+    crates.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+    Ok(crates)
+}
+
+lazy_static! {
+    /// The crates in the store, with the store's directory they were listed from. Every link to
+    /// another crate looks up the crate's latest version, so the crates are only listed again when
+    /// one is added or removed, or the store moves.
+    static ref CRATE_LIST: Mutex<Option<(PathBuf, Vec<CrateInfo>)>> = Mutex::new(None);
+}
+
+/// Makes the next lookup of the latest version of a crate list the crates again.
+fn forget_crate_list() {
+    *CRATE_LIST.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+}
+
+/// The latest version of the crate with the given name in the store, if there is one.
+pub fn latest_crate_version(name: &str) -> Option<CrateInfo> {
+    let registry_path = match paths::doc_registry_path() {
+        Ok(path) => path,
+        Err(_)   => return None,
+    };
+
+    let mut cached = CRATE_LIST.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let stale = match *cached {
+        Some((ref path, _)) => *path != registry_path,
+        None                => true,
+    };
+    if stale {
+        match list_crates() {
+            Ok(crates) => *cached = Some((registry_path, crates)),
+            Err(_)     => return None,
+        }
+    }
+
+    let crates = match *cached {
+        Some((_, ref crates)) => crates,
+        None                  => return None,
+    };
+    crates.iter()
+        .filter(|info| info.name == name)
+        .filter_map(|info| parse_version(&info.version).map(|version| (version, info)))
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, info)| info.clone())
+}
+
+/// Lazily loads the documentation of a crate from the store, one file at a time, so memory use
+/// doesn't grow with the size of the crate. Files that can't be loaded are returned as errors
+/// without ending the iteration.
+pub fn iter_docs(crate_info: &CrateInfo) -> DocIter {
+    let (pending, failure) = match paths::crate_doc_path(crate_info) {
+        Ok(ref path) if path.is_dir() => (vec![path.clone()], None),
+        Ok(_) => (Vec::new(), Some(ErrorKind::CrateNotFound(crate_info.to_string()).into())),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+
+    DocIter {
+        pending: pending,
+        failure: failure,
+    }
+}
+
+/// Like `iter_docs`, but loads the documentation files under any directory, like a copy of a
+/// crate's documentation taken before regenerating it.
+pub fn iter_docs_in_dir(dir: &Path) -> DocIter {
+    let (pending, failure) = if dir.is_dir() {
+        (vec![dir.to_path_buf()], None)
+    } else {
+        (Vec::new(), Some(ErrorKind::NoSuchDirectory(dir.display().to_string()).into()))
+    };
+
+    DocIter {
+        pending: pending,
+        failure: failure,
+    }
+}
+
+/// An iterator over the documentation files of a crate. See `iter_docs`.
+pub struct DocIter {
+    /// The files and directories left to visit, the next one last.
+    pending: Vec<PathBuf>,
+    /// An error finding the crate's documentation, returned as the only item.
+    failure: Option<Error>,
+}
+
+impl Iterator for DocIter {
+    type Item = Result<Documentation>;
+
+    fn next(&mut self) -> Option<Result<Documentation>> {
+        if let Some(e) = self.failure.take() {
+            return Some(Err(e));
+        }
+
+        while let Some(path) = self.pending.pop() {
+            if path.is_dir() {
+                match read_dir_sorted(&path) {
+                    Ok(entries) => self.pending.extend(entries.into_iter().rev()),
+                    Err(e) => return Some(Err(e)),
+                }
+            } else if path.extension().map_or(false, |ext| ext == "odoc") {
+                return Some(deserialize_object(&path));
+            }
+        }
+        None
+    }
+}
+
+/// Calls the visitor with every documented item of a crate in the store, in a stable order.
+/// Stops at the first file that can't be loaded and returns its error.
+pub fn visit_all(crate_info: &CrateInfo, visitor: &mut FnMut(&Documentation)) -> Result<()> {
+    for doc in iter_docs(crate_info) {
+        visitor(&doc?);
+    }
+    Ok(())
+}
+
+/// Like `visit_all`, but skips the files that can't be loaded and returns their errors once every
+/// other item has been visited.
+pub fn visit_all_collecting_errors(crate_info: &CrateInfo,
+                                   visitor: &mut FnMut(&Documentation)) -> Vec<Error> {
+    let mut errors = Vec::new();
+    for doc in iter_docs(crate_info) {
+        match doc {
+            Ok(doc) => visitor(&doc),
+            Err(e)  => errors.push(e),
+        }
+    }
+    errors
+}
+
+/// The paths of the entries in a directory, sorted so iteration order doesn't depend on the
+/// file system.
+fn read_dir_sorted(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir)
+        .chain_err(|| ErrorKind::ReadFailed(dir.to_path_buf()))?;
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let entry = entry.chain_err(|| ErrorKind::ReadFailed(dir.to_path_buf()))?;
+        paths.push(entry.path());
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Deletes the `.odoc` files under `dir` that aren't in `keep`, along with their JSON copies, and
+/// then any directories left empty. This removes the documentation of items deleted from the
+/// source since it was last generated. Returns the number of files deleted.
+pub fn prune_stale_docs(dir: &Path, keep: &HashSet<PathBuf>) -> Result<usize> {
+    let mut pruned = 0;
+
+    for path in read_dir_sorted(dir)? {
+        if path.is_dir() {
+            pruned += prune_stale_docs(&path, keep)?;
+            if read_dir_sorted(&path)?.is_empty() {
+                fs::remove_dir(&path)
+                    .chain_err(|| ErrorKind::RemoveDirectoryFailed(path.clone()))?;
+            }
+        } else if path.extension().map_or(false, |ext| ext == "odoc") && !keep.contains(&path) {
+            debug!("Pruning stale documentation {}", path.display());
+            fs::remove_file(&path)
+                .chain_err(|| ErrorKind::RemoveFileFailed(path.clone()))?;
+
+            let json_path = path.with_extension("odoc.json");
+            if json_path.is_file() {
+                fs::remove_file(&json_path)
+                    .chain_err(|| ErrorKind::RemoveFileFailed(json_path.clone()))?;
+            }
+            pruned += 1;
+        }
+    }
 
-    let mut bincoded_file = create_or_open_file(path_as)?;
-    bincoded_file.write(data.as_slice())
-        .chain_err(|| format!("Failed to write file {}", path_as.display()))?;
+    Ok(pruned)
+}
+
+/// A documentation file that couldn't be read back, found by `verify`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CorruptFile {
+    pub path: PathBuf,
+
+    /// Why the file couldn't be read, like a checksum mismatch.
+    pub reason: String,
+}
+
+impl CorruptFile {
+    fn new(path: PathBuf, error: &Error) -> CorruptFile {
+        let reason = error.iter().last().map_or(error.to_string(), |cause| cause.to_string());
+        CorruptFile {
+            path: path,
+            reason: reason,
+        }
+    }
+}
+
+impl fmt::Display for CorruptFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.reason)
+    }
+}
+
+/// Reads back every documentation file of a crate, returning the ones that are truncated, corrupt
+/// or were written by another version of oxidoc.
+pub fn verify(crate_info: &CrateInfo) -> Result<Vec<CorruptFile>> {
+    let crate_doc_path = paths::crate_doc_path(crate_info)?;
+    if !crate_doc_path.is_dir() {
+        bail!(ErrorKind::CrateNotFound(crate_info.to_string()));
+    }
+
+    let mut corrupt = Vec::new();
+    let mut pending = vec![crate_doc_path];
+    while let Some(path) = pending.pop() {
+        if path.is_dir() {
+            pending.extend(read_dir_sorted(&path)?.into_iter().rev());
+        } else if path.extension().map_or(false, |ext| ext == "odoc") {
+            if let Err(e) = deserialize_object::<Documentation, _>(&path) {
+                corrupt.push(CorruptFile::new(path, &e));
+            }
+        }
+    }
+    Ok(corrupt)
+}
+
+/// Reads all the documentation files in a directory, like `iter_docs_in_dir`, but collects the
+/// files that can't be read instead of stopping at the first one.
+pub fn read_docs_in_dir(dir: &Path) -> Result<(Vec<Documentation>, Vec<CorruptFile>)> {
+    if !dir.is_dir() {
+        bail!(ErrorKind::NoSuchDirectory(dir.display().to_string()));
+    }
+
+    let mut docs = Vec::new();
+    let mut unreadable = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(path) = pending.pop() {
+        if path.is_dir() {
+            pending.extend(read_dir_sorted(&path)?.into_iter().rev());
+        } else if path.extension().map_or(false, |ext| ext == "odoc") {
+            match deserialize_object::<Documentation, _>(&path) {
+                Ok(doc) => docs.push(doc),
+                Err(e)  => unreadable.push(CorruptFile::new(path, &e)),
+            }
+        }
+    }
+    Ok((docs, unreadable))
+}
+
+/// Deletes a crate's documentation from the store, including its entry in the store index.
+pub fn remove_crate(crate_info: &CrateInfo) -> Result<()> {
+    let crate_doc_path = paths::crate_doc_path(crate_info)?;
+    if !crate_doc_path.is_dir() {
+        bail!(ErrorKind::CrateNotFound(crate_info.to_string()));
+    }
+
+    fs::remove_dir_all(&crate_doc_path)
+        .chain_err(|| ErrorKind::RemoveDirectoryFailed(crate_doc_path.clone()))?;
+    forget_crate_list();
+
+    let _lock = StoreLock::acquire()?;
+    let mut store = Store::load();
+    store.remove_docset(crate_info);
+    store.save()?;
+    SearchIndex::new(&store).save()?;
+
+    remove_from_index::<FullTextIndex>(crate_info)?;
+    remove_from_index::<CompletionIndex>(crate_info)
+}
+
+fn remove_from_index<I: CrateIndex>(crate_info: &CrateInfo) -> Result<()> {
+    let mut index = I::load_or_new();
+    index.remove_crate(crate_info);
+    index.save()
+}
+
+/// The documentation files left as they were by `migrate`.
+#[derive(Clone, Debug)]
+pub struct MigrationReport {
+    /// The number of files rewritten in the current format.
+    pub migrated: usize,
+
+    /// The number of files that were already in the current format.
+    pub current: usize,
+
+    /// The files that couldn't be migrated, with the reason.
+    pub failed: Vec<CorruptFile>,
+
+    /// The crates with files that couldn't be migrated. They're left out of the store and the
+    /// search indices until they're regenerated.
+    pub unindexed: Vec<CrateInfo>,
+
+    /// The directory the original files were copied to.
+    pub backup_dir: PathBuf,
+}
+
+/// Rewrites the documentation in the store written with the `from` version of the format in the
+/// `to` version, which has to be the current one, so it doesn't have to be regenerated. See the
+/// `migration` module for how each version is migrated.
+///
+/// Documents are migrated from the JSON copies written next to them when they're generated with
+/// `GenerateOptions::json_copies`, since bincoded files can't be read with another shape. The
+/// originals are copied to a `backup-v<from>` directory in the store first, and the store and
+/// search indices are rebuilt from the documentation afterwards. Files written with other versions
+/// or without a JSON copy are left alone and listed in the report, and their crates are left out
+/// of the indices until they're regenerated.
+pub fn migrate(from: u32, to: u32) -> Result<MigrationReport> {
+    if to != FORMAT_VERSION {
+        bail!(ErrorKind::NoMigration(from, to));
+    }
+    migration::migrations(from, to)?;
+
+    let _lock = StoreLock::acquire()?;
+    let registry_path = paths::doc_registry_path()?;
+    let backup_dir = registry_path.join(format!("backup-v{}", from));
+
+    let mut report = MigrationReport {
+        migrated: 0,
+        current: 0,
+        failed: Vec::new(),
+        unindexed: Vec::new(),
+        backup_dir: backup_dir.clone(),
+    };
+
+    let indices = [
+        paths::store_file_path()?,
+        paths::search_index_path()?,
+        paths::full_text_index_path()?,
+        paths::completion_index_path()?,
+    ];
+    for path in indices.iter() {
+        if path.is_file() {
+            back_up_file(path, &registry_path, &backup_dir)?;
+        }
+    }
+
+    let mut store = Store::new();
+    let mut full_text = FullTextIndex::new();
+    let mut completions = CompletionIndex::new();
+
+    for crate_info in list_crates()? {
+        let failed_before = report.failed.len();
+        let mut documents = Vec::new();
+        let mut migrated_paths = Vec::new();
 
+        let mut pending = vec![paths::crate_doc_path(&crate_info)?];
+        while let Some(path) = pending.pop() {
+            if path.is_dir() {
+                pending.extend(read_dir_sorted(&path)?.into_iter().rev());
+            } else if path.extension().map_or(false, |ext| ext == "odoc") {
+                match migrate_file(&path, from, to) {
+                    Ok((doc, true)) => {
+                        back_up_file(&path, &registry_path, &backup_dir)?;
+                        documents.push(doc);
+                        migrated_paths.push(Some(path));
+                    },
+                    Ok((doc, false)) => {
+                        documents.push(doc);
+                        migrated_paths.push(None);
+                        report.current += 1;
+                    },
+                    Err(e) => report.failed.push(CorruptFile::new(path, &e)),
+                }
+            }
+        }
+
+        conversion::move_exported_macros(&mut documents);
+        conversion::link_methods(&mut documents);
+
+        for (doc, path) in documents.iter().zip(migrated_paths.iter()) {
+            if let Some(ref path) = *path {
+                // Migrations can change where a document is saved, like that of an exported
+                // macro.
+                let new_path = doc.to_store_location().to_filepath()?;
+                if new_path != *path {
+                    fs::remove_file(path)
+                        .chain_err(|| ErrorKind::RemoveFileFailed(path.clone()))?;
+                    let json_path = path.with_extension("odoc.json");
+                    fs::remove_file(&json_path)
+                        .chain_err(|| ErrorKind::RemoveFileFailed(json_path.clone()))?;
+                    create_dir_all(new_path.parent().unwrap())?;
+                }
+                serialize_object(doc, &new_path)?;
+                serialize_object_json(doc, new_path.with_extension("odoc.json"))?;
+                report.migrated += 1;
+            }
+        }
+
+        // The entries of a crate with files left in the old format would point to files that
+        // can't be read.
+        if report.failed.len() > failed_before {
+            report.unindexed.push(crate_info);
+            continue;
+        }
+
+        let mut docset = Docset::new();
+        docset.add_docs(&documents);
+        full_text.add_docs(&crate_info, &documents);
+        completions.add_docs(&crate_info, &documents);
+        store.add_docset(crate_info, docset);
+    }
+
+    store.save()?;
+    SearchIndex::new(&store).save()?;
+    full_text.save()?;
+    completions.save()?;
+
+    Ok(report)
+}
+
+/// Reads a documentation file written with the `from` version of the format from its JSON copy
+/// and migrates it, or reads it as it is if it's already in the `to` version. Returns whether it
+/// was migrated along with the document.
+fn migrate_file(path: &Path, from: u32, to: u32) -> Result<(Documentation, bool)> {
+    let mut data = Vec::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut data))
+        .chain_err(|| ErrorKind::ReadFailed(path.to_path_buf()))?;
+
+    let version = format_version(&data);
+    if version == to {
+        return deserialize_object(path).map(|doc| (doc, false));
+    }
+    if version != from {
+        bail!(ErrorKind::StoreVersionMismatch(path.to_path_buf(), version, from));
+    }
+
+    let json_path = path.with_extension("odoc.json");
+    if !json_path.is_file() {
+        bail!("there is no JSON copy to migrate from");
+    }
+
+    let mut document: serde_json::Value = deserialize_object_json(&json_path)?;
+    migration::migrate_document(&mut document, from, to)?;
+    let doc = serde_json::from_value(document)
+        .chain_err(|| ErrorKind::DeserializeFailed(json_path.clone()))?;
+    Ok((doc, true))
+}
+
+/// Copies a file in the store to the same place under the backup directory, along with its JSON
+/// copy if it has one.
+fn back_up_file(path: &Path, registry_path: &Path, backup_dir: &Path) -> Result<()> {
+    let relative = path.strip_prefix(registry_path)
+        .chain_err(|| ErrorKind::InvalidDocPath(path.to_path_buf()))?;
+    let backup_path = backup_dir.join(relative);
+    create_dir_all(backup_path.parent().unwrap())?;
+
+    fs::copy(path, &backup_path)
+        .chain_err(|| ErrorKind::WriteFailed(backup_path.clone()))?;
+
+    let json_path = path.with_extension("odoc.json");
+    if json_path.is_file() {
+        let json_backup_path = backup_path.with_extension("odoc.json");
+        fs::copy(&json_path, &json_backup_path)
+            .chain_err(|| ErrorKind::WriteFailed(json_backup_path))?;
+    }
     Ok(())
 }
 
@@ -71,14 +949,15 @@ type CrateVersion = String;
 type CrateName = String;
 
 /// Mapping of version strings for a crate to the documentation for that crate version.
-type CrateVersions = HashMap<CrateVersion, Docset>;
+type CrateVersions = BTreeMap<CrateVersion, Docset>;
 
-/// Top-level storage of all crates and their documents, organized by version.
-type DocumentCorpus = HashMap<CrateName, CrateVersions>;
+/// Top-level storage of all crates and their documents, organized by version. The maps of the store
+/// are ordered so saving the same documentation always writes the same bytes.
+type DocumentCorpus = BTreeMap<CrateName, CrateVersions>;
 
 /// Mapping from module path keywords to the full module paths that use those keywords. Used for
 /// quick lookup of documentation based on keywords.
-type ModuleExpansions = HashMap<String, HashSet<String>>;
+type ModuleExpansions = BTreeMap<String, BTreeSet<String>>;
 
 /// The central point for retrieving documentation. Stores a map of crate names to their versions,
 /// which map to their individual documentation stores. Also contains a keyword prefix map for
@@ -91,13 +970,18 @@ pub struct Store {
     /// A map from individual module path segments to fully resolved module paths that use them.
     /// "vec" => ["std::vec::Vec", ...]
     module_expansions: ModuleExpansions,
+
+    /// A map from the aliases of items to their fully resolved module paths.
+    /// "malloc" => ["alloc::allocate", ...]
+    aliases: ModuleExpansions,
 }
 
 impl Store {
     pub fn new() -> Self {
         Store {
-            items: HashMap::new(),
-            module_expansions: HashMap::new(),
+            items: BTreeMap::new(),
+            module_expansions: BTreeMap::new(),
+            aliases: BTreeMap::new(),
         }
     }
 
@@ -119,25 +1003,80 @@ impl Store {
     }
 
     /// Add documentation for a specific version of a crate.
-    pub fn add_docset(&mut self, crate_info: CrateInfo, docset: Docset) {
+    pub fn add_docset(&mut self, crate_info: CrateInfo, mut docset: Docset) {
         // TODO: Any way to remove old module expansions if docset is regenerated?
-        for doc in docset.documents.values() {
-            self.add_module_expansions(doc);
+        for doc in docset.documents.values().flat_map(|locations| locations.iter()) {
+            self.add_module_expansions(&doc.mod_path);
+            for alias in doc.aliases.iter() {
+                self.aliases.entry(alias.to_lowercase())
+                    .or_insert(BTreeSet::new())
+                    .insert(doc.mod_path.to_string().to_lowercase());
+            }
+        }
+        // Pending re-exports are searchable by their path once they're resolved.
+        for path in docset.reexports.keys().chain(docset.pending_reexports.keys()) {
+            let full_path = ModPath::from(format!("{}::{}", crate_info.name, path));
+            self.add_module_expansions(&full_path);
+        }
+
+        // The re-exports of the new crate's items by the crates in the store, and the re-exports
+        // of the items of the crates in the store by the new crate.
+        let mut expanded = Vec::new();
+        for (name, versions) in self.items.iter_mut() {
+            for (version, other) in versions.iter_mut() {
+                let other_info = CrateInfo {
+                    name: name.clone(),
+                    version: version.clone(),
+                    lib_path: None,
+                };
+                for path in other.resolve_pending_reexports(&other_info, &crate_info, &docset) {
+                    expanded.push(ModPath::from(format!("{}::{}", name, path)));
+                }
+                let added = docset.resolve_pending_reexports(&crate_info, &other_info, other);
+                for path in added {
+                    expanded.push(ModPath::from(format!("{}::{}", crate_info.name, path)));
+                }
+            }
+        }
+        for path in expanded.iter() {
+            self.add_module_expansions(path);
         }
 
-        let mut entry = self.items.entry(crate_info.name).or_insert(HashMap::new());
+        let mut entry = self.items.entry(crate_info.name).or_insert(BTreeMap::new());
         entry.insert(crate_info.version, docset);
     }
 
-    /// Adds the keywords for module paths in the provided document to the prefix map used for
-    /// document loookup.
-    fn add_module_expansions(&mut self, doc: &StoreLocation) {
-        for segment in doc.mod_path.0.iter() {
-            let mod_path = doc.mod_path.to_string().to_lowercase();
+    /// Whether there is documentation for a specific version of a crate.
+    pub fn has_docset(&self, crate_info: &CrateInfo) -> bool {
+        self.items.get(&crate_info.name)
+            .map_or(false, |versions| versions.contains_key(&crate_info.version))
+    }
+
+    /// Removes the documentation for a specific version of a crate.
+    pub fn remove_docset(&mut self, crate_info: &CrateInfo) {
+        // TODO: The module expansions of the removed docset are left behind.
+        let now_empty = match self.items.get_mut(&crate_info.name) {
+            Some(versions) => {
+                versions.remove(&crate_info.version);
+                versions.is_empty()
+            }
+            None => false,
+        };
+
+        if now_empty {
+            self.items.remove(&crate_info.name);
+        }
+    }
+
+    /// Adds the keywords for the provided module path to the prefix map used for document
+    /// loookup.
+    fn add_module_expansions(&mut self, path: &ModPath) {
+        for segment in path.0.iter() {
+            let mod_path = path.to_string().to_lowercase();
 
             let entry = self.module_expansions
-                .entry(segment.identifier.to_lowercase())
-                .or_insert(HashSet::new());
+                .entry(unraw(&segment.identifier).to_lowercase())
+                .or_insert(BTreeSet::new());
 
             entry.insert(mod_path);
         }
@@ -147,23 +1086,28 @@ impl Store {
         let mut results = Vec::new();
         for krate in self.items.values() {
             for version in krate.values() {
-                results.extend(version.documents.values().cloned().collect::<Vec<StoreLocation>>());
+                results.extend(version.documents.values().flat_map(|locations| locations.iter().cloned()));
             }
         }
         results
     }
 
     /// Search the documentation store for a keyword and return the documents with a match inside
-    /// their module paths.
+    /// their module paths, or with the keyword as an alias.
     pub fn lookup_name(&self, query: &str) -> Vec<&StoreLocation> {
         let mut results = Vec::new();
 
-        let matches = get_all_matching_paths(query.to_string(), &self.module_expansions);
+        let mut matches = get_all_matching_paths(query.to_string(), &self.module_expansions);
+        if let Some(paths) = self.aliases.get(&query.to_lowercase()) {
+            for path in paths.iter() {
+                if !matches.contains(path) {
+                    matches.push(path.clone());
+                }
+            }
+        }
 
         for mat in matches {
-            if let Some(loc) = self.retrieve_match(mat) {
-                results.push(loc);
-            }
+            results.extend(self.retrieve_match(mat));
         }
 
         results.sort_by_key(|loc| levenshtein(query, &loc.mod_path.to_string()));
@@ -171,8 +1115,9 @@ impl Store {
         results
     }
 
-    /// Searches the documentation store for the given fully resolved module path string.
-    fn retrieve_match(&self, mat: String) -> Option<&StoreLocation> {
+    /// Searches the documentation store for the given fully resolved module path string. Several
+    /// items can share a path, like a struct and a module with the same name.
+    fn retrieve_match(&self, mat: String) -> Vec<&StoreLocation> {
         let krate_name = mat.split("::").next().unwrap().to_string();
 
         let path_in_krate = ModPath::from(mat.clone());
@@ -180,41 +1125,100 @@ impl Store {
     }
 
     /// Retrieves the latest documentation for a crate matching the given module path
-    fn latest_doc_with_match(&self, krate_name: &str, path_in_krate: ModPath) -> Option<&StoreLocation> {
+    fn latest_doc_with_match(&self, krate_name: &str, path_in_krate: ModPath) -> Vec<&StoreLocation> {
         // FIXME: Doesn't handle items that exist in old versions and removed in the latest version
         if let Some(krate_versions) = self.items.get(krate_name) {
             if let Some(version) = latest_version(krate_versions) {
                 krate_versions.get(version).and_then(|docset| {
                     let path = path_in_krate.tail().to_string();
-                    docset.documents.get(&path)
-                })
+                    docset.documents.get(&path).or_else(|| docset.reexports.get(&path))
+                }).map_or(Vec::new(), |locations| locations.iter().collect())
             } else {
-                None
+                Vec::new()
             }
         } else {
-            None
+            Vec::new()
         }
     }
 }
 
-fn latest_version(versions: &CrateVersions) -> Option<&CrateVersion> {
-    let mut max = None;
-    let mut res = None;
-    for version in versions.keys() {
-        let hash = version_number_hash(version);
-        if max.map_or(true, |m| hash > m) {
-            res = Some(version);
-            max = Some(hash);
+/// A flat list of every documented item in the store, saved to a single file so searching doesn't
+/// have to load the whole store and walk its maps.
+#[derive(Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub locations: Vec<StoreLocation>,
+}
+
+impl SearchIndex {
+    pub fn new(store: &Store) -> Self {
+        SearchIndex {
+            locations: store.all_locations(),
         }
     }
-    res
+
+    pub fn save(&self) -> Result<()> {
+        let index_file = paths::search_index_path()?;
+        serialize_object(self, index_file)
+    }
+}
+
+/// Rebuilds the search index from the store on disk.
+pub fn build_search_index() -> Result<()> {
+    let _lock = StoreLock::acquire()?;
+    let store = Store::load_from_disk()?;
+    SearchIndex::new(&store).save()
+}
+
+/// Adds a crate's documentation to the store index on disk and rebuilds the search index, holding
+/// the store's lock so the crates added by other processes at the same time are kept. Returns the
+/// updated store.
+pub fn save_docset(crate_info: CrateInfo, docset: Docset) -> Result<Store> {
+    let _lock = StoreLock::acquire()?;
+    let mut store = Store::load();
+    store.add_docset(crate_info, docset);
+    store.save()?;
+    SearchIndex::new(&store).save()?;
+    Ok(store)
+}
+
+/// Adds the documents generated for part of a crate to its docset in the store, replacing the
+/// items whose paths match `is_replaced`, and saves it.
+pub fn update_docset<F>(crate_info: CrateInfo,
+                        documents: &[Documentation],
+                        is_replaced: F) -> Result<Store>
+    where F: Fn(&ModPath) -> bool
+{
+    let _lock = StoreLock::acquire()?;
+    let mut store = Store::load();
+    let mut docset = store.items.get_mut(&crate_info.name)
+        .and_then(|versions| versions.remove(&crate_info.version))
+        .unwrap_or_else(Docset::new);
+    docset.remove_docs(&crate_info.name, is_replaced);
+    docset.add_docs(documents);
+    store.add_docset(crate_info, docset);
+    store.save()?;
+    SearchIndex::new(&store).save()?;
+    Ok(store)
+}
+
+/// Loads the search index written by the last documentation generation.
+pub fn load_search_index() -> Result<SearchIndex> {
+    let index_file = paths::search_index_path()?;
+    deserialize_object(index_file)
+}
+
+fn latest_version(versions: &CrateVersions) -> Option<&CrateVersion> {
+    versions.keys()
+        .filter_map(|version| parse_version(version).map(|parsed| (parsed, version)))
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, version)| version)
 }
 
 /// Returns the module paths which contain all the provided path segments.
 fn get_all_matching_paths(query: String,
                           module_expansions: &ModuleExpansions)
                           -> Vec<String> {
-    let query_lower = query.to_lowercase();
+    let query_lower = unraw_path(&query.to_lowercase());
     let path_segments: Vec<String> = query_lower.split("::").map(|s| s.to_string()).collect();
 
     let mut result = Vec::new();
@@ -229,13 +1233,13 @@ fn get_all_matching_paths(query: String,
         }
     }
 
-    result.retain(|res| res.to_lowercase().contains(&query_lower));
+    result.retain(|res| unraw_path(&res.to_lowercase()).contains(&query_lower));
 
     result
 }
 
 /// Returns the strings that exist in both `target` and `other`.
-fn intersect(target: Vec<String>, other: &HashSet<String>) -> Vec<String> {
+fn intersect(target: Vec<String>, other: &BTreeSet<String>) -> Vec<String> {
     let mut in_common = Vec::new();
     let mut other_vec: Vec<_> = other.iter().collect();
 
@@ -249,46 +1253,219 @@ fn intersect(target: Vec<String>, other: &HashSet<String>) -> Vec<String> {
     in_common
 }
 
-/// Returns an integer that can be used to compare Semantic Versioning strings.
-fn version_number_hash(version: &str) -> u64 {
-    let slice: Vec<String> = version.split(".").map(|s| s.to_string()).collect();
-    if slice.len() != 3 {
-        return 0;
+/// A parsed Semantic Versioning string, ordered the way versions are: by major, minor and patch
+/// number, with a pre-release like `1.0.0-beta` before the release it leads up to.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    is_release: bool,
+    pre_release: Vec<PreReleasePart>,
+}
+
+/// A dot-separated part of a pre-release. Numeric parts are ordered before other ones.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+enum PreReleasePart {
+    Numeric(u64),
+    Alphanumeric(String),
+}
+
+/// Parses a version like `1.2.3` or `1.0.0-beta.2`, ignoring build metadata after a `+`. Returns
+/// `None` for versions that don't have three numbers.
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.split('+').next().unwrap_or("");
+    let (numbers, pre_release) = match version.find('-') {
+        Some(pos) => (&version[..pos], Some(&version[pos + 1..])),
+        None      => (version, None),
+    };
+
+    let numbers: Vec<Option<u64>> = numbers.split('.').map(|n| n.parse::<u64>().ok()).collect();
+    if numbers.len() != 3 || numbers.iter().any(|n| n.is_none()) {
+        return None;
     }
-    let a = slice[0].parse::<u64>().unwrap();
-    let b = slice[1].parse::<u64>().unwrap();
-    let c = slice[2].parse::<u64>().unwrap();
-    (a << 16) + (b << 8) + c
+
+    let pre_release = pre_release.map_or(Vec::new(), |pre_release| {
+        pre_release.split('.')
+            .map(|part| match part.parse::<u64>() {
+                Ok(n)  => PreReleasePart::Numeric(n),
+                Err(_) => PreReleasePart::Alphanumeric(part.to_string()),
+            })
+            .collect()
+    });
+
+    Some(Version {
+        major: numbers[0].unwrap(),
+        minor: numbers[1].unwrap(),
+        patch: numbers[2].unwrap(),
+        is_release: pre_release.is_empty(),
+        pre_release: pre_release,
+    })
 }
 
 /// A set of documentation for a specific crate version.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Docset {
-    /// Mapping from a crate-local module path string to the corresponding location
-    /// "vec::Vec" => StoreLocation { name: Vec, /* ... */ }
-    pub documents: HashMap<String, StoreLocation>,
+    /// Mapping from a crate-local module path string to the locations of the items with that path
+    /// "vec::Vec" => [StoreLocation { name: Vec, /* ... */ }]
+    pub documents: BTreeMap<String, Vec<StoreLocation>>,
+
+    /// Mapping from the crate-local paths items are re-exported at to their original locations.
+    /// Kept apart from `documents` so every item is only listed once.
+    pub reexports: BTreeMap<String, Vec<StoreLocation>>,
+
+    /// Re-exports of items from other crates that weren't in the store yet, by the crate-local
+    /// path they're re-exported at. They're moved to `reexports` once the other crate is added.
+    pub pending_reexports: BTreeMap<String, Vec<DocLink>>,
 }
 
 impl Docset {
     pub fn new() -> Self {
         Docset {
-            documents: HashMap::new(),
+            documents: BTreeMap::new(),
+            reexports: BTreeMap::new(),
+            pending_reexports: BTreeMap::new(),
         }
     }
 
-    fn add_doc(&mut self, document: Documentation) -> Result<()> {
+    fn add_reexports(&mut self, module: &Documentation) {
+        if let Some(links) = module.links.get(&DocType::Reexport) {
+            for link in links.iter() {
+                // Renamed re-exports are found by the name they're re-exported under. Glob
+                // re-exports of other crates' modules wait for the crate's docset, which lists the
+                // module's items.
+                let mut path = module.mod_path.tail();
+                if link.glob {
+                    path.push_string("*".to_string());
+                    let path = path.to_string().to_lowercase();
+                    self.pending_reexports.entry(path).or_insert(Vec::new()).push(link.clone());
+                    continue;
+                }
+                path.push_string(link.alias.clone().unwrap_or(link.name.clone()));
+                let path = path.to_string().to_lowercase();
+                match link.resolve(&module.crate_info, &DocType::Reexport) {
+                    Some(location) => {
+                        self.reexports.entry(path).or_insert(Vec::new()).push(location);
+                    },
+                    None => {
+                        self.pending_reexports.entry(path).or_insert(Vec::new()).push(link.clone());
+                    },
+                }
+            }
+        }
+    }
+
+    /// Resolves the pending re-exports of items from the given crate, now that its docset is in
+    /// the store. Re-exports of items the crate doesn't have stay pending. Returns the paths of the
+    /// items the glob re-exports of its modules added.
+    fn resolve_pending_reexports(&mut self,
+                                 crate_info: &CrateInfo,
+                                 target_info: &CrateInfo,
+                                 target: &Docset) -> Vec<String> {
+        let mut added = Vec::new();
+        let paths: Vec<String> = self.pending_reexports.keys().cloned().collect();
+        for path in paths {
+            let links = self.pending_reexports.remove(&path).unwrap_or(Vec::new());
+            let mut still_pending = Vec::new();
+            for link in links {
+                if link.krate.as_ref() != Some(&target_info.name) {
+                    still_pending.push(link);
+                } else if link.glob {
+                    let expanded = self.expand_glob_reexport(&path, &link, target_info, target);
+                    if expanded.is_empty() {
+                        still_pending.push(link);
+                    }
+                    added.extend(expanded);
+                } else {
+                    match link.resolve(crate_info, &DocType::Reexport) {
+                        Some(location) => {
+                            self.reexports.entry(path.clone())
+                                .or_insert(Vec::new())
+                                .push(location);
+                        },
+                        None => still_pending.push(link),
+                    }
+                }
+            }
+            if !still_pending.is_empty() {
+                self.pending_reexports.insert(path, still_pending);
+            }
+        }
+        added
+    }
+
+    /// Lists the items of the module a pending glob re-export links to as re-exported by the
+    /// module at `path`, like `net::*`, if the target docset is of the latest version of the
+    /// module's crate. Returns the paths they were re-exported at.
+    fn expand_glob_reexport(&mut self,
+                            path: &str,
+                            link: &DocLink,
+                            target_info: &CrateInfo,
+                            target: &Docset) -> Vec<String> {
+        let is_latest = latest_crate_version(&target_info.name)
+            .map_or(false, |latest| latest.version == target_info.version);
+        if !is_latest {
+            return Vec::new();
+        }
+
+        let module = path.trim_right_matches('*');
+        let mut added = Vec::new();
+        for location in target.documents.values().flat_map(|locations| locations.iter()) {
+            if location.mod_path.parent().as_ref() != Some(&link.path) {
+                continue;
+            }
+            let item_path = format!("{}{}", module, location.name.to_lowercase());
+            self.reexports.entry(item_path.clone()).or_insert(Vec::new()).push(location.clone());
+            added.push(item_path);
+        }
+        added
+    }
+
+    fn add_doc(&mut self, document: &Documentation) {
         let relative_path = document.mod_path.tail().to_string();
         let store_location = document.to_store_location();
-        self.documents.insert(relative_path.to_lowercase(), store_location);
-        document.save()
-            .chain_err(|| format!("Could not add doc {} to docset", document.mod_path))
+        self.documents.entry(relative_path.to_lowercase())
+            .or_insert(Vec::new())
+            .push(store_location);
+    }
+
+    /// Adds the locations of the given documents. The documents themselves must be saved
+    /// separately.
+    pub fn add_docs(&mut self, documents: &[Documentation]) {
+        for doc in documents.iter() {
+            self.add_doc(doc);
+            self.add_reexports(doc);
+        }
     }
 
-    pub fn add_docs(&mut self, documents: Vec<Documentation>) -> Result<()> {
-        for doc in documents.into_iter() {
-            self.add_doc(doc)?;
+    /// Removes the locations of the items whose paths match `is_removed`, and the re-exports of
+    /// the modules whose paths do.
+    fn remove_docs<F>(&mut self, crate_name: &str, is_removed: F)
+        where F: Fn(&ModPath) -> bool
+    {
+        let mut emptied = Vec::new();
+        for (path, locations) in self.documents.iter_mut() {
+            locations.retain(|location| !is_removed(&location.mod_path));
+            if locations.is_empty() {
+                emptied.push(path.clone());
+            }
+        }
+        for path in emptied.iter() {
+            self.documents.remove(path);
+        }
+
+        let removed_reexports: Vec<String> = self.reexports.keys()
+            .chain(self.pending_reexports.keys())
+            .filter(|path| {
+                let full_path = ModPath::from(format!("{}::{}", crate_name, path));
+                full_path.parent().map_or(false, |module| is_removed(&module))
+            })
+            .cloned()
+            .collect();
+        for path in removed_reexports.iter() {
+            self.reexports.remove(path);
+            self.pending_reexports.remove(path);
         }
-        Ok(())
     }
 }
 
@@ -300,6 +1477,19 @@ pub struct StoreLocation {
     pub crate_info: CrateInfo,
     pub mod_path: ModPath,
     pub doc_type: DocType,
+    pub disambiguator: usize,
+
+    /// Other names the item can be searched for by, from `#[doc(alias = "...")]`.
+    pub aliases: Vec<String>,
+
+    /// Whether the item is marked `#[unstable]`.
+    pub unstable: bool,
+
+    /// The features the item is only available with. See `Attributes::features`.
+    pub features: Vec<String>,
+
+    /// Whether the item is marked `#[deprecated]`.
+    pub deprecated: bool,
 }
 
 impl StoreLocation {
@@ -313,19 +1503,178 @@ impl StoreLocation {
             crate_info: crate_info,
             mod_path: mod_path,
             doc_type: doc_type,
+            disambiguator: 0,
+            aliases: Vec::new(),
+            unstable: false,
+            features: Vec::new(),
+            deprecated: false,
+        }
+    }
+
+    pub fn to_doc_link(&self) -> DocLink {
+        DocLink {
+            name: self.name.clone(),
+            path: self.mod_path.clone(),
+            disambiguator: self.disambiguator,
+            doc_type: Some(self.doc_type.clone()),
+            order: None,
+            krate: None,
+            alias: None,
+            glob: false,
+        }
+    }
+
+    pub fn to_filepath(&self) -> Result<PathBuf> {
+        let mut path = paths::crate_doc_path(&self.crate_info)?;
+        path.push(self.relative_filepath());
+        Ok(path)
+    }
+
+    /// The path of the JSON version of the documentation file, next to the bincoded one.
+    pub fn to_json_filepath(&self) -> Result<PathBuf> {
+        self.to_filepath().map(|path| path.with_extension("odoc.json"))
+    }
+
+    /// The path of the documentation file relative to the documentation directory of the given
+    /// crate, going through the store's root for files of other crates.
+    pub fn relative_filepath_from(&self, crate_info: &CrateInfo) -> PathBuf {
+        if self.crate_info.name == crate_info.name && self.crate_info.version == crate_info.version {
+            self.relative_filepath()
+        } else {
+            PathBuf::from("..").join(self.crate_info.to_path_prefix()).join(self.relative_filepath())
         }
     }
 
-    pub fn to_filepath(&self) -> PathBuf {
-        let mut path = paths::crate_doc_path(&self.crate_info).unwrap();
-        let doc_path = self.mod_path.to_filepath();
-        path.push(doc_path);
-        let filename = format!("{}{}.odoc", self.doc_type.get_file_prefix(), self.name);
+    /// The path of the documentation file relative to the crate's documentation directory, in the
+    /// layout chosen with `OXIDOC_STORE_LAYOUT`.
+    pub fn relative_filepath(&self) -> PathBuf {
+        self.relative_filepath_in(StoreLayout::from_env())
+    }
+
+    /// The path of the documentation file relative to the crate's documentation directory, in the
+    /// given layout.
+    pub fn relative_filepath_in(&self, layout: StoreLayout) -> PathBuf {
+        let disambiguator = match self.disambiguator {
+            0 => "".to_string(),
+            n => format!("-{}", n),
+        };
+        let prefix = match layout {
+            StoreLayout::Prefixed => self.doc_type.get_file_prefix(),
+            StoreLayout::Readable => self.doc_type.get_readable_file_prefix(),
+            StoreLayout::Hashed => {
+                let key = format!("{}\0{}\0{}",
+                                  self.mod_path,
+                                  self.doc_type.get_file_prefix(),
+                                  disambiguator);
+                return PathBuf::from(format!("{:016x}.odoc", fnv1a_hash(key.as_bytes())));
+            }
+        };
+
+        let mut path = self.mod_path.to_filepath();
+        let filename = format!("{}{}{}.odoc",
+                               prefix,
+                               paths::sanitize_filename(&self.name),
+                               disambiguator);
         path.push(filename);
         path
     }
 }
 
+/// The environment variable used to choose how documentation files are named in the store.
+const STORE_LAYOUT_VAR: &str = "OXIDOC_STORE_LAYOUT";
+
+/// How the documentation files of a crate are laid out in the store. Each crate's metadata records
+/// the layout it was generated in, and a crate can't be regenerated in another one until its
+/// documentation is removed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StoreLayout {
+    /// One directory per module, with files named like `sdesc-Name.odoc`. The default.
+    #[serde(rename = "prefixed")]
+    Prefixed,
+    /// Like `Prefixed`, but with the type spelled out, like `struct-Name.odoc`.
+    #[serde(rename = "readable")]
+    Readable,
+    /// Every file directly in the crate's directory, named by a hash of the item's path.
+    #[serde(rename = "hashed")]
+    Hashed,
+}
+
+impl StoreLayout {
+    /// Parses the names accepted by `OXIDOC_STORE_LAYOUT`: `prefixed`, `readable` or `hashed`.
+    pub fn from_name(name: &str) -> Option<StoreLayout> {
+        match name {
+            "prefixed" => Some(StoreLayout::Prefixed),
+            "readable" => Some(StoreLayout::Readable),
+            "hashed"   => Some(StoreLayout::Hashed),
+            _          => None,
+        }
+    }
+
+    /// The name of the layout, as accepted by `from_name`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            StoreLayout::Prefixed => "prefixed",
+            StoreLayout::Readable => "readable",
+            StoreLayout::Hashed   => "hashed",
+        }
+    }
+
+    /// The layout chosen with `OXIDOC_STORE_LAYOUT`, falling back to `Prefixed`.
+    pub fn from_env() -> StoreLayout {
+        match env::var(STORE_LAYOUT_VAR) {
+            Ok(ref name) if !name.is_empty() => {
+                StoreLayout::from_name(name).unwrap_or_else(|| {
+                    warn!("Unknown store layout \"{}\", using \"prefixed\"", name);
+                    StoreLayout::Prefixed
+                })
+            },
+            _ => StoreLayout::Prefixed,
+        }
+    }
+}
+
+impl Default for StoreLayout {
+    fn default() -> Self {
+        StoreLayout::Prefixed
+    }
+}
+
+/// 64-bit FNV-1a, used for the hashed layout since it has to give the same file names on every
+/// platform and Rust version.
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    let mut hasher = Fnv1aHasher::new();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// A `Hasher` computing 64-bit FNV-1a, for hashes that are stored and compared by later runs.
+/// Unlike `DefaultHasher`, its output doesn't change between Rust versions.
+pub struct Fnv1aHasher(u64);
+
+impl Fnv1aHasher {
+    pub fn new() -> Self {
+        Fnv1aHasher(0xcbf29ce484222325)
+    }
+}
+
+impl Default for Fnv1aHasher {
+    fn default() -> Self {
+        Fnv1aHasher::new()
+    }
+}
+
+impl Hasher for Fnv1aHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0 = bytes.iter().fold(self.0, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
 impl fmt::Display for StoreLocation {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{} ({} {})", self.mod_path, self.crate_info.name, self.crate_info.version)
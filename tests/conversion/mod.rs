@@ -1 +1,4 @@
 mod test_paths;
+mod test_markup;
+mod test_links;
+mod test_rustdoc_json;
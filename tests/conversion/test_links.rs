@@ -0,0 +1,357 @@
+use oxidoc::conversion::{DocInnerData, DocLink, DocType};
+use oxidoc::document::ModPath;
+use oxidoc::generation::{self, GenerateOptions};
+use oxidoc::markup::FormatOptions;
+use oxidoc::store::{self, Docset, Store};
+
+use util::{get_crate_info, source_to_docs, source_to_docs_with, TempStore};
+
+#[test]
+fn test_no_dangling_links() {
+    let _store = TempStore::new("no_dangling_links");
+    let docs = source_to_docs(r#"
+pub mod module {
+    pub fn func() {}
+}
+
+pub struct MyStruct {
+    pub field: u32,
+}
+"#);
+
+    for doc in docs.iter() {
+        doc.save().expect("Write failed");
+    }
+
+    assert!(generation::check_doc_links(&docs).is_empty());
+}
+
+#[test]
+fn test_dangling_link() {
+    let _store = TempStore::new("dangling_link");
+    let mut docs = source_to_docs(r#"
+pub fn func() {}
+"#);
+
+    for doc in docs.iter() {
+        doc.save().expect("Write failed");
+    }
+
+    let missing = DocLink::new("missing".to_string(),
+                               ModPath::from("crate::missing".to_string()));
+    docs.iter_mut()
+        .find(|d| d.name == "crate")
+        .unwrap()
+        .links
+        .entry(DocType::Function)
+        .or_insert(Vec::new())
+        .push(missing.clone());
+
+    let dangling = generation::check_doc_links(&docs);
+    assert_eq!(dangling.len(), 1);
+    assert_eq!(dangling[0].link, missing);
+    assert_eq!(dangling[0].category, DocType::Function);
+}
+
+#[test]
+fn test_intra_doc_links() {
+    let docs = source_to_docs(r#"
+/// Returns a [`MyStruct`]. See [the module](crate::module::func) and [`Missing`].
+pub fn make() {}
+
+pub struct MyStruct;
+
+pub mod module {
+    /// Calls [`super::make`].
+    pub fn func() {}
+}
+"#);
+
+    let make = docs.iter().find(|d| d.name == "make").unwrap();
+    let structs = make.links.get(&DocType::Struct).unwrap();
+    assert_eq!(structs[0].path, ModPath::from("crate::MyStruct".to_string()));
+    let fns = make.links.get(&DocType::Function).unwrap();
+    assert_eq!(fns[0].path, ModPath::from("crate::module::func".to_string()));
+    assert!(make.attrs.doc_strings.join("\n").contains("[`Missing`]"));
+
+    let func = docs.iter().find(|d| d.name == "func").unwrap();
+    let fns = func.links.get(&DocType::Function).unwrap();
+    assert_eq!(fns[0].path, ModPath::from("crate::make".to_string()));
+}
+
+#[test]
+fn test_implementors() {
+    let docs = source_to_docs(r#"
+pub trait Shape {}
+pub trait Named {}
+
+pub struct Circle;
+pub enum Polygon { Square }
+
+impl Shape for Circle {}
+impl Named for Circle {}
+impl Shape for Polygon {}
+impl Clone for Polygon { fn clone(&self) -> Self { Polygon::Square } }
+"#);
+
+    let names = |links: &Vec<DocLink>| -> Vec<String> {
+        links.iter().map(|l| l.name.clone()).collect()
+    };
+
+    let circle = docs.iter().find(|d| d.name == "Circle").unwrap();
+    let traits = circle.links.get(&DocType::TraitImpl).unwrap();
+    assert_eq!(names(traits), vec!["Named", "Shape"]);
+    assert_eq!(traits[1].path, ModPath::from("crate::Shape".to_string()));
+
+    let polygon = docs.iter().find(|d| d.name == "Polygon").unwrap();
+    let traits = polygon.links.get(&DocType::TraitImpl).unwrap();
+    assert_eq!(names(traits), vec!["Clone", "Shape"]);
+    assert!(!traits[0].has_target());
+
+    let shape = docs.iter().find(|d| d.name == "Shape").unwrap();
+    let implementors = shape.links.get(&DocType::Implementor).unwrap();
+    assert_eq!(names(implementors), vec!["Circle", "Polygon"]);
+    assert_eq!(implementors[1].doc_type, Some(DocType::Enum));
+}
+
+#[test]
+fn test_provided_methods() {
+    let docs = source_to_docs(r#"
+pub trait Greet {
+    fn name(&self) -> String;
+    fn hello(&self) -> String { format!("Hello, {}", self.name()) }
+    fn bye(&self) -> String { format!("Bye, {}", self.name()) }
+}
+
+pub struct Polite;
+
+impl Greet for Polite {
+    fn name(&self) -> String { "you".to_string() }
+    fn bye(&self) -> String { "Farewell".to_string() }
+}
+"#);
+
+    let polite = docs.iter().find(|d| d.name == "Polite").unwrap();
+    let block = match polite.inner_data {
+        DocInnerData::StructDoc(ref struct_) => &struct_.impls[0],
+        _ => panic!("Polite isn't a struct"),
+    };
+    assert_eq!(block.trait_link.as_ref().map(|link| link.path.to_string()),
+               Some("crate::Greet".to_string()));
+    assert!(block.overrides("bye"));
+    assert!(!block.overrides("hello"));
+    assert!(!block.overrides("name"));
+
+    let inherited: Vec<String> = block.inherited_methods().iter()
+        .map(|link| link.path.to_string())
+        .collect();
+    assert_eq!(inherited, vec!["crate::Greet::hello"]);
+
+    let mut options = FormatOptions::new();
+    options.color = false;
+    let formatted = polite.format_with(&options).to_string();
+    assert!(formatted.contains("    name\n    bye (overridden)\n    hello (provided)"),
+            "{}", formatted);
+    let markdown = polite.to_markdown();
+    assert!(markdown.contains(") (overridden)\n- [`hello`]("), "{}", markdown);
+}
+
+#[test]
+fn test_inherent_methods() {
+    let _store = TempStore::new("inherent_methods");
+    let docs = source_to_docs(r#"
+pub fn bar() {}
+
+pub struct Foo;
+
+impl Foo {
+    pub fn bar(&self) {}
+    pub const MAX: u32 = 1;
+}
+
+impl Foo {
+    pub fn baz(&self) {}
+    fn secret(&self) {}
+}
+"#);
+
+    for doc in docs.iter() {
+        doc.save().expect("Write failed");
+    }
+
+    let foo = docs.iter().find(|d| d.name == "Foo").unwrap();
+    let methods: Vec<String> = foo.links.get(&DocType::Function).unwrap()
+        .iter()
+        .map(|l| l.path.to_string())
+        .collect();
+    // Private methods of inherent impls aren't documented by default.
+    assert_eq!(methods, vec!["crate::Foo::bar", "crate::Foo::baz"]);
+    assert!(docs.iter().all(|d| d.name != "secret"));
+
+    let consts = foo.links.get(&DocType::AssocConst).unwrap();
+    assert_eq!(consts[0].path, ModPath::from("crate::Foo::MAX".to_string()));
+
+    let krate = docs.iter().find(|d| d.name == "crate").unwrap();
+    let fns = krate.links.get(&DocType::Function).unwrap();
+    assert_eq!(fns.len(), 1);
+    assert_eq!(fns[0].path, ModPath::from("crate::bar".to_string()));
+
+    assert!(generation::check_doc_links(&docs).is_empty());
+}
+
+#[test]
+fn test_module_children() {
+    let _store = TempStore::new("module_children");
+    let docs = source_to_docs(r#"
+/// See [`crate::module`] and [`other`].
+pub mod module {
+    pub struct Inner;
+    pub fn func() {}
+}
+
+pub fn other() {}
+"#);
+
+    for doc in docs.iter() {
+        doc.save().expect("Write failed");
+    }
+
+    let module = docs.iter().find(|d| d.name == "module").unwrap();
+    let children = module.children().expect("Load failed");
+    let names: Vec<String> = children.iter().map(|c| c.name.clone()).collect();
+    assert_eq!(names, vec!["func", "Inner"]);
+
+    let func = docs.iter().find(|d| d.name == "func").unwrap();
+    assert!(func.children().unwrap().is_empty());
+}
+
+#[test]
+fn test_reexports() {
+    let docs = source_to_docs(r#"
+pub mod inner {
+    pub struct Foo;
+    pub fn func() {}
+}
+
+pub use inner::Foo;
+
+pub mod prelude {
+    pub use inner::*;
+    pub use std::fmt::Display;
+}
+"#);
+
+    let krate = docs.iter().find(|d| d.name == "crate").unwrap();
+    let reexports = krate.links.get(&DocType::Reexport).unwrap();
+    assert_eq!(reexports.len(), 2);
+    let reexport = reexports.iter().find(|l| !l.glob).unwrap();
+    assert_eq!(reexport.name, "Widget");
+    assert_eq!(reexport.krate, Some("other_crate_for_reexports".to_string()));
+    assert_eq!(reexport.path, ModPath::from("other_crate_for_reexports::Widget".to_string()));
+
+    // The items of the other crate's module aren't known yet, so the glob links to the module.
+    let glob = reexports.iter().find(|l| l.glob).unwrap();
+    assert_eq!(glob.shown_name(), "prelude::*");
+    assert_eq!(glob.doc_type, Some(DocType::Module));
+    assert_eq!(glob.path, ModPath::from("other_crate_for_reexports::prelude".to_string()));
+
+    // The other crate isn't in the store, so the links can't be resolved.
+    assert!(reexport.resolve(&krate.crate_info, &DocType::Reexport).is_none());
+    assert!(glob.resolve(&krate.crate_info, &DocType::Reexport).is_none());
+    assert!(krate.dangling_links().is_empty());
+
+    let mut docset = Docset::new();
+    docset.add_docs(&docs);
+    assert!(docset.reexports.is_empty());
+    assert_eq!(docset.pending_reexports.len(), 2);
+    let mut index = Store::new();
+    index.add_docset(krate.crate_info.clone(), docset);
+    assert!(index.lookup_name("crate::Widget").is_empty());
+    assert!(index.lookup_name("crate::Gadget").is_empty());
+
+    // They're resolved once the other crate is generated.
+    let other_info = get_crate_info("other_crate_for_reexports", "1.0.0");
+    let mut other_docs = source_to_docs(r#"
+pub struct Widget;
+
+pub mod prelude {
+    pub struct Gadget;
+}
+"#);
+    for doc in other_docs.iter_mut() {
+        doc.crate_info = other_info.clone();
+        doc.mod_path = ModPath::join(&ModPath::from("other_crate_for_reexports".to_string()),
+                                     &doc.mod_path.tail());
+        doc.save().expect("Write failed");
+    }
+    store::save_crate_metadata(&other_info).expect("Write failed");
+
+    let mut other_docset = Docset::new();
+    other_docset.add_docs(&other_docs);
+    index.add_docset(other_info, other_docset);
+    let found = index.lookup_name("crate::Widget");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].mod_path,
+               ModPath::from("other_crate_for_reexports::Widget".to_string()));
+    let found = index.lookup_name("crate::Gadget");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].mod_path,
+               ModPath::from("other_crate_for_reexports::prelude::Gadget".to_string()));
+}
+
+#[test]
+fn test_return_type_links() {
+    let docs = source_to_docs(r#"
+pub struct Config;
+
+pub struct Builder;
+
+impl Builder {
+    pub fn new() -> Self { Builder }
+    pub fn verbose<'a>(&'a mut self) -> &'a mut Self { self }
+    pub fn build(self) -> Result<Config, String> { Ok(Config) }
+    pub fn name(&self) -> String { String::new() }
+}
+"#);
+
+    let return_paths = |name: &str| -> Vec<String> {
+        let doc = docs.iter().find(|d| d.name == name).unwrap();
+        match doc.inner_data {
+            DocInnerData::FnDoc(ref func) => {
+                func.return_links.iter().map(|link| link.path.to_string()).collect()
+            }
+            _ => panic!("{} isn't a function", name),
+        }
+    };
+    assert_eq!(return_paths("new"), vec!["crate::Builder"]);
+    assert_eq!(return_paths("verbose"), vec!["crate::Builder"]);
+    assert_eq!(return_paths("build"), vec!["crate::Config"]);
+    assert!(return_paths("name").is_empty());
+
+    let verbose = docs.iter().find(|d| d.name == "verbose").unwrap();
+    let markdown = verbose.to_markdown();
+    assert!(markdown.contains("*Returns [`Builder`](crate/Builder/sdesc-Builder.odoc)*"),
+            "{}", markdown);
+    let html = verbose.to_html();
+    assert!(html.contains(concat!("-&gt; &amp;&#39;a mut ",
+                                  r#"<a href="crate/Builder/sdesc-Builder.html">Self</a>"#)),
+            "{}", html);
+    assert!(!html.contains("Returns"), "{}", html);
+    let build = docs.iter().find(|d| d.name == "build").unwrap();
+    let html = build.to_html();
+    assert!(html.contains(concat!("-&gt; Result&lt;",
+                                  r#"<a href="crate/Config/sdesc-Config.html">Config</a>"#,
+                                  ", String&gt;")),
+            "{}", html);
+
+    let mut options = FormatOptions::new();
+    options.color = false;
+    let formatted = verbose.format_with(&options).to_string();
+    assert!(formatted.contains("  pub fn verbose<'a>(&'a mut self) -> &'a mut Self\n"),
+            "{}", formatted);
+    assert!(!formatted.contains("Returns"), "{}", formatted);
+    options.hyperlinks = true;
+    let formatted = verbose.format_with(&options).to_string();
+    assert!(formatted.contains("(&'a mut self) -> &'a mut \x1b]8;;file://"), "{}", formatted);
+    assert!(formatted.contains("sdesc-Builder.odoc\x1b\\Self\x1b]8;;\x1b\\\n"), "{}", formatted);
+}
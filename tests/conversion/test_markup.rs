@@ -0,0 +1,998 @@
+use oxidoc::conversion::{DocInnerData, DocType};
+use std::mem;
+
+use oxidoc::generation::ast_ty_wrappers::{DEFAULT_MAX_TYPE_DEPTH, Stability};
+use oxidoc::generation::ty_printer;
+use oxidoc::markup::FormatOptions;
+use oxidoc::search::{JsonResult, SearchFilter};
+use syntax::ast;
+use syntax::codemap::{DUMMY_SP, FilePathMapping};
+use syntax::parse::{self, ParseSess};
+use syntax::ptr::P;
+use util::source_to_docs;
+
+#[test]
+fn test_module_to_markdown() {
+    let docs = source_to_docs(r#"
+/// A test struct.
+pub struct MyStruct;
+
+pub fn func() {}
+"#);
+    let krate = docs.iter().find(|d| d.name == "crate").unwrap();
+    let markdown = krate.to_markdown();
+
+    assert!(markdown.starts_with("# Crate crate\n"), "{}", markdown);
+    assert!(markdown.contains("## Structs\n\n- [`MyStruct`](crate/MyStruct/sdesc-MyStruct.odoc)"),
+            "{}", markdown);
+    assert!(markdown.contains("## Functions\n\n- [`func`](crate/func/func.odoc)"),
+            "{}", markdown);
+}
+
+#[test]
+fn test_item_to_markdown() {
+    let docs = source_to_docs(r#"
+/// A test struct.
+pub struct MyStruct;
+"#);
+    let strukt = docs.iter().find(|d| d.name == "MyStruct").unwrap();
+    let markdown = strukt.to_markdown();
+
+    assert!(markdown.contains("```rust\npub struct MyStruct"), "{}", markdown);
+    assert!(markdown.contains("A test struct."), "{}", markdown);
+}
+
+#[test]
+fn test_no_color_output() {
+    let docs = source_to_docs(r#"
+/// A **bold** test struct.
+pub struct MyStruct;
+"#);
+    let strukt = docs.iter().find(|d| d.name == "MyStruct").unwrap();
+    let mut options = FormatOptions::new();
+    options.color = false;
+    let formatted = strukt.format_with(&options).to_string();
+
+    assert!(!formatted.contains('\x1b'), "{:?}", formatted);
+    assert!(formatted.contains("bold"), "{}", formatted);
+}
+
+#[test]
+fn test_reflow_doc_body() {
+    let code = concat!("let numbers = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, ",
+                       "11, 12, 13, 14, 15, 16, 17, 18, 19, 20];");
+    let docs = source_to_docs(&format!(r#"
+/// Hard-wrapped text
+/// is joined.
+///
+/// A paragraph that is much longer than the width of the terminal, which is eighty columns when
+/// the output isn't a terminal, is wrapped at spaces so that none of its lines spill past the edge.
+///
+/// ```text
+/// {}
+/// ```
+pub struct MyStruct;
+"#, code));
+    let strukt = docs.iter().find(|d| d.name == "MyStruct").unwrap();
+    let mut options = FormatOptions::new();
+    options.color = false;
+    let formatted = strukt.format_with(&options).to_string();
+
+    assert!(formatted.contains("Hard-wrapped text is joined."), "{}", formatted);
+    assert!(formatted.contains(code), "{}", formatted);
+    for line in formatted.lines().filter(|line| !line.contains(code)) {
+        assert!(line.chars().count() <= 80, "{}", formatted);
+    }
+}
+
+#[test]
+fn test_deprecation() {
+    let docs = source_to_docs(r#"
+#[deprecated(since = "1.2", note = "use bar")]
+pub fn foo() {}
+
+#[deprecated]
+pub fn baz() {}
+"#);
+
+    let foo = docs.iter().find(|d| d.name == "foo").unwrap();
+    let deprecation = foo.attrs.deprecation.as_ref().unwrap();
+    assert_eq!(deprecation.since, Some("1.2".to_string()));
+    assert_eq!(deprecation.note, Some("use bar".to_string()));
+    assert!(foo.to_markdown().contains("> **Deprecated** since 1.2: use bar"));
+
+    let baz = docs.iter().find(|d| d.name == "baz").unwrap();
+    assert!(baz.attrs.deprecation.is_some());
+    assert!(baz.to_markdown().contains("> **Deprecated**\n"));
+
+    // Search results flag deprecated items.
+    assert_eq!(baz.summary_line_with_width(100), "pub fn baz() (deprecated)");
+    assert!(baz.to_store_location().deprecated);
+    assert!(JsonResult::new(baz).deprecated);
+}
+
+#[test]
+fn test_cfg_availability() {
+    let docs = source_to_docs(r#"
+#[cfg(feature = "x")]
+pub fn foo() {}
+
+#[cfg(unix)]
+pub fn bar() {}
+"#);
+
+    let foo = docs.iter().find(|d| d.name == "foo").unwrap();
+    assert_eq!(foo.attrs.cfgs, vec!["feature = \"x\"".to_string()]);
+    assert!(foo.to_markdown().contains("*Available on feature `x` only*"));
+
+    let bar = docs.iter().find(|d| d.name == "bar").unwrap();
+    assert!(bar.to_markdown().contains("*Available on `unix` only*"));
+
+    let mut filter = SearchFilter::new();
+    filter.features = vec!["x".to_string()];
+    assert!(filter.matches(&foo.to_store_location()));
+    assert!(!filter.matches(&bar.to_store_location()));
+    filter.features = vec!["y".to_string()];
+    assert!(!filter.matches(&foo.to_store_location()));
+}
+
+#[test]
+fn test_must_use() {
+    let docs = source_to_docs(r#"
+#[must_use]
+pub struct Builder;
+
+#[must_use = "the result may be an error"]
+pub fn parse() -> Result<(), ()> { Ok(()) }
+
+pub fn plain() {}
+"#);
+    let builder = docs.iter().find(|d| d.name == "Builder").unwrap();
+    assert_eq!(builder.attrs.must_use, Some("".to_string()));
+    assert!(builder.to_markdown().contains("*Must be used*"));
+
+    let parse = docs.iter().find(|d| d.name == "parse").unwrap();
+    assert!(parse.to_markdown().contains("*Must be used: the result may be an error*"));
+
+    let plain = docs.iter().find(|d| d.name == "plain").unwrap();
+    assert_eq!(plain.attrs.must_use, None);
+}
+
+#[test]
+fn test_stability() {
+    let docs = source_to_docs(r#"
+#[unstable(feature = "future_api", issue = "123")]
+pub fn soon() {}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn now() {}
+
+pub fn plain() {}
+"#);
+    let soon = docs.iter().find(|d| d.name == "soon").unwrap();
+    assert_eq!(soon.attrs.stability, Some(Stability::Unstable {
+        feature: Some("future_api".to_string()),
+        issue: Some("123".to_string()),
+    }));
+    assert!(soon.to_markdown().contains("*Unstable (feature future_api, issue 123)*"));
+
+    let now = docs.iter().find(|d| d.name == "now").unwrap();
+    assert!(now.to_markdown().contains("*Stable since 1.0.0*"));
+
+    let plain = docs.iter().find(|d| d.name == "plain").unwrap();
+    assert_eq!(plain.attrs.stability, None);
+
+    let mut filter = SearchFilter::new();
+    filter.stable_only = true;
+    assert!(!filter.matches(&soon.to_store_location()));
+    assert!(filter.matches(&now.to_store_location()));
+    assert!(filter.matches(&plain.to_store_location()));
+}
+
+#[test]
+fn test_precomputed_signature() {
+    let mut docs = source_to_docs(r#"
+pub fn parse(input: &str) -> u32 { 0 }
+"#);
+    let krate = docs.iter().find(|d| d.name == "crate").unwrap();
+    assert_eq!(krate.signature, None);
+
+    let parse = docs.iter_mut().find(|d| d.name == "parse").unwrap();
+    assert_eq!(parse.signature, Some("pub fn parse(input: &str) -> u32".to_string()));
+    assert_eq!(parse.signature, parse.build_signature());
+
+    parse.signature = Some("pub fn parse(input: &[u8]) -> u32".to_string());
+    assert_eq!(parse.signature(), parse.signature);
+    assert!(parse.to_markdown().contains("pub fn parse(input: &[u8]) -> u32"));
+}
+
+#[test]
+fn test_deeply_nested_types() {
+    let nested = format!("{}u32{}", "Option<".repeat(40), ">".repeat(40));
+    let docs = source_to_docs(&format!(r#"
+pub fn callbacks() -> Vec<HashMap<String, Vec<Option<Box<Fn() -> u32>>>>> {{ Vec::new() }}
+
+pub fn deep(value: {}) {{}}
+"#, nested));
+
+    let signature = |name: &str| {
+        docs.iter().find(|d| d.name == name).unwrap().signature().unwrap()
+    };
+    assert_eq!(signature("callbacks"),
+               "pub fn callbacks() -> Vec<HashMap<String, Vec<Option<Box<Fn() -> u32>>>>>");
+
+    let deep = signature("deep");
+    assert!(deep.contains("Option<...>"), "{}", deep);
+    assert_eq!(deep.matches("Option").count(), DEFAULT_MAX_TYPE_DEPTH, "{}", deep);
+    assert!(deep.ends_with(">)"), "{}", deep);
+
+    let limited = |source: &str, max_depth| {
+        let session = ParseSess::new(FilePathMapping::empty());
+        let source = format!("type T = {};", source);
+        let krate = parse::parse_crate_from_source_str("test.rs".to_string(), source, &session)
+            .unwrap();
+        match krate.module.items[0].node {
+            ast::ItemKind::Ty(ref ty, _) => ty_printer::ty_to_string(ty, max_depth),
+            _ => panic!("Not a type alias"),
+        }
+    };
+    assert_eq!(limited("Vec<Option<Box<u32>>>", 2), "Vec<Option<...>>");
+    assert_eq!(limited("Box<Fn(u32) -> u32>", 2), "Box<Fn(...) -> ...>");
+    assert_eq!(limited("Box<Fn(u32) -> u32>", 1), "Box<...>");
+    assert_eq!(limited("[u8; 4]", 1), "[...; 4]");
+    assert_eq!(limited("HashMap<&'a str, (u8,)>", 3), "HashMap<&'a str, (u8,)>");
+}
+
+#[test]
+fn test_types_too_deep_to_recurse_through() {
+    // Printing all of this type would overflow the stack.
+    let never = ast::Ty { id: ast::DUMMY_NODE_ID, node: ast::TyKind::Never, span: DUMMY_SP };
+    let mut ty = P(never);
+    for _ in 0..100_000 {
+        ty = P(ast::Ty { id: ast::DUMMY_NODE_ID, node: ast::TyKind::Slice(ty), span: DUMMY_SP });
+    }
+
+    let printed = ty_printer::ty_to_string(&ty, DEFAULT_MAX_TYPE_DEPTH);
+    let brackets = DEFAULT_MAX_TYPE_DEPTH;
+    assert_eq!(printed, format!("{}...{}", "[".repeat(brackets), "]".repeat(brackets)));
+
+    // Dropping it would recurse as deeply as printing it.
+    mem::forget(ty);
+}
+
+#[test]
+fn test_struct_kinds() {
+    let docs = source_to_docs(r#"
+pub struct Meters(pub f64);
+
+pub struct Pair(pub u32, u32);
+
+pub struct Unit;
+
+pub struct Empty {}
+
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+"#);
+    let signature = |name: &str| {
+        docs.iter().find(|d| d.name == name).unwrap().signature().unwrap()
+    };
+
+    assert_eq!(signature("Meters"), "pub struct Meters(pub f64);");
+    assert_eq!(signature("Pair"), "pub struct Pair(pub u32, _);");
+    assert_eq!(signature("Unit"), "pub struct Unit;");
+    assert_eq!(signature("Empty"), "pub struct Empty {}");
+    assert_eq!(signature("Point"), "pub struct Point {\n    pub x: f64,\n    pub y: f64,\n}");
+
+    let pair = docs.iter().find(|d| d.name == "Pair").unwrap();
+    let fields: Vec<String> = pair.links.get(&DocType::StructField).unwrap()
+        .iter()
+        .map(|link| link.name.clone())
+        .collect();
+    assert_eq!(fields, vec!["0", "1"]);
+}
+
+#[test]
+fn test_non_exhaustive() {
+    let docs = source_to_docs(r#"
+#[non_exhaustive]
+pub struct Config {
+    pub verbose: bool,
+}
+
+#[non_exhaustive]
+pub enum Error {
+    Io,
+    Parse,
+}
+
+pub enum Exhaustive {
+    A,
+}
+"#);
+    let config = docs.iter().find(|d| d.name == "Config").unwrap();
+    match config.inner_data {
+        DocInnerData::StructDoc(ref struct_) => assert!(struct_.non_exhaustive),
+        _ => panic!("Expected a struct"),
+    }
+    assert!(config.to_markdown().contains("*Non-exhaustive: more fields may be added"),
+            "{}", config.to_markdown());
+    assert!(config.attrs.codegen_attrs.is_empty());
+
+    let error = docs.iter().find(|d| d.name == "Error").unwrap();
+    assert!(error.to_markdown().contains("must include a wildcard arm"), "{}", error.to_markdown());
+    assert!(error.to_html().contains("<p class=\"non-exhaustive\">"));
+
+    let exhaustive = docs.iter().find(|d| d.name == "Exhaustive").unwrap();
+    match exhaustive.inner_data {
+        DocInnerData::EnumDoc(ref enum_) => assert!(!enum_.non_exhaustive),
+        _ => panic!("Expected an enum"),
+    }
+    assert!(!exhaustive.to_markdown().contains("Non-exhaustive"));
+}
+
+#[test]
+fn test_codegen_attributes() {
+    let docs = source_to_docs(r#"
+#[repr(C)]
+#[derive(Clone)]
+pub struct Header {
+    pub len: u32,
+}
+
+#[inline(always)]
+#[no_mangle]
+#[allow(unused)]
+pub extern "C" fn header_len() -> u32 { 0 }
+"#);
+    let header = docs.iter().find(|d| d.name == "Header").unwrap();
+    assert_eq!(header.attrs.codegen_attrs, vec!["#[repr(C)]".to_string()]);
+    assert!(header.to_markdown().contains("```rust\n#[repr(C)]\npub struct Header {"),
+            "{}", header.to_markdown());
+
+    let header_len = docs.iter().find(|d| d.name == "header_len").unwrap();
+    assert_eq!(header_len.attrs.codegen_attrs,
+               vec!["#[inline(always)]".to_string(), "#[no_mangle]".to_string()]);
+}
+
+#[test]
+fn test_subitems_sorted() {
+    let docs = source_to_docs(r#"
+pub fn zeta() {}
+pub fn Alpha() {}
+pub fn beta() {}
+"#);
+    let krate = docs.iter().find(|d| d.name == "crate").unwrap();
+    let names: Vec<&str> = krate.links.get(&DocType::Function).unwrap()
+        .iter()
+        .map(|link| &*link.name)
+        .collect();
+
+    assert_eq!(names, vec!["Alpha", "beta", "zeta"]);
+}
+
+#[test]
+fn test_trait_supertraits() {
+    let docs = source_to_docs(r#"
+pub trait Base {}
+
+pub trait Sub<T: Clone>: Base + PartialOrd<Self> where T: Default {}
+"#);
+    let sub = docs.iter().find(|d| d.name == "Sub").unwrap();
+    let markdown = sub.to_markdown();
+
+    assert!(markdown.contains("pub trait Sub<T: Clone>: Base + PartialOrd<Self> where T: Default"),
+            "{}", markdown);
+    assert!(markdown.contains("## Supertraits\n\n- [`Base`](crate/Base/tdesc-Base.odoc)\n- `PartialOrd`"),
+            "{}", markdown);
+}
+
+#[test]
+fn test_trait_assoc_consts() {
+    let docs = source_to_docs(r#"
+pub trait Buffer {
+    const CAPACITY: usize = 64;
+    const ALIGN: usize;
+}
+"#);
+    let markdown = |name: &str| {
+        docs.iter().find(|d| d.name == name).unwrap().to_markdown()
+    };
+
+    let capacity = markdown("CAPACITY");
+    assert!(capacity.contains("const CAPACITY: usize = 64;"), "{}", capacity);
+    let align = markdown("ALIGN");
+    assert!(align.contains("const ALIGN: usize;"), "{}", align);
+}
+
+#[test]
+fn test_type_generics_in_header() {
+    let docs = source_to_docs(r#"
+pub struct Matrix<'a, T: Copy = u8> where T: Default {
+    data: &'a [T],
+}
+
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+"#);
+    let matrix = docs.iter().find(|d| d.name == "Matrix").unwrap();
+    let markdown = matrix.to_markdown();
+    assert!(markdown.contains("pub struct Matrix<'a, T: Copy = u8> where T: Default"),
+            "{}", markdown);
+
+    let either = docs.iter().find(|d| d.name == "Either").unwrap();
+    let markdown = either.to_markdown();
+    assert!(markdown.contains("enum Either<L, R> {\n    Left(L),"), "{}", markdown);
+}
+
+#[test]
+fn test_relaxed_and_higher_ranked_bounds() {
+    let docs = source_to_docs(r#"
+pub fn size_of_val<T: ?Sized>(val: &T) -> usize { 0 }
+
+pub fn call<F>(f: F) where F: for<'a> Fn(&'a u8) -> &'a u8 {}
+
+pub fn apply<F: for<'a> Fn(&'a T), T: Clone + ?Sized + Send>(f: F, value: &T) {}
+
+pub trait Deref {
+    type Target: ?Sized;
+}
+"#);
+    let markdown = |name: &str| {
+        docs.iter().find(|d| d.name == name).unwrap().to_markdown()
+    };
+
+    let size_of_val = markdown("size_of_val");
+    assert!(size_of_val.contains("pub fn size_of_val<T: ?Sized>(val: &T) -> usize"),
+            "{}", size_of_val);
+
+    let call = markdown("call");
+    assert!(call.contains("where F: for<'a> Fn(&'a u8) -> &'a u8"), "{}", call);
+
+    let apply = markdown("apply");
+    assert!(apply.contains("pub fn apply<F: for<'a> Fn(&'a T), T: Clone + ?Sized + Send>"),
+            "{}", apply);
+
+    let target = markdown("Target");
+    assert!(target.contains("type Target: ?Sized;"), "{}", target);
+}
+
+#[test]
+fn test_item_to_html() {
+    let docs = source_to_docs(r#"
+/// A <b>test</b> struct & more.
+pub struct MyStruct<T> {
+    pub field: T,
+}
+
+impl<T> MyStruct<T> {
+    pub fn method(&self) {}
+}
+"#);
+    let strukt = docs.iter().find(|d| d.name == "MyStruct").unwrap();
+    let html = strukt.to_html();
+
+    assert!(html.contains("<pre class=\"rust\">pub struct MyStruct&lt;T&gt;"), "{}", html);
+    assert!(html.contains("&lt;b&gt;test&lt;/b&gt; struct &amp; more."), "{}", html);
+    assert!(html.contains("<h2>Functions</h2>\n<ul>\n\
+                           <li><a href=\"crate/MyStruct/method/method.html\"><code>method</code></a></li>"),
+            "{}", html);
+    assert!(html.contains("<li><code>pub field: T</code></li>"), "{}", html);
+}
+
+#[test]
+fn test_struct_fields() {
+    let docs = source_to_docs(r#"
+pub struct Point {
+    /// The horizontal coordinate.
+    pub x: f64,
+    hidden: bool,
+}
+
+pub struct Pair(pub u8, u16);
+
+pub struct Unit;
+"#);
+    let markdown = |name: &str| {
+        docs.iter().find(|d| d.name == name).unwrap().to_markdown()
+    };
+
+    let point = markdown("Point");
+    assert!(point.contains("pub struct Point {\n    pub x: f64,\n    /* private fields */\n}"),
+            "{}", point);
+    assert!(point.contains("- `pub x: f64`\n  The horizontal coordinate.\n- `hidden: bool`"),
+            "{}", point);
+
+    let pair = markdown("Pair");
+    assert!(pair.contains("pub struct Pair(pub u8, _);"), "{}", pair);
+    assert!(pair.contains("- `pub 0: u8`\n- `1: u16`"), "{}", pair);
+
+    let unit = markdown("Unit");
+    assert!(unit.contains("pub struct Unit;"), "{}", unit);
+}
+
+#[test]
+fn test_variant_docs() {
+    let docs = source_to_docs(r#"
+pub enum Error {
+    /// The file doesn't exist. It may have been removed.
+    NotFound(String),
+    /// The file couldn't be parsed.
+    Parse {
+        /// The line of the error.
+        line: usize,
+        column: usize,
+    },
+    Other,
+}
+"#);
+    let error = docs.iter().find(|d| d.name == "Error").unwrap();
+
+    let markdown = error.to_markdown();
+    assert!(markdown.contains("- `NotFound(String)`\n  The file doesn't exist. It may have been \
+                               removed.\n- `Parse { line: usize, column: usize }`\n  \
+                               The file couldn't be parsed.\n  - `line: usize`\n    \
+                               The line of the error.\n- `Other`"),
+            "{}", markdown);
+
+    let html = error.to_html();
+    assert!(html.contains("<li><code>NotFound(String)</code>\n<p>The file doesn"),
+            "{}", html);
+    assert!(html.contains("<li><code>Other</code></li>"), "{}", html);
+
+    let mut options = FormatOptions::new();
+    options.color = false;
+    let formatted = error.format_with(&options).to_string();
+    assert!(formatted.contains("  NotFound(String)  — The file doesn't exist.\n  \
+                                Parse { line: usize, column: usize }  — The file couldn't be \
+                                parsed.\n      line  — The line of the error.\n  Other"),
+            "{}", formatted);
+}
+
+#[test]
+fn test_fn_argument_patterns() {
+    let docs = source_to_docs(r#"
+pub struct Map;
+
+impl Map {
+    pub fn insert(&mut self, key: u32, mut value: String) -> Option<String> { None }
+    pub fn into_pair<'a>(self: Box<Self>, (a, b): (u8, u8)) {}
+    pub fn get<'a>(&'a self) {}
+}
+"#);
+    let signature = |name: &str| {
+        docs.iter().find(|d| d.name == name).unwrap().to_markdown()
+    };
+
+    let insert = signature("insert");
+    assert!(insert.contains("fn insert(&mut self, key: u32, mut value: String) -> Option<String>"),
+            "{}", insert);
+    let into_pair = signature("into_pair");
+    assert!(into_pair.contains("fn into_pair<'a>(self: Box<Self>, (a, b): (u8, u8))"),
+            "{}", into_pair);
+    let get = signature("get");
+    assert!(get.contains("fn get<'a>(&'a self)"), "{}", get);
+}
+
+#[test]
+fn test_fn_qualifiers() {
+    let docs = source_to_docs(r#"
+pub const fn konst() -> u32 { 1 }
+pub unsafe extern "C" fn ffi(x: i32) {}
+pub extern "system" fn sys() {}
+"#);
+    let signature = |name: &str| {
+        docs.iter().find(|d| d.name == name).unwrap().to_markdown()
+    };
+
+    assert!(signature("konst").contains("pub const fn konst() -> u32"));
+    assert!(signature("ffi").contains("pub unsafe extern \"C\" fn ffi(x: i32)"));
+    assert!(signature("sys").contains("pub extern \"system\" fn sys()"));
+}
+
+#[test]
+fn test_lifetimes_as_written() {
+    let docs = source_to_docs(r#"
+pub fn first(s: &str) -> &str { s }
+pub fn longest<'a, 'b: 'a>(x: &'a str, y: &'b str) -> &'a str { x }
+pub fn name() -> &'static str { "" }
+
+pub struct Parser<'a>(&'a str);
+
+impl<'a> Parser<'a> {
+    pub fn peek(&self) -> Option<&str> { None }
+    pub fn rest(&mut self) -> &'a str { self.0 }
+    pub fn reborrow(&self) -> Parser<'_> { Parser(self.0) }
+    pub fn source<'s>(&'s self) -> &'s &'a str { &self.0 }
+}
+"#);
+    let signature = |name: &str| {
+        docs.iter().find(|d| d.name == name).unwrap().signature().unwrap()
+    };
+
+    assert_eq!(signature("first"), "pub fn first(s: &str) -> &str");
+    assert_eq!(signature("longest"),
+               "pub fn longest<'a, 'b: 'a>(x: &'a str, y: &'b str) -> &'a str");
+    assert_eq!(signature("name"), "pub fn name() -> &'static str");
+    assert_eq!(signature("peek"), "pub fn peek(&self) -> Option<&str>");
+    assert_eq!(signature("rest"), "pub fn rest(&mut self) -> &'a str");
+    assert_eq!(signature("reborrow"), "pub fn reborrow(&self) -> Parser<'_>");
+    assert_eq!(signature("source"), "pub fn source<'s>(&'s self) -> &'s &'a str");
+}
+
+#[test]
+fn test_trait_types() {
+    let docs = source_to_docs(r#"
+pub fn evens(limit: u32) -> impl Iterator<Item = u32> { (0..limit).filter(|n| n % 2 == 0) }
+pub fn sum(numbers: impl IntoIterator<Item = u32>) -> u32 { 0 }
+pub fn borrow_all<'a>(items: &'a [u32]) -> impl Iterator<Item = &'a u32> + 'a { items.iter() }
+pub fn fail() -> Box<Error + Send + 'static> { unimplemented!() }
+pub fn report(error: &(Error + Send + 'static)) {}
+pub fn call<F: Fn(u32)>(callbacks: Vec<Box<for<'a> Fn(&'a str)>>) {}
+"#);
+    let signature = |name: &str| {
+        docs.iter().find(|d| d.name == name).unwrap().signature().unwrap()
+    };
+
+    assert_eq!(signature("evens"), "pub fn evens(limit: u32) -> impl Iterator<Item = u32>");
+    assert_eq!(signature("sum"), "pub fn sum(numbers: impl IntoIterator<Item = u32>) -> u32");
+    assert_eq!(signature("borrow_all"),
+               "pub fn borrow_all<'a>(items: &'a [u32]) -> impl Iterator<Item = &'a u32> + 'a");
+    assert_eq!(signature("fail"), "pub fn fail() -> Box<Error + Send + 'static>");
+    assert_eq!(signature("report"), "pub fn report(error: &(Error + Send + 'static))");
+    assert_eq!(signature("call"),
+               "pub fn call<F: Fn(u32)>(callbacks: Vec<Box<for<'a> Fn(&'a str)>>)");
+}
+
+#[test]
+fn test_summary_line() {
+    let docs = source_to_docs(r#"
+pub struct Map;
+
+impl Map {
+    /// Inserts a key-value pair. Returns the old value.
+    pub fn insert(&mut self, k: u32, v: u32) -> Option<u32> { None }
+
+    pub fn clear(&mut self) {}
+}
+"#);
+    let insert = docs.iter().find(|d| d.name == "insert").unwrap();
+    assert_eq!(insert.summary_line_with_width(100),
+               "pub fn insert(&mut self, k: u32, v: u32) -> Option<u32>  — Inserts a key-value pair.");
+    assert_eq!(insert.summary_line_with_width(20), "pub fn insert(&mut …");
+
+    let clear = docs.iter().find(|d| d.name == "clear").unwrap();
+    assert_eq!(clear.summary_line_with_width(100), "pub fn clear(&mut self)");
+}
+
+#[test]
+fn test_summary_paragraph() {
+    let docs = source_to_docs(r#"
+///
+/// Opens the file at the path,
+/// creating it if needed.
+///
+/// Fails if the directory doesn't exist.
+pub fn open() {}
+
+pub fn close() {}
+"#);
+    let open = docs.iter().find(|d| d.name == "open").unwrap();
+    let paragraph = open.attrs.summary_paragraph().unwrap();
+    let lines: Vec<&str> = paragraph.lines().map(|line| line.trim()).collect();
+    assert_eq!(lines, vec!["Opens the file at the path,", "creating it if needed."]);
+    assert!(open.attrs.doc_body().contains("Fails if the directory doesn't exist."));
+    assert_eq!(open.summary(),
+               Some("Opens the file at the path, creating it if needed.".to_string()));
+
+    let close = docs.iter().find(|d| d.name == "close").unwrap();
+    assert_eq!(close.attrs.summary_paragraph(), None);
+    assert_eq!(close.summary(), None);
+}
+
+#[test]
+fn test_const_values() {
+    let docs = source_to_docs(r#"
+/// The ratio of a circle's circumference to its diameter.
+pub const PI: f64 = 3.14159;
+
+pub static GREETING: &str = "a very long greeting that goes on and on for quite a while";
+"#);
+    let pi = docs.iter().find(|d| d.name == "PI").unwrap();
+    assert_eq!(pi.signature(), Some("pub const PI: f64 = 3.14159".to_string()));
+    assert_eq!(pi.summary_line_with_width(100),
+               "pub const PI: f64 = 3.14159  — The ratio of a circle's circumference to its diameter.");
+
+    let greeting = docs.iter().find(|d| d.name == "GREETING").unwrap();
+    assert_eq!(greeting.summary_line_with_width(100),
+               "pub static GREETING: &str = \"a very long greeting that goes on and …");
+    assert!(greeting.to_markdown()
+                .contains("\"a very long greeting that goes on and on for quite a while\""),
+            "{}", greeting.to_markdown());
+}
+
+#[test]
+fn test_macro_rules() {
+    let docs = source_to_docs(r#"
+/// Squares a number.
+#[macro_export]
+macro_rules! square {
+    ($x:expr) => { $x * $x };
+    ($x:expr, $y:expr) => { $x * $y };
+}
+
+macro_rules! private {
+    () => {};
+}
+
+mod inner {
+    #[macro_export]
+    macro_rules! cube {
+        ($x:expr) => { $x * $x * $x };
+    }
+}
+"#);
+    assert!(docs.iter().find(|d| d.name == "private").is_none());
+
+    // Exported macros are used from the crate root, whichever module defines them.
+    let cube = docs.iter().find(|d| d.name == "cube").unwrap();
+    assert_eq!(cube.mod_path.to_string(), "crate::cube");
+    let krate = docs.iter().find(|d| d.mod_path.to_string() == "crate").unwrap();
+    let macros: Vec<String> = krate.subitems_in_category(&DocType::Macro).unwrap().iter()
+        .map(|link| link.path.to_string())
+        .collect();
+    assert_eq!(macros, vec!["crate::cube", "crate::square"]);
+    let filepath = cube.to_store_location().relative_filepath();
+    assert!(filepath.to_str().unwrap().starts_with("macro-"), "{}", filepath.display());
+
+    let square = docs.iter().find(|d| d.name == "square").unwrap();
+    assert_eq!(square.get_type(), DocType::Macro);
+
+    let markdown = square.to_markdown();
+    assert!(markdown.contains("# Macro crate::square"), "{}", markdown);
+    assert!(markdown.contains("macro_rules! square {\n"), "{}", markdown);
+    assert_eq!(markdown.matches("=> { ... };").count(), 2, "{}", markdown);
+    assert!(markdown.contains("Squares a number."), "{}", markdown);
+}
+
+#[test]
+fn test_impl_blocks() {
+    let docs = source_to_docs(r#"
+pub struct Wrapper<T>(T);
+
+impl<T> Wrapper<T> {
+    pub const SIZE: usize = 1;
+    pub fn new(value: T) -> Self { Wrapper(value) }
+}
+
+impl<T: Clone> Wrapper<T> where T: Default {
+    pub fn duplicate(&self) -> Self { Wrapper(self.0.clone()) }
+}
+"#);
+    let wrapper = docs.iter().find(|d| d.name == "Wrapper").unwrap();
+    let markdown = wrapper.to_markdown();
+    assert!(markdown.contains("## Implementations\n\n\
+                               ### `impl<T> Wrapper<T>`\n\n\
+                               - [`SIZE`](crate/Wrapper/SIZE/cdesc-SIZE.odoc)\n\
+                               - [`new`](crate/Wrapper/new/new.odoc)\n\n\
+                               ### `impl<T: Clone> Wrapper<T> where T: Default`\n\n\
+                               - [`duplicate`](crate/Wrapper/duplicate/duplicate.odoc)"),
+            "{}", markdown);
+
+    // The items are only listed with their impl block.
+    assert!(!markdown.contains("## Functions"), "{}", markdown);
+    assert_eq!(markdown.matches("[`new`]").count(), 1, "{}", markdown);
+    let html = wrapper.to_html();
+    assert_eq!(html.matches(">new<").count(), 1, "{}", html);
+    let mut options = FormatOptions::new();
+    options.color = false;
+    let formatted = wrapper.format_with(&options).to_string();
+    assert_eq!(formatted.matches(" new\n").count(), 1, "{}", formatted);
+
+    let duplicate = docs.iter().find(|d| d.name == "duplicate").unwrap();
+    let markdown = duplicate.to_markdown();
+    assert!(markdown.contains("*From `impl<T: Clone> Wrapper<T> where T: Default`*"),
+            "{}", markdown);
+}
+
+#[test]
+fn test_methods_and_associated_functions() {
+    let docs = source_to_docs(r#"
+pub struct Stack(Vec<u32>);
+
+impl Stack {
+    pub const LIMIT: usize = 16;
+    pub fn new() -> Self { Stack(Vec::new()) }
+    pub fn len(&self) -> usize { self.0.len() }
+}
+"#);
+    let new = docs.iter().find(|d| d.name == "new").unwrap();
+    match new.inner_data {
+        DocInnerData::FnDoc(ref function) => assert!(!function.has_receiver),
+        _ => panic!("new isn't a function"),
+    }
+
+    let stack = docs.iter().find(|d| d.name == "Stack").unwrap();
+    match stack.inner_data {
+        DocInnerData::StructDoc(ref struct_) => {
+            assert!(struct_.impls[0].is_method("len"));
+            assert!(!struct_.impls[0].is_method("new"));
+        },
+        _ => panic!("Stack isn't a struct"),
+    }
+
+    let markdown = stack.to_markdown();
+    assert!(markdown.contains("### `impl Stack`\n\n\
+                               - [`LIMIT`](crate/Stack/LIMIT/cdesc-LIMIT.odoc)\n\n\
+                               #### Associated Functions\n\n\
+                               - [`new`](crate/Stack/new/new.odoc)\n\n\
+                               #### Methods\n\n\
+                               - [`len`](crate/Stack/len/len.odoc)"),
+            "{}", markdown);
+
+    let html = stack.to_html();
+    assert!(html.contains("<h4>Associated Functions</h4>"), "{}", html);
+    assert!(html.contains("<h4>Methods</h4>"), "{}", html);
+}
+
+#[test]
+fn test_methods_with_the_same_path() {
+    let docs = source_to_docs(r#"
+pub struct Cell<T>(T);
+
+impl Cell<u8> {
+    pub fn get(&self) -> u8 { self.0 }
+}
+
+impl Cell<u16> {
+    pub fn get(value: u16) -> u16 { value }
+}
+"#);
+    let cell = docs.iter().find(|d| d.name == "Cell").unwrap();
+    match cell.inner_data {
+        DocInnerData::StructDoc(ref struct_) => {
+            let block = |ty: &str| {
+                struct_.impls.iter()
+                    .find(|block| block.header.to_string() == format!("impl Cell<{}>", ty))
+                    .unwrap()
+            };
+            assert!(block("u8").is_method("get"));
+            assert!(!block("u16").is_method("get"));
+        },
+        _ => panic!("Cell isn't a struct"),
+    }
+}
+
+#[test]
+fn test_foreign_items() {
+    let docs = source_to_docs(r#"
+extern "C" {
+    /// The length of a C string.
+    pub fn strlen(s: *const u8) -> usize;
+    pub static mut errno: i32;
+    fn hidden();
+}
+"#);
+    assert!(docs.iter().find(|d| d.name == "hidden").is_none());
+
+    let strlen = docs.iter().find(|d| d.name == "strlen").unwrap();
+    let markdown = strlen.to_markdown();
+    assert!(markdown.contains("pub unsafe extern \"C\" fn strlen(s: *const u8) -> usize"),
+            "{}", markdown);
+    assert!(markdown.contains("The length of a C string."), "{}", markdown);
+
+    let errno = docs.iter().find(|d| d.name == "errno").unwrap();
+    let markdown = errno.to_markdown();
+    assert!(markdown.contains("pub extern \"C\" static mut errno: i32\n"), "{}", markdown);
+}
+
+#[test]
+fn test_source_order() {
+    let docs = source_to_docs(r#"
+pub trait Reader {
+    fn read(&mut self) -> u8;
+    type Item;
+    const SIZE: usize;
+    fn peek(&self) -> u8 { 0 }
+}
+"#);
+    let reader = docs.iter().find(|d| d.name == "Reader").unwrap();
+    let names: Vec<&str> = reader.subitems_in_source_order(&reader.member_categories())
+        .into_iter()
+        .map(|(_, link)| &*link.name)
+        .collect();
+    assert_eq!(names, vec!["read", "Item", "SIZE", "peek"]);
+
+    let mut options = FormatOptions::new();
+    options.color = false;
+    options.source_order = true;
+    let formatted = reader.format_with(&options).to_string();
+    assert!(formatted.contains("fn read\n  type Item\n  const SIZE\n  fn peek (provided)"),
+            "{}", formatted);
+
+    options.source_order = false;
+    let formatted = reader.format_with(&options).to_string();
+    assert!(!formatted.contains("Trait Items"), "{}", formatted);
+}
+
+#[test]
+fn test_wrap_where_clauses() {
+    let docs = source_to_docs(r#"
+pub fn apply<F, T>(f: F, value: T) -> T where F: Fn(T, T) -> T, T: Clone + Into<(u8, u8)> {
+    f(value.clone(), value)
+}
+
+pub fn short<T>(value: T) where T: Clone {}
+"#);
+    let mut options = FormatOptions::new();
+    options.color = false;
+    options.signature_width = 40;
+
+    let apply = docs.iter().find(|d| d.name == "apply").unwrap();
+    let formatted = apply.format_with(&options).to_string();
+    assert!(formatted.contains("  pub fn apply<F, T>(f: F, value: T) -> T\n  \
+                                where\n      \
+                                F: Fn(T, T) -> T,\n      \
+                                T: Clone + Into<(u8, u8)>,\n"),
+            "{}", formatted);
+
+    let short = docs.iter().find(|d| d.name == "short").unwrap();
+    let formatted = short.format_with(&options).to_string();
+    assert!(formatted.contains("  pub fn short<T>(value: T) where T: Clone\n"), "{}", formatted);
+}
+
+#[test]
+fn test_hyperlinks() {
+    let docs = source_to_docs(r#"
+pub mod inner {
+    pub fn func() {}
+}
+"#);
+    let inner = docs.iter().find(|d| d.name == "inner").unwrap();
+    let func = docs.iter().find(|d| d.name == "func").unwrap();
+
+    let mut options = FormatOptions::new();
+    options.color = false;
+    let formatted = func.format_with(&options).to_string();
+    assert!(formatted.contains("Defined in test.rs:3-3\n"), "{}", formatted);
+    let formatted = inner.format_with(&options).to_string();
+    assert!(formatted.contains("  func\n"), "{}", formatted);
+    assert!(!formatted.contains("\x1b]8;;"), "{}", formatted);
+
+    options.hyperlinks = true;
+    let formatted = func.format_with(&options).to_string();
+    assert!(formatted.contains("Defined in \x1b]8;;file://"), "{}", formatted);
+    assert!(formatted.contains("/test.rs\x1b\\test.rs:3-3\x1b]8;;\x1b\\\n"), "{}", formatted);
+    let formatted = inner.format_with(&options).to_string();
+    assert!(formatted.contains(".odoc\x1b\\func\x1b]8;;\x1b\\\n"), "{}", formatted);
+}
+
+#[test]
+fn test_doc_attributes() {
+    let docs = source_to_docs(r#"
+/// Opens the file at the path.
+#[doc = "Creates it if needed."]
+///
+#[doc = "Fails if the directory\ndoesn't exist."]
+/// Never blocks.
+pub fn open() {}
+
+#[doc = "Closes the file."]
+pub fn close() {}
+"#);
+    let open = docs.iter().find(|d| d.name == "open").unwrap();
+    assert_eq!(open.attrs.doc_strings, vec!["Opens the file at the path.",
+                                            "Creates it if needed.",
+                                            "",
+                                            "Fails if the directory",
+                                            "doesn't exist.",
+                                            "Never blocks."]);
+    assert_eq!(open.summary(), Some("Opens the file at the path.".to_string()));
+
+    let close = docs.iter().find(|d| d.name == "close").unwrap();
+    assert_eq!(close.summary(), Some("Closes the file.".to_string()));
+}
@@ -1,7 +1,9 @@
-use oxidoc::conversion::Documentation;
+use oxidoc::conversion::{Documentation, DocInnerData, DocType};
 use oxidoc::document::ModPath;
+use oxidoc::generation::GenerateOptions;
 
-use util::{source_to_docs, print_paths};
+use util::{files_to_docs, source_to_docs, source_to_docs_with, source_to_skipped_items,
+           try_source_to_docs_with, print_paths};
 
 fn assert_paths_found(converted: &Vec<Documentation>, mut paths: Vec<&str>) {
     let mut converted_strings: Vec<String> = converted
@@ -63,6 +65,152 @@ pub mod test {
     assert_paths_found(&docs, vec!["crate", "crate::test"]);
 }
 
+#[test]
+fn test_doc_hidden_members() {
+    let source = r#"
+#[doc(hidden)]
+pub mod internal {
+    pub fn helper() {}
+}
+
+pub trait Visible {
+    fn shown(&self);
+    #[doc(hidden)]
+    fn __private(&self);
+}
+
+pub struct MyStruct;
+
+impl MyStruct {
+    pub fn method() {}
+    #[doc(hidden)]
+    pub fn __hidden_method() {}
+}"#;
+    let docs = source_to_docs(source);
+    assert_paths_found(&docs, vec!["crate",
+                                   "crate::Visible",
+                                   "crate::Visible::shown",
+                                   "crate::MyStruct",
+                                   "crate::MyStruct::method"]);
+
+    let krate = docs.iter().find(|d| d.name == "crate").unwrap();
+    assert!(krate.links.get(&DocType::Module).is_none());
+
+    let mut options = GenerateOptions::new();
+    options.document_hidden = true;
+    let docs = source_to_docs_with(source, &options);
+    assert_paths_found(&docs, vec!["crate",
+                                   "crate::internal",
+                                   "crate::internal::helper",
+                                   "crate::Visible",
+                                   "crate::Visible::shown",
+                                   "crate::Visible::__private",
+                                   "crate::MyStruct",
+                                   "crate::MyStruct::method",
+                                   "crate::MyStruct::__hidden_method"]);
+
+    let internal = docs.iter().find(|d| d.name == "internal").unwrap();
+    assert!(internal.attrs.hidden);
+    assert!(internal.to_markdown().contains("*Hidden from the public documentation*"));
+}
+
+#[test]
+fn test_document_private_items() {
+    let source = r#"
+pub struct Public;
+
+struct Private;
+
+pub(crate) fn internal() {}
+
+mod inner {
+    pub fn inside() {}
+}
+
+impl Public {
+    pub fn shown(&self) {}
+    fn helper(&self) {}
+}
+
+impl Clone for Public {
+    fn clone(&self) -> Public { Public }
+}
+"#;
+    let docs = source_to_docs(source);
+    assert_paths_found(&docs, vec!["crate",
+                                   "crate::Public",
+                                   "crate::Public::shown",
+                                   "crate::Public::clone"]);
+    assert!(docs.iter().all(|doc| !doc.is_private()));
+
+    let mut options = GenerateOptions::new();
+    options.document_private = true;
+    let docs = source_to_docs_with(source, &options);
+    assert_paths_found(&docs, vec!["crate",
+                                   "crate::Public",
+                                   "crate::Private",
+                                   "crate::internal",
+                                   "crate::inner",
+                                   "crate::inner::inside",
+                                   "crate::Public::shown",
+                                   "crate::Public::helper",
+                                   "crate::Public::clone"]);
+
+    let private: Vec<String> = docs.iter()
+        .filter(|doc| doc.is_private())
+        .map(|doc| doc.mod_path.to_string())
+        .collect();
+    assert_eq!(private.len(), 5);
+    for path in &["crate::Private", "crate::internal", "crate::inner", "crate::inner::inside",
+                  "crate::Public::helper"] {
+        assert!(private.contains(&path.to_string()), "{}", path);
+    }
+
+    let helper = docs.iter().find(|d| d.name == "helper").unwrap();
+    assert!(helper.to_markdown().contains("*Private: not part of the crate's public API*"));
+}
+
+#[test]
+fn test_select_modules() {
+    let source = r#"
+pub mod outer {
+    pub mod inner {
+        pub struct Selected;
+
+        impl Selected {
+            pub fn method(&self) {}
+        }
+    }
+
+    pub fn sibling() {}
+}
+
+pub fn top() {}
+"#;
+    let mut options = GenerateOptions::new();
+    options.modules = vec!["crate::outer::inner".to_string()];
+    let docs = source_to_docs_with(source, &options);
+    assert_paths_found(&docs, vec!["crate",
+                                   "crate::outer",
+                                   "crate::outer::inner",
+                                   "crate::outer::inner::Selected",
+                                   "crate::outer::inner::Selected::method"]);
+
+    let outer = docs.iter().find(|d| d.name == "outer").unwrap();
+    let links: Vec<String> = outer.links.values()
+        .flat_map(|links| links.iter().map(|link| link.path.to_string()))
+        .collect();
+    assert_eq!(links, vec!["crate::outer::inner".to_string()]);
+
+    options.modules = vec!["outer::inner::Selected".to_string()];
+    let docs = source_to_docs_with(source, &options);
+    assert!(docs.iter().any(|d| d.name == "method"));
+    assert!(docs.iter().all(|d| d.name != "sibling" && d.name != "top"));
+
+    options.modules = vec!["outer::missing".to_string()];
+    assert!(try_source_to_docs_with(source, &options).is_err());
+}
+
 #[test]
 fn test_one_method() {
     let docs = source_to_docs(
@@ -97,7 +245,6 @@ pub mod a {
     assert_paths_found(&docs, vec!["crate", "crate::a", "crate::a::b"]);
 }
 
-#[cfg(never)]
 #[test]
 fn test_private_module() {
     let docs = source_to_docs("mod a { }");
@@ -245,3 +392,315 @@ pub mod a {
         ]
     );
 }
+
+#[test]
+fn test_one_union() {
+    let docs = source_to_docs(r#"
+pub union MyUnion {
+    pub a: u32,
+    pub b: f32,
+}
+
+impl MyUnion {
+    pub fn method() {}
+}
+"#);
+    assert_paths_found(
+        &docs,
+        vec![
+            "crate",
+            "crate::MyUnion",
+            "crate::MyUnion::method",
+        ]
+    );
+}
+
+#[test]
+fn test_statics() {
+    let docs = source_to_docs(r#"
+pub static FOO: u32 = 1;
+pub static mut BAR: u32 = 2;
+"#);
+    assert_paths_found(&docs, vec!["crate", "crate::FOO", "crate::BAR"]);
+}
+
+#[test]
+fn test_typedef() {
+    let docs = source_to_docs(r#"
+pub mod error {
+    pub struct Error;
+    pub type Result<T> = ::std::result::Result<T, Error>;
+}
+"#);
+    assert_paths_found(
+        &docs,
+        vec![
+            "crate",
+            "crate::error",
+            "crate::error::Error",
+            "crate::error::Result",
+        ],
+    );
+
+    let typedef = docs.iter().find(|d| d.name == "Result").unwrap();
+    match typedef.inner_data {
+        DocInnerData::TypedefDoc(ref t) => {
+            assert_eq!(t.generics.to_string(), "<T>");
+            assert_eq!(t.ty.name, "::std::result::Result<T, Error>");
+        }
+        _ => panic!("Expected a typedef"),
+    }
+}
+
+#[test]
+fn test_fn_generics() {
+    let docs = source_to_docs(r#"
+pub fn parse<'a, T: FromStr + 'a, U = u8>(s: &'a str) -> Result<T, T::Err> where T: Debug {}
+"#);
+
+    let func = docs.iter().find(|d| d.name == "parse").unwrap();
+    match func.inner_data {
+        DocInnerData::FnDoc(ref f) => {
+            assert_eq!(f.generics.to_string(), "<'a, T: FromStr + 'a, U = u8>");
+            assert_eq!(f.generics.where_predicates, vec!["T: Debug".to_string()]);
+        }
+        _ => panic!("Expected a function"),
+    }
+}
+
+#[test]
+fn test_restricted_visibility() {
+    let docs = source_to_docs(r#"
+pub mod a {
+    pub mod b {
+        pub struct Fields {
+            pub(crate) krate: u32,
+            pub(super) sup: u32,
+            pub(in a) path: u32,
+            private: u32,
+        }
+    }
+}
+"#);
+
+    let strukt = docs.iter().find(|d| d.name == "Fields").unwrap();
+    match strukt.inner_data {
+        DocInnerData::StructDoc(ref s) => {
+            let vis: Vec<String> = s.fields.iter().map(|f| f.vis.to_string()).collect();
+            assert_eq!(vis, vec!["pub(crate)", "pub(super)", "pub(in a)", ""]);
+        }
+        _ => panic!("Expected a struct"),
+    }
+}
+
+#[test]
+fn test_source_spans() {
+    let docs = source_to_docs(r#"
+/// Documented.
+pub fn func() {
+}
+
+pub struct MyStruct;
+
+impl MyStruct {
+    pub fn method(&self) {}
+}
+"#);
+
+    let func = docs.iter().find(|d| d.name == "func").unwrap();
+    let source = func.source.as_ref().unwrap();
+    assert_eq!(source.file.to_str(), Some("test.rs"));
+    assert_eq!((source.lo_line, source.hi_line), (3, 4));
+
+    let method = docs.iter().find(|d| d.name == "method").unwrap();
+    let source = method.source.as_ref().unwrap();
+    assert_eq!((source.lo_line, source.hi_line), (9, 9));
+}
+
+#[test]
+fn test_path_attribute_modules() {
+    let docs = files_to_docs("path_attribute", &[
+        ("lib.rs", r#"
+#[path = "custom.rs"]
+pub mod renamed;
+
+pub mod outer;
+"#),
+        ("custom.rs", r#"
+pub fn inside() {}
+"#),
+        ("outer.rs", r#"
+#[path = "generated/inner_impl.rs"]
+pub mod inner;
+"#),
+        ("generated/inner_impl.rs", r#"
+
+pub struct Deep;
+"#),
+    ]);
+
+    assert_paths_found(&docs, vec!["crate",
+                                   "crate::renamed",
+                                   "crate::renamed::inside",
+                                   "crate::outer",
+                                   "crate::outer::inner",
+                                   "crate::outer::inner::Deep"]);
+
+    let source_file = |name: &str| {
+        let doc = docs.iter().find(|d| d.name == name).unwrap();
+        doc.source.as_ref().unwrap().file.clone()
+    };
+    assert!(source_file("inside").ends_with("custom.rs"));
+    assert!(source_file("renamed").ends_with("custom.rs"));
+    assert!(source_file("Deep").ends_with("generated/inner_impl.rs"));
+
+    let deep = docs.iter().find(|d| d.name == "Deep").unwrap();
+    assert_eq!(deep.source.as_ref().unwrap().lo_line, 3);
+}
+
+#[test]
+fn test_skipped_items() {
+    let source = r#"
+pub struct MyStruct;
+
+impl Iterator for MyStruct {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> { None }
+}
+
+lazy_static! {
+    pub static ref TABLE: u32 = 1;
+}
+
+pub mod inner {
+    impl super::MyStruct {
+        pub fn method() {}
+    }
+}
+
+impl Missing {
+    pub fn method() {}
+}
+
+impl<'a> Iterator for &'a MyStruct {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> { None }
+}
+
+impl<T> From<T> for Vec<MyStruct> {
+    fn from(_: T) -> Vec<MyStruct> { Vec::new() }
+}
+"#;
+    let skipped = source_to_skipped_items(source);
+
+    let found: Vec<(&str, String)> = skipped.iter()
+        .map(|item| (item.kind.as_str(), item.path.to_string()))
+        .collect();
+    assert!(found.contains(&("associated type", "crate::MyStruct::Item".to_string())),
+            "{:?}", found);
+    assert!(found.contains(&("macro invocation", "crate".to_string())), "{:?}", found);
+    // Only the impl of the crate's own type is missing something, not those of types of other
+    // crates or unknown types.
+    assert!(found.contains(&("impl", "crate::inner".to_string())), "{:?}", found);
+    assert_eq!(skipped.len(), 3);
+
+    let lazy = skipped.iter().find(|item| item.kind == "macro invocation").unwrap();
+    assert_eq!(lazy.source.as_ref().unwrap().lo_line, 9);
+    assert!(lazy.to_string().contains("`lazy_static!`"), "{}", lazy);
+
+    let mut options = GenerateOptions::new();
+    options.strict = true;
+    let result = try_source_to_docs_with(source, &options);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_duplicate_paths_disambiguated() {
+    let docs = source_to_docs(r#"
+pub struct Thing;
+
+impl Thing {
+    pub fn new() -> Thing { Thing }
+}
+
+impl Thing {
+    pub fn new() -> Thing { Thing }
+}
+"#);
+
+    let news: Vec<&Documentation> = docs.iter().filter(|d| d.name == "new").collect();
+    assert_eq!(news.len(), 2);
+    assert_ne!(news[0].to_store_location().to_filepath().unwrap(),
+               news[1].to_store_location().to_filepath().unwrap());
+}
+
+#[test]
+fn test_enum_variants() {
+    let docs = source_to_docs(r#"
+pub enum Foo {
+    Unit,
+    Bar(u32, String),
+    Baz { x: i32 },
+    Five = 5,
+}
+"#);
+
+    let enum_ = docs.iter().find(|d| d.name == "Foo").unwrap();
+    match enum_.inner_data {
+        DocInnerData::EnumDoc(ref e) => {
+            let variants: Vec<String> = e.variants.iter().map(|v| v.to_string()).collect();
+            assert_eq!(variants, vec!["Unit", "Bar(u32, String)", "Baz { x: i32 }", "Five = 5"]);
+        }
+        _ => panic!("Expected an enum"),
+    }
+}
+
+#[test]
+fn test_trait_provided_methods() {
+    let docs = source_to_docs(r#"
+pub trait Foo {
+    fn required(&self);
+    fn provided(&self) -> u32 { 42 }
+}
+"#);
+
+    assert_paths_found(&docs, vec!["crate",
+                                   "crate::Foo",
+                                   "crate::Foo::required",
+                                   "crate::Foo::provided"]);
+
+    let trait_ = docs.iter().find(|d| d.name == "Foo").unwrap();
+    let names = |doc_type| -> Vec<String> {
+        trait_.links.get(&doc_type).unwrap().iter().map(|l| l.name.clone()).collect()
+    };
+    assert_eq!(names(DocType::TraitItemMethod), vec!["required"]);
+    assert_eq!(names(DocType::TraitItemProvidedMethod), vec!["provided"]);
+
+    let provided = docs.iter().find(|d| d.name == "provided").unwrap();
+    assert_eq!(provided.get_type(), DocType::TraitItemProvidedMethod);
+}
+
+#[test]
+fn test_mod_path_filepath_round_trip() {
+    let docs = source_to_docs(r#"
+pub mod outer {
+    pub mod inner {
+        pub struct Vec3;
+
+        impl Vec3 {
+            pub fn len(&self) -> f32 { 0.0 }
+        }
+    }
+}
+"#);
+
+    for doc in docs.iter() {
+        let path = ModPath::from_filepath(doc.mod_path.to_filepath()).unwrap();
+        assert_eq!(path, doc.mod_path);
+    }
+
+    assert!(ModPath::from_filepath("").is_err());
+    assert!(ModPath::from_filepath("/crate/a").is_err());
+    assert!(ModPath::from_filepath("crate/../a").is_err());
+    assert!(ModPath::from_filepath("crate/a.b").is_err());
+}
@@ -0,0 +1,488 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use oxidoc::conversion::{self, DocInnerData, DocType, Visibility};
+use oxidoc::generation::{self, GenerateOptions};
+use util::{TempDir, TempStore};
+
+/// Writes the JSON to a file in the directory, for importing.
+fn write_json(dir: &TempDir, json: &str) -> PathBuf {
+    let path = dir.path().join("crate.json");
+    File::create(&path).unwrap().write_all(json.as_bytes()).unwrap();
+    path
+}
+
+const RUSTDOC_JSON: &str = r##"{
+  "root": 0,
+  "crate_version": "0.1.0",
+  "format_version": 39,
+  "index": {
+    "0": {
+      "id": 0, "crate_id": 0, "name": "mycrate", "visibility": "public",
+      "docs": "A test crate.", "attrs": [], "deprecation": null, "span": null,
+      "inner": {"module": {"is_crate": true, "items": [1, 2, 3, 5, 6]}}
+    },
+    "1": {
+      "id": 1, "crate_id": 0, "name": "add", "visibility": "public",
+      "docs": "Adds two numbers.", "attrs": ["#[must_use]"], "deprecation": null,
+      "span": {"filename": "src/lib.rs", "begin": [3, 0], "end": [5, 1]},
+      "inner": {"function": {
+        "sig": {
+          "inputs": [["a", {"primitive": "i32"}], ["b", {"primitive": "i32"}]],
+          "output": {"primitive": "i32"},
+          "is_c_variadic": false
+        },
+        "generics": {"params": [], "where_predicates": []},
+        "header": {"is_const": false, "is_unsafe": false, "is_async": false, "abi": "Rust"},
+        "has_body": true
+      }}
+    },
+    "2": {
+      "id": 2, "crate_id": 0, "name": "Point", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"struct": {
+        "kind": {"plain": {"fields": [4, 8], "has_stripped_fields": false}},
+        "generics": {"params": [], "where_predicates": []},
+        "impls": [7]
+      }}
+    },
+    "3": {
+      "id": 3, "crate_id": 0, "name": "Shape", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"trait": {
+        "is_auto": false, "is_unsafe": false, "is_dyn_compatible": true,
+        "items": [10],
+        "generics": {"params": [], "where_predicates": []},
+        "bounds": [],
+        "implementations": []
+      }}
+    },
+    "4": {
+      "id": 4, "crate_id": 0, "name": "x", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"struct_field": {"primitive": "f64"}}
+    },
+    "5": {
+      "id": 5, "crate_id": 0, "name": "u8", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"primitive": {"name": "u8", "impls": []}}
+    },
+    "6": {
+      "id": 6, "crate_id": 0, "name": "square", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"macro": "macro_rules! square {\n    ($x:expr) => { ... };\n}"}
+    },
+    "7": {
+      "id": 7, "crate_id": 0, "name": null, "visibility": "default",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"impl": {
+        "is_unsafe": false,
+        "generics": {"params": [], "where_predicates": []},
+        "provided_trait_methods": [],
+        "trait": null,
+        "for": {"resolved_path": {"path": "Point", "id": 2, "args": null}},
+        "items": [9],
+        "is_negative": false,
+        "is_synthetic": false,
+        "blanket_impl": null
+      }}
+    },
+    "8": {
+      "id": 8, "crate_id": 0, "name": "y", "visibility": "default",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"struct_field": {"primitive": "f64"}}
+    },
+    "9": {
+      "id": 9, "crate_id": 0, "name": "norm", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"function": {
+        "sig": {
+          "inputs": [["self", {"borrowed_ref": {
+            "lifetime": null, "is_mutable": false, "type": {"generic": "Self"}
+          }}]],
+          "output": {"primitive": "f64"},
+          "is_c_variadic": false
+        },
+        "generics": {"params": [], "where_predicates": []},
+        "header": {"is_const": false, "is_unsafe": false, "is_async": false, "abi": "Rust"},
+        "has_body": true
+      }}
+    },
+    "10": {
+      "id": 10, "crate_id": 0, "name": "area", "visibility": "default",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"function": {
+        "sig": {
+          "inputs": [["self", {"borrowed_ref": {
+            "lifetime": null, "is_mutable": false, "type": {"generic": "Self"}
+          }}]],
+          "output": {"primitive": "f64"},
+          "is_c_variadic": false
+        },
+        "generics": {"params": [], "where_predicates": []},
+        "header": {"is_const": false, "is_unsafe": false, "is_async": false, "abi": "Rust"},
+        "has_body": false
+      }}
+    }
+  },
+  "paths": {}
+}"##;
+
+#[test]
+fn test_from_rustdoc_json() {
+    let dir = TempDir::new("rustdoc");
+    let path = write_json(&dir, RUSTDOC_JSON);
+
+    let docs = conversion::from_rustdoc_json(&path).expect("Import failed");
+
+    let mut paths: Vec<String> = docs.iter().map(|doc| doc.mod_path.to_string()).collect();
+    paths.sort();
+    // The primitive isn't supported, so it's skipped.
+    assert_eq!(paths, vec!["mycrate",
+                           "mycrate::Point",
+                           "mycrate::Point::norm",
+                           "mycrate::Shape",
+                           "mycrate::Shape::area",
+                           "mycrate::add",
+                           "mycrate::square"]);
+
+    let add = docs.iter().find(|doc| doc.name == "add").unwrap();
+    assert_eq!(add.crate_info.version, "0.1.0");
+    assert_eq!(add.attrs.must_use, Some("".to_string()));
+    assert_eq!(add.source.as_ref().map(|span| span.lo_line), Some(3));
+    match add.inner_data {
+        DocInnerData::FnDoc(ref func) => assert_eq!(func.decl.to_string(),
+                                                    "(a: i32, b: i32) -> i32"),
+        _ => panic!("add isn't a function"),
+    }
+
+    let point = docs.iter().find(|doc| doc.name == "Point").unwrap();
+    match point.inner_data {
+        DocInnerData::StructDoc(ref struct_) => {
+            assert_eq!(struct_.fields.len(), 2);
+            assert_eq!(struct_.fields[1].vis, Visibility::Inherited);
+            assert_eq!(struct_.impls.len(), 1);
+        }
+        _ => panic!("Point isn't a struct"),
+    }
+    let methods = point.subitems_in_category(&DocType::Function).unwrap();
+    assert_eq!(methods[0].path.to_string(), "mycrate::Point::norm");
+
+    let norm = docs.iter().find(|doc| doc.name == "norm").unwrap();
+    match norm.inner_data {
+        DocInnerData::FnDoc(ref func) => assert_eq!(func.decl.to_string(), "(&self) -> f64"),
+        _ => panic!("norm isn't a function"),
+    }
+
+    let shape = docs.iter().find(|doc| doc.name == "Shape").unwrap();
+    assert!(shape.subitems_in_category(&DocType::TraitItemMethod).is_some());
+}
+
+const TRAIT_TYPES_JSON: &str = r##"{
+  "root": 0,
+  "crate_version": "0.1.0",
+  "format_version": 39,
+  "index": {
+    "0": {
+      "id": 0, "crate_id": 0, "name": "traits", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"module": {"is_crate": true, "items": [1, 2]}}
+    },
+    "1": {
+      "id": 1, "crate_id": 0, "name": "report", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"function": {
+        "sig": {
+          "inputs": [["error", {"borrowed_ref": {
+            "lifetime": null, "is_mutable": false, "type": {"dyn_trait": {
+              "traits": [
+                {"trait": {"path": "Error", "id": 10, "args": null}, "generic_params": []},
+                {"trait": {"path": "Send", "id": 11, "args": null}, "generic_params": []}
+              ],
+              "lifetime": "'static"
+            }}
+          }}]],
+          "output": {"resolved_path": {"path": "Box", "id": 12, "args": {"angle_bracketed": {
+            "args": [{"type": {"dyn_trait": {
+              "traits": [{"trait": {"path": "Error", "id": 10, "args": null},
+                          "generic_params": []}],
+              "lifetime": null
+            }}}],
+            "constraints": []
+          }}}},
+          "is_c_variadic": false
+        },
+        "generics": {"params": [], "where_predicates": []},
+        "header": {"is_const": false, "is_unsafe": false, "is_async": false, "abi": "Rust"},
+        "has_body": true
+      }}
+    },
+    "2": {
+      "id": 2, "crate_id": 0, "name": "evens", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"function": {
+        "sig": {
+          "inputs": [["callback", {"resolved_path": {"path": "Box", "id": 12, "args": {
+            "angle_bracketed": {"args": [{"type": {"dyn_trait": {
+              "traits": [{
+                "trait": {"path": "Fn", "id": 13, "args": {"parenthesized": {
+                  "inputs": [{"borrowed_ref": {
+                    "lifetime": "'a", "is_mutable": false, "type": {"primitive": "str"}
+                  }}],
+                  "output": null
+                }}},
+                "generic_params": [{"name": "'a", "kind": {"lifetime": {"outlives": []}}}]
+              }],
+              "lifetime": null
+            }}}], "constraints": []}
+          }}}]],
+          "output": {"impl_trait": [
+            {"trait_bound": {
+              "trait": {"path": "Iterator", "id": 14, "args": {"angle_bracketed": {
+                "args": [],
+                "constraints": [{"name": "Item", "args": null,
+                                 "binding": {"equality": {"type": {"primitive": "u32"}}}}]
+              }}},
+              "generic_params": [],
+              "modifier": "none"
+            }},
+            {"outlives": "'static"}
+          ]},
+          "is_c_variadic": false
+        },
+        "generics": {"params": [], "where_predicates": []},
+        "header": {"is_const": false, "is_unsafe": false, "is_async": false, "abi": "Rust"},
+        "has_body": true
+      }}
+    }
+  },
+  "paths": {}
+}"##;
+
+#[test]
+fn test_rustdoc_json_trait_types() {
+    let dir = TempDir::new("rustdoc_trait_types");
+    let path = write_json(&dir, TRAIT_TYPES_JSON);
+
+    let docs = conversion::from_rustdoc_json(&path).expect("Import failed");
+    let decl = |name: &str| {
+        match docs.iter().find(|doc| doc.name == name).unwrap().inner_data {
+            DocInnerData::FnDoc(ref func) => func.decl.to_string(),
+            _ => panic!("{} isn't a function", name),
+        }
+    };
+
+    assert_eq!(decl("report"), "(error: &(dyn Error + Send + 'static)) -> Box<dyn Error>");
+    assert_eq!(decl("evens"),
+               "(callback: Box<dyn for<'a> Fn(&'a str)>) -> impl Iterator<Item = u32> + 'static");
+}
+
+const RELAXED_BOUNDS_JSON: &str = r##"{
+  "root": 0,
+  "crate_version": "0.1.0",
+  "format_version": 39,
+  "index": {
+    "0": {
+      "id": 0, "crate_id": 0, "name": "bounds", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"module": {"is_crate": true, "items": [1, 2]}}
+    },
+    "1": {
+      "id": 1, "crate_id": 0, "name": "inspect", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"function": {
+        "sig": {
+          "inputs": [["value", {"borrowed_ref": {
+            "lifetime": null, "is_mutable": false, "type": {"generic": "T"}
+          }}], ["f", {"generic": "F"}]],
+          "output": null,
+          "is_c_variadic": false
+        },
+        "generics": {
+          "params": [
+            {"name": "T", "kind": {"type": {
+              "bounds": [
+                {"trait_bound": {"trait": {"path": "Sized", "id": 10, "args": null},
+                                 "generic_params": [], "modifier": "maybe"}},
+                {"trait_bound": {"trait": {"path": "Send", "id": 11, "args": null},
+                                 "generic_params": [], "modifier": "none"}}
+              ],
+              "default": null, "is_synthetic": false
+            }}},
+            {"name": "F", "kind": {"type": {
+              "bounds": [], "default": null, "is_synthetic": false
+            }}}
+          ],
+          "where_predicates": [{"bound_predicate": {
+            "type": {"generic": "F"},
+            "bounds": [{"trait_bound": {
+              "trait": {"path": "Fn", "id": 12, "args": {"parenthesized": {
+                "inputs": [{"borrowed_ref": {
+                  "lifetime": "'a", "is_mutable": false, "type": {"generic": "T"}
+                }}],
+                "output": null
+              }}},
+              "generic_params": [],
+              "modifier": "none"
+            }}],
+            "generic_params": [{"name": "'a", "kind": {"lifetime": {"outlives": []}}}]
+          }}]
+        },
+        "header": {"is_const": false, "is_unsafe": false, "is_async": false, "abi": "Rust"},
+        "has_body": true
+      }}
+    },
+    "2": {
+      "id": 2, "crate_id": 0, "name": "Deref", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"trait": {
+        "is_auto": false, "is_unsafe": false, "is_dyn_compatible": true,
+        "items": [3],
+        "generics": {"params": [], "where_predicates": []},
+        "bounds": [],
+        "implementations": []
+      }}
+    },
+    "3": {
+      "id": 3, "crate_id": 0, "name": "Target", "visibility": "default",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"assoc_type": {
+        "generics": {"params": [], "where_predicates": []},
+        "bounds": [{"trait_bound": {"trait": {"path": "Sized", "id": 10, "args": null},
+                                    "generic_params": [], "modifier": "maybe"}}],
+        "type": null
+      }}
+    }
+  },
+  "paths": {}
+}"##;
+
+#[test]
+fn test_rustdoc_json_relaxed_bounds() {
+    let dir = TempDir::new("rustdoc_relaxed_bounds");
+    let path = write_json(&dir, RELAXED_BOUNDS_JSON);
+
+    let docs = conversion::from_rustdoc_json(&path).expect("Import failed");
+
+    let inspect = docs.iter().find(|doc| doc.name == "inspect").unwrap();
+    match inspect.inner_data {
+        DocInnerData::FnDoc(ref func) => {
+            assert_eq!(func.generics.to_string(), "<T: ?Sized + Send, F>");
+            assert_eq!(func.generics.where_clause(), " where for<'a> F: Fn(&'a T)");
+        },
+        _ => panic!("inspect isn't a function"),
+    }
+
+    let target = docs.iter().find(|doc| doc.name == "Target").unwrap();
+    let markdown = target.to_markdown();
+    assert!(markdown.contains("type Target: ?Sized;"), "{}", markdown);
+}
+
+const PRIVATE_MODULE_JSON: &str = r##"{
+  "root": 0,
+  "crate_version": "0.1.0",
+  "format_version": 39,
+  "index": {
+    "0": {
+      "id": 0, "crate_id": 0, "name": "reexports", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"module": {"is_crate": true, "items": [1, 2]}}
+    },
+    "1": {
+      "id": 1, "crate_id": 0, "name": "inner", "visibility": "default",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"module": {"is_crate": false, "items": [3, 4]}}
+    },
+    "2": {
+      "id": 2, "crate_id": 0, "name": null, "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"use": {"source": "inner::Exported", "name": "Exported", "id": 3,
+                        "is_glob": false}}
+    },
+    "3": {
+      "id": 3, "crate_id": 0, "name": "Exported", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"struct": {
+        "kind": "unit",
+        "generics": {"params": [], "where_predicates": []},
+        "impls": []
+      }}
+    },
+    "4": {
+      "id": 4, "crate_id": 0, "name": "NotExported", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"struct": {
+        "kind": "unit",
+        "generics": {"params": [], "where_predicates": []},
+        "impls": []
+      }}
+    }
+  },
+  "paths": {
+    "3": {"crate_id": 0, "path": ["reexports", "inner", "Exported"], "kind": "struct"},
+    "4": {"crate_id": 0, "path": ["reexports", "inner", "NotExported"], "kind": "struct"}
+  }
+}"##;
+
+#[test]
+fn test_rustdoc_json_reexports_from_private_modules() {
+    let dir = TempDir::new("rustdoc_private_module");
+    let path = write_json(&dir, PRIVATE_MODULE_JSON);
+
+    let docs = conversion::from_rustdoc_json(&path).expect("Import failed");
+
+    let mut paths: Vec<String> = docs.iter().map(|doc| doc.mod_path.to_string()).collect();
+    paths.sort();
+    assert_eq!(paths, vec!["reexports", "reexports::inner::Exported"]);
+
+    let krate = docs.iter().find(|doc| doc.name == "reexports").unwrap();
+    let reexported = krate.subitems_in_category(&DocType::Reexport).unwrap();
+    assert_eq!(reexported[0].path.to_string(), "reexports::inner::Exported");
+}
+
+const UNSUPPORTED_KINDS_JSON: &str = r##"{
+  "root": 0,
+  "crate_version": "0.1.0",
+  "format_version": 39,
+  "index": {
+    "0": {
+      "id": 0, "crate_id": 0, "name": "aliases", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"module": {"is_crate": true, "items": [1, 2]}}
+    },
+    "1": {
+      "id": 1, "crate_id": 0, "name": "Shareable", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"trait_alias": {
+        "generics": {"params": [], "where_predicates": []},
+        "params": []
+      }}
+    },
+    "2": {
+      "id": 2, "crate_id": 0, "name": "core", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"extern_crate": {"name": "core", "rename": null}}
+    }
+  },
+  "paths": {}
+}"##;
+
+#[test]
+fn test_rustdoc_json_unsupported_kinds_skipped() {
+    let dir = TempDir::new("rustdoc_unsupported_kinds");
+    let path = write_json(&dir, UNSUPPORTED_KINDS_JSON);
+
+    let (docs, skipped) = conversion::from_rustdoc_json_with_skipped(&path)
+        .expect("Import failed");
+    assert_eq!(docs.len(), 1);
+    // Extern crates aren't documented, but aren't reported either.
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0].kind, "trait alias");
+    assert_eq!(skipped[0].path.to_string(), "aliases::Shareable");
+
+    let _store = TempStore::new("rustdoc_unsupported_kinds");
+    let mut options = GenerateOptions::new();
+    options.strict = true;
+    assert!(generation::generate_docs_from_rustdoc_json(&path, &options).is_err());
+}
@@ -0,0 +1,130 @@
+use std::fs;
+
+use oxidoc::diff::{diff_doc_dirs, diff_docs};
+use oxidoc::generation::GenerateOptions;
+
+use util::{get_crate_info, source_to_docs, source_to_docs_with, TempStore};
+
+#[test]
+fn test_diff_docs() {
+    let old = source_to_docs(r#"
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+pub fn distance(a: &Point, b: &Point) -> f64 { 0.0 }
+
+pub fn origin() -> Point { Point { x: 0, y: 0 } }
+"#);
+    let new = source_to_docs(r#"
+pub struct Point {
+    pub x: i32,
+    y: i32,
+}
+
+pub fn distance(a: &Point, b: &Point) -> f32 { 0.0 }
+
+pub fn center() -> Point { Point { x: 0, y: 0 } }
+"#);
+    let diff = diff_docs(&old, &new);
+
+    let added: Vec<&str> = diff.added.iter().map(|item| &*item.path).collect();
+    assert_eq!(added, vec!["crate::center"]);
+    let removed: Vec<&str> = diff.removed.iter().map(|item| &*item.path).collect();
+    assert_eq!(removed, vec!["crate::origin"]);
+
+    let point = diff.modified.iter().find(|change| change.path == "crate::Point").unwrap();
+    assert_eq!(point.breaking, vec!["field `y` is no longer public".to_string()]);
+
+    let distance = diff.modified.iter().find(|change| change.path == "crate::distance").unwrap();
+    assert_eq!(distance.breaking, vec!["signature changed".to_string()]);
+    assert_eq!(distance.new_signature,
+               Some("pub fn distance(a: &Point, b: &Point) -> f32".to_string()));
+    assert!(diff.is_breaking());
+
+    let text = diff.to_string();
+    assert!(text.contains("Added:\n  + crate::center: pub fn center() -> Point"), "{}", text);
+    assert!(text.contains("  ~ crate::Point [breaking: field `y` is no longer public]"),
+            "{}", text);
+
+    assert!(diff_docs(&old, &old).is_empty());
+}
+
+#[test]
+fn test_diff_private_items() {
+    let mut options = GenerateOptions::new();
+    options.document_private = true;
+    let old = source_to_docs_with(r#"
+pub fn kept() {}
+
+fn helper() {}
+
+mod imp {
+    pub fn inside() {}
+}
+"#, &options);
+    let new = source_to_docs_with("pub fn kept() {}", &options);
+
+    let diff = diff_docs(&old, &new);
+    assert_eq!(diff.removed.len(), 3);
+    assert!(diff.removed.iter().all(|item| item.private));
+    assert!(!diff.is_breaking());
+    assert_eq!(diff.breaking_count(), 0);
+}
+
+#[test]
+fn test_diff_generics_and_field_types() {
+    let old = source_to_docs(r#"
+pub struct Wrapper<T> {
+    pub value: T,
+    count: usize,
+}
+"#);
+    let new = source_to_docs(r#"
+pub struct Wrapper<T: Clone> {
+    pub value: Option<T>,
+    count: u32,
+}
+"#);
+    let diff = diff_docs(&old, &new);
+
+    let wrapper = diff.modified.iter().find(|change| change.path == "crate::Wrapper").unwrap();
+    assert_eq!(wrapper.breaking, vec!["generic parameters or bounds changed".to_string(),
+                                      "type of field `value` changed".to_string()]);
+}
+
+#[test]
+fn test_diff_doc_dirs() {
+    let temp_store = TempStore::new("diff_doc_dirs");
+    let source = r#"
+pub struct MyStruct;
+
+pub fn func() {}
+"#;
+
+    let mut truncated = None;
+    for version in vec!["0.1.0", "0.2.0"] {
+        for mut doc in source_to_docs(source) {
+            doc.crate_info = get_crate_info("oxidoc_test_diff", version);
+            doc.save().expect("Write failed");
+
+            let path = doc.to_store_location().to_filepath().unwrap();
+            if path.ends_with("func.odoc") {
+                truncated = Some(path);
+            }
+        }
+    }
+    let old_dir = temp_store.path().join("oxidoc_test_diff-0.1.0");
+    let new_dir = temp_store.path().join("oxidoc_test_diff-0.2.0");
+    assert!(diff_doc_dirs(&old_dir, &new_dir).expect("Diff failed").is_empty());
+
+    let truncated = truncated.unwrap();
+    let length = fs::metadata(&truncated).unwrap().len();
+    fs::OpenOptions::new().write(true).open(&truncated).unwrap().set_len(length - 2).unwrap();
+
+    let diff = diff_doc_dirs(&old_dir, &new_dir).expect("Diff failed");
+    assert_eq!(diff.unreadable.len(), 1);
+    assert_eq!(diff.unreadable[0].path, truncated);
+    assert!(diff.to_string().contains("Unreadable:\n  ! "), "{}", diff);
+}
@@ -0,0 +1,47 @@
+use log::LogLevelFilter;
+use oxidoc::conversion::DocType;
+use oxidoc::document::ModPath;
+use oxidoc::generation::{GenerateOptions, Verbosity};
+use oxidoc::store::StoreLocation;
+use util::{self, TempStore};
+
+#[test]
+fn test_verbosity_log_level() {
+    assert_eq!(Verbosity::Quiet.log_level(), LogLevelFilter::Error);
+    assert_eq!(Verbosity::Normal.log_level(), LogLevelFilter::Warn);
+    assert_eq!(Verbosity::Verbose.log_level(), LogLevelFilter::Info);
+    assert_eq!(GenerateOptions::new().verbosity.log_level(), LogLevelFilter::Warn);
+}
+
+#[test]
+fn test_log_filter_leaves_other_crates() {
+    // Nothing but oxidoc logs at more than its own level.
+    assert_eq!(Verbosity::Quiet.log_builder(None).build().filter(), LogLevelFilter::Error);
+    assert_eq!(Verbosity::Verbose.log_builder(None).build().filter(), LogLevelFilter::Info);
+
+    // Other crates keep the levels `RUST_LOG` gives them, whatever oxidoc's is.
+    let logger = Verbosity::Quiet.log_builder(Some("syntax=debug")).build();
+    assert_eq!(logger.filter(), LogLevelFilter::Debug);
+    let logger = Verbosity::Verbose.log_builder(Some("syntax=error")).build();
+    assert_eq!(logger.filter(), LogLevelFilter::Info);
+}
+
+#[test]
+fn test_json_copies() {
+    let _store = TempStore::new("json_copies");
+    let location = |name: &str| {
+        StoreLocation::new(name.to_string(),
+                           util::get_crate_info("crate", "1.0.0"),
+                           ModPath::from(format!("crate::{}", name)),
+                           DocType::Function)
+    };
+
+    util::source_to_store_with("pub fn without_copy() {}", &GenerateOptions::new());
+    assert!(location("without_copy").to_filepath().unwrap().is_file());
+    assert!(!location("without_copy").to_json_filepath().unwrap().exists());
+
+    let mut options = GenerateOptions::new();
+    options.json_copies = true;
+    util::source_to_store_with("pub fn with_copy() {}", &options);
+    assert!(location("with_copy").to_json_filepath().unwrap().is_file());
+}
@@ -0,0 +1,122 @@
+use oxidoc::conversion::{DocInnerData, DocType, Documentation};
+use oxidoc::document::ModPath;
+use oxidoc::merge::{merge_generations, Generation};
+use util::{get_crate_info, source_to_docs, TempStore};
+
+fn generation(features: &[&str], src: &str) -> Generation {
+    Generation {
+        features: features.iter().map(|f| f.to_string()).collect(),
+        documents: source_to_docs(src),
+    }
+}
+
+#[test]
+fn test_merge_generations() {
+    let merged = merge_generations(vec![
+        generation(&["std"], r#"
+pub fn always() {}
+
+pub fn parse(input: &str) -> u32 { 0 }
+"#),
+        generation(&["std", "serde"], r#"
+pub fn always() {}
+
+pub fn parse(input: &[u8]) -> u32 { 0 }
+
+pub fn serialize() {}
+"#),
+    ]);
+
+    let paths: Vec<String> = merged.documents.iter().map(|d| d.mod_path.to_string()).collect();
+    assert_eq!(paths, vec!["crate", "crate::always", "crate::parse", "crate::serialize"]);
+
+    let always = merged.documents.iter().find(|d| d.name == "always").unwrap();
+    assert!(always.attrs.feature_sets.is_empty());
+
+    let serialize = merged.documents.iter().find(|d| d.name == "serialize").unwrap();
+    assert_eq!(serialize.attrs.feature_sets,
+               vec![vec!["std".to_string(), "serde".to_string()]]);
+    assert!(serialize.to_markdown().contains("*Available with features [std, serde] only*"),
+            "{}", serialize.to_markdown());
+
+    let krate = merged.documents.iter().find(|d| d.name == "crate").unwrap();
+    let functions: Vec<String> = krate.links.get(&DocType::Function).unwrap()
+        .iter()
+        .map(|link| link.name.clone())
+        .collect();
+    assert_eq!(functions, vec!["always", "parse", "serialize"]);
+
+    assert_eq!(merged.conflicts.len(), 1);
+    let conflict = &merged.conflicts[0];
+    assert_eq!(conflict.path, "crate::parse");
+    assert_eq!(conflict.signatures[0].1, Some("pub fn parse(input: &str) -> u32".to_string()));
+    assert_eq!(conflict.signatures[1].1, Some("pub fn parse(input: &[u8]) -> u32".to_string()));
+
+    // The first generation's signature is kept.
+    let parse = merged.documents.iter().find(|d| d.name == "parse").unwrap();
+    assert_eq!(parse.signature(), Some("pub fn parse(input: &str) -> u32".to_string()));
+    assert_eq!(parse.attrs.feature_signatures,
+               vec![(vec!["std".to_string()], "pub fn parse(input: &str) -> u32".to_string()),
+                    (vec!["std".to_string(), "serde".to_string()],
+                     "pub fn parse(input: &[u8]) -> u32".to_string())]);
+    assert!(always.attrs.feature_signatures.is_empty());
+}
+
+#[test]
+fn test_merged_signatures_round_trip() {
+    let _store = TempStore::new("merged_signatures");
+    let merged = merge_generations(vec![
+        generation(&["std"], "pub fn parse(input: &str) -> u32 { 0 }"),
+        generation(&["bytes"], "pub fn parse(input: &[u8]) -> u32 { 0 }"),
+    ]);
+    for doc in merged.documents.iter() {
+        doc.save().expect("Write failed");
+    }
+
+    let info = get_crate_info("crate", "1.0.0");
+    let path = ModPath::from("crate::parse".to_string());
+    let parse = Documentation::load(&info, &path, DocType::Function, 0).expect("Load failed");
+    assert_eq!(parse.attrs.feature_signatures.len(), 2);
+
+    let markdown = parse.to_markdown();
+    assert!(markdown.contains("*With features [bytes]:*\n```rust\npub fn parse(input: &[u8])"),
+            "{}", markdown);
+    let html = parse.to_html();
+    assert!(html.contains("With features [std]:</p>"), "{}", html);
+}
+
+#[test]
+fn test_merge_impls() {
+    let merged = merge_generations(vec![
+        generation(&["std"], r#"
+pub struct Parser;
+
+impl Parser {
+    pub fn parse_str(&self) {}
+}
+"#),
+        generation(&["bytes"], r#"
+pub struct Parser;
+
+impl Parser {
+    pub fn parse_bytes(&self) {}
+    pub fn parse_str(&self) {}
+}
+"#),
+    ]);
+
+    let parser = merged.documents.iter().find(|d| d.name == "Parser").unwrap();
+    let impls = match parser.inner_data {
+        DocInnerData::StructDoc(ref struct_) => &struct_.impls,
+        _ => panic!("Not a struct"),
+    };
+    assert_eq!(impls.len(), 1);
+
+    // `parse_str` is at another position in the second generation, but it's still the same item.
+    let mut functions: Vec<String> = impls[0].items.get(&DocType::Function).unwrap()
+        .iter()
+        .map(|link| link.name.clone())
+        .collect();
+    functions.sort();
+    assert_eq!(functions, vec!["parse_bytes", "parse_str"]);
+}
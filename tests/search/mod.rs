@@ -1 +1,5 @@
 mod test_lookup;
+mod test_fuzzy;
+mod test_full_text;
+mod test_completion;
+mod test_crate_index;
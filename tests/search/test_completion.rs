@@ -0,0 +1,53 @@
+use oxidoc::completion::{CompletionIndex, CompletionItem};
+use oxidoc::store::CrateIndex;
+use util::{get_crate_info, source_to_docs};
+
+const SOURCE: &str = r#"
+pub trait Shape {
+    fn area(&self) -> f64;
+}
+
+pub struct Square;
+
+impl Square {
+    pub fn new(side: f64) -> Square { Square }
+}
+
+impl Shape for Square {
+    fn area(&self) -> f64 { 0.0 }
+}
+
+pub const SIDES: u32 = 4;
+
+pub mod util {
+    pub fn   round(x: f64)
+        -> f64 { x }
+}
+"#;
+
+fn item(path: &str, kind: &str, signature: Option<&str>) -> CompletionItem {
+    CompletionItem {
+        path: path.to_string(),
+        kind: kind.to_string(),
+        signature: signature.map(|s| s.to_string()),
+    }
+}
+
+#[test]
+fn test_completion_items() {
+    let mut index = CompletionIndex::new();
+    index.add_docs(&get_crate_info("crate", "1.0.0"), &source_to_docs(SOURCE));
+
+    let items = index.items();
+    let paths: Vec<&str> = items.iter().map(|item| item.path.as_str()).collect();
+    assert!(paths.contains(&"crate::Shape"), "{:?}", paths);
+    assert!(paths.contains(&"crate::Square::new"), "{:?}", paths);
+    assert!(paths.contains(&"crate::SIDES"), "{:?}", paths);
+
+    let round = items.iter().find(|item| item.path == "crate::util::round").unwrap();
+    assert_eq!(**round, item("crate::util::round", "fn", Some("pub fn round(x: f64) -> f64")));
+
+    let mut sorted = paths.clone();
+    sorted.sort();
+    assert_eq!(paths, sorted);
+}
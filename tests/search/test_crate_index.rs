@@ -0,0 +1,71 @@
+use oxidoc::completion::CompletionIndex;
+use oxidoc::conversion::Documentation;
+use oxidoc::document::CrateInfo;
+use oxidoc::full_text::FullTextIndex;
+use oxidoc::search::SearchFilter;
+use oxidoc::store::CrateIndex;
+use util::{get_crate_info, source_to_docs, TempStore};
+
+fn crate_docs(crate_info: &CrateInfo, src: &str) -> Vec<Documentation> {
+    let mut docs = source_to_docs(src);
+    for doc in docs.iter_mut() {
+        doc.crate_info = crate_info.clone();
+    }
+    docs
+}
+
+/// Checks that regenerating a crate replaces its entries in the index and keeps the other
+/// crates', given the paths of the items the index has entries for.
+fn check_regeneration<I: CrateIndex, F: Fn(&I) -> Vec<String>>(paths: F) {
+    let info = get_crate_info("crate", "1.0.0");
+    let other = get_crate_info("other", "0.1.0");
+    let mut index = I::new();
+    index.add_docs(&info, &crate_docs(&info, "/// Documented.\npub struct Square;"));
+    index.add_docs(&other, &crate_docs(&other, "/// Documented.\npub fn other() {}"));
+
+    index.add_docs(&info, &crate_docs(&info, "/// Documented.\npub struct Replaced;"));
+    let mut found = paths(&index);
+    found.sort();
+    assert_eq!(found, vec!["crate::Replaced", "crate::other"]);
+
+    // An index made since this one was loaded replaces the entries of its crates.
+    let mut newer = I::new();
+    newer.add_docs(&info, &crate_docs(&info, "/// Documented.\npub struct Merged;"));
+    index.merge(newer, &[info.clone()]);
+    let mut found = paths(&index);
+    found.sort();
+    assert_eq!(found, vec!["crate::Merged", "crate::other"]);
+
+    index.remove_crate(&info);
+    assert_eq!(paths(&index), vec!["crate::other"]);
+}
+
+#[test]
+fn test_full_text_regeneration() {
+    check_regeneration(|index: &FullTextIndex| {
+        index.search("documented", 10, &SearchFilter::new()).iter()
+            .map(|result| result.location.mod_path.to_string())
+            .collect()
+    });
+}
+
+#[test]
+fn test_completion_regeneration() {
+    check_regeneration(|index: &CompletionIndex| {
+        index.items().iter().map(|item| item.path.clone()).collect()
+    });
+}
+
+#[test]
+fn test_crate_index_persistence() {
+    let _store = TempStore::new("crate_index_persistence");
+    assert!(CompletionIndex::load().is_err());
+    assert!(CompletionIndex::load_or_new().items().is_empty());
+
+    let mut index = CompletionIndex::new();
+    index.add_docs(&get_crate_info("crate", "1.0.0"), &source_to_docs("pub fn saved() {}"));
+    index.save().expect("Write failed");
+
+    let loaded = CompletionIndex::load().expect("Read failed");
+    assert_eq!(loaded.items(), index.items());
+}
@@ -0,0 +1,48 @@
+use oxidoc::conversion::DocType;
+use oxidoc::full_text::FullTextIndex;
+use oxidoc::search::SearchFilter;
+use oxidoc::store::CrateIndex;
+use util::{get_crate_info, source_to_docs};
+
+fn index_from_source(src: &str) -> FullTextIndex {
+    let mut index = FullTextIndex::new();
+    index.add_docs(&get_crate_info("crate", "1.0.0"), &source_to_docs(src));
+    index
+}
+
+const SOURCE: &str = r#"
+/// A queue that is thread safe. Every method of it is thread safe, so it can be shared between
+/// threads without a lock.
+pub struct Queue;
+
+/// Spawns a new thread. The closure must be safe to send to it.
+pub fn spawn() {}
+
+/// Not thread safe.
+pub mod local {}
+
+/// Nothing relevant.
+pub fn other() {}
+"#;
+
+#[test]
+fn test_full_text_search() {
+    let index = index_from_source(SOURCE);
+
+    let results = index.search("thread safe", 10, &SearchFilter::new());
+    let paths: Vec<String> = results.iter().map(|r| r.location.mod_path.to_string()).collect();
+    assert_eq!(paths, vec!["crate::Queue", "crate::local", "crate::spawn"]);
+    assert_eq!(results[0].score, 4);
+    assert!(results[0].snippet.starts_with("A queue that is thread safe."), "{}", results[0].snippet);
+    assert!(results[0].snippet.ends_with("..."), "{}", results[0].snippet);
+
+    assert!(index.search("THREAD", 10, &SearchFilter::new()).len() == 3);
+    assert!(index.search("thread lock", 10, &SearchFilter::new()).len() == 1);
+    assert!(index.search("missing", 10, &SearchFilter::new()).is_empty());
+
+    let mut filter = SearchFilter::new();
+    filter.doc_types.push(DocType::Function);
+    let results = index.search("thread safe", 10, &filter);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].location.name, "spawn");
+}
@@ -0,0 +1,260 @@
+use oxidoc::conversion::DocType;
+use oxidoc::errors::ErrorKind;
+use oxidoc::search::{self, Scorer, SearchFilter, SubsequenceScorer};
+use oxidoc::store::{SearchIndex, Store};
+use oxidoc::generation;
+use util;
+
+fn store_from_source(src: &str) -> Store {
+    let docs = util::source_to_docs(src);
+    let docset = generation::make_docset(docs, 2).unwrap();
+
+    let mut store = Store::new();
+    store.add_docset(util::get_crate_info("crate", "1.0.0"), docset);
+    store
+}
+
+#[test]
+fn test_subsequence_scorer() {
+    let scorer = SubsequenceScorer;
+    assert!(scorer.score("hashmp", "HashMap").is_some());
+    assert!(scorer.score("hsahmap", "HashMap").is_none());
+    assert!(scorer.score("hashmap", "HashMap") > scorer.score("hashmap", "HashMapExt"));
+}
+
+#[test]
+fn test_fuzzy_search() {
+    let _store = util::TempStore::new("fuzzy_search");
+    let store = store_from_source(r#"
+pub struct HashMap;
+pub struct HashSet;
+pub fn hash() {}
+"#);
+
+    let results = search::fuzzy_search_with(&store, "hashmp", 10, &SearchFilter::new(), &SubsequenceScorer);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].0.name, "HashMap");
+
+    let results = search::fuzzy_search_with(&store, "hash", 2, &SearchFilter::new(), &SubsequenceScorer);
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].0.name, "hash");
+}
+
+struct PrefixScorer;
+
+impl Scorer for PrefixScorer {
+    fn score(&self, query: &str, candidate: &str) -> Option<f64> {
+        if candidate.starts_with(query) { Some(1.0) } else { None }
+    }
+}
+
+#[test]
+fn test_custom_scorer() {
+    let _store = util::TempStore::new("custom_scorer");
+    let store = store_from_source(r#"
+pub struct HashMap;
+"#);
+
+    let results = search::fuzzy_search_with(&store, "Hash", 10, &SearchFilter::new(), &PrefixScorer);
+    assert_eq!(results.len(), 1);
+    let results = search::fuzzy_search_with(&store, "hashmp", 10, &SearchFilter::new(), &PrefixScorer);
+    assert!(results.is_empty());
+}
+
+#[test]
+fn test_filter_by_type() {
+    let _store = util::TempStore::new("filter_by_type");
+    let store = store_from_source(r#"
+pub struct Hash;
+pub trait Hasher {}
+pub fn hash() {}
+"#);
+
+    let mut filter = SearchFilter::new();
+    filter.doc_types.push(DocType::Trait);
+
+    let results = search::fuzzy_search_with(&store, "hash", 10, &filter, &SubsequenceScorer);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].0.name, "Hasher");
+
+    filter.doc_types.push("fn".parse().unwrap());
+    let results = search::fuzzy_search_with(&store, "hash", 10, &filter, &SubsequenceScorer);
+    assert_eq!(results.len(), 2);
+}
+
+#[test]
+fn test_filter_by_type_includes_trait_items() {
+    let _store = util::TempStore::new("filter_by_type_includes_trait_items");
+    let store = store_from_source(r#"
+pub trait Hasher {
+    const HASH_SIZE: usize;
+    const HASH_SEED: u64 = 0;
+    fn hash_required(&self);
+    fn hash_provided(&self) {}
+}
+
+pub const HASH_MAX: u64 = 0;
+"#);
+
+    let mut filter = SearchFilter::new();
+    filter.doc_types.push("method".parse().unwrap());
+    let results = search::fuzzy_search_with(&store, "hash", 10, &filter, &SubsequenceScorer);
+    let mut names: Vec<&str> = results.iter().map(|r| r.0.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["hash_provided", "hash_required"]);
+
+    let mut filter = SearchFilter::new();
+    filter.doc_types.push("const".parse().unwrap());
+    let results = search::fuzzy_search_with(&store, "hash", 10, &filter, &SubsequenceScorer);
+    let mut names: Vec<&str> = results.iter().map(|r| r.0.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["HASH_MAX", "HASH_SEED", "HASH_SIZE"]);
+}
+
+#[test]
+fn test_search_index() {
+    let _store = util::TempStore::new("search_index");
+    let store = store_from_source(r#"
+pub struct HashMap;
+pub fn hash() {}
+"#);
+    let index = SearchIndex::new(&store);
+    assert_eq!(index.locations.len(), store.all_locations().len());
+
+    let from_index = search::fuzzy_search_locations(&index.locations, "hash", 10,
+                                                    &SearchFilter::new(), &SubsequenceScorer);
+    let from_store = search::fuzzy_search_with(&store, "hash", 10,
+                                               &SearchFilter::new(), &SubsequenceScorer);
+    assert_eq!(from_index, from_store);
+}
+
+#[test]
+fn test_resolve_partial_path() {
+    let _store = util::TempStore::new("resolve_partial_path");
+    let store = store_from_source(r#"
+pub mod map {
+    pub struct HashMap;
+
+    impl HashMap {
+        pub fn insert(&mut self) {}
+    }
+}
+
+pub mod set {
+    pub struct HashSet;
+
+    impl HashSet {
+        pub fn insert(&mut self) {}
+    }
+}
+"#);
+    let locations = store.all_locations();
+    let paths = |query: &str| {
+        search::resolve_partial_path(&locations, query).iter()
+            .map(|loc| loc.mod_path.to_string())
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(paths("insert"), vec!["crate::map::HashMap::insert",
+                                     "crate::set::HashSet::insert"]);
+    assert_eq!(paths("hashmap::insert"), vec!["crate::map::HashMap::insert"]);
+    assert_eq!(paths("crate::set::HashSet"), vec!["crate::set::HashSet"]);
+    assert_eq!(paths("CRATE::Set::hashset"), vec!["crate::set::HashSet"]);
+    assert!(paths("remove").is_empty());
+}
+
+#[test]
+fn test_fuzzy_search_aliases() {
+    let _store = util::TempStore::new("fuzzy_search_aliases");
+    let store = store_from_source(r#"
+#[doc(alias = "malloc")]
+pub fn allocate() {}
+"#);
+
+    let results = search::fuzzy_search_with(&store, "malloc", 10, &SearchFilter::new(),
+                                            &SubsequenceScorer);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].0.name, "allocate");
+}
+
+#[test]
+fn test_search_modes() {
+    let _store = util::TempStore::new("search_modes");
+    use oxidoc::search::SearchMode;
+
+    let store = store_from_source(r#"
+pub struct HashMap;
+pub struct HashSet;
+pub struct IndexMap;
+pub fn hash() {}
+"#);
+    let locations = store.all_locations();
+    let names = |query: &str, mode: SearchMode| -> Vec<String> {
+        search::name_search_locations(&locations, query, mode, 10, &SearchFilter::new())
+            .into_iter()
+            .map(|(link, _)| link.name)
+            .collect()
+    };
+
+    assert_eq!(names("HashMap", SearchMode::Exact), vec!["HashMap"]);
+    assert!(names("hashmap", SearchMode::Exact).is_empty());
+    assert_eq!(names("crate::HashSet", SearchMode::Exact), vec!["HashSet"]);
+
+    assert_eq!(SearchMode::default(), SearchMode::CaseInsensitive);
+    assert_eq!(names("hashmap", SearchMode::CaseInsensitive), vec!["HashMap"]);
+    // Both match at the start of a word, so the shorter name ranks higher.
+    assert_eq!(names("map", SearchMode::CaseInsensitive), vec!["HashMap", "IndexMap"]);
+    assert_eq!(names("hash", SearchMode::CaseInsensitive)[0], "hash");
+    assert_eq!(names("Map", SearchMode::CaseInsensitive)[0], "HashMap");
+
+    assert_eq!(names("Hash", SearchMode::Prefix), vec!["HashMap", "HashSet", "hash"]);
+    assert_eq!(names("hash", SearchMode::Prefix)[0], "hash");
+    assert_eq!(names("crate::Index", SearchMode::Prefix), vec!["IndexMap"]);
+    assert!(names("Map", SearchMode::Prefix).is_empty());
+
+    assert_eq!(SearchMode::from_name("prefix"), Some(SearchMode::Prefix));
+    assert_eq!(SearchMode::from_name("insensitive"), Some(SearchMode::CaseInsensitive));
+    assert_eq!(SearchMode::from_name("fuzzy"), None);
+}
+
+#[test]
+fn test_regex_search() {
+    let _store = util::TempStore::new("regex_search");
+    let store = store_from_source(r#"
+pub struct Buffer;
+
+impl Buffer {
+    pub fn new() -> Buffer { Buffer }
+    pub fn new_with_capacity(capacity: usize) -> Buffer { Buffer }
+    pub fn renew(&mut self) {}
+}
+
+pub struct NewType;
+"#);
+    let locations = store.all_locations();
+    let names = |pattern: &str, filter: &SearchFilter| -> Vec<String> {
+        search::regex_search_locations(&locations, pattern, 10, filter)
+            .unwrap()
+            .into_iter()
+            .map(|(link, _)| link.name)
+            .collect()
+    };
+
+    assert_eq!(names("^new", &SearchFilter::new()), vec!["new", "new_with_capacity"]);
+    assert_eq!(names("(?i)^new", &SearchFilter::new()),
+               vec!["new", "NewType", "new_with_capacity"]);
+    let mut methods = names("Buffer::.*new$", &SearchFilter::new());
+    methods.sort();
+    assert_eq!(methods, vec!["new", "renew"]);
+
+    let mut filter = SearchFilter::new();
+    filter.doc_types.push(DocType::Struct);
+    assert_eq!(names("(?i)^new", &filter), vec!["NewType"]);
+
+    let error = search::regex_search_locations(&locations, "new(", 10, &SearchFilter::new())
+        .unwrap_err();
+    match *error.kind() {
+        ErrorKind::InvalidRegex(ref pattern, _) => assert_eq!(pattern, "new("),
+        ref kind => panic!("Unexpected error: {}", kind),
+    }
+}
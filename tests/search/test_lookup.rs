@@ -1,10 +1,17 @@
+use std::fs::File;
+use std::io::Write;
+
+use oxidoc::conversion;
+use oxidoc::document::ModPath;
 use oxidoc::store::Store;
 use oxidoc::generation;
+use oxidoc::search::{self, JsonResult};
+use serde_json;
 use util;
 
 fn add_docs(store: &mut Store, krate_name: &str, version: &str, src: &str) {
     let docs = util::source_to_docs(src);
-    let docset = generation::make_docset(docs).unwrap();
+    let docset = generation::make_docset(docs, 2).unwrap();
 
     store.add_docset(util::get_crate_info(krate_name, version), docset);
 }
@@ -36,12 +43,14 @@ fn assert_search_query(store: &Store, query: &str, expected_paths: Vec<&str>) {
 
 #[test]
 fn test_search_ignores_case() {
+    let _store = util::TempStore::new("search_ignores_case");
     let store = store_from_source("pub struct Test;");
     assert_search_query(&store, "test", vec!["crate::Test"]);
 }
 
 #[test]
 fn test_nested_search() {
+    let _store = util::TempStore::new("nested_search");
     let store = store_from_source(r#"
 pub mod a {
     pub mod b {
@@ -56,6 +65,7 @@ pub mod a {
 
 #[test]
 fn test_same_segment_name() {
+    let _store = util::TempStore::new("same_segment_name");
     let store = store_from_source(r#"
 pub mod nyanko {
     pub struct Nyanko;
@@ -66,6 +76,7 @@ pub mod nyanko {
 
 #[test]
 fn test_search_for_removed_item() {
+    let _store = util::TempStore::new("search_for_removed_item");
     let mut store = Store::new();
     add_docs(&mut store, "crate", "0.0.1", r#"
 pub mod stuff {
@@ -78,3 +89,156 @@ pub mod stuff {
 "#);
     assert_search_query(&store, "stuff::depreciated", vec!["crate::stuff::depreciated"]);
 }
+
+#[test]
+fn test_same_path_different_items() {
+    let _store = util::TempStore::new("same_path_different_items");
+    let store = store_from_source(r#"
+pub struct Thing;
+pub mod thing {}
+"#);
+    assert_search_query(&store, "thing", vec!["crate::Thing", "crate::thing"]);
+}
+
+#[test]
+fn test_search_reexport() {
+    let _store = util::TempStore::new("search_reexport");
+    let store = store_from_source(r#"
+pub mod inner {
+    pub struct Hidden;
+}
+
+pub use inner::Hidden;
+"#);
+    // Both paths lead to the original item.
+    assert_search_query(&store, "crate::Hidden", vec!["crate::inner::Hidden"]);
+    assert_search_query(&store, "inner::Hidden", vec!["crate::inner::Hidden"]);
+}
+
+#[test]
+fn test_search_renamed_reexport() {
+    let _store = util::TempStore::new("search_renamed_reexport");
+    let store = store_from_source(r#"
+pub mod inner {
+    pub struct Original;
+    pub struct Listed;
+}
+
+pub use inner::Original as Renamed;
+pub use inner::{Listed as AlsoRenamed};
+"#);
+    assert_search_query(&store, "crate::Renamed", vec!["crate::inner::Original"]);
+    assert_search_query(&store, "AlsoRenamed", vec!["crate::inner::Listed"]);
+    assert_search_query(&store, "crate::Original", vec![]);
+}
+
+#[test]
+fn test_search_by_alias() {
+    let _store = util::TempStore::new("search_by_alias");
+    let store = store_from_source(r#"
+#[doc(alias = "malloc")]
+#[doc(alias = "alloc")]
+pub fn allocate() {}
+
+pub fn free() {}
+"#);
+    assert_search_query(&store, "malloc", vec!["crate::allocate"]);
+    assert_search_query(&store, "ALLOC", vec!["crate::allocate"]);
+    assert_search_query(&store, "free", vec!["crate::free"]);
+}
+
+#[test]
+fn test_json_result() {
+    let docs = util::source_to_docs(r#"
+/// Makes a new widget. It starts out empty.
+pub fn make_widget() {}
+
+pub struct Widget;
+"#);
+    let result = |name: &str| {
+        let doc = docs.iter().find(|d| d.name == name).unwrap();
+        serde_json::to_string(&JsonResult::new(doc)).unwrap()
+    };
+
+    assert_eq!(result("make_widget"),
+               concat!(r#"{"name":"make_widget","path":"crate::make_widget","type":"fn","#,
+                       r#""summary":"Makes a new widget.","deprecated":false}"#));
+    assert_eq!(result("Widget"),
+               concat!(r#"{"name":"Widget","path":"crate::Widget","type":"struct","#,
+                       r#""summary":null,"deprecated":false}"#));
+}
+
+const RAW_IDENTS_JSON: &str = r##"{
+  "root": 0,
+  "crate_version": "1.0.0",
+  "format_version": 39,
+  "index": {
+    "0": {
+      "id": 0, "crate_id": 0, "name": "raw", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"module": {"is_crate": true, "items": [1]}}
+    },
+    "1": {
+      "id": 1, "crate_id": 0, "name": "r#type", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"module": {"is_crate": false, "items": [2, 3]}}
+    },
+    "2": {
+      "id": 2, "crate_id": 0, "name": "r#async", "visibility": "public",
+      "docs": "Runs [r#match] later.", "attrs": [], "deprecation": null, "span": null,
+      "inner": {"function": {
+        "sig": {"inputs": [], "output": null, "is_c_variadic": false},
+        "generics": {"params": [], "where_predicates": []},
+        "header": {"is_const": false, "is_unsafe": false, "is_async": false, "abi": "Rust"},
+        "has_body": true
+      }}
+    },
+    "3": {
+      "id": 3, "crate_id": 0, "name": "r#match", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"struct": {
+        "kind": "unit",
+        "generics": {"params": [], "where_predicates": []},
+        "impls": []
+      }}
+    }
+  },
+  "paths": {}
+}"##;
+
+#[test]
+fn test_raw_identifiers() {
+    let dir = util::TempDir::new("raw_idents");
+    let path = dir.path().join("raw.json");
+    File::create(&path).unwrap().write_all(RAW_IDENTS_JSON.as_bytes()).unwrap();
+    let docs = conversion::from_rustdoc_json(&path).expect("Import failed");
+
+    let func = docs.iter().find(|d| d.name == "r#async").unwrap();
+    assert_eq!(func.mod_path.to_string(), "raw::r#type::r#async");
+    assert_eq!(func.signature(), Some("pub fn r#async()".to_string()));
+    let links = func.subitems_in_category(&conversion::DocType::Struct).unwrap();
+    assert_eq!(links[0].path.to_string(), "raw::r#type::r#match");
+
+    let location = func.to_store_location();
+    let filepath = location.relative_filepath();
+    assert_eq!(ModPath::from_filepath(filepath.parent().unwrap()).unwrap().to_string(),
+               "r#type");
+
+    let locations: Vec<_> = docs.iter().map(|doc| doc.to_store_location()).collect();
+    let found = search::resolve_partial_path(&locations, "type::async");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].mod_path.to_string(), "raw::r#type::r#async");
+
+    let temp_store = util::TempStore::new("raw_identifiers");
+    let docset = generation::make_docset(docs, 2).unwrap();
+    drop(temp_store);
+    let mut store = Store::new();
+    store.add_docset(util::get_crate_info("raw", "1.0.0"), docset);
+
+    assert_search_query(&store, "async", vec!["raw::r#type::r#async"]);
+    assert_search_query(&store, "r#async", vec!["raw::r#type::r#async"]);
+    assert_search_query(&store, "type::match", vec!["raw::r#type::r#match"]);
+    assert_search_query(&store, "r#type", vec!["raw::r#type",
+                                               "raw::r#type::r#async",
+                                               "raw::r#type::r#match"]);
+}
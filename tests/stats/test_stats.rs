@@ -0,0 +1,53 @@
+use oxidoc::conversion::DocType;
+use oxidoc::stats::{doc_stats, TypeStats};
+use util::source_to_docs;
+
+#[test]
+fn test_doc_stats() {
+    let docs = source_to_docs(r#"
+//! The crate.
+
+/// Documented.
+pub fn documented() {}
+
+pub fn undocumented() {}
+
+///
+pub fn empty_doc() {}
+
+pub(crate) fn internal() {}
+
+/// A point.
+pub struct Point;
+
+/// A shape.
+pub trait Shape {
+    /// The area.
+    fn area(&self) -> f64;
+
+    fn name(&self) -> String { String::new() }
+}
+"#);
+    let stats = doc_stats(&docs);
+
+    assert_eq!(stats.by_type.get(&DocType::Function),
+               Some(&TypeStats { total: 3, documented: 1 }));
+    assert_eq!(stats.by_type.get(&DocType::Struct),
+               Some(&TypeStats { total: 1, documented: 1 }));
+    assert_eq!(stats.by_type.get(&DocType::TraitItemMethod),
+               Some(&TypeStats { total: 1, documented: 1 }));
+    assert_eq!(stats.by_type.get(&DocType::TraitItemProvidedMethod),
+               Some(&TypeStats { total: 1, documented: 0 }));
+
+    let total = stats.total();
+    assert_eq!(total.total - total.documented, 3);
+    assert!(stats.coverage() < 100.0);
+    assert!(stats.to_string().ends_with(&format!("{:.1}%", stats.coverage())));
+}
+
+#[test]
+fn test_empty_stats() {
+    let stats = doc_stats(&[]);
+    assert_eq!(stats.total(), TypeStats { total: 0, documented: 0 });
+    assert_eq!(stats.coverage(), 100.0);
+}
@@ -0,0 +1,3 @@
+mod test_serialization;
+mod test_crates;
+mod test_site;
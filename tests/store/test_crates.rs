@@ -0,0 +1,347 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Write;
+
+use oxidoc::completion::CompletionIndex;
+use oxidoc::conversion::Documentation;
+use oxidoc::document::ModPath;
+use oxidoc::generation::GenerateOptions;
+use oxidoc::generation::plan::GenerationPlan;
+use oxidoc::store::{self, CrateIndex, StoreLayout};
+
+use util::{get_crate_info, source_to_docs, source_to_store_as, source_to_store_with, TempDir,
+           TempStore};
+
+#[test]
+fn test_list_crates() {
+    let _store = TempStore::new("list_crates");
+    let info = get_crate_info("oxidoc_test_list_crates", "0.1.0");
+    store::save_crate_metadata(&info).expect("Write failed");
+
+    let crates = store::list_crates().expect("Listing failed");
+    assert!(crates.contains(&info));
+}
+
+#[test]
+fn test_remove_crate() {
+    let _store = TempStore::new("remove_crate");
+    let info = get_crate_info("oxidoc_test_remove_crate", "0.1.0");
+    store::save_crate_metadata(&info).expect("Write failed");
+
+    store::remove_crate(&info).expect("Removal failed");
+    assert!(!store::list_crates().unwrap().contains(&info));
+    assert!(store::remove_crate(&info).is_err());
+}
+
+#[test]
+fn test_latest_crate_version() {
+    let _store = TempStore::new("latest_crate_version");
+    for version in &["0.9.0", "1.0.0-beta.2", "1.0.0", "1.0.0-rc.1", "0.300.0", "nightly"] {
+        let info = get_crate_info("oxidoc_test_latest", version);
+        store::save_crate_metadata(&info).expect("Write failed");
+    }
+
+    let latest = store::latest_crate_version("oxidoc_test_latest").expect("No version found");
+    assert_eq!(latest.version, "1.0.0");
+
+    let in_order = ["0.9.0", "0.300.0", "1.0.0-beta.2", "1.0.0-beta.11", "1.0.0-rc.1", "1.0.0",
+                    "1.0.1+build"];
+    for pair in in_order.windows(2) {
+        assert!(store::parse_version(pair[0]) < store::parse_version(pair[1]), "{:?}", pair);
+    }
+    assert!(store::parse_version("nightly").is_none());
+    assert!(store::parse_version("1.0").is_none());
+}
+
+#[test]
+fn test_crate_metadata() {
+    let _store = TempStore::new("crate_metadata");
+    let info = get_crate_info("oxidoc_test_crate_metadata", "0.1.0");
+    store::save_crate_metadata(&info).expect("Write failed");
+
+    let meta = store::crate_metadata(&info).expect("Read failed");
+    assert_eq!(meta.crate_info, info);
+    assert!(meta.generated_at > 0);
+    assert_eq!(meta.layout, StoreLayout::from_env());
+}
+
+#[test]
+fn test_mixed_store_layouts() {
+    let temp_store = TempStore::new("mixed_store_layouts");
+    let info = get_crate_info("oxidoc_test_mixed_layouts", "0.1.0");
+    store::save_crate_metadata(&info).expect("Write failed");
+    assert!(store::check_crate_layout(&info).is_ok());
+
+    // Documentation generated earlier in another layout.
+    let mut meta = store::crate_metadata(&info).expect("Read failed");
+    meta.layout = match StoreLayout::from_env() {
+        StoreLayout::Hashed => StoreLayout::Prefixed,
+        _ => StoreLayout::Hashed,
+    };
+    let meta_path = temp_store.path().join(info.to_path_prefix()).join("meta.json");
+    store::serialize_object_json(&meta, &meta_path).expect("Write failed");
+
+    let error = store::check_crate_layout(&info).unwrap_err().to_string();
+    assert!(error.contains(meta.layout.name()), "{}", error);
+    assert!(error.contains("--remove"), "{}", error);
+
+    // Metadata from before layouts were recorded is read as prefixed.
+    let mut file = File::create(&meta_path).unwrap();
+    write!(file, "{{\"crate_info\": {{\"name\": \"oxidoc_test_mixed_layouts\", \
+                  \"version\": \"0.1.0\", \"lib_path\": null}}, \
+                  \"rustc_version\": null, \"generated_at\": 1}}").unwrap();
+    drop(file);
+    let meta = store::crate_metadata(&info).expect("Read failed");
+    assert_eq!(meta.layout, StoreLayout::Prefixed);
+}
+
+#[test]
+fn test_iter_docs() {
+    let _store = TempStore::new("iter_docs");
+    let info = get_crate_info("oxidoc_test_iter_docs", "0.1.0");
+    let docs = source_to_docs(r#"
+pub struct MyStruct;
+
+impl MyStruct {
+    pub fn method(&self) {}
+}
+"#);
+    let count = docs.len();
+    let mut dir = None;
+    for mut doc in docs {
+        doc.crate_info = info.clone();
+        doc.save().expect("Write failed");
+        dir = doc.to_store_location().to_filepath().unwrap().parent().map(|p| p.to_path_buf());
+    }
+
+    let corrupt = dir.unwrap().join("corrupt.odoc");
+    File::create(&corrupt).unwrap().write_all(b"garbage").unwrap();
+
+    let results: Vec<_> = store::iter_docs(&info).collect();
+    assert_eq!(results.len(), count + 1);
+    assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+
+    store::remove_crate(&info).expect("Removal failed");
+    let missing: Vec<_> = store::iter_docs(&info).collect();
+    assert_eq!(missing.len(), 1);
+    assert!(missing[0].is_err());
+}
+
+#[test]
+fn test_visit_all() {
+    let _store = TempStore::new("visit_all");
+    let info = get_crate_info("oxidoc_test_visit_all", "0.1.0");
+    let docs = source_to_docs(r#"
+/// Documented.
+pub struct MyStruct;
+
+pub fn undocumented() {}
+"#);
+    let count = docs.len();
+    let mut dir = None;
+    for mut doc in docs {
+        doc.crate_info = info.clone();
+        doc.save().expect("Write failed");
+        dir = doc.to_store_location().to_filepath().unwrap().parent().map(|p| p.to_path_buf());
+    }
+
+    let mut names = Vec::new();
+    store::visit_all(&info, &mut |doc| names.push(doc.name.clone())).expect("Visit failed");
+    assert_eq!(names.len(), count);
+    assert!(names.contains(&"MyStruct".to_string()));
+
+    let corrupt = dir.unwrap().join("corrupt.odoc");
+    File::create(&corrupt).unwrap().write_all(b"garbage").unwrap();
+
+    let mut visited = 0;
+    assert!(store::visit_all(&info, &mut |_| visited += 1).is_err());
+    assert!(visited <= count);
+
+    let mut undocumented = Vec::new();
+    let errors = store::visit_all_collecting_errors(&info, &mut |doc| {
+        if doc.attrs.doc_strings.is_empty() {
+            undocumented.push(doc.name.clone());
+        }
+    });
+    assert_eq!(errors.len(), 1);
+    assert!(undocumented.contains(&"undocumented".to_string()));
+
+    store::remove_crate(&info).expect("Removal failed");
+}
+
+#[test]
+fn test_verify() {
+    let _store = TempStore::new("verify");
+    let info = get_crate_info("oxidoc_test_verify", "0.1.0");
+    let docs = source_to_docs(r#"
+pub struct MyStruct;
+
+pub fn func() {}
+"#);
+    let mut paths = Vec::new();
+    for mut doc in docs {
+        doc.crate_info = info.clone();
+        doc.save().expect("Write failed");
+        paths.push(doc.to_store_location().to_filepath().unwrap());
+    }
+    assert!(store::verify(&info).expect("Verification failed").is_empty());
+
+    let truncated = paths.iter().find(|path| path.ends_with("func.odoc")).unwrap();
+    let length = fs::metadata(truncated).unwrap().len();
+    fs::OpenOptions::new().write(true).open(truncated).unwrap().set_len(length - 2).unwrap();
+
+    let corrupt = store::verify(&info).expect("Verification failed");
+    assert_eq!(corrupt.len(), 1);
+    assert_eq!(corrupt[0].path, *truncated);
+    assert!(corrupt[0].reason.contains("checksum"), "{}", corrupt[0]);
+
+    store::remove_crate(&info).expect("Removal failed");
+    assert!(store::verify(&info).is_err());
+}
+
+#[test]
+fn test_generation_plan() {
+    let _store = TempStore::new("generation_plan");
+    let info = get_crate_info("oxidoc_test_generation_plan", "0.1.0");
+    let mut docs = source_to_docs(r#"
+pub struct MyStruct;
+
+pub fn func() {}
+"#);
+    for doc in docs.iter_mut() {
+        doc.crate_info = info.clone();
+    }
+
+    let plan = GenerationPlan::new(&docs).expect("Planning failed");
+    assert_eq!(plan.files.len(), docs.len());
+    assert!(plan.overwrites().is_empty());
+    assert!(plan.collisions().is_empty());
+    assert!(plan.files.iter().all(|file| !file.path.exists()));
+
+    docs.iter().find(|doc| doc.name == "func").unwrap().save().expect("Write failed");
+    let mut again = source_to_docs("pub fn func() {}")
+        .into_iter()
+        .find(|doc| doc.name == "func")
+        .unwrap();
+    again.crate_info = info.clone();
+    docs.push(again);
+
+    let plan = GenerationPlan::new(&docs).expect("Planning failed");
+    let overwrites = plan.overwrites();
+    assert_eq!(overwrites.len(), 2);
+    assert!(overwrites.iter().all(|file| file.path.ends_with("func.odoc")));
+
+    let collisions = plan.collisions();
+    assert_eq!(collisions.len(), 1);
+    assert_eq!(collisions[0].1.len(), 2);
+    assert!(plan.to_string().contains("with 1 collisions"), "{}", plan);
+
+    store::remove_crate(&info).expect("Removal failed");
+}
+
+#[test]
+fn test_generate_selected_modules() {
+    let _store = TempStore::new("generate_selected_modules");
+    source_to_store_with(r#"
+pub mod outer {
+    pub mod inner {
+        pub fn old() {}
+    }
+
+    pub fn sibling() {}
+}
+
+pub fn top() {}
+"#, &GenerateOptions::new());
+
+    let mut options = GenerateOptions::new();
+    options.modules = vec!["outer::added".to_string()];
+    source_to_store_with(r#"
+pub mod outer {
+    pub mod inner {
+        pub fn old() {}
+    }
+
+    pub mod added {
+        pub fn new_item() {}
+    }
+
+    pub fn sibling() {}
+}
+
+pub fn top() {}
+"#, &options);
+
+    let info = get_crate_info("crate", "1.0.0");
+    let outer = Documentation::load_any(&info, &ModPath::from("crate::outer".to_string()), 0)
+        .expect("Load failed");
+    let mut links: Vec<String> = outer.links.values()
+        .flat_map(|links| links.iter().map(|link| link.path.to_string()))
+        .collect();
+    links.sort();
+    assert_eq!(links, vec!["crate::outer::added", "crate::outer::inner", "crate::outer::sibling"]);
+
+    for path in &["crate::outer::added::new_item", "crate::outer::inner::old", "crate::top"] {
+        let path = ModPath::from(path.to_string());
+        assert!(Documentation::load_any(&info, &path, 0).is_ok(), "{}", path);
+    }
+    let paths: Vec<String> = store::Store::load().all_locations().iter()
+        .map(|location| location.mod_path.to_string())
+        .collect();
+    assert!(paths.contains(&"crate::outer::added::new_item".to_string()));
+    assert!(paths.contains(&"crate::top".to_string()));
+
+    // Only the entries of the generated items were replaced.
+    let completions: Vec<String> = CompletionIndex::load().expect("Read failed").items().iter()
+        .map(|item| item.path.clone())
+        .collect();
+    for path in &["crate::outer::added::new_item", "crate::outer::sibling", "crate::top"] {
+        let found = completions.iter().filter(|found| found.as_str() == *path).count();
+        assert_eq!(found, 1, "{}", path);
+    }
+}
+
+#[test]
+fn test_generate_module_named_like_crate() {
+    let _store = TempStore::new("generate_module_named_like_crate");
+    let info = get_crate_info("nested", "1.0.0");
+    let mut options = GenerateOptions::new();
+    options.modules = vec!["nested".to_string()];
+    source_to_store_as(r#"
+pub mod nested {
+    pub fn inside() {}
+}
+
+pub fn outside() {}
+"#, info, &options);
+
+    let paths: Vec<String> = store::Store::load().all_locations().iter()
+        .map(|location| location.mod_path.to_string())
+        .collect();
+    assert!(paths.contains(&"nested::nested::inside".to_string()));
+    assert!(!paths.contains(&"nested::outside".to_string()));
+}
+
+#[test]
+fn test_prune_stale_docs() {
+    let temp_dir = TempDir::new("prune");
+    let dir = temp_dir.path();
+    fs::create_dir_all(dir.join("kept")).unwrap();
+    fs::create_dir_all(dir.join("removed")).unwrap();
+
+    let kept = dir.join("kept").join("fdesc-kept.odoc");
+    let stale = dir.join("removed").join("fdesc-removed.odoc");
+    let meta = dir.join("metadata.json");
+    for path in &[&kept, &stale, &meta] {
+        File::create(path).unwrap().write_all(b"data").unwrap();
+    }
+
+    let mut keep = HashSet::new();
+    keep.insert(kept.clone());
+    assert_eq!(store::prune_stale_docs(dir, &keep).expect("Pruning failed"), 1);
+
+    assert!(kept.is_file());
+    assert!(meta.is_file());
+    assert!(!stale.exists());
+    assert!(!dir.join("removed").exists());
+}
@@ -0,0 +1,575 @@
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+use bincode;
+use oxidoc::completion::CompletionIndex;
+use oxidoc::conversion::{self, DocType, Documentation, GenericParam, Generics};
+use oxidoc::document::ModPath;
+use oxidoc::errors::{ErrorKind, Result};
+use oxidoc::full_text::FullTextIndex;
+use oxidoc::generation::GenerateOptions;
+use oxidoc::migration;
+use oxidoc::store::{self, CrateIndex, StoreLayout, StoreLocation};
+use serde_json::{self, Value};
+
+use util::{get_crate_info, source_to_docs, source_to_store_as, TempDir, TempStore};
+
+#[test]
+fn test_json_round_trip() {
+    let _store = TempStore::new("json_round_trip");
+    let docs = source_to_docs(r#"
+/// Documented.
+pub struct MyStruct {
+    pub field: u32,
+}
+
+impl MyStruct {
+    pub fn method(&self) {}
+}
+"#);
+
+    for doc in docs.iter() {
+        doc.save_json().expect("Write failed");
+        let path = doc.to_store_location().to_json_filepath().unwrap();
+        let result: Documentation = store::deserialize_object_json(&path).expect("Read failed");
+        assert_eq!(*doc, result);
+    }
+}
+
+#[test]
+fn test_union_bincode_round_trip() {
+    let docs = source_to_docs(r#"
+/// A number seen as either type.
+pub union Number {
+    pub int: u32,
+    pub float: f32,
+}
+
+impl Number {
+    pub fn bits(&self) -> u32 { unsafe { self.int } }
+}
+"#);
+    let union_ = docs.iter().find(|d| d.name == "Number").unwrap();
+    assert_eq!(union_.get_type(), DocType::Union);
+
+    let dir = TempDir::new("union_round_trip");
+    let path = dir.path().join("Number.odoc");
+    store::serialize_object(union_, &path).expect("Write failed");
+    let result: Documentation = store::deserialize_object(&path).expect("Read failed");
+    assert_eq!(*union_, result);
+}
+
+/// Rewrites the JSON of a document the way it was before functions were told apart from methods.
+fn remove_receivers(value: &mut Value) {
+    if let Some(function) = value["inner_data"].get_mut("FnDoc") {
+        function.as_object_mut().unwrap().remove("has_receiver");
+    }
+    if let Some(struct_) = value["inner_data"].get_mut("StructDoc") {
+        for block in struct_["impls"].as_array_mut().unwrap().iter_mut() {
+            block.as_object_mut().unwrap().remove("methods");
+        }
+    }
+}
+
+/// Removes a field of every link in the JSON of a document, found like the migrations find them.
+fn remove_link_field(value: &mut Value, field: &str) {
+    match *value {
+        Value::Object(ref mut object) => {
+            if object.contains_key("krate") {
+                object.remove(field);
+            }
+            for (_, child) in object.iter_mut() {
+                remove_link_field(child, field);
+            }
+        },
+        Value::Array(ref mut array) => {
+            for child in array.iter_mut() {
+                remove_link_field(child, field);
+            }
+        },
+        _ => (),
+    }
+}
+
+/// Rewrites the header of a bincoded file as if it was written with another format version.
+fn set_format_version(path: &Path, version: u32) {
+    let mut bytes = read_bytes(path);
+    for i in 0..4 {
+        bytes[4 + i] = (version >> (i * 8)) as u8;
+    }
+    File::create(path).unwrap().write_all(&bytes).unwrap();
+}
+
+#[test]
+fn test_migrate_document() {
+    let docs = source_to_docs(r#"
+pub struct Counter(u32);
+
+impl Counter {
+    pub fn new() -> Self { Counter(0) }
+    pub fn get(&self) -> u32 { self.0 }
+}
+"#);
+
+    // Documents as they were written with version 30, before the stability of items, version 32,
+    // before functions were told apart from methods, version 36, before merged signatures,
+    // version 37, before unreachable items, and version 38, before glob links.
+    let mut migrated = Vec::new();
+    for doc in docs.iter() {
+        let mut value = serde_json::to_value(doc).unwrap();
+        value["attrs"].as_object_mut().unwrap().remove("stability");
+        value["attrs"].as_object_mut().unwrap().remove("feature_signatures");
+        value["attrs"].as_object_mut().unwrap().remove("unreachable");
+        remove_receivers(&mut value);
+        remove_link_field(&mut value, "glob");
+
+        migration::migrate_document(&mut value, 30, store::FORMAT_VERSION)
+            .expect("Migration failed");
+        let doc: Documentation = serde_json::from_value(value).expect("Read failed");
+        migrated.push(doc);
+    }
+    conversion::link_methods(&mut migrated);
+
+    assert_eq!(docs, migrated);
+
+    let error = migration::migrate_document(&mut Value::Null, 3, store::FORMAT_VERSION)
+        .unwrap_err();
+    match *error.kind() {
+        ErrorKind::NoMigration(3, to) => assert_eq!(to, store::FORMAT_VERSION),
+        ref kind => panic!("Expected a missing migration, got {}", kind),
+    }
+}
+
+#[test]
+fn test_migrate_store() {
+    let temp_store = TempStore::new("migrate_store");
+    let info = get_crate_info("oxidoc_test_migrate", "0.1.0");
+    let broken = get_crate_info("oxidoc_test_migrate_broken", "0.1.0");
+
+    let mut docs = source_to_docs(r#"
+pub struct Counter(u32);
+
+impl Counter {
+    pub fn new() -> Self { Counter(0) }
+    pub fn get(&self) -> u32 { self.0 }
+}
+"#);
+    let mut broken_docs = source_to_docs("pub fn lost() {}");
+    for doc in docs.iter_mut() {
+        doc.crate_info = info.clone();
+    }
+    for doc in broken_docs.iter_mut() {
+        doc.crate_info = broken.clone();
+    }
+
+    // A store written with version 32, where the documents of one crate have no JSON copies.
+    let mut index = store::Store::new();
+    let mut full_text = FullTextIndex::new();
+    let mut completions = CompletionIndex::new();
+    for &(crate_info, crate_docs) in [(&info, &docs), (&broken, &broken_docs)].iter() {
+        store::save_crate_metadata(crate_info).expect("Write failed");
+        let mut docset = store::Docset::new();
+        docset.add_docs(crate_docs);
+        index.add_docset(crate_info.clone(), docset);
+        full_text.add_docs(crate_info, crate_docs);
+        completions.add_docs(crate_info, crate_docs);
+
+        for doc in crate_docs.iter() {
+            doc.save().expect("Write failed");
+            set_format_version(&doc.to_store_location().to_filepath().unwrap(), 32);
+        }
+    }
+    for doc in docs.iter() {
+        let mut value = serde_json::to_value(doc).unwrap();
+        remove_receivers(&mut value);
+        let path = doc.to_store_location().to_json_filepath().unwrap();
+        store::serialize_object_json(&value, &path).expect("Write failed");
+    }
+    index.save().expect("Write failed");
+    full_text.save().expect("Write failed");
+    completions.save().expect("Write failed");
+    for name in &["store", "full_text_index", "completion_index"] {
+        set_format_version(&temp_store.path().join(name), 32);
+    }
+
+    let report = store::migrate(32, store::FORMAT_VERSION).expect("Migration failed");
+    assert_eq!(report.migrated, docs.len());
+    assert_eq!(report.current, 0);
+    assert_eq!(report.failed.len(), broken_docs.len());
+    assert!(report.failed[0].reason.contains("JSON"), "{}", report.failed[0]);
+
+    for doc in docs.iter() {
+        let path = doc.to_store_location().to_filepath().unwrap();
+        let loaded = Documentation::load_any(&info, &doc.mod_path, doc.disambiguator)
+            .expect("Load failed");
+        assert_eq!(loaded, *doc);
+        assert!(report.backup_dir.join(path.strip_prefix(temp_store.path()).unwrap()).is_file());
+    }
+
+    // The crate that couldn't be migrated is left out until it's regenerated, since its files
+    // can't be read anymore.
+    assert_eq!(report.unindexed, vec![broken.clone()]);
+    let index = store::Store::load_from_disk().expect("Read failed");
+    assert!(index.has_docset(&info));
+    assert!(!index.has_docset(&broken));
+    let completions = CompletionIndex::load().expect("Read failed");
+    assert!(!completions.items().iter().any(|item| item.path == "crate::lost"));
+    assert!(completions.items().iter().any(|item| item.path == "crate::Counter::get"));
+}
+
+#[test]
+fn test_load_doc_by_path() {
+    let _store = TempStore::new("load_doc_by_path");
+    let docs = source_to_docs(r#"
+pub struct Loaded;
+
+pub mod module {
+    pub fn load_me() {}
+}
+"#);
+
+    for doc in docs.iter() {
+        doc.save().expect("Write failed");
+    }
+    let info = get_crate_info("crate", "1.0.0");
+
+    let path = ModPath::from("crate::Loaded".to_string());
+    let loaded = Documentation::load(&info, &path, DocType::Struct, 0).expect("Load failed");
+    assert_eq!(loaded, *docs.iter().find(|d| d.name == "Loaded").unwrap());
+    assert!(Documentation::load(&info, &path, DocType::Function, 0).is_err());
+
+    let path = ModPath::from("crate::module::load_me".to_string());
+    let loaded = Documentation::load_any(&info, &path, 0).expect("Load failed");
+    assert_eq!(loaded.get_type(), DocType::Function);
+
+    let missing = ModPath::from("crate::missing".to_string());
+    assert!(Documentation::load_any(&info, &missing, 0).is_err());
+}
+
+#[test]
+fn test_load_disambiguated_doc() {
+    let _store = TempStore::new("load_disambiguated_doc");
+    let docs = source_to_docs(r#"
+pub struct Loaded;
+
+#[cfg(unix)]
+impl Loaded {
+    pub fn name(&self) -> &str { "unix" }
+}
+
+#[cfg(windows)]
+impl Loaded {
+    pub fn name(&self) -> &str { "windows" }
+}
+"#);
+    for doc in docs.iter() {
+        doc.save().expect("Write failed");
+    }
+    let info = get_crate_info("crate", "1.0.0");
+
+    let path = ModPath::from("crate::Loaded::name".to_string());
+    let first = Documentation::load_any(&info, &path, 0).expect("Load failed");
+    let second = Documentation::load_any(&info, &path, 1).expect("Load failed");
+    assert_eq!(first.disambiguator, 0);
+    assert_eq!(second.disambiguator, 1);
+    assert!(Documentation::load(&info, &path, DocType::Function, 2).is_err());
+}
+
+#[test]
+fn test_bincode_round_trip() {
+    let dir = TempDir::new("round_trip");
+    let path = dir.path().join("round_trip.odoc");
+
+    let string = "Test.".to_string();
+    store::serialize_object(&string, &path).expect("Write failed");
+    let result: String = store::deserialize_object(&path).expect("Read failed");
+
+    assert_eq!(string, result);
+}
+
+#[test]
+fn test_concurrent_writes() {
+    let dir = TempDir::new("concurrent");
+    let path = dir.path().join("concurrent.odoc");
+
+    let writers: Vec<_> = (0..8u8).map(|i| {
+        let path = path.clone();
+        thread::spawn(move || {
+            let data = vec![i; 100_000];
+            for _ in 0..10 {
+                store::serialize_object(&data, &path).expect("Write failed");
+                let result: Vec<u8> = store::deserialize_object(&path).expect("Read failed");
+                assert_eq!(result.len(), data.len());
+                assert!(result.iter().all(|b| *b == result[0]));
+            }
+        })
+    }).collect();
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    let leftovers = dir.path().read_dir().unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.starts_with(".concurrent.odoc.") && name.ends_with(".tmp")
+        })
+        .count();
+    assert_eq!(leftovers, 0);
+}
+
+/// Makes `test_concurrent_generation_child` generate a crate of this name, for
+/// `test_concurrent_generation` to run it in other processes.
+const CHILD_CRATE_VAR: &str = "OXIDOC_TEST_CHILD_CRATE";
+
+#[test]
+fn test_concurrent_generation() {
+    let temp_store = TempStore::new("concurrent_generation");
+    let exe = env::current_exe().unwrap();
+    let names: Vec<String> = (0..4).map(|i| format!("oxidoc_test_concurrent_{}", i)).collect();
+
+    let children: Vec<_> = names.iter().map(|name| {
+        Command::new(&exe)
+            .args(&["--exact", "store::test_serialization::test_concurrent_generation_child"])
+            .env(CHILD_CRATE_VAR, name)
+            .env("OXIDOC_STORE", temp_store.path())
+            .spawn()
+            .expect("Couldn't start the generating process")
+    }).collect();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    let store = store::Store::load_from_disk().expect("Store not saved");
+    let completions = CompletionIndex::load().expect("Completions not saved");
+    for name in names.iter() {
+        assert!(store.has_docset(&get_crate_info(name, "0.1.0")), "{}", name);
+        let func = format!("{}::func", name);
+        assert!(completions.items().iter().any(|item| item.path == func), "{}", func);
+    }
+    assert!(!temp_store.path().join("store.lock").exists());
+}
+
+/// Generates a crate into the store when run by `test_concurrent_generation`, and does nothing
+/// otherwise.
+#[test]
+fn test_concurrent_generation_child() {
+    let name = match env::var(CHILD_CRATE_VAR) {
+        Ok(name) => name,
+        Err(_)   => return,
+    };
+    source_to_store_as("/// A function.\npub fn func() {}", get_crate_info(&name, "0.1.0"),
+                       &GenerateOptions::new());
+}
+
+#[test]
+fn test_generics_round_trip() {
+    let dir = TempDir::new("generics");
+    let path = dir.path().join("generics.odoc");
+
+    let generics = Generics {
+        params: vec![
+            GenericParam::Lifetime { name: "'a".to_string(), bounds: vec!["'b".to_string()] },
+            GenericParam::Const {
+                name: "N".to_string(),
+                ty: "usize".to_string(),
+                default: Some("4".to_string()),
+            },
+            GenericParam::Type {
+                name: "T".to_string(),
+                bounds: vec!["Copy".to_string()],
+                default: Some("u8".to_string()),
+            },
+        ],
+        where_predicates: vec!["T: Default".to_string()],
+    };
+
+    store::serialize_object(&generics, &path).expect("Write failed");
+    let result: Generics = store::deserialize_object(&path).expect("Read failed");
+
+    assert_eq!(generics, result);
+    assert_eq!(result.to_string(), "<'a: 'b, const N: usize = 4, T: Copy = u8>");
+}
+
+#[test]
+fn test_format_version_mismatch() {
+    let dir = TempDir::new("version_mismatch");
+    let path = dir.path().join("version_mismatch.odoc");
+
+    {
+        let mut file = File::create(&path).unwrap();
+        file.write_all(b"ODOC").unwrap();
+        file.write_all(&[0xff, 0, 0, 0]).unwrap();
+        file.write_all(b"garbage").unwrap();
+    }
+
+    let result: Result<String> = store::deserialize_object(&path);
+    match result {
+        Err(e) => {
+            let mismatch = e.iter().any(|e| e.to_string().contains("version mismatch"));
+            assert!(mismatch, "{}", e);
+        }
+        Ok(_) => panic!("Expected a version mismatch"),
+    }
+}
+
+#[test]
+fn test_structured_errors() {
+    let dir = TempDir::new("structured_errors");
+    let mut path = dir.path().join("structured_errors.odoc");
+
+    {
+        let mut file = File::create(&path).unwrap();
+        file.write_all(b"ODOC").unwrap();
+        file.write_all(&[0xff, 0, 0, 0]).unwrap();
+    }
+
+    let result: Result<String> = store::deserialize_object(&path);
+    match *result.unwrap_err().kind() {
+        ErrorKind::StoreVersionMismatch(ref found_path, found, expected) => {
+            assert_eq!(*found_path, path);
+            assert_eq!(found, 0xff);
+            assert_eq!(expected, store::FORMAT_VERSION);
+        }
+        ref kind => panic!("Expected a version mismatch, got {}", kind),
+    }
+
+    path.set_file_name("structured_errors_missing.odoc");
+    let result: Result<String> = store::deserialize_object(&path);
+    match *result.unwrap_err().kind() {
+        ErrorKind::ReadFailed(ref missing) => assert_eq!(*missing, path),
+        ref kind => panic!("Expected a read failure, got {}", kind),
+    }
+}
+
+#[test]
+fn test_uncompressed_file_loads() {
+    let dir = TempDir::new("uncompressed");
+    let path = dir.path().join("uncompressed.odoc");
+
+    let string = "Test.".to_string();
+    let write = |compression: u8| {
+        let mut file = File::create(&path).unwrap();
+        file.write_all(b"ODOC").unwrap();
+        file.write_all(&[store::FORMAT_VERSION as u8, 0, 0, 0, compression]).unwrap();
+        file.write_all(&bincode::serialize(&string, bincode::Infinite).unwrap()).unwrap();
+        // The CRC-32 of the data, little-endian.
+        file.write_all(&[0x9f, 0xfc, 0x02, 0x12]).unwrap();
+    };
+
+    write(0);
+    let result: String = store::deserialize_object(&path).expect("Read failed");
+    assert_eq!(string, result);
+
+    // Data with an unknown compression byte in the header isn't read.
+    write(7);
+    let result: Result<String> = store::deserialize_object(&path);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_corrupt_file_detected() {
+    let dir = TempDir::new("corrupt_file");
+    let path = dir.path().join("corrupt_file.odoc");
+
+    let long = "A string long enough to be damaged in the middle.".repeat(10);
+    store::serialize_object(&long, &path).expect("Write failed");
+    let bytes = read_bytes(&path);
+
+    let mut damaged = bytes.clone();
+    let middle = damaged.len() / 2;
+    damaged[middle] ^= 0xff;
+    File::create(&path).unwrap().write_all(&damaged).unwrap();
+
+    let result: Result<String> = store::deserialize_object(&path);
+    match *result.unwrap_err().kind() {
+        ErrorKind::DeserializeFailed(ref corrupt_path) => assert_eq!(*corrupt_path, path),
+        ref kind => panic!("Expected a deserialization failure, got {}", kind),
+    }
+
+    File::create(&path).unwrap().write_all(&bytes[..bytes.len() - 10]).unwrap();
+    let result: Result<String> = store::deserialize_object(&path);
+    match *result.unwrap_err().kind() {
+        ErrorKind::DeserializeFailed(ref corrupt_path) => assert_eq!(*corrupt_path, path),
+        ref kind => panic!("Expected a deserialization failure, got {}", kind),
+    }
+}
+
+#[test]
+fn test_store_layouts() {
+    let loc = StoreLocation::new("Thing".to_string(),
+                                 get_crate_info("crate", "1.0.0"),
+                                 ModPath::from("crate::module::Thing".to_string()),
+                                 DocType::Struct);
+
+    assert_eq!(loc.relative_filepath_in(StoreLayout::Prefixed),
+               PathBuf::from("crate/module/Thing/sdesc-Thing.odoc"));
+    assert_eq!(loc.relative_filepath_in(StoreLayout::Readable),
+               PathBuf::from("crate/module/Thing/struct-Thing.odoc"));
+
+    let hashed = loc.relative_filepath_in(StoreLayout::Hashed);
+    assert_eq!(hashed.components().count(), 1);
+    assert_eq!(hashed.extension().and_then(|ext| ext.to_str()), Some("odoc"));
+
+    let mut other = loc.clone();
+    other.disambiguator = 1;
+    assert!(other.relative_filepath_in(StoreLayout::Hashed) != hashed);
+
+    assert_eq!(StoreLayout::from_name("readable"), Some(StoreLayout::Readable));
+    assert_eq!(StoreLayout::from_name("cryptic"), None);
+}
+
+fn read_bytes(path: &Path) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    File::open(path).unwrap().read_to_end(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn test_deterministic_output() {
+    let source = r#"
+/// Documented.
+pub struct MyStruct {
+    pub field: u32,
+}
+
+impl MyStruct {
+    pub fn method(&self) {}
+    pub fn other(&self) {}
+}
+
+pub trait MyTrait {}
+impl MyTrait for MyStruct {}
+
+pub mod module {
+    pub fn func() {}
+    pub const CONST: u32 = 0;
+}
+"#;
+
+    let dir = TempDir::new("deterministic");
+    let first = dir.path().join("first.odoc");
+    let second = dir.path().join("second.odoc");
+
+    for (a, b) in source_to_docs(source).iter().zip(source_to_docs(source).iter()) {
+        store::serialize_object(a, &first).expect("Write failed");
+        store::serialize_object(b, &second).expect("Write failed");
+        assert_eq!(read_bytes(&first), read_bytes(&second), "{}", a.mod_path);
+    }
+
+    let save_store = |path: &Path| {
+        let mut docset = store::Docset::new();
+        docset.add_docs(&source_to_docs(source));
+        let mut store = store::Store::new();
+        store.add_docset(get_crate_info("crate", "1.0.0"), docset);
+        store::serialize_object(&store, path).expect("Write failed");
+    };
+    save_store(&first);
+    save_store(&second);
+    assert_eq!(read_bytes(&first), read_bytes(&second));
+}
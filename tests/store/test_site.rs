@@ -0,0 +1,119 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use oxidoc::conversion;
+use oxidoc::markup;
+use oxidoc::site;
+use oxidoc::store;
+
+use util::{get_crate_info, source_to_docs, TempDir, TempStore};
+
+fn read_page(path: &Path) -> String {
+    let mut html = String::new();
+    File::open(path).expect("Missing page").read_to_string(&mut html).unwrap();
+    html
+}
+
+#[test]
+fn test_generate_site() {
+    let temp_store = TempStore::new("generate_site");
+    let info = get_crate_info("oxidoc_test_site", "0.1.0");
+    let mut docs = source_to_docs(r#"
+pub mod shapes {
+    /// A widget, drawn with a [`Helper`].
+    pub struct Widget;
+
+    pub struct Helper;
+}
+"#);
+    for doc in docs.iter_mut() {
+        doc.crate_info = info.clone();
+        doc.save().expect("Write failed");
+    }
+    store::save_crate_metadata(&info).expect("Write failed");
+
+    let out_dir = temp_store.path().join("site");
+    let generated = site::generate_site(&info, &out_dir).expect("Generation failed");
+    assert_eq!(generated.pages, docs.len() + 1);
+
+    let site_dir = out_dir.join(info.to_path_prefix());
+    let page = |name: &str| {
+        let doc = docs.iter().find(|d| d.name == name).unwrap();
+        doc.to_store_location().relative_filepath_from(&info).with_extension("html")
+    };
+
+    let index = read_page(&generated.index);
+    assert!(index.contains("<h2>Structs</h2>"), "{}", index);
+    assert!(index.contains(&format!("<a href=\"{}\"><code>crate::shapes::Helper</code></a>",
+                                    markup::relative_url(&page("Helper")))),
+            "{}", index);
+
+    let widget = read_page(&site_dir.join(page("Widget")));
+    assert!(widget.contains("<style>"), "{}", widget);
+    assert!(widget.contains(&format!("<li class=\"current\"><a href=\"{}\">shapes</a>",
+                                     markup::relative_url(&page("shapes")))),
+            "{}", widget);
+    let link = format!("<a href=\"{}\"><code>Helper</code></a>",
+                       markup::relative_url(&page("Helper")));
+    assert!(widget.contains(&format!("<p>A widget, drawn with a {}.</p>", link)),
+            "{}", widget);
+
+    // Links are relative to the crate's directory, which every page sets as its base.
+    let depth = page("Widget").components().count() - 1;
+    assert!(widget.contains(&format!("<base href=\"{}\">", "../".repeat(depth))), "{}", widget);
+    assert!(site_dir.join(page("Helper")).is_file());
+}
+
+const RAW_IDENTS_JSON: &str = r##"{
+  "root": 0,
+  "crate_version": "0.1.0",
+  "format_version": 39,
+  "index": {
+    "0": {
+      "id": 0, "crate_id": 0, "name": "oxidoc_test_site_raw", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"module": {"is_crate": true, "items": [1]}}
+    },
+    "1": {
+      "id": 1, "crate_id": 0, "name": "r#async", "visibility": "public",
+      "docs": null, "attrs": [], "deprecation": null, "span": null,
+      "inner": {"function": {
+        "sig": {"inputs": [], "output": null, "is_c_variadic": false},
+        "generics": {"params": [], "where_predicates": []},
+        "header": {"is_const": false, "is_unsafe": false, "is_async": false, "abi": "Rust"},
+        "has_body": true
+      }}
+    }
+  },
+  "paths": {}
+}"##;
+
+#[test]
+fn test_generate_site_escaped_names() {
+    let temp_dir = TempDir::new("site_raw_idents");
+    let json = temp_dir.path().join("raw.json");
+    File::create(&json).unwrap().write_all(RAW_IDENTS_JSON.as_bytes()).unwrap();
+
+    let temp_store = TempStore::new("generate_site_escaped_names");
+    let info = get_crate_info("oxidoc_test_site_raw", "0.1.0");
+    let docs = conversion::from_rustdoc_json(&json).expect("Import failed");
+    for doc in docs.iter() {
+        doc.save().expect("Write failed");
+    }
+    store::save_crate_metadata(&info).expect("Write failed");
+
+    let out_dir = temp_store.path().join("site");
+    let generated = site::generate_site(&info, &out_dir).expect("Generation failed");
+
+    // The page's file is named with a `%` escape, which is escaped again in its URL.
+    let func = docs.iter().find(|d| d.name == "r#async").unwrap();
+    let page = func.to_store_location().relative_filepath_from(&info).with_extension("html");
+    assert!(page.to_string_lossy().contains("%23"), "{}", page.display());
+    assert!(out_dir.join(info.to_path_prefix()).join(&page).is_file());
+
+    let url = markup::relative_url(&page);
+    assert!(url.contains("%2523") && !url.contains("%23async"), "{}", url);
+    let index = read_page(&generated.index);
+    assert!(index.contains(&format!("<a href=\"{}\">", url)), "{}", index);
+}
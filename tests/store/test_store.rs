@@ -41,7 +41,7 @@ fn test_store_loc_to_path() {
         doc_type: DocType::Struct,
     };
 
-    let path = loc.to_filepath().display().to_string();
+    let path = loc.to_filepath().unwrap().display().to_string();
     assert!(path.contains("test-0.1.0"), "{}", path);
     assert!(path.contains("crate"), "{}", path);
     assert!(path.contains("thing"), "{}", path);
@@ -1,5 +1,5 @@
 #[macro_use] extern crate log;
-extern crate lazy_static;
+#[macro_use] extern crate lazy_static;
 extern crate error_chain;
 extern crate clap;
 extern crate ansi_term;
@@ -8,6 +8,7 @@ extern crate cursive;
 extern crate env_logger;
 extern crate regex;
 extern crate serde;
+extern crate serde_json;
 extern crate syntex_syntax as syntax;
 extern crate toml;
 extern crate oxidoc;
@@ -16,5 +17,10 @@ extern crate oxidoc;
 extern crate pager;
 
 mod conversion;
+mod diff;
+mod generation;
+mod merge;
 mod search;
+mod stats;
+mod store;
 mod util;
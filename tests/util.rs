@@ -1,11 +1,100 @@
+use std::cell::Cell;
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{Mutex, MutexGuard};
+
 use oxidoc::conversion::Documentation;
 use oxidoc::document::{CrateInfo, ModPath};
+use oxidoc::errors::Result;
 use oxidoc::generation;
+use oxidoc::store;
 
 use syntax::codemap::FilePathMapping;
 use syntax::parse::{self, ParseSess};
 use syntax::ast;
 
+lazy_static! {
+    static ref STORE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+thread_local! {
+    // Whether the test running on this thread holds a `TempStore`.
+    static HOLDS_STORE: Cell<bool> = Cell::new(false);
+}
+
+/// Points the store at a directory of its own in the temporary directory while it's kept, and
+/// removes the directory afterwards. The store's location is shared by the whole process, so tests
+/// that read or write the store hold one, and run one at a time. A test can only hold one at a
+/// time, so helpers that use the store leave creating it to the tests calling them. Like `TempDir`,
+/// its name has the process id in it.
+pub struct TempStore {
+    dir: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl TempStore {
+    pub fn new(name: &str) -> TempStore {
+        // Waiting for the lock this thread already holds would never end.
+        if HOLDS_STORE.with(|holds| holds.replace(true)) {
+            panic!("A temporary store is already held by this test");
+        }
+
+        // A test that panicked while holding the lock still cleaned up after itself.
+        let lock = STORE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let mut dir = env::temp_dir();
+        dir.push(format!("oxidoc_store_{}_{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        store::set_store_root(Some(dir.clone()));
+
+        TempStore {
+            dir: dir,
+            _lock: lock,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for TempStore {
+    fn drop(&mut self) {
+        store::set_store_root(None);
+        let _ = fs::remove_dir_all(&self.dir);
+        HOLDS_STORE.with(|holds| holds.set(false));
+    }
+}
+
+/// A directory of its own in the temporary directory, removed when it's dropped. The process id
+/// is part of its name, so runs of the tests at the same time don't share it.
+pub struct TempDir {
+    dir: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        let mut dir = env::temp_dir();
+        dir.push(format!("oxidoc_test_{}_{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        TempDir { dir: dir }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
 pub fn get_crate_info(name: &str, version: &str) -> CrateInfo {
     CrateInfo {
         name: name.to_string(),
@@ -14,11 +103,9 @@ pub fn get_crate_info(name: &str, version: &str) -> CrateInfo {
     }
 }
 
-fn parse_crate_from_source(docs_string: String) -> ast::Crate {
-    let parse_session = ParseSess::new(FilePathMapping::empty());
-
+fn parse_crate_from_source(docs_string: String, parse_session: &ParseSess) -> ast::Crate {
     let result = parse::parse_crate_from_source_str("test.rs".to_string(), docs_string,
-                                                    &parse_session);
+                                                    parse_session);
 
     match result {
         Ok(_) if parse_session.span_diagnostic.has_errors() => panic!("Parse error"),
@@ -28,16 +115,81 @@ fn parse_crate_from_source(docs_string: String) -> ast::Crate {
 }
 
 pub fn source_to_docs(docs_str: &str) -> Vec<Documentation> {
-    let krate = parse_crate_from_source(docs_str.to_string());
+    source_to_docs_with(docs_str, &generation::GenerateOptions::new())
+}
 
-    let crate_info = get_crate_info("crate", "1.0.0");
-    let l = generation::generate_crate_docs(krate, crate_info).unwrap();
+pub fn source_to_docs_with(docs_str: &str,
+                           options: &generation::GenerateOptions) -> Vec<Documentation> {
+    let l = try_source_to_docs_with(docs_str, options).unwrap();
     for i in l.iter() {
         debug!("{}", i.mod_path);
     }
     l
 }
 
+pub fn try_source_to_docs_with(docs_str: &str,
+                               options: &generation::GenerateOptions)
+                               -> Result<Vec<Documentation>> {
+    let parse_session = ParseSess::new(FilePathMapping::empty());
+    let krate = parse_crate_from_source(docs_str.to_string(), &parse_session);
+
+    let crate_info = get_crate_info("crate", "1.0.0");
+    generation::generate_crate_docs(krate, crate_info, parse_session.codemap(), options)
+}
+
+/// Converts the source like `source_to_docs_with` and saves it to the store, like generating it
+/// from a crate directory.
+pub fn source_to_store_with(docs_str: &str, options: &generation::GenerateOptions) {
+    source_to_store_as(docs_str, get_crate_info("crate", "1.0.0"), options)
+}
+
+/// Like `source_to_store_with`, but for a crate with another name or version.
+pub fn source_to_store_as(docs_str: &str,
+                          crate_info: CrateInfo,
+                          options: &generation::GenerateOptions) {
+    let parse_session = ParseSess::new(FilePathMapping::empty());
+    let krate = parse_crate_from_source(docs_str.to_string(), &parse_session);
+
+    generation::generate_doc_cache(krate, crate_info, parse_session.codemap(), options)
+        .unwrap();
+}
+
+/// Converts the source like `source_to_docs`, returning the items that were left out.
+pub fn source_to_skipped_items(docs_str: &str) -> Vec<generation::SkippedItem> {
+    let parse_session = ParseSess::new(FilePathMapping::empty());
+    let krate = parse_crate_from_source(docs_str.to_string(), &parse_session);
+
+    let crate_info = get_crate_info("crate", "1.0.0");
+    let (_, skipped) = generation::generate_crate_docs_with_skipped(
+        krate, crate_info, parse_session.codemap(), &generation::GenerateOptions::new()).unwrap();
+    skipped
+}
+
+/// Like `source_to_docs`, but for crates split over several files. The files, given as paths
+/// relative to the crate root and their contents, are written to a `TempDir` named `dir_name`, and
+/// the crate is parsed from its `lib.rs`.
+pub fn files_to_docs(dir_name: &str, files: &[(&str, &str)]) -> Vec<Documentation> {
+    let temp_dir = TempDir::new(dir_name);
+    let dir = temp_dir.path();
+    for &(path, contents) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(&path).unwrap().write_all(contents.as_bytes()).unwrap();
+    }
+
+    let parse_session = ParseSess::new(FilePathMapping::empty());
+    let krate = match parse::parse_crate_from_file(&dir.join("lib.rs"), &parse_session) {
+        Ok(_) if parse_session.span_diagnostic.has_errors() => panic!("Parse error"),
+        Ok(krate) => krate,
+        Err(_) => panic!("Failed to parse"),
+    };
+
+    let crate_info = get_crate_info("crate", "1.0.0");
+    generation::generate_crate_docs(krate, crate_info, parse_session.codemap(),
+                                    &generation::GenerateOptions::new())
+        .unwrap()
+}
+
 pub fn print_paths(paths: &Vec<ModPath>) -> String {
     let strings: Vec<String> = paths.iter().cloned().map(|p| p.to_string()).collect();
     strings.join("\n")